@@ -0,0 +1,196 @@
+//! SHA-1 throughput benchmark.
+//!
+//! Verification hashes two things: each manifest's decompressed payload,
+//! against the header, and every installed file, against the file list.
+//! Both are measured here, next to a raw buffer for reference. Compare
+//! backends by running it with and without the `sha1-asm` feature:
+//!
+//! ```bash
+//! cargo bench --bench sha1 --no-default-features
+//! cargo bench --bench sha1 --no-default-features --features sha1-asm
+//! ```
+//!
+//! The manifest workloads parse with the crate, which an executable can
+//! only link without the `napi` feature; with it only the raw buffer runs.
+
+use std::time::{Duration, Instant};
+
+use sha1::{Digest, Sha1};
+
+const BUFFER_SIZE: usize = 256 * 1024 * 1024;
+const ROUNDS: u32 = 5;
+/// File content hashed per manifest and round; large builds are cut off
+/// here rather than hashing their whole install size.
+#[cfg(not(feature = "napi"))]
+const FILE_DATA_BUDGET: u64 = 1024 * 1024 * 1024;
+
+fn throughput(bytes: u64, elapsed: Duration) -> f64 {
+    bytes as f64 / (1024.0 * 1024.0) / elapsed.as_secs_f64()
+}
+
+/// Pseudo-random bytes, so nothing downstream benefits from trivially
+/// compressible content.
+fn random_bytes(len: usize) -> Vec<u8> {
+    let mut state = 0x9E37_79B9_7F4A_7C15u64;
+    (0..len)
+        .map(|_| {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            state as u8
+        })
+        .collect()
+}
+
+/// Best of [`ROUNDS`] runs of `f`, which returns the bytes it hashed.
+fn best_of(mut f: impl FnMut() -> u64) -> (Duration, u64) {
+    let mut best = Duration::MAX;
+    let mut bytes = 0;
+    for _ in 0..ROUNDS {
+        let start = Instant::now();
+        bytes = f();
+        best = best.min(start.elapsed());
+    }
+    (best, bytes)
+}
+
+fn bench_raw_hashing(buf: &[u8]) {
+    let (best, bytes) = best_of(|| {
+        std::hint::black_box(Sha1::digest(buf));
+        buf.len() as u64
+    });
+    println!(
+        "raw {} MiB buffer: best {:?} ({:.1} MiB/s)",
+        buf.len() / (1024 * 1024),
+        best,
+        throughput(bytes, best)
+    );
+}
+
+#[cfg(not(feature = "napi"))]
+mod manifests {
+    use std::io::Cursor;
+    use std::path::{Path, PathBuf};
+
+    use egdata_manifests_parser::{load, ManifestHeader};
+    use miniz_oxide::inflate::decompress_to_vec_zlib;
+    use sha1::{Digest, Sha1};
+
+    use super::{best_of, throughput, BUFFER_SIZE, FILE_DATA_BUDGET};
+
+    pub fn manifest_paths() -> Vec<PathBuf> {
+        let test_dir = Path::new("test-manifests");
+        let Ok(entries) = std::fs::read_dir(test_dir) else {
+            println!("test-manifests directory not found, skipping manifest benchmarks");
+            return Vec::new();
+        };
+        let mut paths: Vec<_> = entries
+            .filter_map(|entry| {
+                let path = entry.ok()?.path();
+                (path.extension()? == "manifest").then_some(path)
+            })
+            .collect();
+        paths.sort();
+        paths
+    }
+
+    /// The decompressed payload of a binary manifest, as the payload hash
+    /// check sees it. `None` for JSON and encrypted manifests.
+    fn payload(data: &[u8]) -> Option<Vec<u8>> {
+        let header = ManifestHeader::read(Cursor::new(data)).ok()?;
+        if header.is_encrypted() {
+            return None;
+        }
+        let start = header.header_size as usize;
+        let stored = data.get(start..start + header.stored_size() as usize)?;
+        if header.is_compressed() {
+            decompress_to_vec_zlib(stored).ok()
+        } else {
+            Some(stored.to_vec())
+        }
+    }
+
+    /// The payload hash check of each manifest.
+    pub fn bench_payloads(paths: &[PathBuf]) {
+        for path in paths {
+            let data = std::fs::read(path).expect("Failed to read manifest");
+            let Some(payload) = payload(&data) else {
+                continue;
+            };
+            // Repeat small payloads so timings are above timer resolution.
+            let repeat = (BUFFER_SIZE / 16 / payload.len().max(1)).max(1);
+            let (best, bytes) = best_of(|| {
+                for _ in 0..repeat {
+                    std::hint::black_box(Sha1::digest(&payload));
+                }
+                (payload.len() * repeat) as u64
+            });
+            println!(
+                "payload {} ({} KiB) x{}: best {:?} ({:.1} MiB/s)",
+                path.file_name().unwrap().to_string_lossy(),
+                payload.len() / 1024,
+                repeat,
+                best,
+                throughput(bytes, best)
+            );
+        }
+    }
+
+    /// Hashing each file of each manifest on its own, as install verification
+    /// does, with `content` standing in for the file data.
+    pub fn bench_file_data(paths: &[PathBuf], content: &[u8]) {
+        for path in paths {
+            let Ok(manifest) = load(path) else {
+                continue;
+            };
+            let sizes: Vec<usize> = manifest
+                .file_list
+                .iter()
+                .flat_map(|list| &list.file_manifest_list)
+                .map(|file| (file.file_size.max(0) as usize).min(content.len()))
+                .collect();
+            if sizes.iter().all(|&size| size == 0) {
+                continue;
+            }
+            let (best, bytes) = best_of(|| {
+                let mut hashed = 0u64;
+                for &size in sizes.iter().cycle() {
+                    std::hint::black_box(Sha1::digest(&content[..size]));
+                    hashed += size as u64;
+                    if hashed >= FILE_DATA_BUDGET {
+                        break;
+                    }
+                }
+                hashed
+            });
+            println!(
+                "files {} ({} files): best {:?} ({:.1} MiB/s)",
+                path.file_name().unwrap().to_string_lossy(),
+                sizes.len(),
+                best,
+                throughput(bytes, best)
+            );
+        }
+    }
+}
+
+fn main() {
+    println!(
+        "SHA-1 backend: {}",
+        if cfg!(feature = "sha1-asm") {
+            "asm"
+        } else {
+            "portable"
+        }
+    );
+    let buf = random_bytes(BUFFER_SIZE);
+    bench_raw_hashing(&buf);
+    #[cfg(not(feature = "napi"))]
+    {
+        let paths = manifests::manifest_paths();
+        manifests::bench_payloads(&paths);
+        manifests::bench_file_data(&paths, &buf);
+    }
+    #[cfg(feature = "napi")]
+    println!("built with `napi`, skipping manifest benchmarks (use --no-default-features)");
+}
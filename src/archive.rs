@@ -0,0 +1,144 @@
+//! Manifests inside `.zip`, `.tar` and `.tar.gz` archives, as preservation
+//! dumps often arrive, parsed straight from memory without extracting to
+//! disk.
+
+use std::fs::File;
+use std::io::{BufReader, Read, Seek, SeekFrom};
+use std::ops::ControlFlow;
+use std::path::Path;
+
+use flate2::read::GzDecoder;
+
+use crate::error::ManifestError;
+use crate::options::ParseOptions;
+use crate::types::manifest::Manifest;
+
+/// Largest archive entry read into memory. Real manifests stay far below
+/// this; a larger entry is reported as an error instead of exhausting memory
+/// on a decompression bomb.
+pub const MAX_ENTRY_SIZE: u64 = 512 * 1024 * 1024;
+
+/// Container formats recognized by [`ArchiveKind::detect`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArchiveKind {
+    Zip,
+    Tar,
+    TarGz,
+}
+
+impl ArchiveKind {
+    /// The format of an archive starting with `head` (its first 512 bytes
+    /// suffice), from magic numbers only.
+    pub fn detect(head: &[u8]) -> Option<Self> {
+        if head.starts_with(b"PK\x03\x04") || head.starts_with(b"PK\x05\x06") {
+            Some(ArchiveKind::Zip)
+        } else if head.starts_with(&[0x1f, 0x8b]) {
+            Some(ArchiveKind::TarGz)
+        } else if head.get(257..262) == Some(b"ustar") {
+            Some(ArchiveKind::Tar)
+        } else {
+            None
+        }
+    }
+}
+
+/// A manifest entry of an archive and how parsing it went.
+#[derive(Debug)]
+pub struct ArchivedManifest {
+    /// Path of the entry inside the archive.
+    pub name: String,
+    pub manifest: Result<Manifest, ManifestError>,
+}
+
+/// Whether `name` looks like a manifest entry.
+fn is_manifest_entry(name: &str) -> bool {
+    name.to_ascii_lowercase().ends_with(".manifest")
+}
+
+/// Read an entry of at most [`MAX_ENTRY_SIZE`] bytes.
+fn read_entry(entry: impl Read, size_hint: u64, name: &str) -> Result<Vec<u8>, ManifestError> {
+    if size_hint > MAX_ENTRY_SIZE {
+        return Err(ManifestError::Archive(format!("{} is larger than {} bytes", name, MAX_ENTRY_SIZE)));
+    }
+    let mut buf = Vec::with_capacity(size_hint.min(64 * 1024 * 1024) as usize);
+    // The header's size can't be trusted; stop reading just past the limit.
+    entry.take(MAX_ENTRY_SIZE + 1).read_to_end(&mut buf)?;
+    if buf.len() as u64 > MAX_ENTRY_SIZE {
+        return Err(ManifestError::Archive(format!("{} is larger than {} bytes", name, MAX_ENTRY_SIZE)));
+    }
+    Ok(buf)
+}
+
+/// Parse every `*.manifest` entry of the archive in `rdr`, in stored
+/// order, handing each to `visit` as soon as it is parsed; `visit` returns
+/// `ControlFlow::Break` to stop early. Errors in single entries go to
+/// `visit`; only unreadable archives fail the call.
+pub fn visit_archive<R: Read + Seek>(
+    mut rdr: R,
+    options: &ParseOptions,
+    mut visit: impl FnMut(ArchivedManifest) -> ControlFlow<()>,
+) -> Result<(), ManifestError> {
+    let mut head = Vec::with_capacity(512);
+    (&mut rdr).take(512).read_to_end(&mut head)?;
+    rdr.seek(SeekFrom::Start(0))?;
+    let parse = |name: String, data: Result<Vec<u8>, ManifestError>| ArchivedManifest {
+        manifest: data.and_then(|data| crate::process_manifest_data_with(data, options)),
+        name,
+    };
+
+    match ArchiveKind::detect(&head) {
+        Some(ArchiveKind::Zip) => {
+            let mut archive = zip::ZipArchive::new(rdr).map_err(|e| ManifestError::Archive(e.to_string()))?;
+            for index in 0..archive.len() {
+                let entry = archive.by_index(index).map_err(|e| ManifestError::Archive(e.to_string()))?;
+                if !entry.is_file() || !is_manifest_entry(entry.name()) {
+                    continue;
+                }
+                let name = entry.name().to_string();
+                let size = entry.size();
+                let data = read_entry(entry, size, &name);
+                if visit(parse(name, data)).is_break() {
+                    break;
+                }
+            }
+            Ok(())
+        }
+        Some(ArchiveKind::TarGz) => visit_tar(GzDecoder::new(rdr), visit, parse),
+        Some(ArchiveKind::Tar) => visit_tar(rdr, visit, parse),
+        None => Err(ManifestError::Archive("not a zip, tar or tar.gz archive".to_string())),
+    }
+}
+
+fn visit_tar(
+    rdr: impl Read,
+    mut visit: impl FnMut(ArchivedManifest) -> ControlFlow<()>,
+    parse: impl Fn(String, Result<Vec<u8>, ManifestError>) -> ArchivedManifest,
+) -> Result<(), ManifestError> {
+    let mut archive = tar::Archive::new(rdr);
+    for entry in archive.entries()? {
+        let entry = entry?;
+        if !entry.header().entry_type().is_file() {
+            continue;
+        }
+        let name = entry.path()?.to_string_lossy().into_owned();
+        if !is_manifest_entry(&name) {
+            continue;
+        }
+        let size = entry.size();
+        let data = read_entry(entry, size, &name);
+        if visit(parse(name, data)).is_break() {
+            break;
+        }
+    }
+    Ok(())
+}
+
+/// Parse every `*.manifest` entry of the archive at `path`.
+pub fn parse_archive(path: impl AsRef<Path>, options: &ParseOptions) -> Result<Vec<ArchivedManifest>, ManifestError> {
+    let mut manifests = Vec::new();
+    visit_archive(BufReader::new(File::open(path)?), options, |manifest| {
+        manifests.push(manifest);
+        ControlFlow::Continue(())
+    })?;
+    Ok(manifests)
+}
@@ -0,0 +1,132 @@
+//! Data-quality checks for archived manifests.
+//!
+//! Filenames are decoded lossily by default, so bytes that are not valid
+//! UTF-8 (or unpaired UTF-16 surrogates) silently become U+FFFD. The audit
+//! finds those names and recovers the bytes actually stored, so damaged
+//! manifests can be flagged instead of served with mangled paths.
+
+use serde::{Deserialize, Serialize};
+use std::io::{self, Cursor, Seek, SeekFrom};
+use std::ops::ControlFlow;
+
+use crate::error::ManifestError;
+use crate::hooks::{ParserHooks, Section};
+use crate::options::{ByteTrace, ParseOptions};
+use crate::parser::reader::{ReadExt, MAX_ARRAY_LEN};
+use crate::types::header::ManifestHeader;
+use crate::types::manifest::Manifest;
+
+/// How a string was stored.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum StringEncoding {
+    Utf8,
+    /// UTF-16LE, flagged by a negative FString length.
+    Utf16,
+}
+
+/// A filename that did not decode cleanly.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FilenameIssue {
+    /// Index into the file list.
+    pub index: u32,
+    /// The name as parsed, with U+FFFD in place of the undecodable bytes.
+    pub filename: String,
+    /// The stored string body, terminator included. For JSON manifests
+    /// this is the UTF-8 of the name, which already held U+FFFD.
+    pub raw: Vec<u8>,
+    pub encoding: StringEncoding,
+    /// Number of U+FFFD characters in `filename`.
+    pub replacements: u32,
+}
+
+/// Records where the file list starts in the payload.
+#[derive(Default)]
+struct FileListOffset(Option<u64>);
+
+impl ParserHooks for FileListOffset {
+    fn on_section_start(&mut self, section: Section, offset: u64) -> ControlFlow<()> {
+        if section == Section::FileList {
+            self.0 = Some(offset);
+        }
+        ControlFlow::Continue(())
+    }
+}
+
+/// Parse the manifest in `buf` and report every filename containing U+FFFD,
+/// with the bytes it was decoded from.
+pub fn audit_filenames(buf: &[u8]) -> Result<Vec<FilenameIssue>, ManifestError> {
+    let mut hooks = FileListOffset::default();
+    let (manifest, report) = crate::parse_with_hooks(buf, &ParseOptions::default(), &mut hooks)?;
+    let Some(file_list_offset) = hooks.0 else {
+        return Ok(json_issues(&manifest));
+    };
+
+    let buf = &buf[report.offset..];
+    let header = ManifestHeader::read(&mut Cursor::new(buf))?;
+    let (payload, _) = crate::manifest_payload(buf, &header, ByteTrace::from_env(), None)?;
+    let mut rdr = Cursor::new(payload);
+    rdr.seek(SeekFrom::Start(file_list_offset))?;
+    let _data_size = rdr.u32()?;
+    let _data_version = rdr.u8()?;
+    let count = rdr.read_count(MAX_ARRAY_LEN, 4)?;
+
+    let mut issues = Vec::new();
+    for index in 0..count {
+        let (raw, encoding) = raw_fstring(&mut rdr)?;
+        let filename = match encoding {
+            StringEncoding::Utf8 => String::from_utf8_lossy(&raw).into_owned(),
+            StringEncoding::Utf16 => {
+                let units: Vec<u16> = raw.chunks_exact(2).map(|p| u16::from_le_bytes([p[0], p[1]])).collect();
+                String::from_utf16_lossy(&units)
+            }
+        };
+        if let Some(issue) = issue(index, filename, raw, encoding) {
+            issues.push(issue);
+        }
+    }
+    Ok(issues)
+}
+
+fn json_issues(manifest: &Manifest) -> Vec<FilenameIssue> {
+    manifest
+        .file_list
+        .iter()
+        .flat_map(|f| &f.file_manifest_list)
+        .enumerate()
+        .filter_map(|(index, file)| {
+            let raw = file.filename.as_bytes().to_vec();
+            issue(index as u32, file.filename.clone(), raw, StringEncoding::Utf8)
+        })
+        .collect()
+}
+
+fn issue(index: u32, filename: String, raw: Vec<u8>, encoding: StringEncoding) -> Option<FilenameIssue> {
+    let replacements = filename.chars().filter(|&c| c == char::REPLACEMENT_CHARACTER).count() as u32;
+    (replacements > 0).then(|| FilenameIssue {
+        index,
+        filename: filename.trim_end_matches('\0').to_string(),
+        raw,
+        encoding,
+        replacements,
+    })
+}
+
+/// An FString's body bytes, undecoded.
+fn raw_fstring(rdr: &mut Cursor<Vec<u8>>) -> io::Result<(Vec<u8>, StringEncoding)> {
+    let len = rdr.i32()?;
+    let (size, encoding) = if len < 0 {
+        (len.unsigned_abs() as usize * 2, StringEncoding::Utf16)
+    } else {
+        (len as usize, StringEncoding::Utf8)
+    };
+    let remaining = rdr.get_ref().len().saturating_sub(rdr.position() as usize);
+    if size > remaining {
+        return Err(io::Error::new(
+            io::ErrorKind::UnexpectedEof,
+            format!("filename of {} bytes runs past the payload", size),
+        ));
+    }
+    Ok((rdr.read_bytes(size)?, encoding))
+}
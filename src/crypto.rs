@@ -0,0 +1,55 @@
+//! AES-256 keys for the encrypted storage flag.
+//!
+//! Epic's tooling encrypts stored data with AES-256 in ECB mode, 16-byte
+//! blocks and no IV, before compressing it (see UE's `FAES`). The key is
+//! never part of a distribution, so callers have to supply it.
+
+use aes::cipher::{generic_array::GenericArray, BlockDecrypt, KeyInit};
+use aes::Aes256;
+use std::fmt;
+
+use crate::error::ManifestError;
+
+/// A 256-bit AES key. Its `Debug` output does not show the key.
+#[derive(Clone, PartialEq, Eq)]
+pub struct AesKey([u8; 32]);
+
+impl fmt::Debug for AesKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("AesKey(..)")
+    }
+}
+
+impl AesKey {
+    pub fn new(key: [u8; 32]) -> Self {
+        Self(key)
+    }
+
+    /// A key written as 64 hex digits, optionally prefixed with `0x` as in
+    /// UE's `EncryptionKey` settings.
+    pub fn from_hex(hex: &str) -> Result<Self, ManifestError> {
+        let hex = hex.trim();
+        let hex = hex.strip_prefix("0x").unwrap_or(hex);
+        let bytes = hex::decode(hex)?;
+        let key = bytes
+            .try_into()
+            .map_err(|b: Vec<u8>| ManifestError::Invalid(format!("AES-256 key is {} bytes, not 32", b.len())))?;
+        Ok(Self(key))
+    }
+
+    /// Decrypt `data`, whose length must be a multiple of the 16-byte block.
+    pub fn decrypt(&self, data: &[u8]) -> Result<Vec<u8>, ManifestError> {
+        if !data.len().is_multiple_of(16) {
+            return Err(ManifestError::Invalid(format!(
+                "encrypted data of {} bytes is not a whole number of AES blocks",
+                data.len()
+            )));
+        }
+        let cipher = Aes256::new(GenericArray::from_slice(&self.0));
+        let mut out = data.to_vec();
+        for block in out.chunks_exact_mut(16) {
+            cipher.decrypt_block(GenericArray::from_mut_slice(block));
+        }
+        Ok(out)
+    }
+}
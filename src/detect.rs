@@ -0,0 +1,109 @@
+//! Cheap format sniffing, for routing files before committing to a parse.
+
+use byteorder::{ByteOrder, LittleEndian};
+#[cfg(feature = "napi")]
+use napi_derive::napi;
+use serde::{Deserialize, Serialize};
+
+use crate::format::{MANIFEST_HEADER_SIZE_V1, MANIFEST_MAGIC};
+
+const V1_HEADER_SIZE: i32 = MANIFEST_HEADER_SIZE_V1 as i32;
+/// Upper bound used to reject magic-looking bytes that aren't a header.
+const MAX_PLAUSIBLE_HEADER_SIZE: i32 = 4096;
+
+/// What a buffer looks like, judging only by its first bytes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ManifestFormat {
+    /// Binary manifest whose header predates the version field.
+    BinaryV1,
+    /// Binary manifest with a versioned header. `feature_level` is `None`
+    /// when the buffer is too short to contain it.
+    BinaryV2Plus { feature_level: Option<i32> },
+    Json,
+    Unknown,
+}
+
+/// Identify the manifest format of `data` from its magic bytes or JSON keys,
+/// without decompressing or parsing anything.
+pub fn detect_format(data: &[u8]) -> ManifestFormat {
+    if data.len() >= 8 && LittleEndian::read_u32(&data[..4]) == MANIFEST_MAGIC {
+        let header_size = LittleEndian::read_i32(&data[4..8]);
+        if header_size <= V1_HEADER_SIZE {
+            return ManifestFormat::BinaryV1;
+        }
+        // magic, header size, two data sizes, SHA-1, stored_as, then version.
+        let feature_level = data.get(37..41).map(LittleEndian::read_i32);
+        return ManifestFormat::BinaryV2Plus { feature_level };
+    }
+
+    let text = data.strip_prefix(b"\xEF\xBB\xBF").unwrap_or(data);
+    let text = match text.iter().position(|b| !b.is_ascii_whitespace()) {
+        Some(start) => &text[start..],
+        None => return ManifestFormat::Unknown,
+    };
+    if text.first() == Some(&b'{') && contains(text, b"\"ManifestFileVersion\"") {
+        return ManifestFormat::Json;
+    }
+
+    ManifestFormat::Unknown
+}
+
+fn contains(haystack: &[u8], needle: &[u8]) -> bool {
+    find(haystack, needle).is_some()
+}
+
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack.windows(needle.len()).position(|w| w == needle)
+}
+
+/// Offset of the first manifest embedded in `data`, for archives and HTTP
+/// dumps that put preamble bytes in front of it. Looks for the binary magic
+/// (with a plausible header size) or the opening brace of a JSON manifest.
+pub fn scan_for_manifest(data: &[u8]) -> Option<usize> {
+    let magic = MANIFEST_MAGIC.to_le_bytes();
+    let mut binary = None;
+    let mut from = 0;
+    while let Some(pos) = find(&data[from..], &magic).map(|p| p + from) {
+        if let Some(size) = data.get(pos + 4..pos + 8).map(LittleEndian::read_i32) {
+            if (V1_HEADER_SIZE..=MAX_PLAUSIBLE_HEADER_SIZE).contains(&size) {
+                binary = Some(pos);
+                break;
+            }
+        }
+        from = pos + 1;
+    }
+
+    let json = find(data, b"\"ManifestFileVersion\"").and_then(|key| {
+        let brace = data[..key].iter().rposition(|b| !b.is_ascii_whitespace())?;
+        (data[brace] == b'{').then_some(brace)
+    });
+
+    match (binary, json) {
+        (Some(b), Some(j)) => Some(b.min(j)),
+        (b, j) => b.or(j),
+    }
+}
+
+/// NAPI view of [`ManifestFormat`]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[cfg_attr(feature = "napi", napi(object))]
+pub struct ManifestFormatInfo {
+    /// `"binary-v1"`, `"binary-v2+"`, `"json"` or `"unknown"`
+    pub kind: String,
+    pub feature_level: Option<i32>,
+}
+
+impl From<ManifestFormat> for ManifestFormatInfo {
+    fn from(format: ManifestFormat) -> Self {
+        let (kind, feature_level) = match format {
+            ManifestFormat::BinaryV1 => ("binary-v1", None),
+            ManifestFormat::BinaryV2Plus { feature_level } => ("binary-v2+", feature_level),
+            ManifestFormat::Json => ("json", None),
+            ManifestFormat::Unknown => ("unknown", None),
+        };
+        Self {
+            kind: kind.to_string(),
+            feature_level,
+        }
+    }
+}
@@ -0,0 +1,156 @@
+//! Patch-note style summaries of a [`ManifestDiff`].
+//!
+//! The heuristics only look at paths: file extensions give the kind of
+//! content ("shader", "pak") and directories named after a culture code mark
+//! language packs. Every note still carries the counts behind it, so callers
+//! can word things differently.
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+use crate::diff::compare::{ChangeKind, FileChange, ManifestDiff};
+
+/// Culture codes recognised as language pack directories, optionally
+/// followed by a region or script (`pt-BR`, `zh-Hans`, `es-419`).
+const CULTURES: [&str; 25] = [
+    "ar", "cs", "da", "de", "el", "en", "es", "fi", "fr", "he", "hu", "it", "ja", "ko", "nl", "pl", "pt", "ro", "ru",
+    "sv", "th", "tr", "uk", "vi", "zh",
+];
+
+/// Path components kept when grouping changes by directory.
+const GROUP_DEPTH: usize = 3;
+
+/// One bullet point of a change summary.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ChangeNote {
+    pub text: String,
+    /// `None` for notes about directories, such as language packs.
+    pub kind: Option<ChangeKind>,
+    /// Directory the note is about; empty for the install root.
+    pub directory: String,
+    pub files: u32,
+    /// Installed size delta of the files covered.
+    pub size_delta: i64,
+}
+
+impl std::fmt::Display for ChangeNote {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.text)
+    }
+}
+
+impl ManifestDiff {
+    /// Group the changes into patch-note bullets: language packs that were
+    /// added or removed first, then one note per change kind, content type
+    /// and directory, biggest groups first.
+    pub fn summarize_changes(&self) -> Vec<ChangeNote> {
+        let mut notes = Vec::new();
+        for (dirs, verb) in [(&self.added_directories, "New"), (&self.removed_directories, "Removed")] {
+            for dir in dirs {
+                let Some(culture) = dir.rsplit('/').next().filter(|name| is_culture(name)) else {
+                    continue;
+                };
+                let covered: Vec<&FileChange> = self.changes.iter().filter(|c| is_under(&c.path, dir)).collect();
+                notes.push(ChangeNote {
+                    text: format!("{} language pack: {}", verb, culture),
+                    kind: None,
+                    directory: dir.clone(),
+                    files: covered.len() as u32,
+                    size_delta: covered.iter().map(|c| c.size_delta()).sum(),
+                });
+            }
+        }
+
+        let mut groups: BTreeMap<(ChangeKind, &str, String), Vec<&FileChange>> = BTreeMap::new();
+        for change in &self.changes {
+            if notes.iter().any(|n| n.kind.is_none() && is_under(&change.path, &n.directory)) {
+                continue;
+            }
+            let key = (change.kind, content_type(&change.path), group_dir(&change.path));
+            groups.entry(key).or_default().push(change);
+        }
+        let mut groups: Vec<_> = groups.into_iter().collect();
+        groups.sort_by(|a, b| b.1.len().cmp(&a.1.len()).then_with(|| a.0.cmp(&b.0)));
+
+        for ((kind, content, directory), changes) in groups {
+            let verb = match kind {
+                ChangeKind::Added => "added",
+                ChangeKind::Removed => "removed",
+                ChangeKind::Modified => "updated",
+            };
+            let text = match changes.as_slice() {
+                [only] => format!("{} {}", capitalize(verb), only.path),
+                _ => {
+                    let place = if directory.is_empty() { String::new() } else { format!(" in {}", directory) };
+                    format!("{} {} {}{}", changes.len(), plural(content), verb, place)
+                }
+            };
+            notes.push(ChangeNote {
+                text,
+                kind: Some(kind),
+                directory,
+                files: changes.len() as u32,
+                size_delta: changes.iter().map(|c| c.size_delta()).sum(),
+            });
+        }
+        notes
+    }
+}
+
+fn is_under(path: &str, dir: &str) -> bool {
+    path.strip_prefix(dir).is_some_and(|rest| rest.starts_with('/'))
+}
+
+fn is_culture(name: &str) -> bool {
+    let (language, region) = match name.split_once(['-', '_']) {
+        Some((language, region)) => (language, Some(region)),
+        None => (name, None),
+    };
+    CULTURES.contains(&language)
+        && region.is_none_or(|r| (2..=4).contains(&r.len()) && r.chars().all(|c| c.is_ascii_alphanumeric()))
+}
+
+/// The first [`GROUP_DEPTH`] components of the file's directory.
+fn group_dir(path: &str) -> String {
+    let Some((dir, _)) = path.rsplit_once('/') else {
+        return String::new();
+    };
+    dir.split('/').take(GROUP_DEPTH).collect::<Vec<_>>().join("/")
+}
+
+/// What kind of content a file is, judged by its name.
+fn content_type(path: &str) -> &'static str {
+    let name = path.rsplit('/').next().unwrap_or(path).to_ascii_lowercase();
+    let extension = name.rsplit_once('.').map(|(_, ext)| ext).unwrap_or_default();
+    match extension {
+        "ushaderbytecode" | "shadercache" | "upipelinecache" | "stablepc" | "spv" => "shader",
+        "pak" | "ucas" | "utoc" | "sig" => "pak",
+        "exe" | "dll" | "so" | "dylib" => "binary",
+        "pdb" | "debug" | "dsym" => "debug symbol",
+        "locres" | "locmeta" => "localization",
+        "bk2" | "mp4" | "webm" | "bik" => "movie",
+        "bnk" | "wem" | "ogg" | "wav" => "audio",
+        "ini" | "cfg" | "json" | "xml" | "yaml" | "toml" => "config",
+        "uasset" | "umap" | "uexp" | "ubulk" => "asset",
+        "ttf" | "otf" => "font",
+        "txt" | "pdf" | "html" | "md" | "rtf" => "document",
+        _ => "file",
+    }
+}
+
+fn plural(content: &str) -> String {
+    match content {
+        "binary" => "binaries".to_string(),
+        "file" | "asset" | "movie" | "font" | "document" => format!("{}s", content),
+        _ => format!("{} files", content),
+    }
+}
+
+fn capitalize(word: &str) -> String {
+    let mut chars = word.chars();
+    chars
+        .next()
+        .map(|first| first.to_uppercase().chain(chars).collect())
+        .unwrap_or_default()
+}
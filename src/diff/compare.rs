@@ -0,0 +1,173 @@
+//! File-level differences between two builds of an app.
+
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+
+use crate::types::file::FileManifest;
+use crate::types::manifest::Manifest;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ChangeKind {
+    Added,
+    Removed,
+    /// Content, flags or symlink target changed.
+    Modified,
+}
+
+/// One file that differs between the builds. Sizes and hashes are `None`
+/// on the side the file does not exist.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FileChange {
+    pub path: String,
+    pub kind: ChangeKind,
+    pub old_size: Option<u64>,
+    pub new_size: Option<u64>,
+    pub old_sha_hash: Option<String>,
+    pub new_sha_hash: Option<String>,
+}
+
+impl FileChange {
+    /// New size minus old size.
+    pub fn size_delta(&self) -> i64 {
+        self.new_size.unwrap_or_default() as i64 - self.old_size.unwrap_or_default() as i64
+    }
+}
+
+/// What changed between two manifests.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ManifestDiff {
+    pub old_version: String,
+    pub new_version: String,
+    /// Changed files, sorted by path.
+    pub changes: Vec<FileChange>,
+    pub unchanged_files: u32,
+    /// Chunks of the new build that the old one does not have.
+    pub new_chunks: u32,
+    /// Compressed size of `new_chunks`: what updating downloads.
+    pub download_bytes: u64,
+    /// Directories only the new build has, outermost only.
+    #[serde(default)]
+    pub added_directories: Vec<String>,
+    /// Directories only the old build has, outermost only.
+    #[serde(default)]
+    pub removed_directories: Vec<String>,
+}
+
+impl ManifestDiff {
+    /// Compare `old` with `new`, matching files by path.
+    pub fn between(old: &Manifest, new: &Manifest) -> Self {
+        let old_files = by_path(old);
+        let new_files = by_path(new);
+
+        let mut diff = ManifestDiff {
+            old_version: build_version(old),
+            new_version: build_version(new),
+            ..Default::default()
+        };
+        for (&path, &before) in &old_files {
+            match new_files.get(path) {
+                None => diff.changes.push(change(path, ChangeKind::Removed, Some(before), None)),
+                Some(&after) if same_file(before, after) => diff.unchanged_files += 1,
+                Some(&after) => diff.changes.push(change(path, ChangeKind::Modified, Some(before), Some(after))),
+            }
+        }
+        for (&path, &after) in &new_files {
+            if !old_files.contains_key(path) {
+                diff.changes.push(change(path, ChangeKind::Added, None, Some(after)));
+            }
+        }
+        diff.changes.sort_by(|a, b| a.path.cmp(&b.path));
+
+        let old_dirs = directories(old_files.keys());
+        let new_dirs = directories(new_files.keys());
+        diff.added_directories = outermost(new_dirs.difference(&old_dirs));
+        diff.removed_directories = outermost(old_dirs.difference(&new_dirs));
+
+        let old_chunks: HashSet<&str> = chunks(old).map(|c| c.guid.as_str()).collect();
+        for chunk in chunks(new).filter(|c| !old_chunks.contains(c.guid.as_str())) {
+            diff.new_chunks += 1;
+            diff.download_bytes += chunk.file_size.parse::<u64>().unwrap_or_default();
+        }
+        diff
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+
+    pub fn of_kind(&self, kind: ChangeKind) -> impl Iterator<Item = &FileChange> {
+        self.changes.iter().filter(move |c| c.kind == kind)
+    }
+
+    /// Installed size of the new build minus the old one.
+    pub fn size_delta(&self) -> i64 {
+        self.changes.iter().map(FileChange::size_delta).sum()
+    }
+}
+
+fn by_path(manifest: &Manifest) -> BTreeMap<&str, &FileManifest> {
+    manifest
+        .file_list
+        .iter()
+        .flat_map(|f| &f.file_manifest_list)
+        .map(|f| (f.filename.trim_end_matches('\0'), f))
+        .collect()
+}
+
+/// Every ancestor directory of `paths`.
+fn directories<'a>(paths: impl Iterator<Item = &'a &'a str>) -> BTreeSet<&'a str> {
+    let mut dirs = BTreeSet::new();
+    for path in paths {
+        let mut end = path.len();
+        while let Some(slash) = path[..end].rfind('/') {
+            if !dirs.insert(&path[..slash]) {
+                break;
+            }
+            end = slash;
+        }
+    }
+    dirs
+}
+
+/// `dirs` without those whose parent is also listed.
+fn outermost<'a>(dirs: impl Iterator<Item = &'a &'a str>) -> Vec<String> {
+    let dirs: BTreeSet<&str> = dirs.copied().collect();
+    dirs.iter()
+        .filter(|dir| !dir.rsplit_once('/').is_some_and(|(parent, _)| dirs.contains(parent)))
+        .map(|dir| dir.to_string())
+        .collect()
+}
+
+fn chunks(manifest: &Manifest) -> impl Iterator<Item = &crate::types::chunk::Chunk> {
+    manifest.chunk_list.iter().flat_map(|c| &c.elements)
+}
+
+fn build_version(manifest: &Manifest) -> String {
+    manifest
+        .meta
+        .as_ref()
+        .map(|m| m.build_version.trim_end_matches('\0').to_string())
+        .unwrap_or_default()
+}
+
+fn same_file(a: &FileManifest, b: &FileManifest) -> bool {
+    a.sha_hash.eq_ignore_ascii_case(&b.sha_hash)
+        && a.file_size == b.file_size
+        && a.file_meta_flags == b.file_meta_flags
+        && a.symlink_target.trim_end_matches('\0') == b.symlink_target.trim_end_matches('\0')
+}
+
+fn change(path: &str, kind: ChangeKind, old: Option<&FileManifest>, new: Option<&FileManifest>) -> FileChange {
+    let size = |f: &FileManifest| f.file_size.max(0) as u64;
+    FileChange {
+        path: path.to_string(),
+        kind,
+        old_size: old.map(size),
+        new_size: new.map(size),
+        old_sha_hash: old.map(|f| f.sha_hash.clone()),
+        new_sha_hash: new.map(|f| f.sha_hash.clone()),
+    }
+}
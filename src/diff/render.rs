@@ -0,0 +1,148 @@
+//! [`ManifestDiff`] renderers: JSON for tooling, unified-style text for
+//! terminals and commit messages, and a standalone HTML page for changelogs.
+
+use std::io::Write;
+
+use crate::diff::compare::{ChangeKind, FileChange, ManifestDiff};
+use crate::error::ManifestError;
+use crate::pretty::bytes;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum DiffFormat {
+    /// The serialized [`ManifestDiff`], pretty-printed.
+    #[default]
+    Json,
+    /// `---`/`+++` header, then one `+`, `-` or `~` line per changed file.
+    Text,
+    /// A self-contained page with a collapsible section per change kind.
+    Html,
+}
+
+const SECTIONS: [(ChangeKind, &str, char); 3] = [
+    (ChangeKind::Added, "Added", '+'),
+    (ChangeKind::Removed, "Removed", '-'),
+    (ChangeKind::Modified, "Modified", '~'),
+];
+
+impl ManifestDiff {
+    pub fn render(&self, format: DiffFormat, mut out: impl Write) -> Result<(), ManifestError> {
+        match format {
+            DiffFormat::Json => {
+                serde_json::to_writer_pretty(&mut out, self)?;
+                writeln!(out)?;
+            }
+            DiffFormat::Text => self.render_text(&mut out)?,
+            DiffFormat::Html => self.render_html(&mut out)?,
+        }
+        Ok(())
+    }
+
+    pub fn render_string(&self, format: DiffFormat) -> Result<String, ManifestError> {
+        let mut out = Vec::new();
+        self.render(format, &mut out)?;
+        Ok(String::from_utf8_lossy(&out).into_owned())
+    }
+
+    /// "3 added, 1 removed, 2 modified, 120 unchanged; 12.00 MiB to download"
+    pub fn stats_line(&self) -> String {
+        let count = |kind| self.of_kind(kind).count();
+        format!(
+            "{} added, {} removed, {} modified, {} unchanged; {} to download",
+            count(ChangeKind::Added),
+            count(ChangeKind::Removed),
+            count(ChangeKind::Modified),
+            self.unchanged_files,
+            bytes(self.download_bytes)
+        )
+    }
+
+    fn render_text(&self, out: &mut impl Write) -> std::io::Result<()> {
+        writeln!(out, "--- {}", version_label(&self.old_version))?;
+        writeln!(out, "+++ {}", version_label(&self.new_version))?;
+        writeln!(out, "@@ {} @@", self.stats_line())?;
+        for (kind, _, marker) in SECTIONS {
+            for change in self.of_kind(kind) {
+                writeln!(out, "{} {}  ({})", marker, change.path, size_label(change))?;
+            }
+        }
+        Ok(())
+    }
+
+    fn render_html(&self, out: &mut impl Write) -> std::io::Result<()> {
+        let title = format!(
+            "{} &rarr; {}",
+            escape(version_label(&self.old_version)),
+            escape(version_label(&self.new_version))
+        );
+        writeln!(out, "<!DOCTYPE html>")?;
+        writeln!(out, "<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">")?;
+        writeln!(out, "<title>Manifest diff: {}</title>", title)?;
+        writeln!(
+            out,
+            "<style>\
+body{{font-family:system-ui,sans-serif;margin:2rem;color:#1f2328}}\
+summary{{cursor:pointer;font-weight:600;margin:.75rem 0}}\
+table{{border-collapse:collapse;width:100%;font-size:.9rem}}\
+td{{padding:.2rem .6rem;border-bottom:1px solid #d0d7de}}\
+td.size{{text-align:right;white-space:nowrap;font-variant-numeric:tabular-nums}}\
+.added summary{{color:#1a7f37}}.removed summary{{color:#cf222e}}.modified summary{{color:#9a6700}}\
+</style>"
+        )?;
+        writeln!(out, "</head>\n<body>")?;
+        writeln!(out, "<h1>{}</h1>", title)?;
+        writeln!(out, "<p>{}</p>", escape(&self.stats_line()))?;
+        for (kind, name, _) in SECTIONS {
+            let changes: Vec<&FileChange> = self.of_kind(kind).collect();
+            if changes.is_empty() {
+                continue;
+            }
+            writeln!(out, "<details class=\"{}\" open>", name.to_ascii_lowercase())?;
+            writeln!(out, "<summary>{} ({})</summary>\n<table>", name, changes.len())?;
+            for change in changes {
+                writeln!(
+                    out,
+                    "<tr><td>{}</td><td class=\"size\">{}</td></tr>",
+                    escape(&change.path),
+                    escape(&size_label(change))
+                )?;
+            }
+            writeln!(out, "</table>\n</details>")?;
+        }
+        writeln!(out, "</body>\n</html>")
+    }
+}
+
+fn version_label(version: &str) -> &str {
+    if version.is_empty() {
+        "(unknown build)"
+    } else {
+        version
+    }
+}
+
+fn size_label(change: &FileChange) -> String {
+    match (change.old_size, change.new_size) {
+        (Some(old), Some(new)) if old != new => {
+            let delta = change.size_delta();
+            let sign = if delta < 0 { '-' } else { '+' };
+            format!("{} -> {}, {}{}", bytes(old), bytes(new), sign, bytes(delta.unsigned_abs()))
+        }
+        (_, Some(size)) | (Some(size), None) => bytes(size),
+        (None, None) => String::new(),
+    }
+}
+
+fn escape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&#39;"),
+            c => out.push(c),
+        }
+    }
+    out
+}
@@ -0,0 +1,131 @@
+//! Checking which chunks of a build the CDN still serves.
+//!
+//! Builds disappear from Epic's CDN piecemeal: a manifest can outlive some of
+//! its chunks. [`check_cdn_availability`] asks the CDN for every chunk with a
+//! HEAD request instead of downloading it, so preservationists can tell
+//! whether a build is still fully downloadable.
+
+use log::debug;
+use std::time::Duration;
+
+use crate::download::downloader::{cdn_feature_level, Transport};
+use crate::download::retry::{FetchError, RetryPolicy};
+use crate::parallelism::Parallelism;
+use crate::store::availability::ChunkAvailability;
+use crate::types::manifest::Manifest;
+
+/// Requests in flight at once by default.
+pub const DEFAULT_CDN_CHECK_CONCURRENCY: usize = 16;
+
+/// How [`check_cdn_availability`] issues its requests.
+#[derive(Debug, Clone)]
+pub struct CdnCheckOptions {
+    /// Requests in flight at once; defaults to
+    /// [`DEFAULT_CDN_CHECK_CONCURRENCY`] threads.
+    pub parallelism: Parallelism,
+    /// Transient failures are retried per this policy; its
+    /// `alternate_base_urls` are ignored, as the check is about one host.
+    pub retry: RetryPolicy,
+    /// Waits between retries (tests).
+    pub sleep: fn(Duration),
+}
+
+impl Default for CdnCheckOptions {
+    fn default() -> Self {
+        Self {
+            parallelism: Parallelism::with_threads(DEFAULT_CDN_CHECK_CONCURRENCY),
+            retry: RetryPolicy::default(),
+            sleep: std::thread::sleep,
+        }
+    }
+}
+
+/// A chunk the CDN did not confirm.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnavailableChunk {
+    /// Index into `ChunkDataList.elements`.
+    pub index: u32,
+    pub guid: String,
+    pub error: FetchError,
+}
+
+/// Outcome of [`check_cdn_availability`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CdnAvailability {
+    /// The chunks the CDN has; see [`ChunkAvailability::summary`].
+    pub available: ChunkAvailability,
+    /// Chunks answered with a permanent error such as 404 or 403, in chunk
+    /// list order.
+    pub missing: Vec<UnavailableChunk>,
+    /// Chunks still failing transiently after the retries, whose presence is
+    /// unknown.
+    pub unchecked: Vec<UnavailableChunk>,
+}
+
+impl CdnAvailability {
+    /// Whether every chunk was confirmed.
+    pub fn is_complete(&self) -> bool {
+        self.missing.is_empty() && self.unchecked.is_empty()
+    }
+}
+
+/// Send a HEAD request for each chunk of `manifest` under `base_url` (the
+/// build's CloudDir) through `transport`, at most
+/// `options.parallelism` at a time.
+pub fn check_cdn_availability<T: Transport>(
+    transport: &T,
+    manifest: &Manifest,
+    base_url: &str,
+    options: &CdnCheckOptions,
+) -> CdnAvailability {
+    let chunks = manifest
+        .chunk_list
+        .as_ref()
+        .map(|c| c.elements.as_slice())
+        .unwrap_or_default();
+    let feature_level = cdn_feature_level(manifest);
+    let base_url = base_url.trim_end_matches('/');
+
+    let head = |index: usize| {
+        let url = format!("{}/{}", base_url, chunks[index].cdn_path(feature_level));
+        let mut attempt = 0;
+        loop {
+            attempt += 1;
+            let error = match transport.head(&url) {
+                Ok(()) => return Ok(()),
+                Err(error) => error,
+            };
+            if !error.is_transient() || !options.retry.should_retry(&error, attempt, 1) {
+                return Err(error);
+            }
+            debug!("HEAD {} attempt {} failed ({}); retrying", url, attempt, error);
+            (options.sleep)(options.retry.backoff(attempt));
+        }
+    };
+
+    let mut report = CdnAvailability {
+        available: ChunkAvailability::new(chunks.len() as u32),
+        ..CdnAvailability::default()
+    };
+    options.parallelism.run_indexed(chunks.len(), head, |index, result| {
+        match result {
+            Ok(()) => report.available.set(index as u32, true),
+            Err(error) => {
+                let chunk = UnavailableChunk {
+                    index: index as u32,
+                    guid: chunks[index].guid.clone(),
+                    error,
+                };
+                if chunk.error.is_transient() {
+                    report.unchecked.push(chunk);
+                } else {
+                    report.missing.push(chunk);
+                }
+            }
+        }
+        std::ops::ControlFlow::Continue(())
+    });
+    report.missing.sort_by_key(|c| c.index);
+    report.unchecked.sort_by_key(|c| c.index);
+    report
+}
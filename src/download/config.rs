@@ -0,0 +1,130 @@
+//! HTTP settings handed to a [`crate::download::downloader::Transport`].
+//!
+//! The crate does not make HTTP requests itself, so these are plain data:
+//! transports backed by an HTTP client apply them in
+//! [`Transport::configure`](crate::download::downloader::Transport::configure),
+//! other providers (local mirrors, test doubles) ignore them.
+
+use std::path::Path;
+
+/// `User-Agent` sent when none is configured.
+pub const DEFAULT_USER_AGENT: &str = concat!("egdata-manifests-parser/", env!("CARGO_PKG_VERSION"));
+
+/// Connection settings for chunk downloads.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TransportConfig {
+    pub user_agent: String,
+    pub proxy: Option<ProxyConfig>,
+    /// Extra trust anchors, e.g. a corporate MITM proxy's CA, trusted in
+    /// addition to the system roots.
+    pub root_certificates: Vec<RootCertificate>,
+}
+
+impl Default for TransportConfig {
+    fn default() -> Self {
+        Self {
+            user_agent: DEFAULT_USER_AGENT.to_string(),
+            proxy: None,
+            root_certificates: Vec::new(),
+        }
+    }
+}
+
+impl TransportConfig {
+    /// Defaults plus the proxy from the environment, if any.
+    pub fn from_env() -> Self {
+        Self {
+            proxy: ProxyConfig::from_env(),
+            ..Default::default()
+        }
+    }
+
+    /// The proxy to use for `url`, honouring the proxy's bypass list.
+    pub fn proxy_for(&self, url: &str) -> Option<&ProxyConfig> {
+        self.proxy.as_ref().filter(|proxy| !proxy.bypasses(url))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProxyConfig {
+    /// Proxy URL, e.g. `http://proxy.corp:3128` or `socks5://127.0.0.1:1080`.
+    pub url: String,
+    pub username: Option<String>,
+    pub password: Option<String>,
+    /// Hosts reached directly: exact names, `.suffix` domain matches, or `*`
+    /// for everything (the `NO_PROXY` conventions).
+    pub no_proxy: Vec<String>,
+}
+
+impl ProxyConfig {
+    pub fn new(url: impl Into<String>) -> Self {
+        Self {
+            url: url.into(),
+            username: None,
+            password: None,
+            no_proxy: Vec::new(),
+        }
+    }
+
+    /// Read `HTTPS_PROXY`, then `ALL_PROXY`, then `HTTP_PROXY` (either case)
+    /// and `NO_PROXY`.
+    pub fn from_env() -> Option<Self> {
+        let var = |name: &str| {
+            std::env::var(name)
+                .or_else(|_| std::env::var(name.to_ascii_lowercase()))
+                .ok()
+                .filter(|value| !value.trim().is_empty())
+        };
+        let url = var("HTTPS_PROXY").or_else(|| var("ALL_PROXY")).or_else(|| var("HTTP_PROXY"))?;
+        let no_proxy = var("NO_PROXY")
+            .map(|list| list.split(',').map(|h| h.trim().to_string()).filter(|h| !h.is_empty()).collect())
+            .unwrap_or_default();
+        Some(Self {
+            no_proxy,
+            ..Self::new(url.trim())
+        })
+    }
+
+    /// Whether `url` should skip the proxy.
+    pub fn bypasses(&self, url: &str) -> bool {
+        let host = host_of(url);
+        self.no_proxy.iter().any(|entry| {
+            let entry = entry.trim_start_matches('*').to_ascii_lowercase();
+            if entry.is_empty() {
+                return true;
+            }
+            let domain = entry.trim_start_matches('.');
+            host == domain || host.ends_with(&format!(".{}", domain))
+        })
+    }
+}
+
+/// Lower-cased host of `url`, without scheme, credentials or port.
+fn host_of(url: &str) -> String {
+    let rest = url.split_once("://").map_or(url, |(_, rest)| rest);
+    let authority = rest.split(['/', '?', '#']).next().unwrap_or_default();
+    let host = authority.rsplit_once('@').map_or(authority, |(_, host)| host);
+    let host = match host.strip_prefix('[') {
+        Some(v6) => v6.split(']').next().unwrap_or_default(),
+        None => host.split(':').next().unwrap_or_default(),
+    };
+    host.to_ascii_lowercase()
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RootCertificate {
+    Pem(Vec<u8>),
+    Der(Vec<u8>),
+}
+
+impl RootCertificate {
+    /// Load a certificate file, PEM if it looks like PEM and DER otherwise.
+    pub fn from_file(path: impl AsRef<Path>) -> std::io::Result<Self> {
+        let bytes = std::fs::read(path)?;
+        Ok(if bytes.windows(11).any(|w| w == b"-----BEGIN ") {
+            RootCertificate::Pem(bytes)
+        } else {
+            RootCertificate::Der(bytes)
+        })
+    }
+}
@@ -0,0 +1,223 @@
+//! Chunk downloader: URL building, retries and verification on top of a
+//! pluggable [`Transport`].
+//!
+//! The crate has no HTTP client of its own; wrap whichever one the
+//! application already uses (reqwest, ureq, curl, ...) in a [`Transport`].
+
+use log::{debug, warn};
+use sha1::{Digest, Sha1};
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::download::config::TransportConfig;
+use crate::download::mirrors::MirrorSet;
+use crate::download::retry::{FetchError, RetryPolicy};
+use crate::crypto::AesKey;
+use crate::error::ManifestError;
+use crate::provider::ChunkProvider;
+use crate::types::chunk::Chunk;
+use crate::types::chunk_file::decode_chunk_file_with_key;
+use crate::types::manifest::Manifest;
+
+/// Fetches a URL. Implementations classify their failures with
+/// [`FetchError::Transient`] or [`FetchError::Permanent`]
+/// ([`FetchError::from_status`] helps for HTTP).
+pub trait Transport: Send + Sync {
+    fn get(&self, url: &str) -> Result<Vec<u8>, FetchError>;
+
+    /// Check that `url` exists without fetching it. HTTP transports should
+    /// send a HEAD request; the default downloads the body and drops it.
+    fn head(&self, url: &str) -> Result<(), FetchError> {
+        self.get(url).map(drop)
+    }
+
+    /// Apply proxy, TLS and user-agent settings. HTTP transports should
+    /// honour them; the default ignores them, which suits non-HTTP providers.
+    fn configure(&mut self, config: &TransportConfig) -> Result<(), FetchError> {
+        let _ = config;
+        Ok(())
+    }
+}
+
+impl<F> Transport for F
+where
+    F: Fn(&str) -> Result<Vec<u8>, FetchError> + Send + Sync,
+{
+    fn get(&self, url: &str) -> Result<Vec<u8>, FetchError> {
+        self(url)
+    }
+}
+
+/// The feature level deciding `manifest`'s chunk paths: the meta's, or the
+/// header version for manifests without one.
+pub(crate) fn cdn_feature_level(manifest: &Manifest) -> i32 {
+    manifest
+        .meta
+        .as_ref()
+        .map(|m| m.feature_level)
+        .filter(|&level| level > 0)
+        .unwrap_or(manifest.header.version)
+}
+
+/// Downloads and verifies the chunks of one manifest.
+pub struct ChunkDownloader<T> {
+    transport: T,
+    mirrors: Arc<MirrorSet>,
+    policy: RetryPolicy,
+    manifest: Manifest,
+    feature_level: i32,
+    sleep: fn(Duration),
+    key: Option<AesKey>,
+}
+
+impl<T: Transport> ChunkDownloader<T> {
+    /// `base_url` is the build's CloudDir, the directory holding
+    /// `ChunksV4/...`.
+    pub fn new(transport: T, base_url: impl Into<String>, manifest: Manifest) -> Self {
+        let feature_level = cdn_feature_level(&manifest);
+        Self {
+            transport,
+            mirrors: Arc::new(MirrorSet::new([base_url.into()])),
+            policy: RetryPolicy::default(),
+            manifest,
+            feature_level,
+            sleep: std::thread::sleep,
+            key: None,
+        }
+    }
+
+    /// Set the retry policy; its `alternate_base_urls` join the mirror set
+    /// after the existing hosts.
+    pub fn with_retry_policy(mut self, policy: RetryPolicy) -> Self {
+        for url in &policy.alternate_base_urls {
+            self.mirrors.add(url);
+        }
+        self.policy = policy;
+        self
+    }
+
+    /// Fetch from `mirrors` instead of the base URL given to
+    /// [`ChunkDownloader::new`]. Sharing one set between downloaders pools
+    /// their health tracking.
+    pub fn with_mirrors(mut self, mirrors: Arc<MirrorSet>) -> Self {
+        for url in &self.policy.alternate_base_urls {
+            mirrors.add(url);
+        }
+        self.mirrors = mirrors;
+        self
+    }
+
+    pub fn transport(&self) -> &T {
+        &self.transport
+    }
+
+    pub fn mirrors(&self) -> &Arc<MirrorSet> {
+        &self.mirrors
+    }
+
+    /// Pass connection settings to the transport.
+    pub fn with_transport_config(mut self, config: &TransportConfig) -> Result<Self, FetchError> {
+        self.transport.configure(config)?;
+        Ok(self)
+    }
+
+    /// Decrypt chunks flagged encrypted with `key`.
+    pub fn with_key(mut self, key: AesKey) -> Self {
+        self.key = Some(key);
+        self
+    }
+
+    /// Replace the function used to wait between retries (tests).
+    pub fn with_sleep(mut self, sleep: fn(Duration)) -> Self {
+        self.sleep = sleep;
+        self
+    }
+
+    pub fn manifest(&self) -> &Manifest {
+        &self.manifest
+    }
+
+    fn chunk(&self, guid: &str) -> Result<&Chunk, ManifestError> {
+        let chunk_list = self.manifest.chunk_list.as_ref();
+        chunk_list
+            .and_then(|c| c.chunk_lookup.get(guid).and_then(|&i| c.elements.get(i as usize)))
+            .ok_or_else(|| ManifestError::Invalid(format!("chunk {} not in manifest", guid)))
+    }
+
+    /// Decode a downloaded chunk file and check it against the manifest's
+    /// SHA-1 for it, or the chunk header's when the manifest has none.
+    fn verify(
+        chunk: &Chunk,
+        stored_sha: Option<&str>,
+        key: Option<&AesKey>,
+        url: &str,
+        file: &[u8],
+    ) -> Result<Vec<u8>, FetchError> {
+        let corrupt = |message: String| FetchError::Corrupt {
+            guid: chunk.guid.clone(),
+            url: url.to_string(),
+            message,
+        };
+        let (header, data) = decode_chunk_file_with_key(file, key).map_err(|e| corrupt(e.to_string()))?;
+        let expected = stored_sha.or(header.sha_hash.as_deref());
+        if let Some(expected) = expected {
+            let actual = hex::encode(Sha1::digest(&data));
+            if !actual.eq_ignore_ascii_case(expected) {
+                return Err(corrupt(format!("SHA-1 {} does not match {}", actual, expected)));
+            }
+        }
+        Ok(data)
+    }
+
+    /// Fetch, decode and verify chunk `guid`, retrying per the policy.
+    /// Returns the uncompressed chunk data.
+    pub fn fetch(&self, guid: &str) -> Result<Vec<u8>, FetchError> {
+        let chunk = self.chunk(guid).map_err(|e| FetchError::Permanent {
+            url: String::new(),
+            message: e.to_string(),
+        })?;
+        let path = chunk.cdn_path(self.feature_level);
+        let stored_sha = self.manifest.chunk_list.as_ref().and_then(|list| list.stored_sha_hash(chunk));
+        // Ranked once per chunk so one fetch does not chase its own failures.
+        let hosts = self.mirrors.ranked();
+        if hosts.is_empty() {
+            return Err(FetchError::Permanent {
+                url: path,
+                message: "no CDN base URL configured".to_string(),
+            });
+        }
+
+        let mut attempt = 0;
+        loop {
+            let host = &hosts[attempt as usize % hosts.len()];
+            let url = format!("{}/{}", host, path);
+            attempt += 1;
+
+            let result = self
+                .transport
+                .get(&url)
+                .and_then(|file| Self::verify(chunk, stored_sha, self.key.as_ref(), &url, &file));
+            let error = match result {
+                Ok(data) => {
+                    self.mirrors.record_success(host);
+                    return Ok(data);
+                }
+                Err(error) => error,
+            };
+            self.mirrors.record_failure(host, &error);
+            if !self.policy.should_retry(&error, attempt, hosts.len()) {
+                warn!("Giving up on chunk {} after {} attempts: {}", guid, attempt, error);
+                return Err(error);
+            }
+            let delay = self.policy.backoff(attempt);
+            debug!("Chunk {} attempt {} failed ({}); retrying in {:?}", guid, attempt, error, delay);
+            (self.sleep)(delay);
+        }
+    }
+}
+
+impl<T: Transport> ChunkProvider for ChunkDownloader<T> {
+    fn chunk_data(&self, guid: &str) -> Result<Vec<u8>, ManifestError> {
+        Ok(self.fetch(guid)?)
+    }
+}
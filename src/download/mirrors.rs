@@ -0,0 +1,176 @@
+//! CDN base URLs with health tracking.
+//!
+//! Epic serves chunks from several hosts and community mirrors add their
+//! own. A [`MirrorSet`] ranks them by recent behaviour so the downloader
+//! tries healthy hosts first and rests ones that keep failing.
+
+use serde::Serialize;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use crate::download::retry::FetchError;
+
+/// Custom field holding a manifest's comma-separated CDN base URLs.
+pub const BASE_URL_FIELD: &str = "BaseUrl";
+
+/// Consecutive failures before a mirror is rested.
+const DEFAULT_FAILURE_THRESHOLD: u32 = 3;
+const DEFAULT_COOLDOWN: Duration = Duration::from_secs(30);
+
+#[derive(Debug)]
+struct Mirror {
+    url: String,
+    successes: u64,
+    failures: u64,
+    consecutive_failures: u32,
+    resting_until: Option<Instant>,
+}
+
+/// Health of one mirror, as reported by [`MirrorSet::health`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MirrorHealth {
+    pub url: String,
+    pub successes: u64,
+    pub failures: u64,
+    pub consecutive_failures: u32,
+    /// Whether the mirror is currently rested after repeated failures.
+    pub resting: bool,
+}
+
+/// An ordered, shareable set of CDN base URLs.
+///
+/// Earlier URLs are preferred while healthy. Only transient failures and
+/// corrupt chunks count against a mirror: a 404 says the chunk is missing,
+/// not that the host is down.
+#[derive(Debug)]
+pub struct MirrorSet {
+    mirrors: Mutex<Vec<Mirror>>,
+    failure_threshold: u32,
+    cooldown: Duration,
+}
+
+impl Default for MirrorSet {
+    fn default() -> Self {
+        Self {
+            mirrors: Mutex::new(Vec::new()),
+            failure_threshold: DEFAULT_FAILURE_THRESHOLD,
+            cooldown: DEFAULT_COOLDOWN,
+        }
+    }
+}
+
+impl MirrorSet {
+    pub fn new<I, S>(urls: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        let set = Self::default();
+        for url in urls {
+            set.add(url.as_ref());
+        }
+        set
+    }
+
+    /// Parse the value of a manifest's `BaseUrl` custom field: base URLs
+    /// separated by commas.
+    pub fn from_base_url_field(value: &str) -> Self {
+        Self::new(value.split(','))
+    }
+
+    /// Rest a mirror for `cooldown` after `failure_threshold` consecutive
+    /// failures.
+    pub fn with_cooldown(mut self, failure_threshold: u32, cooldown: Duration) -> Self {
+        self.failure_threshold = failure_threshold.max(1);
+        self.cooldown = cooldown;
+        self
+    }
+
+    /// Append a base URL with the lowest preference. Blank and duplicate URLs
+    /// (ignoring a trailing slash) are skipped.
+    pub fn add(&self, url: &str) {
+        let url = url.trim().trim_end_matches('/');
+        if url.is_empty() {
+            return;
+        }
+        let mut mirrors = self.mirrors.lock().unwrap();
+        if !mirrors.iter().any(|m| m.url == url) {
+            mirrors.push(Mirror {
+                url: url.to_string(),
+                successes: 0,
+                failures: 0,
+                consecutive_failures: 0,
+                resting_until: None,
+            });
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.mirrors.lock().unwrap().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Base URLs in the order they should be tried: mirrors that are not
+    /// resting first, fewest consecutive failures first, then configured
+    /// order. Resting mirrors come last rather than being dropped, so there
+    /// is always somewhere to try.
+    pub fn ranked(&self) -> Vec<String> {
+        let now = Instant::now();
+        let mirrors = self.mirrors.lock().unwrap();
+        let mut order: Vec<(usize, &Mirror)> = mirrors.iter().enumerate().collect();
+        order.sort_by_key(|&(index, m)| {
+            let resting = m.resting_until.filter(|&until| until > now);
+            (resting.is_some(), resting, m.consecutive_failures, index)
+        });
+        order.into_iter().map(|(_, m)| m.url.clone()).collect()
+    }
+
+    pub fn record_success(&self, url: &str) {
+        self.update(url, |m, _| {
+            m.successes += 1;
+            m.consecutive_failures = 0;
+            m.resting_until = None;
+        });
+    }
+
+    pub fn record_failure(&self, url: &str, error: &FetchError) {
+        if let FetchError::Permanent { .. } = error {
+            return;
+        }
+        let (threshold, cooldown) = (self.failure_threshold, self.cooldown);
+        self.update(url, |m, now| {
+            m.failures += 1;
+            m.consecutive_failures += 1;
+            if m.consecutive_failures >= threshold {
+                m.resting_until = Some(now + cooldown);
+            }
+        });
+    }
+
+    pub fn health(&self) -> Vec<MirrorHealth> {
+        let now = Instant::now();
+        let mirrors = self.mirrors.lock().unwrap();
+        mirrors
+            .iter()
+            .map(|m| MirrorHealth {
+                url: m.url.clone(),
+                successes: m.successes,
+                failures: m.failures,
+                consecutive_failures: m.consecutive_failures,
+                resting: m.resting_until.is_some_and(|until| until > now),
+            })
+            .collect()
+    }
+
+    fn update(&self, url: &str, f: impl FnOnce(&mut Mirror, Instant)) {
+        let url = url.trim_end_matches('/');
+        let mut mirrors = self.mirrors.lock().unwrap();
+        if let Some(mirror) = mirrors.iter_mut().find(|m| m.url == url) {
+            f(mirror, Instant::now());
+        }
+    }
+}
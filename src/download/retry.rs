@@ -0,0 +1,104 @@
+//! Retry policy and error classification for chunk fetching.
+
+use std::time::Duration;
+
+/// Why fetching a chunk failed, classified by whether trying again can help.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum FetchError {
+    /// Network trouble or a server-side error (timeouts, resets, 5xx, 429):
+    /// worth retrying, possibly against another host.
+    #[error("transient failure fetching {url}: {message}")]
+    Transient { url: String, message: String },
+    /// The request itself is wrong (404, 403, malformed URL): retrying the
+    /// same URL will not help.
+    #[error("permanent failure fetching {url}: {message}")]
+    Permanent { url: String, message: String },
+    /// The chunk arrived but failed to decode or verify.
+    #[error("corrupt chunk {guid} from {url}: {message}")]
+    Corrupt { guid: String, url: String, message: String },
+}
+
+impl FetchError {
+    /// Classify an HTTP status for transports that only have a status code.
+    pub fn from_status(url: &str, status: u16) -> Self {
+        let message = format!("HTTP {}", status);
+        let url = url.to_string();
+        match status {
+            408 | 425 | 429 | 500..=599 => FetchError::Transient { url, message },
+            _ => FetchError::Permanent { url, message },
+        }
+    }
+
+    pub fn is_transient(&self) -> bool {
+        matches!(self, FetchError::Transient { .. })
+    }
+
+    pub fn is_corrupt(&self) -> bool {
+        matches!(self, FetchError::Corrupt { .. })
+    }
+}
+
+/// How the downloader retries a chunk.
+///
+/// Attempts cycle through the primary base URL and then
+/// `alternate_base_urls`, so a host that keeps failing does not use up every
+/// attempt. Permanent failures move straight on to the next host.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RetryPolicy {
+    /// Attempts per chunk, across all hosts; at least 1.
+    pub max_attempts: u32,
+    /// Delay before the first retry.
+    pub initial_backoff: Duration,
+    /// Growth factor applied to the delay after every retry.
+    pub backoff_multiplier: f64,
+    pub max_backoff: Duration,
+    /// Hosts to fall back to, e.g. `https://download.epicgames.com/Builds/Org/...`.
+    /// Added to the downloader's [`crate::download::mirrors::MirrorSet`].
+    pub alternate_base_urls: Vec<String>,
+    /// Download a chunk again when it fails verification instead of failing
+    /// straight away.
+    pub reverify_on_corruption: bool,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 5,
+            initial_backoff: Duration::from_millis(250),
+            backoff_multiplier: 2.0,
+            max_backoff: Duration::from_secs(10),
+            alternate_base_urls: Vec::new(),
+            reverify_on_corruption: true,
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// No retries at all.
+    pub fn none() -> Self {
+        Self {
+            max_attempts: 1,
+            reverify_on_corruption: false,
+            ..Default::default()
+        }
+    }
+
+    /// Delay before retry number `retry` (1-based).
+    pub fn backoff(&self, retry: u32) -> Duration {
+        let factor = self.backoff_multiplier.max(1.0).powi(retry.saturating_sub(1) as i32);
+        self.initial_backoff.mul_f64(factor).min(self.max_backoff)
+    }
+
+    /// Whether a failed attempt should be followed by another.
+    pub fn should_retry(&self, error: &FetchError, attempt: u32, hosts: usize) -> bool {
+        if attempt >= self.max_attempts.max(1) {
+            return false;
+        }
+        match error {
+            FetchError::Transient { .. } => true,
+            FetchError::Corrupt { .. } => self.reverify_on_corruption,
+            // Another host may still have it.
+            FetchError::Permanent { .. } => (attempt as usize) < hosts,
+        }
+    }
+}
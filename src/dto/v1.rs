@@ -0,0 +1,634 @@
+//! Version 1 of the manifest as handed to JavaScript and serialized for
+//! storage.
+//!
+//! These types are the stable contract: within a major version of the
+//! crate, fields are only ever added (as `Option`s, so older JSON still
+//! deserializes), never renamed, retyped or removed, and the JSON they
+//! serialize to keeps its shape. The parser's own types in
+//! [`crate::types`] carry no such promise and may change in any release;
+//! convert with `From` at the boundary. Strings here never carry the NUL
+//! padding UE stores, whatever [`crate::StringPolicy`] they were parsed
+//! with.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+#[cfg(feature = "napi")]
+use napi_derive::napi;
+
+use crate::types;
+
+fn trimmed(mut value: String) -> String {
+    types::meta::trim_nul(&mut value);
+    value
+}
+
+/// Whole manifest.
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq)]
+#[cfg_attr(feature = "napi", napi(object))]
+pub struct Manifest {
+    pub header: ManifestHeader,
+    pub meta: Option<ManifestMeta>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub chunk_list: Option<ChunkDataList>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub file_list: Option<FileManifestList>,
+    #[serde(default, skip_serializing_if = "CustomFields::is_empty")]
+    pub custom_fields: CustomFields,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "napi", napi(object))]
+pub struct ManifestHeader {
+    pub header_size: i32,
+    pub data_size_uncompressed: i32,
+    pub data_size_compressed: i32,
+    /// Hex
+    pub sha1_hash: String,
+    pub stored_as: u8,
+    pub version: i32,
+    pub guid: String,
+    pub rolling_hash: i64,
+    pub hash_type: u32,
+    /// Fields holding placeholders rather than stored data, by their
+    /// serialized names, e.g. `sha1_hash` for manifests converted from JSON
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub synthetic: Option<Vec<String>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "napi", napi(object))]
+pub struct ManifestMeta {
+    pub data_size: u32,
+    pub data_version: u8,
+    pub feature_level: i32,
+    pub is_file_data: bool,
+    pub app_id: i32,
+    pub app_name: String,
+    pub build_version: String,
+    pub launch_exe: String,
+    pub launch_command: String,
+    pub prereq_ids: Vec<String>,
+    pub prereq_name: String,
+    pub prereq_path: String,
+    pub prereq_args: String,
+    pub build_id: Option<String>,
+    /// Hex of bytes past the fields the parser knows
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub extra_data: Option<String>,
+    /// Fields holding placeholders rather than stored data, by their
+    /// serialized names, e.g. `feature_level` for manifests converted from JSON
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub synthetic: Option<Vec<String>>,
+}
+
+/// The meta fields list views show, without prerequisites and launch
+/// commands.
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "napi", napi(object))]
+pub struct MetaSummary {
+    pub app_name: String,
+    pub build_version: String,
+    /// Stored build id, or the computed one when the manifest has none
+    pub build_id: String,
+    pub feature_level: i32,
+    pub launch_exe: String,
+    /// `"Windows"` or `"Mac"`, inferred from `launch_exe`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub platform: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "napi", napi(object))]
+pub struct ChunkDataList {
+    pub data_size: u32,
+    pub data_version: u8,
+    pub count: u32,
+    pub elements: Vec<Chunk>,
+    /// Index into `elements` by GUID. Not serialized, and rebuilt from
+    /// `elements` when converted back.
+    #[serde(skip)]
+    pub chunk_lookup: HashMap<String, u32>,
+    /// Hex of bytes past the fields the parser knows
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub extra_data: Option<String>,
+    /// Fields of `elements` holding placeholders rather than stored data,
+    /// by their serialized names, e.g. `window_size` for manifests
+    /// converted from JSON
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub synthetic: Option<Vec<String>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "napi", napi(object))]
+pub struct Chunk {
+    pub guid: String,
+    /// Rolling hash, 16 hex digits
+    pub hash: String,
+    /// Hex
+    pub sha_hash: String,
+    pub group: u8,
+    pub window_size: u32,
+    /// Decimal, as JS numbers can't hold every `u64`
+    pub file_size: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "napi", napi(object))]
+pub struct ChunkPart {
+    pub data_size: u32,
+    pub parent_guid: String,
+    pub offset: u32,
+    pub size: u32,
+    /// Hex of bytes past the fields the parser knows
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub extra_data: Option<String>,
+    /// The parent chunk. Not serialized.
+    #[serde(skip)]
+    pub chunk: Option<Chunk>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "napi", napi(object))]
+pub struct FileManifestList {
+    pub data_size: u32,
+    pub data_version: u8,
+    pub count: u32,
+    pub file_manifest_list: Vec<FileManifest>,
+    /// Hex of bytes past the fields the parser knows
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub extra_data: Option<String>,
+    /// Fields of `file_manifest_list` holding placeholders rather than
+    /// stored data, by their serialized names
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub synthetic: Option<Vec<String>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "napi", napi(object))]
+pub struct FileManifest {
+    pub filename: String,
+    pub symlink_target: String,
+    /// Hex
+    pub sha_hash: String,
+    pub file_meta_flags: u8,
+    pub install_tags: Vec<String>,
+    pub chunk_parts: Vec<ChunkPart>,
+    pub file_size: i64,
+    /// Chunk parts of the file were lost, so `file_size` is a lower bound
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub size_unknown: Option<bool>,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub mime_type: String,
+    /// `file_meta_flags` decoded, when asked for
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub flags: Option<FileFlags>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "napi", napi(object))]
+pub struct FileFlags {
+    pub readonly: bool,
+    pub compressed: bool,
+    pub unix_executable: bool,
+}
+
+/// Custom fields in stored order. Serializes as a JSON object, repeating
+/// duplicate keys.
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq, Eq)]
+#[serde(from = "types::custom_fields::CustomFields", into = "types::custom_fields::CustomFields")]
+#[cfg_attr(feature = "napi", napi(object))]
+pub struct CustomFields {
+    pub entries: Vec<CustomField>,
+}
+
+impl CustomFields {
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "napi", napi(object))]
+pub struct CustomField {
+    pub key: String,
+    pub value: String,
+}
+
+/// A JSON manifest as stored, before conversion: UE blob strings and GUIDs
+/// are left as written.
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq)]
+#[cfg_attr(feature = "napi", napi(object))]
+pub struct JsonManifest {
+    pub manifest_file_version: String,
+    pub is_file_data: bool,
+    pub app_id: String,
+    pub app_name_string: String,
+    pub build_version_string: String,
+    pub launch_exe_string: String,
+    pub launch_command: String,
+    pub prereq_ids: Vec<String>,
+    pub prereq_name: String,
+    pub prereq_path: String,
+    pub prereq_args: String,
+    pub file_manifest_list: Vec<JsonFileManifest>,
+    pub custom_fields: CustomFields,
+    /// Blob strings by GUID
+    pub chunk_hash_list: HashMap<String, String>,
+    /// Hex by GUID
+    pub chunk_sha_list: HashMap<String, String>,
+    /// Blob strings by GUID
+    pub chunk_filesize_list: HashMap<String, String>,
+    /// Top-level fields the parser doesn't model, such as `DataGroupList`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub extra: Option<serde_json::Map<String, serde_json::Value>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq)]
+#[cfg_attr(feature = "napi", napi(object))]
+pub struct JsonFileManifest {
+    pub filename: String,
+    /// Blob string
+    pub file_hash: String,
+    pub is_unix_executable: Option<bool>,
+    pub file_chunk_parts: Vec<JsonFileChunkPart>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub extra: Option<serde_json::Map<String, serde_json::Value>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq)]
+#[cfg_attr(feature = "napi", napi(object))]
+pub struct JsonFileChunkPart {
+    pub guid: String,
+    pub offset: String,
+    pub size: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub extra: Option<serde_json::Map<String, serde_json::Value>>,
+}
+
+fn extra(map: serde_json::Map<String, serde_json::Value>) -> Option<serde_json::Map<String, serde_json::Value>> {
+    (!map.is_empty()).then_some(map)
+}
+
+// ------------------------------------------------------------ from parser
+
+impl From<types::manifest::Manifest> for Manifest {
+    fn from(manifest: types::manifest::Manifest) -> Self {
+        Self {
+            header: manifest.header.into(),
+            meta: manifest.meta.map(Into::into),
+            chunk_list: manifest.chunk_list.map(Into::into),
+            file_list: manifest.file_list.map(Into::into),
+            custom_fields: manifest.custom_fields.into(),
+        }
+    }
+}
+
+impl From<&types::manifest::Manifest> for Manifest {
+    fn from(manifest: &types::manifest::Manifest) -> Self {
+        manifest.clone().into()
+    }
+}
+
+impl From<types::header::ManifestHeader> for ManifestHeader {
+    fn from(header: types::header::ManifestHeader) -> Self {
+        Self {
+            header_size: header.header_size,
+            data_size_uncompressed: header.data_size_uncompressed,
+            data_size_compressed: header.data_size_compressed,
+            sha1_hash: header.sha1_hash,
+            stored_as: header.stored_as,
+            version: header.version,
+            guid: header.guid,
+            rolling_hash: header.rolling_hash,
+            hash_type: header.hash_type,
+            synthetic: (!header.synthetic.is_empty()).then_some(header.synthetic),
+        }
+    }
+}
+
+impl From<types::meta::ManifestMeta> for ManifestMeta {
+    fn from(meta: types::meta::ManifestMeta) -> Self {
+        Self {
+            data_size: meta.data_size,
+            data_version: meta.data_version,
+            feature_level: meta.feature_level,
+            is_file_data: meta.is_file_data,
+            app_id: meta.app_id,
+            app_name: trimmed(meta.app_name),
+            build_version: trimmed(meta.build_version),
+            launch_exe: trimmed(meta.launch_exe),
+            launch_command: trimmed(meta.launch_command),
+            prereq_ids: meta.prereq_ids.into_iter().map(trimmed).collect(),
+            prereq_name: trimmed(meta.prereq_name),
+            prereq_path: trimmed(meta.prereq_path),
+            prereq_args: trimmed(meta.prereq_args),
+            build_id: meta.build_id,
+            extra_data: meta.extra_data,
+            synthetic: (!meta.synthetic.is_empty()).then_some(meta.synthetic),
+        }
+    }
+}
+
+impl From<&types::meta::ManifestMeta> for MetaSummary {
+    fn from(meta: &types::meta::ManifestMeta) -> Self {
+        Self {
+            app_name: trimmed(meta.app_name.clone()),
+            build_version: trimmed(meta.build_version.clone()),
+            build_id: meta.build_id(),
+            feature_level: meta.feature_level,
+            launch_exe: trimmed(meta.launch_exe.clone()),
+            platform: meta.platform().map(str::to_string),
+        }
+    }
+}
+
+impl From<types::chunk::ChunkDataList> for ChunkDataList {
+    fn from(list: types::chunk::ChunkDataList) -> Self {
+        Self {
+            data_size: list.data_size,
+            data_version: list.data_version,
+            count: list.count,
+            elements: list.elements.into_iter().map(Into::into).collect(),
+            chunk_lookup: list.chunk_lookup,
+            extra_data: list.extra_data,
+            synthetic: (!list.synthetic.is_empty()).then_some(list.synthetic),
+        }
+    }
+}
+
+impl From<types::chunk::Chunk> for Chunk {
+    fn from(chunk: types::chunk::Chunk) -> Self {
+        Self {
+            guid: chunk.guid,
+            hash: chunk.hash,
+            sha_hash: chunk.sha_hash,
+            group: chunk.group,
+            window_size: chunk.window_size,
+            file_size: chunk.file_size,
+        }
+    }
+}
+
+impl From<types::chunk::ChunkPart> for ChunkPart {
+    fn from(part: types::chunk::ChunkPart) -> Self {
+        Self {
+            data_size: part.data_size,
+            parent_guid: part.parent_guid,
+            offset: part.offset,
+            size: part.size,
+            extra_data: part.extra_data,
+            chunk: part.chunk.map(Into::into),
+        }
+    }
+}
+
+impl From<types::file::FileManifestList> for FileManifestList {
+    fn from(list: types::file::FileManifestList) -> Self {
+        Self {
+            data_size: list.data_size,
+            data_version: list.data_version,
+            count: list.count,
+            file_manifest_list: list.file_manifest_list.into_iter().map(Into::into).collect(),
+            extra_data: list.extra_data,
+            synthetic: (!list.synthetic.is_empty()).then_some(list.synthetic),
+        }
+    }
+}
+
+impl From<types::file::FileManifest> for FileManifest {
+    fn from(file: types::file::FileManifest) -> Self {
+        Self {
+            filename: trimmed(file.filename),
+            symlink_target: file.symlink_target,
+            sha_hash: file.sha_hash,
+            file_meta_flags: file.file_meta_flags,
+            install_tags: file.install_tags.into_iter().map(trimmed).collect(),
+            chunk_parts: file.chunk_parts.into_iter().map(Into::into).collect(),
+            file_size: file.file_size,
+            size_unknown: file.size_unknown.then_some(true),
+            mime_type: file.mime_type,
+            flags: file.flags.map(Into::into),
+        }
+    }
+}
+
+impl From<types::file::FileFlags> for FileFlags {
+    fn from(flags: types::file::FileFlags) -> Self {
+        Self {
+            readonly: flags.readonly,
+            compressed: flags.compressed,
+            unix_executable: flags.unix_executable,
+        }
+    }
+}
+
+impl From<types::custom_fields::CustomFields> for CustomFields {
+    fn from(fields: types::custom_fields::CustomFields) -> Self {
+        Self {
+            entries: fields
+                .entries
+                .into_iter()
+                .map(|entry| CustomField {
+                    key: trimmed(entry.key),
+                    value: trimmed(entry.value),
+                })
+                .collect(),
+        }
+    }
+}
+
+impl From<types::json_manifest::JsonManifest> for JsonManifest {
+    fn from(manifest: types::json_manifest::JsonManifest) -> Self {
+        Self {
+            manifest_file_version: manifest.manifest_file_version,
+            is_file_data: manifest.is_file_data,
+            app_id: manifest.app_id,
+            app_name_string: manifest.app_name_string,
+            build_version_string: manifest.build_version_string,
+            launch_exe_string: manifest.launch_exe_string,
+            launch_command: manifest.launch_command,
+            prereq_ids: manifest.prereq_ids,
+            prereq_name: manifest.prereq_name,
+            prereq_path: manifest.prereq_path,
+            prereq_args: manifest.prereq_args,
+            file_manifest_list: manifest.file_manifest_list.into_iter().map(Into::into).collect(),
+            custom_fields: manifest.custom_fields.into(),
+            chunk_hash_list: manifest.chunk_hash_list.into_iter().collect(),
+            chunk_sha_list: manifest.chunk_sha_list.into_iter().collect(),
+            chunk_filesize_list: manifest.chunk_filesize_list.into_iter().collect(),
+            extra: extra(manifest.extra),
+        }
+    }
+}
+
+impl From<types::json_manifest::JsonFileManifest> for JsonFileManifest {
+    fn from(file: types::json_manifest::JsonFileManifest) -> Self {
+        Self {
+            filename: file.filename,
+            file_hash: file.file_hash,
+            is_unix_executable: file.is_unix_executable,
+            file_chunk_parts: file
+                .file_chunk_parts
+                .into_iter()
+                .map(|part| JsonFileChunkPart {
+                    guid: part.guid,
+                    offset: part.offset,
+                    size: part.size,
+                    extra: extra(part.extra),
+                })
+                .collect(),
+            extra: extra(file.extra),
+        }
+    }
+}
+
+// -------------------------------------------------------------- to parser
+
+impl From<Manifest> for types::manifest::Manifest {
+    fn from(manifest: Manifest) -> Self {
+        Self {
+            header: manifest.header.into(),
+            meta: manifest.meta.map(Into::into),
+            chunk_list: manifest.chunk_list.map(Into::into),
+            file_list: manifest.file_list.map(Into::into),
+            custom_fields: manifest.custom_fields.into(),
+        }
+    }
+}
+
+impl From<ManifestHeader> for types::header::ManifestHeader {
+    fn from(header: ManifestHeader) -> Self {
+        Self {
+            header_size: header.header_size,
+            data_size_uncompressed: header.data_size_uncompressed,
+            data_size_compressed: header.data_size_compressed,
+            sha1_hash: header.sha1_hash,
+            stored_as: header.stored_as,
+            version: header.version,
+            guid: header.guid,
+            rolling_hash: header.rolling_hash,
+            hash_type: header.hash_type,
+            synthetic: header.synthetic.unwrap_or_default(),
+        }
+    }
+}
+
+impl From<ManifestMeta> for types::meta::ManifestMeta {
+    fn from(meta: ManifestMeta) -> Self {
+        Self {
+            data_size: meta.data_size,
+            data_version: meta.data_version,
+            feature_level: meta.feature_level,
+            is_file_data: meta.is_file_data,
+            app_id: meta.app_id,
+            app_name: meta.app_name,
+            build_version: meta.build_version,
+            launch_exe: meta.launch_exe,
+            launch_command: meta.launch_command,
+            prereq_ids: meta.prereq_ids,
+            prereq_name: meta.prereq_name,
+            prereq_path: meta.prereq_path,
+            prereq_args: meta.prereq_args,
+            build_id: meta.build_id,
+            extra_data: meta.extra_data,
+            synthetic: meta.synthetic.unwrap_or_default(),
+        }
+    }
+}
+
+impl From<ChunkDataList> for types::chunk::ChunkDataList {
+    fn from(list: ChunkDataList) -> Self {
+        let chunk_lookup = list
+            .elements
+            .iter()
+            .enumerate()
+            .map(|(i, chunk)| (chunk.guid.clone(), i as u32))
+            .collect();
+        Self {
+            data_size: list.data_size,
+            data_version: list.data_version,
+            count: list.count,
+            elements: list.elements.into_iter().map(Into::into).collect(),
+            chunk_lookup,
+            extra_data: list.extra_data,
+            synthetic: list.synthetic.unwrap_or_default(),
+        }
+    }
+}
+
+impl From<Chunk> for types::chunk::Chunk {
+    fn from(chunk: Chunk) -> Self {
+        Self {
+            guid: chunk.guid,
+            hash: chunk.hash,
+            sha_hash: chunk.sha_hash,
+            group: chunk.group,
+            window_size: chunk.window_size,
+            file_size: chunk.file_size,
+        }
+    }
+}
+
+impl From<ChunkPart> for types::chunk::ChunkPart {
+    fn from(part: ChunkPart) -> Self {
+        Self {
+            data_size: part.data_size,
+            parent_guid: part.parent_guid,
+            offset: part.offset,
+            size: part.size,
+            extra_data: part.extra_data,
+            chunk: part.chunk.map(Into::into),
+        }
+    }
+}
+
+impl From<FileManifestList> for types::file::FileManifestList {
+    fn from(list: FileManifestList) -> Self {
+        Self {
+            data_size: list.data_size,
+            data_version: list.data_version,
+            count: list.count,
+            file_manifest_list: list.file_manifest_list.into_iter().map(Into::into).collect(),
+            path_order: Default::default(),
+            extra_data: list.extra_data,
+            synthetic: list.synthetic.unwrap_or_default(),
+        }
+    }
+}
+
+impl From<FileManifest> for types::file::FileManifest {
+    fn from(file: FileManifest) -> Self {
+        Self {
+            filename: file.filename,
+            symlink_target: file.symlink_target,
+            sha_hash: file.sha_hash,
+            file_meta_flags: file.file_meta_flags,
+            install_tags: file.install_tags,
+            chunk_parts: file.chunk_parts.into_iter().map(Into::into).collect(),
+            file_size: file.file_size,
+            size_unknown: file.size_unknown.unwrap_or_default(),
+            mime_type: file.mime_type,
+            flags: file.flags.map(Into::into),
+        }
+    }
+}
+
+impl From<FileFlags> for types::file::FileFlags {
+    fn from(flags: FileFlags) -> Self {
+        Self {
+            readonly: flags.readonly,
+            compressed: flags.compressed,
+            unix_executable: flags.unix_executable,
+        }
+    }
+}
+
+impl From<CustomFields> for types::custom_fields::CustomFields {
+    fn from(fields: CustomFields) -> Self {
+        let mut converted = Self::default();
+        for entry in fields.entries {
+            converted.push(entry.key, entry.value);
+        }
+        converted
+    }
+}
@@ -11,17 +11,86 @@ pub enum ManifestError {
     #[error("invalid data: {0}")]
     Invalid(String),
 
-    #[error("encrypted manifests are not supported")]
+    /// An encrypted manifest was parsed without
+    /// [`crate::ParseOptions::decryption_key`].
+    #[error("manifest is encrypted and no key was given")]
     EncryptedManifest,
 
-    #[error("SHA-1 mismatch (corrupted file?)")]
-    Sha1Mismatch,
+    /// A chunk flagged encrypted was decoded without a key.
+    #[error("chunk {0} is encrypted and no key was given")]
+    EncryptedChunk(String),
+
+    /// Data whose SHA-1 is not the one recorded for it, such as a payload
+    /// not matching its header under
+    /// [`crate::ParseOptions::require_payload_hash`]. Digests are lowercase
+    /// hex.
+    #[error("SHA-1 mismatch (corrupted file?): expected {expected}, got {actual}")]
+    Sha1Mismatch { expected: String, actual: String },
 
     #[error("JSON: {0}")]
     Json(#[from] serde_json::Error),
 
+    /// Fields of a JSON manifest with the wrong type or missing, in document
+    /// order.
+    #[error("invalid JSON manifest: {}", join_errors(.0))]
+    JsonFields(Vec<crate::types::json_manifest::JsonFieldError>),
+
+    /// The meta section could not be read and
+    /// [`crate::ParseOptions::require_meta`] was set.
+    #[error("unreadable meta at payload offset {offset}: {source}")]
+    Meta {
+        offset: u64,
+        #[source]
+        source: Box<ManifestError>,
+    },
+
+    /// Damage the parser would have worked around, with
+    /// [`crate::ParseOptions::strict`] set.
+    #[error("{section}: {message}")]
+    Strict {
+        section: crate::hooks::Section,
+        message: String,
+    },
+
+    /// A warning, with [`crate::ParseOptions::warnings_as_errors`] set.
+    #[error("{0}")]
+    Warning(crate::hooks::ParseWarning),
+
     #[error("hex: {0}")]
     Hex(#[from] hex::FromHexError),
+
+    /// A section holds more elements than [`crate::ParseOptions::limits`]
+    /// allow.
+    #[error("{section} count {count} is over the limit of {limit}")]
+    LimitExceeded {
+        section: crate::hooks::Section,
+        count: u32,
+        limit: u32,
+    },
+
+    #[error("parsing aborted by a hook at the {0}")]
+    Aborted(crate::hooks::Section),
+
+    /// The [`crate::ParseOptions::deadline`] passed while reading `section`.
+    #[error("parsing timed out in the {section}")]
+    Timeout {
+        section: crate::hooks::Section,
+        /// The sections finished before the deadline, once the header was read.
+        partial: Option<Box<crate::types::manifest::Manifest>>,
+    },
+
+    #[error("archive: {0}")]
+    Archive(String),
+
+    #[error(transparent)]
+    Fetch(#[from] crate::download::retry::FetchError),
+
+    #[error("not enough disk space: need {required} bytes plus {staging} for staging, {available} available")]
+    InsufficientSpace { required: u64, staging: u64, available: u64 },
+}
+
+fn join_errors<T: std::fmt::Display>(errors: &[T]) -> String {
+    errors.iter().map(T::to_string).collect::<Vec<_>>().join("; ")
 }
 
 impl AsRef<str> for ManifestError {
@@ -30,10 +99,21 @@ impl AsRef<str> for ManifestError {
             ManifestError::Io(_) => "I/O error",
             ManifestError::Inflate(_) => "zlib-ng error",
             ManifestError::Invalid(_) => "invalid data",
-            ManifestError::EncryptedManifest => "encrypted manifests are not supported",
-            ManifestError::Sha1Mismatch => "SHA-1 mismatch (corrupted file?)",
+            ManifestError::EncryptedManifest => "encrypted manifest without a key",
+            ManifestError::EncryptedChunk(_) => "encrypted chunk without a key",
+            ManifestError::Sha1Mismatch { .. } => "SHA-1 mismatch (corrupted file?)",
             ManifestError::Json(_) => "JSON error",
+            ManifestError::JsonFields(_) => "invalid JSON manifest",
+            ManifestError::Meta { .. } => "unreadable meta",
+            ManifestError::Strict { .. } => "damaged manifest",
+            ManifestError::Warning(_) => "parse warning",
             ManifestError::Hex(_) => "hex error",
+            ManifestError::Archive(_) => "archive error",
+            ManifestError::InsufficientSpace { .. } => "not enough disk space",
+            ManifestError::Fetch(_) => "chunk fetch failed",
+            ManifestError::LimitExceeded { .. } => "over a parse limit",
+            ManifestError::Aborted(_) => "parsing aborted",
+            ManifestError::Timeout { .. } => "parsing timed out",
         }
     }
 }
@@ -0,0 +1,200 @@
+//! Content-addressable storage layout: every file stored once under
+//! `objects/<first two hex digits>/<sha1>`, plus an `index.json` mapping
+//! manifest paths to blobs.
+
+use log::debug;
+use serde::{Deserialize, Serialize};
+use sha1::{Digest, Sha1};
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use crate::error::ManifestError;
+use crate::install::apply::contained_path;
+use crate::provider::{for_each_file_part, ChunkProvider};
+use crate::types::manifest::Manifest;
+
+pub const CAS_INDEX_FILE: &str = "index.json";
+const CAS_OBJECTS_DIR: &str = "objects";
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct CasEntry {
+    pub filename: String,
+    pub sha1: String,
+    pub size: u64,
+    pub file_meta_flags: u8,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub symlink_target: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct CasIndex {
+    pub version: u32,
+    pub app_name: String,
+    pub build_version: String,
+    /// Header SHA-1 of the manifest the index was exported from.
+    pub manifest_sha1: String,
+    pub files: Vec<CasEntry>,
+}
+
+impl CasIndex {
+    /// Read the index at `root`. Every entry's `sha1` must be 40 lowercase
+    /// hex digits, since it names a file under `root`.
+    pub fn read(root: impl AsRef<Path>) -> Result<Self, ManifestError> {
+        let data = fs::read(root.as_ref().join(CAS_INDEX_FILE))?;
+        let index: Self = serde_json::from_slice(&data)?;
+        if let Some(entry) = index.files.iter().find(|entry| !is_sha1_hex(&entry.sha1)) {
+            return Err(ManifestError::Invalid(format!(
+                "CAS index entry {} has SHA-1 {:?}, not 40 lowercase hex digits",
+                entry.filename, entry.sha1
+            )));
+        }
+        Ok(index)
+    }
+}
+
+fn is_sha1_hex(sha1: &str) -> bool {
+    sha1.len() == 40 && sha1.bytes().all(|b| matches!(b, b'0'..=b'9' | b'a'..=b'f'))
+}
+
+/// Counters reported by [`export_cas`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CasExportStats {
+    pub files: usize,
+    /// Blobs newly written; identical content is stored once.
+    pub blobs_written: usize,
+    pub bytes_written: u64,
+}
+
+/// Path of the blob holding content with SHA-1 `sha1` under `root`.
+/// `sha1` is joined as given, so it must already be checked to be hex.
+pub fn blob_path(root: impl AsRef<Path>, sha1: &str) -> PathBuf {
+    let prefix = sha1.get(..2).unwrap_or("00");
+    root.as_ref().join(CAS_OBJECTS_DIR).join(prefix).join(sha1)
+}
+
+/// Reassemble every file of `manifest` from `provider` into a CAS layout at
+/// `root`. Each file's content is checked against the manifest SHA-1.
+pub fn export_cas<P: ChunkProvider + ?Sized>(
+    manifest: &Manifest,
+    provider: &P,
+    root: impl AsRef<Path>,
+) -> Result<CasExportStats, ManifestError> {
+    let root = root.as_ref();
+    fs::create_dir_all(root.join(CAS_OBJECTS_DIR))?;
+
+    let mut stats = CasExportStats::default();
+    let mut entries = Vec::new();
+    let mut cache = None;
+
+    let files = manifest
+        .file_list
+        .as_ref()
+        .map(|f| f.file_manifest_list.as_slice())
+        .unwrap_or_default();
+    for file in files {
+        let tmp_path = root.join(format!(".incoming-{}", uuid::Uuid::new_v4()));
+        let mut out = fs::File::create(&tmp_path)?;
+        let mut hasher = Sha1::new();
+        let mut size = 0u64;
+        let written = for_each_file_part(provider, file, &mut cache, |bytes| {
+            hasher.update(bytes);
+            size += bytes.len() as u64;
+            out.write_all(bytes)?;
+            Ok(())
+        });
+        drop(out);
+        if let Err(e) = written {
+            let _ = fs::remove_file(&tmp_path);
+            return Err(e);
+        }
+
+        let sha1 = hex::encode(hasher.finalize());
+        if sha1 != file.sha_hash {
+            let _ = fs::remove_file(&tmp_path);
+            return Err(ManifestError::Invalid(format!(
+                "content of {} hashes to {}, manifest says {}",
+                file.filename.trim_end_matches('\0'),
+                sha1,
+                file.sha_hash
+            )));
+        }
+
+        let target = blob_path(root, &sha1);
+        if target.exists() {
+            fs::remove_file(&tmp_path)?;
+        } else {
+            fs::create_dir_all(target.parent().unwrap())?;
+            fs::rename(&tmp_path, &target)?;
+            stats.blobs_written += 1;
+            stats.bytes_written += size;
+        }
+
+        entries.push(CasEntry {
+            filename: file.filename.trim_end_matches('\0').to_string(),
+            sha1,
+            size,
+            file_meta_flags: file.file_meta_flags,
+            symlink_target: file.symlink_target.trim_end_matches('\0').to_string(),
+        });
+        stats.files += 1;
+    }
+
+    let index = CasIndex {
+        version: 1,
+        app_name: manifest
+            .meta
+            .as_ref()
+            .map(|m| m.app_name.trim_end_matches('\0').to_string())
+            .unwrap_or_default(),
+        build_version: manifest
+            .meta
+            .as_ref()
+            .map(|m| m.build_version.trim_end_matches('\0').to_string())
+            .unwrap_or_default(),
+        manifest_sha1: manifest.header.sha1_hash.clone(),
+        files: entries,
+    };
+    fs::write(root.join(CAS_INDEX_FILE), serde_json::to_vec_pretty(&index)?)?;
+
+    debug!(
+        "CAS export: {} files, {} new blobs ({} bytes)",
+        stats.files, stats.blobs_written, stats.bytes_written
+    );
+    Ok(stats)
+}
+
+/// Materialize the files listed in the CAS index at `root` into `target_dir`,
+/// verifying every blob's SHA-1 on the way.
+pub fn import_cas(root: impl AsRef<Path>, target_dir: impl AsRef<Path>) -> Result<CasIndex, ManifestError> {
+    let root = root.as_ref();
+    let target_dir = target_dir.as_ref();
+    let index = CasIndex::read(root)?;
+
+    for entry in &index.files {
+        let Some(relative) = contained_path(&entry.filename) else {
+            return Err(ManifestError::Invalid(format!(
+                "refusing to import path outside target: {}",
+                entry.filename
+            )));
+        };
+
+        // `CasIndex::read` checked the SHA-1, so the blob is under `root`.
+        let data = fs::read(blob_path(root, &entry.sha1))?;
+        let actual = hex::encode(Sha1::digest(&data));
+        if actual != entry.sha1 {
+            return Err(ManifestError::Sha1Mismatch {
+                expected: entry.sha1.clone(),
+                actual,
+            });
+        }
+
+        let out_path = target_dir.join(relative);
+        if let Some(parent) = out_path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(out_path, data)?;
+    }
+
+    Ok(index)
+}
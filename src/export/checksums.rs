@@ -0,0 +1,68 @@
+//! Checksum lists for verifying an install with standard tools
+//! (`sha1sum -c`, `shasum -c`) instead of this crate.
+//!
+//! Manifests only record a SHA-1 per file, so SHA-1 is the only digest that
+//! can be exported without reading the files; CRC32 (`.sfv`) and MD5 lists
+//! would need the content.
+
+use std::io::Write;
+
+use crate::error::ManifestError;
+use crate::types::manifest::Manifest;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ChecksumFormat {
+    /// GNU coreutils: `<hex>  <path>`.
+    #[default]
+    Sha1Sum,
+    /// BSD tagged: `SHA1 (<path>) = <hex>`.
+    Bsd,
+}
+
+/// Write a checksum line for every regular file of `manifest` to `out`,
+/// returning how many were written. Symlinks are skipped since checksum
+/// tools follow them.
+///
+/// Paths keep the manifest's `/` separators and are relative to the install
+/// directory, so run the tool from there. In the GNU format, paths containing
+/// a backslash or newline are escaped the way `sha1sum` does.
+pub fn export_checksums(
+    manifest: &Manifest,
+    format: ChecksumFormat,
+    mut out: impl Write,
+) -> Result<usize, ManifestError> {
+    let files = manifest
+        .file_list
+        .as_ref()
+        .map(|f| f.file_manifest_list.as_slice())
+        .unwrap_or_default();
+
+    let mut written = 0;
+    for file in files {
+        if !file.symlink_target.trim_end_matches('\0').is_empty() {
+            continue;
+        }
+        let path = file.filename.trim_end_matches('\0');
+        let sha1 = file.sha_hash.to_ascii_lowercase();
+        match format {
+            ChecksumFormat::Sha1Sum => {
+                if path.contains(['\\', '\n']) {
+                    let escaped = path.replace('\\', "\\\\").replace('\n', "\\n");
+                    writeln!(out, "\\{}  {}", sha1, escaped)?;
+                } else {
+                    writeln!(out, "{}  {}", sha1, path)?;
+                }
+            }
+            ChecksumFormat::Bsd => writeln!(out, "SHA1 ({}) = {}", path, sha1)?,
+        }
+        written += 1;
+    }
+    Ok(written)
+}
+
+/// [`export_checksums`] into a string.
+pub fn checksums_string(manifest: &Manifest, format: ChecksumFormat) -> Result<String, ManifestError> {
+    let mut out = Vec::new();
+    export_checksums(manifest, format, &mut out)?;
+    String::from_utf8(out).map_err(|e| ManifestError::Invalid(e.to_string()))
+}
@@ -0,0 +1,118 @@
+//! File composition data for visualizations.
+//!
+//! egdata draws each file as a strip coloured by the chunk every byte range
+//! comes from. Shipping the chunk parts themselves for that is wasteful: a
+//! large file has tens of thousands of them, each repeating a GUID.
+//! [`composition`] run-length encodes them instead, referring to chunks by
+//! their position in one shared GUID table.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use crate::types::file::FileManifest;
+use crate::types::manifest::Manifest;
+
+/// `length` bytes of a file coming from chunk `chunk`. Serialized as the
+/// pair `[chunk, length]`.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(from = "(u32, u64)", into = "(u32, u64)")]
+pub struct ChunkRun {
+    /// Index into [`Composition::chunks`].
+    pub chunk: u32,
+    pub length: u64,
+}
+
+impl From<(u32, u64)> for ChunkRun {
+    fn from((chunk, length): (u32, u64)) -> Self {
+        Self { chunk, length }
+    }
+}
+
+impl From<ChunkRun> for (u32, u64) {
+    fn from(run: ChunkRun) -> Self {
+        (run.chunk, run.length)
+    }
+}
+
+/// One file as consecutive runs; the lengths add up to `size`.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct FileComposition {
+    pub filename: String,
+    pub size: u64,
+    pub runs: Vec<ChunkRun>,
+}
+
+/// Output of [`composition`].
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct Composition {
+    /// Chunk GUIDs in the order files first reference them.
+    pub chunks: Vec<String>,
+    /// Every file, in manifest order.
+    pub files: Vec<FileComposition>,
+}
+
+/// Run-length encode which chunk each byte range of every file of
+/// `manifest` comes from. Adjacent chunk parts from the same chunk form one
+/// run, whichever offsets inside the chunk they read; zero-sized parts are
+/// dropped.
+pub fn composition(manifest: &Manifest) -> Composition {
+    let mut palette = Palette::default();
+    let files = manifest
+        .file_list
+        .iter()
+        .flat_map(|f| &f.file_manifest_list)
+        .map(|file| palette.encode(file))
+        .collect();
+    Composition {
+        chunks: palette.chunks,
+        files,
+    }
+}
+
+/// [`composition`] of the single file `filename`, with its own GUID table.
+pub fn file_composition(manifest: &Manifest, filename: &str) -> Option<Composition> {
+    let file = manifest.file_list.as_ref()?.find_by_path(filename)?;
+    let mut palette = Palette::default();
+    let file = palette.encode(file);
+    Some(Composition {
+        chunks: palette.chunks,
+        files: vec![file],
+    })
+}
+
+#[derive(Default)]
+struct Palette {
+    chunks: Vec<String>,
+    indices: HashMap<String, u32>,
+}
+
+impl Palette {
+    fn index(&mut self, guid: &str) -> u32 {
+        if let Some(&index) = self.indices.get(guid) {
+            return index;
+        }
+        let index = self.chunks.len() as u32;
+        self.chunks.push(guid.to_string());
+        self.indices.insert(guid.to_string(), index);
+        index
+    }
+
+    fn encode(&mut self, file: &FileManifest) -> FileComposition {
+        let mut runs: Vec<ChunkRun> = Vec::new();
+        for part in file.chunk_parts.iter().filter(|p| p.size > 0) {
+            let chunk = self.index(&part.parent_guid);
+            match runs.last_mut() {
+                Some(run) if run.chunk == chunk => run.length += part.size as u64,
+                _ => runs.push(ChunkRun {
+                    chunk,
+                    length: part.size as u64,
+                }),
+            }
+        }
+        FileComposition {
+            filename: file.filename.trim_end_matches('\0').to_string(),
+            size: runs.iter().map(|r| r.length).sum(),
+            runs,
+        }
+    }
+}
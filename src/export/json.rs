@@ -0,0 +1,77 @@
+//! JSON export with a choice of how 64-bit values are written.
+//!
+//! Chunk sizes are kept as strings so JavaScript, whose numbers are exact
+//! only up to 2^53, never rounds them. Consumers disagree on whether that
+//! is worth the inconvenience, so [`to_json`] writes every 64-bit value of
+//! a manifest in one [`Int64Format`] picked per export: chunk and file
+//! sizes and the header's rolling hash.
+
+use serde_json::{Map, Value};
+
+use crate::error::ManifestError;
+use crate::types::manifest::Manifest;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Int64Format {
+    /// Decimal strings, `"1048576"`, as chunk sizes already serialize.
+    #[default]
+    String,
+    /// Plain numbers, `1048576`. Convenient, but JavaScript rounds values
+    /// past 2^53.
+    Number,
+    /// Objects holding the decimal string, `{"$num":"1048576"}`, which
+    /// consumers can revive as `BigInt` without guessing which strings are
+    /// numbers.
+    Tagged,
+}
+
+/// Key of the object [`Int64Format::Tagged`] wraps values in.
+pub const TAGGED_KEY: &str = "$num";
+
+/// `manifest` as JSON, laid out like its serde serialization with every
+/// 64-bit value written as `format` says.
+pub fn to_json(manifest: &Manifest, format: Int64Format) -> Result<String, ManifestError> {
+    Ok(serde_json::to_string(&to_json_value(manifest, format)?)?)
+}
+
+/// [`to_json`] as a [`serde_json::Value`].
+pub fn to_json_value(manifest: &Manifest, format: Int64Format) -> Result<Value, ManifestError> {
+    let mut value = serde_json::to_value(manifest)?;
+    if let Some(header) = value.get_mut("header") {
+        rewrite(header, "rolling_hash", format);
+    }
+    let lists = [("chunk_list", "elements"), ("file_list", "file_manifest_list")];
+    for (list, elements) in lists {
+        let Some(Value::Array(elements)) = value.get_mut(list).and_then(|l| l.get_mut(elements)) else {
+            continue;
+        };
+        for element in elements {
+            rewrite(element, "file_size", format);
+        }
+    }
+    Ok(value)
+}
+
+/// Rewrite the integer at `object[key]`, given as a number or a decimal
+/// string, in `format`. Anything else is left as it is.
+fn rewrite(object: &mut Value, key: &str, format: Int64Format) {
+    let Some(value) = object.get_mut(key) else {
+        return;
+    };
+    let number = match value {
+        Value::Number(n) if n.is_i64() || n.is_u64() => n.clone(),
+        Value::String(s) => match (s.parse::<i64>(), s.parse::<u64>()) {
+            (Ok(n), _) => n.into(),
+            (_, Ok(n)) => n.into(),
+            _ => return,
+        },
+        _ => return,
+    };
+    *value = match format {
+        Int64Format::String => Value::String(number.to_string()),
+        Int64Format::Number => Value::Number(number),
+        Int64Format::Tagged => {
+            Value::Object(Map::from_iter([(TAGGED_KEY.to_string(), Value::String(number.to_string()))]))
+        }
+    };
+}
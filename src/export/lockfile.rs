@@ -0,0 +1,262 @@
+//! Manifest lockfiles: a text form of a build meant to be kept in git.
+//!
+//! A lockfile lists every file of a build on its own line, sorted by path,
+//! with its SHA-1 and size, then every chunk sorted by GUID. Lines do not
+//! depend on each other, so a new build shows up in code review as exactly
+//! the files and chunks that changed:
+//!
+//! ```text
+//! # egdata manifest lockfile v1
+//! app Fortnite
+//! version ++Fortnite+Release-30.00-CL-1234567
+//! build-id JU-lxmUVml3jtbUYmxX9hxuiNy0
+//! feature-level 18
+//!
+//! [files]
+//! 2fd4e1c67a2d28fced849ee1bb76e7391b93eb12 1048576 Engine/Binaries/Win64/Game.exe
+//!
+//! [chunks]
+//! 00010203-0405-0607-0809-0a0b0c0d0e0f 4 1048576 520845 23545b78c1fffd32 82c390d991341c3308bf0b6ae09b454f00da113d
+//! ```
+//!
+//! Chunk lines give GUID, group, window size, compressed size, rolling hash
+//! and SHA-1. Backslashes and line breaks in names are escaped as `\\`,
+//! `\n` and `\r`. A missing hash is written as `-`, so every line keeps
+//! its fields. [`Lockfile::parse`] reads a lockfile back, and writing what
+//! it returns reproduces the text byte for byte.
+
+use std::fmt;
+
+use crate::error::ManifestError;
+use crate::types::chunk::normalize_guid;
+use crate::types::manifest::Manifest;
+
+/// First line of every lockfile; the version changes with the format.
+pub const LOCKFILE_HEADER: &str = "# egdata manifest lockfile v1";
+
+/// Stands in for an empty hash field.
+const MISSING: &str = "-";
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Lockfile {
+    pub app_name: String,
+    pub build_version: String,
+    pub build_id: String,
+    pub feature_level: i32,
+    /// Sorted by path.
+    pub files: Vec<LockedFile>,
+    /// Sorted by GUID.
+    pub chunks: Vec<LockedChunk>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LockedFile {
+    pub path: String,
+    /// Lowercase hex.
+    pub sha1: String,
+    pub size: u64,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LockedChunk {
+    /// Hyphenated.
+    pub guid: String,
+    pub group: u8,
+    pub window_size: u32,
+    /// Compressed size.
+    pub file_size: u64,
+    /// Rolling hash, as 16 hex digits.
+    pub hash: String,
+    /// Lowercase hex.
+    pub sha1: String,
+}
+
+impl Lockfile {
+    /// Fails if a chunk's size is not a number, rather than locking in a
+    /// made-up one.
+    pub fn from_manifest(manifest: &Manifest) -> Result<Self, ManifestError> {
+        let meta = manifest.meta.as_ref();
+        let trimmed = |s: &str| s.trim_end_matches('\0').to_string();
+        let mut files: Vec<LockedFile> = manifest
+            .file_list
+            .iter()
+            .flat_map(|f| &f.file_manifest_list)
+            .map(|file| LockedFile {
+                path: trimmed(&file.filename),
+                sha1: file.sha_hash.to_ascii_lowercase(),
+                size: file.file_size.max(0) as u64,
+            })
+            .collect();
+        files.sort_by(|a, b| a.path.cmp(&b.path));
+        let mut chunks: Vec<LockedChunk> = manifest
+            .chunk_list
+            .iter()
+            .flat_map(|c| &c.elements)
+            .map(|chunk| {
+                let file_size = chunk.file_size.parse().map_err(|_| {
+                    ManifestError::Invalid(format!(
+                        "chunk {} has size {:?}, not a number",
+                        chunk.guid, chunk.file_size
+                    ))
+                })?;
+                Ok(LockedChunk {
+                    guid: normalize_guid(&chunk.guid).unwrap_or_else(|| chunk.guid.clone()),
+                    group: chunk.group,
+                    window_size: chunk.window_size,
+                    file_size,
+                    hash: chunk.hash.to_ascii_lowercase(),
+                    sha1: chunk.sha_hash.to_ascii_lowercase(),
+                })
+            })
+            .collect::<Result<_, ManifestError>>()?;
+        chunks.sort_by(|a, b| a.guid.cmp(&b.guid));
+        Ok(Self {
+            app_name: meta.map(|m| trimmed(&m.app_name)).unwrap_or_default(),
+            build_version: meta.map(|m| trimmed(&m.build_version)).unwrap_or_default(),
+            build_id: meta.map(|m| m.build_id()).unwrap_or_default(),
+            feature_level: meta.map_or(manifest.header.version, |m| m.feature_level),
+            files,
+            chunks,
+        })
+    }
+
+    /// Read a lockfile written by this module.
+    pub fn parse(text: &str) -> Result<Self, ManifestError> {
+        let mut lines = text.lines().enumerate().map(|(i, line)| (i + 1, line));
+        if lines.next().map(|(_, line)| line) != Some(LOCKFILE_HEADER) {
+            return Err(ManifestError::Invalid(format!(
+                "lockfile does not start with {:?}",
+                LOCKFILE_HEADER
+            )));
+        }
+
+        let mut lockfile = Self::default();
+        let mut section = "";
+        for (number, line) in lines {
+            let invalid = |message: &str| ManifestError::Invalid(format!("lockfile line {}: {}", number, message));
+            if line.is_empty() {
+                continue;
+            }
+            if line.starts_with('[') {
+                section = match line {
+                    "[files]" => "files",
+                    "[chunks]" => "chunks",
+                    _ => return Err(invalid("unknown section")),
+                };
+                continue;
+            }
+            match section {
+                "" => {
+                    let (key, value) = line.split_once(' ').unwrap_or((line, ""));
+                    match key {
+                        "app" => lockfile.app_name = unescape(value),
+                        "version" => lockfile.build_version = unescape(value),
+                        "build-id" => lockfile.build_id = unescape(value),
+                        "feature-level" => lockfile.feature_level = value.parse().map_err(|_| invalid("bad number"))?,
+                        _ => return Err(invalid("unknown key")),
+                    }
+                }
+                "files" => {
+                    let mut fields = line.splitn(3, ' ');
+                    let (Some(sha1), Some(size), Some(path)) = (fields.next(), fields.next(), fields.next()) else {
+                        return Err(invalid("expected SHA-1, size and path"));
+                    };
+                    lockfile.files.push(LockedFile {
+                        path: unescape(path),
+                        sha1: unmissing(sha1),
+                        size: size.parse().map_err(|_| invalid("bad size"))?,
+                    });
+                }
+                _ => {
+                    let fields: Vec<&str> = line.split(' ').collect();
+                    let [guid, group, window_size, file_size, hash, sha1] = fields[..] else {
+                        return Err(invalid("expected GUID, group, window size, size, hash and SHA-1"));
+                    };
+                    lockfile.chunks.push(LockedChunk {
+                        guid: guid.to_string(),
+                        group: group.parse().map_err(|_| invalid("bad group"))?,
+                        window_size: window_size.parse().map_err(|_| invalid("bad window size"))?,
+                        file_size: file_size.parse().map_err(|_| invalid("bad size"))?,
+                        hash: unmissing(hash),
+                        sha1: unmissing(sha1),
+                    });
+                }
+            }
+        }
+        Ok(lockfile)
+    }
+}
+
+impl fmt::Display for Lockfile {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{}", LOCKFILE_HEADER)?;
+        writeln!(f, "app {}", escape(&self.app_name))?;
+        writeln!(f, "version {}", escape(&self.build_version))?;
+        writeln!(f, "build-id {}", escape(&self.build_id))?;
+        writeln!(f, "feature-level {}", self.feature_level)?;
+        writeln!(f)?;
+        writeln!(f, "[files]")?;
+        for file in &self.files {
+            writeln!(f, "{} {} {}", or_missing(&file.sha1), file.size, escape(&file.path))?;
+        }
+        writeln!(f)?;
+        writeln!(f, "[chunks]")?;
+        for chunk in &self.chunks {
+            writeln!(
+                f,
+                "{} {} {} {} {} {}",
+                chunk.guid,
+                chunk.group,
+                chunk.window_size,
+                chunk.file_size,
+                or_missing(&chunk.hash),
+                or_missing(&chunk.sha1)
+            )?;
+        }
+        Ok(())
+    }
+}
+
+fn or_missing(hash: &str) -> &str {
+    if hash.is_empty() {
+        MISSING
+    } else {
+        hash
+    }
+}
+
+fn unmissing(field: &str) -> String {
+    if field == MISSING {
+        String::new()
+    } else {
+        field.to_string()
+    }
+}
+
+fn escape(value: &str) -> String {
+    if !value.contains(['\\', '\n', '\r']) {
+        return value.to_string();
+    }
+    value.replace('\\', "\\\\").replace('\n', "\\n").replace('\r', "\\r")
+}
+
+fn unescape(value: &str) -> String {
+    if !value.contains('\\') {
+        return value.to_string();
+    }
+    let mut out = String::with_capacity(value.len());
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        match (c, chars.clone().next()) {
+            ('\\', Some('n')) => out.push('\n'),
+            ('\\', Some('r')) => out.push('\r'),
+            ('\\', Some('\\')) => out.push('\\'),
+            _ => {
+                out.push(c);
+                continue;
+            }
+        }
+        chars.next();
+    }
+    out
+}
@@ -0,0 +1,247 @@
+//! BitTorrent v2 (BEP 52) metadata built straight from a parsed manifest.
+
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+
+use crate::error::ManifestError;
+use crate::provider::{for_each_file_part, ChunkProvider};
+use crate::types::manifest::Manifest;
+
+/// BEP 52 merkle leaf size.
+const BLOCK_SIZE: usize = 16 * 1024;
+
+#[derive(Debug, Clone)]
+pub struct TorrentOptions {
+    /// Torrent name; defaults to `<app name> <build version>`.
+    pub name: Option<String>,
+    /// Power of two, at least 16 KiB.
+    pub piece_length: u64,
+    pub announce: Option<String>,
+    pub comment: Option<String>,
+}
+
+impl Default for TorrentOptions {
+    fn default() -> Self {
+        Self {
+            name: None,
+            piece_length: 4 * 1024 * 1024,
+            announce: None,
+            comment: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct TorrentMetadata {
+    /// Bencoded `.torrent` file.
+    pub bytes: Vec<u8>,
+    /// SHA-256 of the bencoded info dictionary.
+    pub info_hash_v2: [u8; 32],
+}
+
+enum Bencode {
+    Int(i64),
+    Bytes(Vec<u8>),
+    Dict(BTreeMap<Vec<u8>, Bencode>),
+}
+
+impl Bencode {
+    fn str(s: &str) -> Self {
+        Bencode::Bytes(s.as_bytes().to_vec())
+    }
+
+    fn encode(&self, out: &mut Vec<u8>) {
+        match self {
+            Bencode::Int(i) => out.extend_from_slice(format!("i{}e", i).as_bytes()),
+            Bencode::Bytes(b) => {
+                out.extend_from_slice(format!("{}:", b.len()).as_bytes());
+                out.extend_from_slice(b);
+            }
+            Bencode::Dict(d) => {
+                out.push(b'd');
+                for (k, v) in d {
+                    Bencode::Bytes(k.clone()).encode(out);
+                    v.encode(out);
+                }
+                out.push(b'e');
+            }
+        }
+    }
+}
+
+/// Streaming BEP 52 merkle hasher for a single file.
+struct FileHasher {
+    block: Vec<u8>,
+    leaves: Vec<[u8; 32]>,
+    length: u64,
+}
+
+impl FileHasher {
+    fn new() -> Self {
+        Self {
+            block: Vec::with_capacity(BLOCK_SIZE),
+            leaves: Vec::new(),
+            length: 0,
+        }
+    }
+
+    fn update(&mut self, mut data: &[u8]) {
+        self.length += data.len() as u64;
+        while !data.is_empty() {
+            let take = (BLOCK_SIZE - self.block.len()).min(data.len());
+            self.block.extend_from_slice(&data[..take]);
+            data = &data[take..];
+            if self.block.len() == BLOCK_SIZE {
+                self.leaves.push(Sha256::digest(&self.block).into());
+                self.block.clear();
+            }
+        }
+    }
+
+    /// Returns the pieces root and the piece layer (empty when the file fits
+    /// in a single piece).
+    fn finish(mut self, piece_length: u64) -> ([u8; 32], Vec<u8>) {
+        if !self.block.is_empty() {
+            self.leaves.push(Sha256::digest(&self.block).into());
+        }
+
+        let blocks_per_piece = (piece_length as usize / BLOCK_SIZE).max(1);
+        let num_pieces = self.length.div_ceil(piece_length) as usize;
+        // The tree spans the file's own blocks; files over a piece already
+        // pad past a whole number of pieces.
+        let width = self.leaves.len().next_power_of_two();
+        let mut layer = self.leaves;
+        layer.resize(width, [0u8; 32]);
+
+        let mut piece_layer = Vec::new();
+        let mut nodes_per_piece = 1;
+        loop {
+            if nodes_per_piece == blocks_per_piece && num_pieces > 1 {
+                piece_layer = layer[..num_pieces].concat();
+            }
+            if layer.len() == 1 {
+                break;
+            }
+            layer = layer
+                .chunks_exact(2)
+                .map(|pair| {
+                    let mut hasher = Sha256::new();
+                    hasher.update(pair[0]);
+                    hasher.update(pair[1]);
+                    hasher.finalize().into()
+                })
+                .collect();
+            nodes_per_piece *= 2;
+        }
+
+        (layer[0], piece_layer)
+    }
+}
+
+fn insert_path(tree: &mut BTreeMap<Vec<u8>, Bencode>, path: &[&str], leaf: Bencode) {
+    match path {
+        [] => {}
+        [name] => {
+            tree.insert(name.as_bytes().to_vec(), leaf);
+        }
+        [dir, rest @ ..] => {
+            let entry = tree
+                .entry(dir.as_bytes().to_vec())
+                .or_insert_with(|| Bencode::Dict(BTreeMap::new()));
+            if let Bencode::Dict(sub) = entry {
+                insert_path(sub, rest, leaf);
+            }
+        }
+    }
+}
+
+/// Build BitTorrent v2 metadata for every file in `manifest`, reading file
+/// contents from `provider` to compute the piece layers.
+pub fn export_torrent<P: ChunkProvider + ?Sized>(
+    manifest: &Manifest,
+    provider: &P,
+    options: &TorrentOptions,
+) -> Result<TorrentMetadata, ManifestError> {
+    if options.piece_length < BLOCK_SIZE as u64 || !options.piece_length.is_power_of_two() {
+        return Err(ManifestError::Invalid(format!(
+            "piece length {} must be a power of two of at least {}",
+            options.piece_length, BLOCK_SIZE
+        )));
+    }
+
+    let name = options.name.clone().unwrap_or_else(|| match &manifest.meta {
+        Some(meta) => format!(
+            "{} {}",
+            meta.app_name.trim_end_matches('\0'),
+            meta.build_version.trim_end_matches('\0')
+        ),
+        None => manifest.header.sha1_hash.clone(),
+    });
+
+    let mut file_tree = BTreeMap::new();
+    let mut piece_layers = BTreeMap::new();
+    let mut cache = None;
+
+    let files = manifest
+        .file_list
+        .as_ref()
+        .map(|f| f.file_manifest_list.as_slice())
+        .unwrap_or_default();
+    for file in files {
+        let filename = file.filename.trim_end_matches('\0');
+        let mut hasher = FileHasher::new();
+        for_each_file_part(provider, file, &mut cache, |bytes| {
+            hasher.update(bytes);
+            Ok(())
+        })?;
+
+        let length = hasher.length;
+        let mut props = BTreeMap::new();
+        props.insert(b"length".to_vec(), Bencode::Int(length as i64));
+        if length > 0 {
+            let (root, layer) = hasher.finish(options.piece_length);
+            props.insert(b"pieces root".to_vec(), Bencode::Bytes(root.to_vec()));
+            if !layer.is_empty() {
+                piece_layers.insert(root.to_vec(), Bencode::Bytes(layer));
+            }
+        }
+        let mut leaf = BTreeMap::new();
+        leaf.insert(Vec::new(), Bencode::Dict(props));
+
+        let components: Vec<&str> = filename.split('/').filter(|c| !c.is_empty()).collect();
+        insert_path(&mut file_tree, &components, Bencode::Dict(leaf));
+    }
+
+    let mut info = BTreeMap::new();
+    info.insert(b"file tree".to_vec(), Bencode::Dict(file_tree));
+    info.insert(b"meta version".to_vec(), Bencode::Int(2));
+    info.insert(b"name".to_vec(), Bencode::str(&name));
+    info.insert(
+        b"piece length".to_vec(),
+        Bencode::Int(options.piece_length as i64),
+    );
+    let info = Bencode::Dict(info);
+
+    let mut info_bytes = Vec::new();
+    info.encode(&mut info_bytes);
+    let info_hash_v2: [u8; 32] = Sha256::digest(&info_bytes).into();
+
+    let mut root = BTreeMap::new();
+    if let Some(announce) = &options.announce {
+        root.insert(b"announce".to_vec(), Bencode::str(announce));
+    }
+    if let Some(comment) = &options.comment {
+        root.insert(b"comment".to_vec(), Bencode::str(comment));
+    }
+    root.insert(b"created by".to_vec(), Bencode::str("egdata-manifests-parser"));
+    root.insert(b"info".to_vec(), info);
+    root.insert(b"piece layers".to_vec(), Bencode::Dict(piece_layers));
+
+    let mut bytes = Vec::new();
+    Bencode::Dict(root).encode(&mut bytes);
+
+    Ok(TorrentMetadata {
+        bytes,
+        info_hash_v2,
+    })
+}
@@ -0,0 +1,63 @@
+//! Content fingerprints for spotting duplicate uploads before parsing.
+//!
+//! [`fingerprint`] hashes the bytes a manifest occupies, section by section,
+//! without decompressing or parsing anything, so an upload endpoint can look
+//! the result up and reject a file it already has in milliseconds. Equal
+//! fingerprints mean byte-identical manifests; the fingerprint reveals
+//! nothing about the contents.
+
+use byteorder::{ByteOrder, LittleEndian};
+use sha2::{Digest, Sha256};
+
+use crate::detect::{detect_format, ManifestFormat};
+use crate::types::flags::STORED_COMPRESSED;
+
+/// Fingerprint scheme version, hashed in so later schemes never collide
+/// with this one.
+const FINGERPRINT_VERSION: &[u8] = b"egdata-fingerprint-v1";
+
+/// SHA-256 over the manifest's header and stored payload (binary
+/// manifests) or its text without a BOM and surrounding whitespace (JSON).
+/// Bytes past the end of a binary manifest's payload are ignored, so a
+/// download with trailing junk matches the clean file. Input that is
+/// neither is hashed whole.
+pub fn fingerprint(buf: &[u8]) -> [u8; 32] {
+    let sections: Vec<(&[u8], &[u8])> = match detect_format(buf) {
+        ManifestFormat::BinaryV1 | ManifestFormat::BinaryV2Plus { .. } => match binary_sections(buf) {
+            Some((header, payload)) => vec![(b"header", header), (b"payload", payload)],
+            None => vec![(b"raw", buf)],
+        },
+        ManifestFormat::Json => {
+            let text = buf.strip_prefix(b"\xEF\xBB\xBF").unwrap_or(buf);
+            vec![(b"json", text.trim_ascii())]
+        }
+        ManifestFormat::Unknown => vec![(b"raw", buf)],
+    };
+
+    let mut hasher = Sha256::new();
+    hasher.update(FINGERPRINT_VERSION);
+    for (name, bytes) in sections {
+        hasher.update(name);
+        hasher.update(Sha256::digest(bytes));
+    }
+    hasher.finalize().into()
+}
+
+/// [`fingerprint`] in hex.
+pub fn fingerprint_hex(buf: &[u8]) -> String {
+    hex::encode(fingerprint(buf))
+}
+
+/// The header and stored payload of a binary manifest, as far as `buf`
+/// holds them; `None` when the header itself is cut off.
+fn binary_sections(buf: &[u8]) -> Option<(&[u8], &[u8])> {
+    // magic, header size, data sizes, SHA-1, then stored_as.
+    let header_size = usize::try_from(LittleEndian::read_i32(buf.get(4..8)?)).ok()?;
+    let uncompressed = LittleEndian::read_u32(buf.get(8..12)?) as usize;
+    let compressed = LittleEndian::read_u32(buf.get(12..16)?) as usize;
+    let stored_as = *buf.get(36)?;
+    let header = buf.get(..header_size)?;
+    let size = if stored_as & STORED_COMPRESSED != 0 { compressed } else { uncompressed };
+    let end = header_size.saturating_add(size).min(buf.len());
+    Some((header, &buf[header_size..end]))
+}
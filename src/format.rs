@@ -0,0 +1,40 @@
+//! Named constants of Epic's manifest and chunk file formats.
+
+/// First four bytes (little-endian) of a binary manifest.
+pub const MANIFEST_MAGIC: u32 = 0x44BEC00C;
+
+/// Manifest header size before the version field was added.
+pub const MANIFEST_HEADER_SIZE_V1: u32 = 37;
+
+/// Manifest header size with the trailing version field.
+pub const MANIFEST_HEADER_SIZE_V2: u32 = 41;
+
+/// Manifest header size of serializations that append the build GUID after
+/// the version field.
+pub const MANIFEST_HEADER_SIZE_GUID: u32 = 57;
+
+/// Manifest header size of serializations that also carry the rolling hash
+/// and hash type after the GUID.
+pub const MANIFEST_HEADER_SIZE_HASH_TYPE: u32 = 66;
+
+/// First four bytes (little-endian) of a `.chunk` file.
+pub const CHUNK_MAGIC: u32 = 0xB1FE3AA2;
+
+/// Chunk header size of header version 1.
+pub const CHUNK_HEADER_SIZE_V1: u32 = 41;
+
+/// Chunk header size of header version 2, which adds the SHA-1 and hash type.
+pub const CHUNK_HEADER_SIZE_V2: u32 = 62;
+
+/// Chunk header size of header version 3, which adds the uncompressed size.
+pub const CHUNK_HEADER_SIZE_V3: u32 = 66;
+
+/// Uncompressed size of a standard chunk window (1 MiB).
+pub const CHUNK_WINDOW_SIZE: u32 = 1024 * 1024;
+
+/// Number of CDN chunk groups; chunk directories are named `00` to `99`.
+pub const CHUNK_GROUP_COUNT: u32 = 100;
+
+/// Serialized size of a chunk part as known to this parser: data size,
+/// GUID, offset and size.
+pub const CHUNK_PART_BASE_SIZE: u32 = 28;
@@ -0,0 +1,166 @@
+//! One-call triage of uploaded manifests.
+//!
+//! [`assess`] runs the truncation estimate, a parse with payload hash check
+//! and the chunk window checks, and boils them down to a [`ManifestHealth`]
+//! that says whether the file can be used or should be uploaded again.
+
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "napi")]
+use napi_derive::napi;
+use std::ops::ControlFlow;
+
+use crate::error::ManifestError;
+use crate::hooks::{ParserHooks, Section};
+use crate::options::ParseOptions;
+use crate::report::PayloadHashCheck;
+use crate::truncation::estimate_truncation;
+use crate::types::header::ManifestHeader;
+
+/// Verdict of [`assess`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", tag = "status")]
+pub enum ManifestHealth {
+    Ok,
+    /// The file ends early; a re-download should fix it. `missing` is the
+    /// number of bytes short (at least that many when the header itself is
+    /// cut), unknown for JSON manifests.
+    Truncated { missing: Option<u64> },
+    /// The file is complete but its contents are damaged, first noticed in
+    /// `section`.
+    Corrupt { section: Section },
+    /// Not something this parser can check, such as an encrypted manifest.
+    Unsupported { reason: String },
+}
+
+impl ManifestHealth {
+    pub fn is_ok(&self) -> bool {
+        *self == ManifestHealth::Ok
+    }
+
+    /// Whether asking for the file again is likely to help.
+    pub fn needs_redownload(&self) -> bool {
+        matches!(self, ManifestHealth::Truncated { .. } | ManifestHealth::Corrupt { .. })
+    }
+}
+
+/// NAPI view of [`ManifestHealth`]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[cfg_attr(feature = "napi", napi(object))]
+pub struct ManifestHealthInfo {
+    /// `"ok"`, `"truncated"`, `"corrupt"` or `"unsupported"`
+    pub status: String,
+    /// Bytes missing from a truncated file, when known
+    pub missing: Option<i64>,
+    /// Section where corruption was found
+    pub section: Option<String>,
+    /// Why the file could not be checked
+    pub reason: Option<String>,
+}
+
+impl From<ManifestHealth> for ManifestHealthInfo {
+    fn from(health: ManifestHealth) -> Self {
+        let mut info = Self {
+            status: String::new(),
+            missing: None,
+            section: None,
+            reason: None,
+        };
+        info.status = match health {
+            ManifestHealth::Ok => "ok",
+            ManifestHealth::Truncated { missing } => {
+                info.missing = missing.map(|m| m as i64);
+                "truncated"
+            }
+            ManifestHealth::Corrupt { section } => {
+                info.section = Some(section.to_string());
+                "corrupt"
+            }
+            ManifestHealth::Unsupported { reason } => {
+                info.reason = Some(reason);
+                "unsupported"
+            }
+        }
+        .to_string();
+        info
+    }
+}
+
+/// Tracks the section being read and the first one that needed recovery.
+#[derive(Default)]
+struct SectionTracker {
+    current: Option<Section>,
+    recovered: Option<Section>,
+}
+
+impl ParserHooks for SectionTracker {
+    fn on_section_start(&mut self, section: Section, _offset: u64) -> ControlFlow<()> {
+        self.current = Some(section);
+        ControlFlow::Continue(())
+    }
+
+    fn on_recovered_error(&mut self, section: Section, _message: &str) {
+        self.recovered.get_or_insert(section);
+    }
+}
+
+/// Decide whether the manifest in `buf` is usable, truncated or corrupt.
+pub fn assess(buf: &[u8]) -> ManifestHealth {
+    assess_with(buf, &ParseOptions::default())
+}
+
+/// [`assess`] parsing under `options`, such as a deadline or higher
+/// limits. Running into either makes the file [`ManifestHealth::Unsupported`],
+/// not corrupt.
+pub fn assess_with(buf: &[u8], options: &ParseOptions) -> ManifestHealth {
+    let truncation = match estimate_truncation(buf) {
+        Ok(truncation) => truncation,
+        Err(e) => return ManifestHealth::Unsupported { reason: e.to_string() },
+    };
+    // Only bytes missing from the input count as truncation; a complete
+    // payload whose sections come up short is damaged instead.
+    let header_missing = truncation.sections.first().map_or(0, |header| header.missing());
+    if truncation.json_ends_early || header_missing > 0 || truncation.missing_bytes.is_some_and(|m| m > 0) {
+        return ManifestHealth::Truncated {
+            missing: truncation.missing_bytes.or((header_missing > 0).then_some(header_missing)),
+        };
+    }
+    if let Some(section) = truncation.first_incomplete() {
+        return ManifestHealth::Corrupt {
+            section: section.section,
+        };
+    }
+    if ManifestHeader::read(std::io::Cursor::new(buf)).is_ok_and(|h| h.is_encrypted()) {
+        return ManifestHealth::Unsupported {
+            reason: "encrypted manifest".to_string(),
+        };
+    }
+
+    let mut tracker = SectionTracker::default();
+    let (manifest, report) = match crate::parse_with_hooks(buf, options, &mut tracker) {
+        Ok(parsed) => parsed,
+        // Limits, deadlines and keys say nothing about the file itself.
+        Err(
+            e @ (ManifestError::LimitExceeded { .. }
+            | ManifestError::Timeout { .. }
+            | ManifestError::EncryptedManifest
+            | ManifestError::EncryptedChunk(_)),
+        ) => return ManifestHealth::Unsupported { reason: e.to_string() },
+        Err(_) => {
+            return ManifestHealth::Corrupt {
+                section: tracker.current.unwrap_or(Section::Header),
+            }
+        }
+    };
+    if report.payload_hash == PayloadHashCheck::Mismatched {
+        return ManifestHealth::Corrupt { section: Section::Payload };
+    }
+    if let Some(section) = tracker.recovered {
+        return ManifestHealth::Corrupt { section };
+    }
+    if !manifest.check_chunk_windows().is_clean() {
+        return ManifestHealth::Corrupt {
+            section: Section::ChunkList,
+        };
+    }
+    ManifestHealth::Ok
+}
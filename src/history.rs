@@ -0,0 +1,162 @@
+//! Build history for one app: what each tracked build looked like and what
+//! moving between builds costs.
+//!
+//! A [`BuildRecord`] keeps a manifest's identity, sizes and chunk sizes, so
+//! patch sizes can be computed long after the manifests themselves are gone.
+//! The history serializes to JSON for storage.
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+
+use crate::error::ManifestError;
+use crate::types::manifest::Manifest;
+
+/// One tracked build.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BuildRecord {
+    pub build_id: String,
+    pub build_version: String,
+    /// When the build was seen, in seconds since the Unix epoch.
+    pub recorded_at: i64,
+    /// Sum of the installed file sizes.
+    pub install_size: u64,
+    /// Sum of the compressed chunk sizes: a fresh download.
+    pub download_size: u64,
+    pub file_count: u32,
+    /// Hex SHA-256 of the manifest's [`Manifest::canonical_json`].
+    pub content_hash: String,
+    /// Compressed size of every chunk, by GUID.
+    #[serde(default)]
+    pub chunks: BTreeMap<String, u64>,
+}
+
+impl BuildRecord {
+    pub fn from_manifest(manifest: &Manifest, recorded_at: i64) -> Result<Self, ManifestError> {
+        let meta = manifest.meta.as_ref();
+        let files = manifest.file_list.iter().flat_map(|f| &f.file_manifest_list);
+        let chunks: BTreeMap<String, u64> = manifest
+            .chunk_list
+            .iter()
+            .flat_map(|c| &c.elements)
+            .map(|c| (c.guid.clone(), c.file_size.parse().unwrap_or_default()))
+            .collect();
+        Ok(Self {
+            build_id: meta.map(|m| m.build_id()).unwrap_or_default(),
+            build_version: meta
+                .map(|m| m.build_version.trim_end_matches('\0').to_string())
+                .unwrap_or_default(),
+            recorded_at,
+            install_size: files.clone().map(|f| f.file_size.max(0) as u64).sum(),
+            download_size: chunks.values().sum(),
+            file_count: files.count() as u32,
+            content_hash: hex::encode(Sha256::digest(manifest.canonical_json()?)),
+            chunks,
+        })
+    }
+
+    /// Bytes to download to go from `from` to this build: chunks `from`
+    /// does not have.
+    pub fn patch_size_from(&self, from: &BuildRecord) -> u64 {
+        self.chunks
+            .iter()
+            .filter(|(guid, _)| !from.chunks.contains_key(*guid))
+            .map(|(_, size)| size)
+            .sum()
+    }
+}
+
+/// Builds of one app, oldest first.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BuildHistory {
+    pub app_name: String,
+    builds: Vec<BuildRecord>,
+}
+
+impl BuildHistory {
+    pub fn new(app_name: impl Into<String>) -> Self {
+        Self {
+            app_name: app_name.into(),
+            builds: Vec::new(),
+        }
+    }
+
+    /// Add a build, keeping the history ordered by `recorded_at`. A build id
+    /// already present is rejected unless its content hash matches, in
+    /// which case nothing changes.
+    pub fn append(&mut self, record: BuildRecord) -> Result<&BuildRecord, ManifestError> {
+        if let Some(index) = self.builds.iter().position(|b| b.build_id == record.build_id) {
+            if self.builds[index].content_hash != record.content_hash {
+                return Err(ManifestError::Invalid(format!(
+                    "build {} is already recorded with different content",
+                    record.build_id
+                )));
+            }
+            return Ok(&self.builds[index]);
+        }
+        let index = self.builds.partition_point(|b| b.recorded_at <= record.recorded_at);
+        self.builds.insert(index, record);
+        Ok(&self.builds[index])
+    }
+
+    /// [`BuildHistory::append`] a record built from `manifest`.
+    pub fn record(&mut self, manifest: &Manifest, recorded_at: i64) -> Result<&BuildRecord, ManifestError> {
+        self.append(BuildRecord::from_manifest(manifest, recorded_at)?)
+    }
+
+    pub fn builds(&self) -> &[BuildRecord] {
+        &self.builds
+    }
+
+    pub fn len(&self) -> usize {
+        self.builds.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.builds.is_empty()
+    }
+
+    pub fn latest(&self) -> Option<&BuildRecord> {
+        self.builds.last()
+    }
+
+    pub fn get(&self, build_id: &str) -> Option<&BuildRecord> {
+        self.builds.iter().find(|b| b.build_id == build_id)
+    }
+
+    /// Builds recorded in `[from, to)` (Unix seconds).
+    pub fn between(&self, from: i64, to: i64) -> &[BuildRecord] {
+        let start = self.builds.partition_point(|b| b.recorded_at < from);
+        let end = self.builds.partition_point(|b| b.recorded_at < to);
+        &self.builds[start..end.max(start)]
+    }
+
+    /// Download size of updating straight from `from_id` to `to_id`.
+    pub fn patch_size(&self, from_id: &str, to_id: &str) -> Option<u64> {
+        Some(self.get(to_id)?.patch_size_from(self.get(from_id)?))
+    }
+
+    /// Download size of taking every update from `from_id` up to `to_id`,
+    /// as a user who patched at each build would have. `None` if either id
+    /// is unknown or `to_id` was recorded before `from_id`.
+    pub fn cumulative_patch_size(&self, from_id: &str, to_id: &str) -> Option<u64> {
+        let from = self.builds.iter().position(|b| b.build_id == from_id)?;
+        let to = self.builds.iter().position(|b| b.build_id == to_id)?;
+        if to < from {
+            return None;
+        }
+        Some(self.builds[from..=to].windows(2).map(|pair| pair[1].patch_size_from(&pair[0])).sum())
+    }
+
+    pub fn to_json(&self) -> Result<String, ManifestError> {
+        Ok(serde_json::to_string(self)?)
+    }
+
+    pub fn from_json(json: &str) -> Result<Self, ManifestError> {
+        let mut history: Self = serde_json::from_str(json)?;
+        history.builds.sort_by_key(|b| b.recorded_at);
+        Ok(history)
+    }
+}
@@ -0,0 +1,137 @@
+//! Callbacks into the parser for metrics and early exit.
+//!
+//! Implement [`ParserHooks`] and pass it to [`crate::parse_with_hooks`] to
+//! observe sections as they are read, collect warnings and recovered errors
+//! without scraping the log, or stop parsing once the part you need is done.
+//! Every method has a no-op default, and [`crate::parse_with_report`] runs
+//! with [`NoHooks`], so unused hooks cost next to nothing.
+
+use serde::{Deserialize, Serialize};
+use std::ops::ControlFlow;
+
+/// Parts of a manifest, in the order they are read.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Section {
+    /// The fixed binary header.
+    Header,
+    /// Decompressing and hashing the payload.
+    Payload,
+    Meta,
+    ChunkList,
+    FileList,
+    CustomFields,
+    /// A whole JSON manifest.
+    Json,
+}
+
+impl std::fmt::Display for Section {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Section::Header => "header",
+            Section::Payload => "payload",
+            Section::Meta => "meta",
+            Section::ChunkList => "chunk list",
+            Section::FileList => "file list",
+            Section::CustomFields => "custom fields",
+            Section::Json => "JSON manifest",
+        })
+    }
+}
+
+/// Something odd that did not stop parsing.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseWarning {
+    /// The manifest was found past a preamble, at this byte offset.
+    EmbeddedManifest { offset: usize },
+    /// The payload does not hash to the header's SHA-1.
+    PayloadHashMismatch,
+    /// The header names a payload hash this parser cannot check.
+    PayloadHashUnsupported { hash_type: u32 },
+    /// A section has a newer data version than this parser knows; see
+    /// [`crate::schema`].
+    NewerSectionVersion { section: Section, data_version: u8 },
+    /// A section holds bytes past the fields this parser knows, kept in
+    /// its `extra_data`.
+    UnknownSectionData { section: Section, bytes: usize },
+    /// A custom field key stored more than once; lookups use the first.
+    DuplicateCustomField { key: String },
+    /// Parsing skipped to `section`, found by scanning from an unreadable
+    /// section before it, at this payload offset.
+    Resynchronized { section: Section, offset: u64 },
+}
+
+impl std::fmt::Display for ParseWarning {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ParseWarning::EmbeddedManifest { offset } => write!(f, "manifest found at byte offset {}", offset),
+            ParseWarning::PayloadHashMismatch => f.write_str("payload does not match the header SHA-1"),
+            ParseWarning::PayloadHashUnsupported { hash_type } => {
+                write!(f, "payload hash type {} is not supported", hash_type)
+            }
+            ParseWarning::NewerSectionVersion { section, data_version } => {
+                write!(f, "{} data version {} is newer than this parser", section, data_version)
+            }
+            ParseWarning::UnknownSectionData { section, bytes } => {
+                write!(f, "{} bytes of unknown data in the {}", bytes, section)
+            }
+            ParseWarning::DuplicateCustomField { key } => write!(f, "custom field {} is stored more than once", key),
+            ParseWarning::Resynchronized { section, offset } => {
+                write!(f, "skipped to the {} at payload offset {}", section, offset)
+            }
+        }
+    }
+}
+
+/// Parser callbacks. Offsets are into the input for [`Section::Header`],
+/// [`Section::Payload`] and [`Section::Json`], and into the decompressed
+/// payload otherwise.
+pub trait ParserHooks {
+    /// About to read `section`. Returning [`ControlFlow::Break`] aborts the
+    /// parse with [`crate::ManifestError::Aborted`].
+    fn on_section_start(&mut self, section: Section, offset: u64) -> ControlFlow<()> {
+        let _ = (section, offset);
+        ControlFlow::Continue(())
+    }
+
+    /// Finished `section`, which spanned `size` bytes. Breaking here aborts
+    /// before the next section.
+    fn on_section_end(&mut self, section: Section, size: u64) -> ControlFlow<()> {
+        let _ = (section, size);
+        ControlFlow::Continue(())
+    }
+
+    fn on_warning(&mut self, warning: &ParseWarning) {
+        let _ = warning;
+    }
+
+    /// A problem the parser worked around, such as unreadable meta or a
+    /// file whose chunk parts were corrupt.
+    fn on_recovered_error(&mut self, section: Section, message: &str) {
+        let _ = (section, message);
+    }
+}
+
+/// Hooks that do nothing.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct NoHooks;
+
+impl ParserHooks for NoHooks {}
+
+impl<H: ParserHooks + ?Sized> ParserHooks for &mut H {
+    fn on_section_start(&mut self, section: Section, offset: u64) -> ControlFlow<()> {
+        (**self).on_section_start(section, offset)
+    }
+
+    fn on_section_end(&mut self, section: Section, size: u64) -> ControlFlow<()> {
+        (**self).on_section_end(section, size)
+    }
+
+    fn on_warning(&mut self, warning: &ParseWarning) {
+        (**self).on_warning(warning)
+    }
+
+    fn on_recovered_error(&mut self, section: Section, message: &str) {
+        (**self).on_recovered_error(section, message)
+    }
+}
@@ -0,0 +1,185 @@
+//! Compact chunk sets for comparing many builds.
+//!
+//! A [`ChunkBloom`] records which chunks a build has in a Bloom filter, a
+//! few bits per chunk instead of a GUID each. Filters built with the same
+//! [`BloomParams`] can be intersected without the manifests, which makes
+//! approximate update sizes across thousands of build pairs affordable.
+//! Every estimate is approximate: membership has false positives at the
+//! configured rate, and counts come from the fraction of bits set.
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use sha2::{Digest, Sha256};
+use uuid::Uuid;
+
+use crate::error::ManifestError;
+use crate::types::chunk::normalize_guid;
+use crate::types::manifest::Manifest;
+
+/// Size of a filter. Only filters with equal parameters can be compared,
+/// so pick one set for every build that will be compared.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BloomParams {
+    /// Filter size in bits, a multiple of 64.
+    pub bits: u32,
+    /// Bit positions set per chunk.
+    pub hashes: u32,
+}
+
+impl BloomParams {
+    /// The smallest filter keeping the false-positive rate at
+    /// `false_positive_rate` with `expected_chunks` chunks in it.
+    pub fn for_capacity(expected_chunks: u32, false_positive_rate: f64) -> Self {
+        let n = expected_chunks.max(1) as f64;
+        let p = false_positive_rate.clamp(1e-9, 0.5);
+        let ln2 = std::f64::consts::LN_2;
+        let bits = (-n * p.ln() / (ln2 * ln2)).ceil().min(u32::MAX as f64 - 63.0) as u32;
+        let bits = bits.div_ceil(64).max(1) * 64;
+        let hashes = ((bits as f64 / n) * ln2).round().clamp(1.0, 32.0) as u32;
+        Self { bits, hashes }
+    }
+}
+
+/// Bloom filter over the chunk GUIDs of one build.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ChunkBloom {
+    pub params: BloomParams,
+    /// Chunks inserted.
+    pub chunk_count: u32,
+    /// Sum of the inserted chunks' compressed sizes, for
+    /// [`ChunkBloom::estimate_patch_size`].
+    pub download_size: u64,
+    /// Base64 of the little-endian words.
+    #[serde(with = "words")]
+    bits: Vec<u64>,
+}
+
+impl ChunkBloom {
+    pub fn new(params: BloomParams) -> Self {
+        Self {
+            params,
+            chunk_count: 0,
+            download_size: 0,
+            bits: vec![0; params.bits.div_ceil(64) as usize],
+        }
+    }
+
+    /// A filter of every chunk in `manifest`'s chunk list.
+    pub fn from_manifest(manifest: &Manifest, params: BloomParams) -> Self {
+        let mut filter = Self::new(params);
+        for chunk in manifest.chunk_list.iter().flat_map(|c| &c.elements) {
+            filter.insert(&chunk.guid);
+            filter.download_size += chunk.file_size.parse::<u64>().unwrap_or_default();
+        }
+        filter
+    }
+
+    /// Add a GUID, written hyphenated or in Epic's 32-hex form.
+    pub fn insert(&mut self, guid: &str) {
+        for bit in self.positions(guid) {
+            self.bits[bit / 64] |= 1 << (bit % 64);
+        }
+        self.chunk_count += 1;
+    }
+
+    /// Whether `guid` may be in the filter; `false` is always right.
+    pub fn contains(&self, guid: &str) -> bool {
+        self.positions(guid).all(|bit| self.bits[bit / 64] & (1 << (bit % 64)) != 0)
+    }
+
+    /// Chunks in both filters, estimated from the size of their union.
+    /// `None` if the filters were built with different parameters.
+    pub fn estimate_intersection(&self, other: &ChunkBloom) -> Option<f64> {
+        let (_, both) = self.estimate_overlap(other)?;
+        Some(both.clamp(0.0, self.chunk_count.min(other.chunk_count) as f64))
+    }
+
+    /// Bytes to download to go from `from` to this build: this build's
+    /// download size, scaled by the share of its chunks `from` lacks.
+    pub fn estimate_patch_size(&self, from: &ChunkBloom) -> Option<u64> {
+        let (own, both) = self.estimate_overlap(from)?;
+        if own <= 0.0 {
+            return Some(0);
+        }
+        // Both counts come from the same estimator, so identical filters
+        // cancel out exactly.
+        let new = ((own - both) / own).clamp(0.0, 1.0);
+        Some((self.download_size as f64 * new).round() as u64)
+    }
+
+    /// Estimated chunks in `self`, and in both filters.
+    fn estimate_overlap(&self, other: &ChunkBloom) -> Option<(f64, f64)> {
+        if self.params != other.params {
+            return None;
+        }
+        let ones = |f: &ChunkBloom| f.bits.iter().map(|w| w.count_ones()).sum();
+        let union = self.bits.iter().zip(&other.bits).map(|(a, b)| (a | b).count_ones()).sum();
+        let own = self.estimate_count(ones(self));
+        let both = own + self.estimate_count(ones(other)) - self.estimate_count(union);
+        Some((own, both))
+    }
+
+    /// Distinct items behind `ones` set bits (Swamidass and Baldi). A full
+    /// filter counts as all but half a bit set, the most it can tell.
+    fn estimate_count(&self, ones: u32) -> f64 {
+        let m = self.params.bits as f64;
+        let k = self.params.hashes as f64;
+        let ones = (ones as f64).min(m - 0.5);
+        -m / k * (1.0 - ones / m).ln()
+    }
+
+    /// Bit positions of `guid`, by double hashing. GUIDs are random, so
+    /// their own 128 bits serve as the two hashes; anything else is hashed
+    /// first.
+    fn positions(&self, guid: &str) -> impl Iterator<Item = usize> {
+        let bytes = match normalize_guid(guid).and_then(|g| Uuid::try_parse(&g).ok()) {
+            Some(uuid) => *uuid.as_bytes(),
+            None => Sha256::digest(guid.as_bytes())[..16].try_into().expect("16 bytes"),
+        };
+        let h1 = u64::from_le_bytes(bytes[..8].try_into().expect("8 bytes"));
+        let h2 = u64::from_le_bytes(bytes[8..].try_into().expect("8 bytes")) | 1;
+        let bits = self.params.bits as u64;
+        (0..self.params.hashes as u64).map(move |i| (h1.wrapping_add(i.wrapping_mul(h2)) % bits) as usize)
+    }
+
+    pub fn to_json(&self) -> Result<String, ManifestError> {
+        Ok(serde_json::to_string(self)?)
+    }
+
+    pub fn from_json(json: &str) -> Result<Self, ManifestError> {
+        let filter: Self = serde_json::from_str(json)?;
+        if filter.params.bits == 0
+            || filter.params.hashes == 0
+            || filter.bits.len() != filter.params.bits.div_ceil(64) as usize
+        {
+            return Err(ManifestError::Invalid(
+                "chunk bloom filter size does not match its parameters".to_string(),
+            ));
+        }
+        Ok(filter)
+    }
+}
+
+mod words {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(words: &[u64], serializer: S) -> Result<S::Ok, S::Error> {
+        let bytes: Vec<u8> = words.iter().flat_map(|w| w.to_le_bytes()).collect();
+        serializer.serialize_str(&STANDARD.encode(bytes))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<u64>, D::Error> {
+        let bytes = STANDARD
+            .decode(String::deserialize(deserializer)?)
+            .map_err(serde::de::Error::custom)?;
+        if bytes.len() % 8 != 0 {
+            return Err(serde::de::Error::custom("bloom filter bits are not whole words"));
+        }
+        Ok(bytes
+            .chunks_exact(8)
+            .map(|w| u64::from_le_bytes(w.try_into().expect("8 bytes")))
+            .collect())
+    }
+}
@@ -0,0 +1,100 @@
+use std::collections::BTreeMap;
+
+use crate::types::file::FileManifestList;
+
+#[derive(Debug, Default)]
+struct DirNode {
+    children: BTreeMap<String, usize>,
+    /// Files directly in this directory, as indices into the file list.
+    files: Vec<u32>,
+    /// Size and count of everything below this directory.
+    total_size: i64,
+    total_files: u32,
+}
+
+/// Directory tree over a file list, answering directory queries in time
+/// proportional to the path depth instead of scanning every file.
+///
+/// Directory arguments are `/`-separated; leading and trailing slashes are
+/// ignored and `""` is the root.
+#[derive(Debug)]
+pub struct DirectoryIndex {
+    nodes: Vec<DirNode>,
+}
+
+impl DirectoryIndex {
+    pub fn build(file_list: &FileManifestList) -> Self {
+        let mut nodes = vec![DirNode::default()];
+
+        for (i, file) in file_list.file_manifest_list.iter().enumerate() {
+            let path = file.filename.trim_end_matches('\0');
+            let mut components: Vec<&str> = path.split('/').filter(|c| !c.is_empty()).collect();
+            // The last component is the file itself.
+            components.pop();
+
+            let mut node = 0;
+            nodes[node].total_size += file.file_size;
+            nodes[node].total_files += 1;
+            for component in components {
+                node = match nodes[node].children.get(component) {
+                    Some(&child) => child,
+                    None => {
+                        nodes.push(DirNode::default());
+                        let child = nodes.len() - 1;
+                        nodes[node].children.insert(component.to_string(), child);
+                        child
+                    }
+                };
+                nodes[node].total_size += file.file_size;
+                nodes[node].total_files += 1;
+            }
+            nodes[node].files.push(i as u32);
+        }
+
+        Self { nodes }
+    }
+
+    fn find(&self, dir: &str) -> Option<&DirNode> {
+        let mut node = &self.nodes[0];
+        for component in dir.split('/').filter(|c| !c.is_empty()) {
+            node = &self.nodes[*node.children.get(component)?];
+        }
+        Some(node)
+    }
+
+    /// Whether `dir` exists (contains at least one file somewhere below it).
+    pub fn contains_dir(&self, dir: &str) -> bool {
+        self.find(dir).is_some()
+    }
+
+    /// Indices of every file below `dir`, recursively.
+    pub fn files_under(&self, dir: &str) -> Vec<u32> {
+        let Some(start) = self.find(dir) else {
+            return Vec::new();
+        };
+        let mut out = Vec::with_capacity(start.total_files as usize);
+        let mut stack = vec![start];
+        while let Some(node) = stack.pop() {
+            out.extend_from_slice(&node.files);
+            stack.extend(node.children.values().rev().map(|&c| &self.nodes[c]));
+        }
+        out
+    }
+
+    /// Total `file_size` of everything below `dir`.
+    pub fn dir_size(&self, dir: &str) -> i64 {
+        self.find(dir).map(|n| n.total_size).unwrap_or(0)
+    }
+
+    /// Number of files below `dir`, recursively.
+    pub fn file_count(&self, dir: &str) -> u32 {
+        self.find(dir).map(|n| n.total_files).unwrap_or(0)
+    }
+
+    /// Names of the immediate subdirectories of `dir`, sorted.
+    pub fn subdirs(&self, dir: &str) -> Vec<String> {
+        self.find(dir)
+            .map(|n| n.children.keys().cloned().collect())
+            .unwrap_or_default()
+    }
+}
@@ -0,0 +1,161 @@
+//! Search across many builds.
+//!
+//! [`ManifestIndex`] maps file paths and chunk GUIDs to the builds that
+//! contain them, so "which builds ship `Engine/Binaries/Win64/Game.exe`"
+//! is one lookup instead of a query per manifest. Only the postings are
+//! kept, not the manifests, and the index serializes to JSON for storage.
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::ops::Bound;
+
+use crate::error::ManifestError;
+use crate::types::chunk::normalize_guid;
+use crate::types::manifest::Manifest;
+
+/// A build known to a [`ManifestIndex`].
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IndexedBuild {
+    pub build_id: String,
+    pub app_name: String,
+    pub build_version: String,
+}
+
+/// File paths and chunk GUIDs of many builds, each mapped to the builds
+/// containing them in the order they were added.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ManifestIndex {
+    builds: Vec<IndexedBuild>,
+    /// Paths without trailing NULs, to indices into `builds`.
+    files: BTreeMap<String, Vec<u32>>,
+    /// Hyphenated GUIDs, to indices into `builds`.
+    chunks: BTreeMap<String, Vec<u32>>,
+}
+
+impl ManifestIndex {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Index `manifest` under its build id. A build id already indexed is
+    /// indexed again from `manifest`, replacing what was recorded for it.
+    pub fn add(&mut self, manifest: &Manifest) -> &IndexedBuild {
+        let meta = manifest.meta.as_ref();
+        let build = IndexedBuild {
+            build_id: meta.map(|m| m.build_id()).unwrap_or_default(),
+            app_name: meta
+                .map(|m| m.app_name.trim_end_matches('\0').to_string())
+                .unwrap_or_default(),
+            build_version: meta
+                .map(|m| m.build_version.trim_end_matches('\0').to_string())
+                .unwrap_or_default(),
+        };
+        self.remove(&build.build_id);
+        let index = self.builds.len() as u32;
+
+        for file in manifest.file_list.iter().flat_map(|f| &f.file_manifest_list) {
+            post(&mut self.files, file.filename.trim_end_matches('\0'), index);
+        }
+        for chunk in manifest.chunk_list.iter().flat_map(|c| &c.elements) {
+            post(&mut self.chunks, &normalize_guid(&chunk.guid).unwrap_or_else(|| chunk.guid.clone()), index);
+        }
+        self.builds.push(build);
+        &self.builds[index as usize]
+    }
+
+    /// Forget `build_id`; returns whether it was indexed.
+    pub fn remove(&mut self, build_id: &str) -> bool {
+        let Some(index) = self.builds.iter().position(|b| b.build_id == build_id) else {
+            return false;
+        };
+        self.builds.remove(index);
+        let index = index as u32;
+        for postings in [&mut self.files, &mut self.chunks] {
+            postings.retain(|_, builds| {
+                builds.retain(|&b| b != index);
+                for b in builds.iter_mut().filter(|b| **b > index) {
+                    *b -= 1;
+                }
+                !builds.is_empty()
+            });
+        }
+        true
+    }
+
+    /// Builds in the order they were added.
+    pub fn builds(&self) -> &[IndexedBuild] {
+        &self.builds
+    }
+
+    pub fn len(&self) -> usize {
+        self.builds.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.builds.is_empty()
+    }
+
+    /// Builds containing a file at exactly `path`.
+    pub fn builds_with_file(&self, path: &str) -> Vec<&IndexedBuild> {
+        self.resolve(self.files.get(path))
+    }
+
+    /// Builds whose chunk list has `guid`, written hyphenated or in Epic's
+    /// 32-hex form.
+    pub fn builds_with_chunk(&self, guid: &str) -> Vec<&IndexedBuild> {
+        let guid = normalize_guid(guid).unwrap_or_else(|| guid.to_string());
+        self.resolve(self.chunks.get(&guid))
+    }
+
+    /// Every indexed path starting with `prefix` (e.g. `Engine/Binaries/`),
+    /// in path order, with the builds containing it.
+    pub fn files_with_prefix<'a>(
+        &'a self,
+        prefix: &'a str,
+    ) -> impl Iterator<Item = (&'a str, Vec<&'a IndexedBuild>)> + 'a {
+        self.files
+            .range::<str, _>((Bound::Included(prefix), Bound::Unbounded))
+            .take_while(move |(path, _)| path.starts_with(prefix))
+            .map(|(path, builds)| (path.as_str(), self.resolve(Some(builds))))
+    }
+
+    fn resolve(&self, postings: Option<&Vec<u32>>) -> Vec<&IndexedBuild> {
+        postings
+            .into_iter()
+            .flatten()
+            .map(|&b| &self.builds[b as usize])
+            .collect()
+    }
+
+    pub fn to_json(&self) -> Result<String, ManifestError> {
+        Ok(serde_json::to_string(self)?)
+    }
+
+    /// Read an index written by [`ManifestIndex::to_json`], rejecting one
+    /// that refers to builds it does not list.
+    pub fn from_json(json: &str) -> Result<Self, ManifestError> {
+        let index: Self = serde_json::from_str(json)?;
+        let builds = index.builds.len() as u32;
+        let dangling = index.files.iter().chain(&index.chunks).find(|(_, b)| b.iter().any(|&b| b >= builds));
+        if let Some((key, _)) = dangling {
+            return Err(ManifestError::Invalid(format!(
+                "manifest index entry {} refers to a build out of range",
+                key
+            )));
+        }
+        Ok(index)
+    }
+}
+
+fn post(postings: &mut BTreeMap<String, Vec<u32>>, key: &str, build: u32) {
+    let builds = match postings.get_mut(key) {
+        Some(builds) => builds,
+        None => postings.entry(key.to_string()).or_default(),
+    };
+    // A file list may name a path twice; keep each build once.
+    if builds.last() != Some(&build) {
+        builds.push(build);
+    }
+}
@@ -0,0 +1,72 @@
+//! What an install, repair or GC does (or would do, in a dry run), as a flat
+//! list a UI can show for confirmation before anything is touched.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+
+use crate::types::file::FileManifest;
+use crate::types::manifest::Manifest;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum Action {
+    /// Fetch a chunk; `bytes` is its compressed (download) size.
+    Download { guid: String, bytes: u64 },
+    /// Create or overwrite a file; `path` is relative to the target directory.
+    Write { path: String, bytes: u64 },
+    /// Delete a file; `path` is relative to the directory operated on.
+    Delete { path: String, bytes: u64 },
+}
+
+/// Actions of one operation, in the order they are (or would be) performed.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ActionList {
+    /// Set when nothing was actually done.
+    pub dry_run: bool,
+    pub actions: Vec<Action>,
+}
+
+impl ActionList {
+    pub fn download_bytes(&self) -> u64 {
+        self.sum(|a| matches!(a, Action::Download { .. }))
+    }
+
+    pub fn write_bytes(&self) -> u64 {
+        self.sum(|a| matches!(a, Action::Write { .. }))
+    }
+
+    pub fn delete_bytes(&self) -> u64 {
+        self.sum(|a| matches!(a, Action::Delete { .. }))
+    }
+
+    fn sum(&self, kind: impl Fn(&Action) -> bool) -> u64 {
+        self.actions
+            .iter()
+            .filter(|a| kind(a))
+            .map(|a| match a {
+                Action::Download { bytes, .. } | Action::Write { bytes, .. } | Action::Delete { bytes, .. } => *bytes,
+            })
+            .sum()
+    }
+}
+
+/// Download actions for every chunk `files` reference, in first-use order.
+pub(crate) fn chunk_downloads<'a>(manifest: &Manifest, files: impl IntoIterator<Item = &'a FileManifest>) -> Vec<Action> {
+    let chunk_list = manifest.chunk_list.as_ref();
+    let mut seen = HashSet::new();
+    files
+        .into_iter()
+        .flat_map(|f| &f.chunk_parts)
+        .filter(|p| seen.insert(p.parent_guid.as_str()))
+        .map(|p| {
+            let bytes = chunk_list
+                .and_then(|c| c.chunk_lookup.get(&p.parent_guid).and_then(|&i| c.elements.get(i as usize)))
+                .and_then(|c| c.file_size.parse().ok())
+                .unwrap_or(0);
+            Action::Download {
+                guid: p.parent_guid.clone(),
+                bytes,
+            }
+        })
+        .collect()
+}
@@ -0,0 +1,143 @@
+//! Installing and repairing a manifest's files under a target directory.
+
+use log::debug;
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+
+use crate::error::ManifestError;
+use crate::install::actions::{chunk_downloads, Action, ActionList};
+use crate::install::assemble::{assemble_file, progress_path};
+use crate::provider::ChunkProvider;
+use crate::types::file::FileManifest;
+use crate::types::manifest::Manifest;
+use crate::verify::install::verify_install;
+
+#[derive(Debug, Clone, Default)]
+pub struct InstallOptions {
+    /// Only compute the actions; write nothing and fetch no chunks.
+    pub dry_run: bool,
+}
+
+/// `name`, a path from a manifest, as a path inside the directory it is
+/// installed to, or `None` if it could leave it. `/` and `\` both separate
+/// components, so the answer is the same on every platform: only plain
+/// names and `.` pass, never roots, drive prefixes such as `C:` or `..`.
+pub(crate) fn contained_path(name: &str) -> Option<PathBuf> {
+    if name.starts_with(['/', '\\']) {
+        return None;
+    }
+    let mut path = PathBuf::new();
+    for part in name.split(['/', '\\']).filter(|part| !part.is_empty()) {
+        // Drive-relative paths (`C:x`) and NTFS streams (`file:stream`).
+        if part.contains(':') {
+            return None;
+        }
+        let mut components = Path::new(part).components();
+        match (components.next(), components.next()) {
+            (Some(Component::Normal(_)), None) => path.push(part),
+            (Some(Component::CurDir), None) => {}
+            _ => return None,
+        }
+    }
+    (!path.as_os_str().is_empty()).then_some(path)
+}
+
+/// Target path of `file` under `target_dir`, refusing paths that escape it.
+pub(crate) fn target_path(target_dir: &Path, file: &FileManifest) -> Result<PathBuf, ManifestError> {
+    let filename = file.filename.trim_end_matches('\0');
+    match contained_path(filename) {
+        Some(relative) => Ok(target_dir.join(relative)),
+        None => Err(ManifestError::Invalid(format!(
+            "refusing to install path outside target: {}",
+            filename
+        ))),
+    }
+}
+
+fn write_action(file: &FileManifest) -> Action {
+    Action::Write {
+        path: file.filename.trim_end_matches('\0').to_string(),
+        bytes: file.file_size.max(0) as u64,
+    }
+}
+
+/// Assemble `files` under `target_dir`, or just list the actions when
+/// `dry_run` is set.
+fn apply<P: ChunkProvider + ?Sized>(
+    manifest: &Manifest,
+    files: Vec<&FileManifest>,
+    provider: &P,
+    target_dir: &Path,
+    options: &InstallOptions,
+    before_write: impl Fn(&Path) -> Result<(), ManifestError>,
+) -> Result<ActionList, ManifestError> {
+    let mut actions = chunk_downloads(manifest, files.iter().copied());
+    let mut targets = Vec::with_capacity(files.len());
+    for file in &files {
+        targets.push(target_path(target_dir, file)?);
+        actions.push(write_action(file));
+    }
+
+    if !options.dry_run {
+        for (file, path) in files.iter().zip(&targets) {
+            before_write(path)?;
+            assemble_file(provider, file, path)?;
+        }
+    }
+    Ok(ActionList {
+        dry_run: options.dry_run,
+        actions,
+    })
+}
+
+/// Install every regular file of `manifest` into `target_dir` from
+/// `provider`. Files interrupted in an earlier run resume where they stopped.
+/// Symlink entries are not created.
+pub fn install_files<P: ChunkProvider + ?Sized>(
+    manifest: &Manifest,
+    provider: &P,
+    target_dir: impl AsRef<Path>,
+    options: &InstallOptions,
+) -> Result<ActionList, ManifestError> {
+    let files: Vec<&FileManifest> = manifest
+        .file_list
+        .as_ref()
+        .map(|f| f.file_manifest_list.as_slice())
+        .unwrap_or_default()
+        .iter()
+        .filter(|f| f.symlink_target.trim_end_matches('\0').is_empty())
+        .collect();
+    debug!("Installing {} files{}", files.len(), if options.dry_run { " (dry run)" } else { "" });
+    apply(manifest, files, provider, target_dir.as_ref(), options, |_| Ok(()))
+}
+
+/// Verify the install in `target_dir` and rewrite every missing or corrupt
+/// file. The verification pass reads the files even in a dry run.
+pub fn repair_install<P: ChunkProvider + ?Sized>(
+    manifest: &Manifest,
+    provider: &P,
+    target_dir: impl AsRef<Path>,
+    options: &InstallOptions,
+) -> Result<ActionList, ManifestError> {
+    let target_dir = target_dir.as_ref();
+    let report = verify_install(manifest, target_dir, None, |_| {})?;
+    let corrupt: HashSet<&str> = report.corrupt.iter().map(|c| c.filename.as_str()).collect();
+    let files: Vec<&FileManifest> = manifest
+        .file_list
+        .as_ref()
+        .map(|f| f.file_manifest_list.as_slice())
+        .unwrap_or_default()
+        .iter()
+        .filter(|f| corrupt.contains(&f.filename.trim_end_matches('\0')))
+        .collect();
+    debug!("Repairing {} files{}", files.len(), if options.dry_run { " (dry run)" } else { "" });
+
+    // Progress left from an earlier install no longer describes the file.
+    apply(manifest, files, provider, target_dir, options, |path| {
+        match fs::remove_file(progress_path(path)) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e.into()),
+            _ => Ok(()),
+        }
+    })
+}
@@ -0,0 +1,272 @@
+//! Restartable file reconstruction.
+//!
+//! A [`PartialFile`] is preallocated to its final size and accepts chunk
+//! parts at their absolute offsets in any order. Completed ranges are
+//! recorded in a `<file>.progress` sidecar at each checkpoint, so an
+//! interrupted install reopens the file and only fetches what is missing.
+
+use log::debug;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs::{self, File, OpenOptions};
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+
+use crate::error::ManifestError;
+use crate::io_backend::backend;
+use crate::platform;
+use crate::provider::ChunkProvider;
+use crate::types::file::FileManifest;
+
+/// Suffix of the sidecar holding a partial file's completed ranges.
+pub const PROGRESS_SUFFIX: &str = ".progress";
+
+/// Bytes written between automatic checkpoints.
+const CHECKPOINT_BYTES: u64 = 16 * 1024 * 1024;
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Progress {
+    file_size: u64,
+    /// Manifest SHA-1 of the file; progress for other content is discarded.
+    sha_hash: String,
+    completed: Vec<(u64, u64)>,
+}
+
+/// Offset of every chunk part of `file` within the reconstructed file.
+pub fn part_offsets(file: &FileManifest) -> Vec<u64> {
+    file.chunk_parts
+        .iter()
+        .scan(0u64, |offset, part| {
+            let start = *offset;
+            *offset += part.size as u64;
+            Some(start)
+        })
+        .collect()
+}
+
+/// A file being written from chunk parts, resumable across runs.
+pub struct PartialFile {
+    path: PathBuf,
+    file: File,
+    file_size: u64,
+    sha_hash: String,
+    /// Disjoint, non-adjacent completed ranges, start -> end.
+    completed: BTreeMap<u64, u64>,
+    unsaved_bytes: u64,
+}
+
+impl PartialFile {
+    /// Open `path` for reconstructing `entry`, resuming from its progress
+    /// sidecar when one exists for the same content, and preallocate it to
+    /// the entry's size.
+    pub fn open(path: impl AsRef<Path>, entry: &FileManifest) -> Result<Self, ManifestError> {
+        let path = path.as_ref().to_path_buf();
+        let file_size = entry.file_size.max(0) as u64;
+        let sha_hash = entry.sha_hash.to_ascii_lowercase();
+
+        let completed = match fs::read(progress_path(&path)) {
+            Ok(data) => match serde_json::from_slice::<Progress>(&data) {
+                Ok(p) if p.file_size == file_size && p.sha_hash == sha_hash && path.exists() => {
+                    p.completed.into_iter().collect()
+                }
+                _ => {
+                    debug!("Discarding stale progress for {}", path.display());
+                    BTreeMap::new()
+                }
+            },
+            Err(_) => BTreeMap::new(),
+        };
+
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(completed.is_empty())
+            .open(&path)?;
+        platform::preallocate(&file, file_size)?;
+
+        Ok(Self {
+            path,
+            file,
+            file_size,
+            sha_hash,
+            completed,
+            unsaved_bytes: 0,
+        })
+    }
+
+    pub fn file_size(&self) -> u64 {
+        self.file_size
+    }
+
+    /// Whether `range` has already been written.
+    pub fn contains(&self, range: &Range<u64>) -> bool {
+        range.is_empty()
+            || self
+                .completed
+                .range(..=range.start)
+                .next_back()
+                .is_some_and(|(_, &end)| end >= range.end)
+    }
+
+    /// Ranges not yet written, in file order.
+    pub fn missing_ranges(&self) -> Vec<Range<u64>> {
+        let mut missing = Vec::new();
+        let mut at = 0;
+        for (&start, &end) in &self.completed {
+            if start > at {
+                missing.push(at..start);
+            }
+            at = end;
+        }
+        if at < self.file_size {
+            missing.push(at..self.file_size);
+        }
+        missing
+    }
+
+    pub fn is_complete(&self) -> bool {
+        self.missing_ranges().is_empty()
+    }
+
+    /// Write `data` at `offset`, checkpointing every few MiB.
+    pub fn write_at(&mut self, offset: u64, data: &[u8]) -> Result<(), ManifestError> {
+        self.write_batch(&[(offset, data)])
+    }
+
+    /// Write several `(offset, data)` pairs through the IO backend in one
+    /// submission, checkpointing every few MiB.
+    pub fn write_batch(&mut self, writes: &[(u64, &[u8])]) -> Result<(), ManifestError> {
+        for &(offset, data) in writes {
+            let end = offset + data.len() as u64;
+            if end > self.file_size {
+                return Err(ManifestError::Invalid(format!(
+                    "write of {}..{} past end of {} ({} bytes)",
+                    offset,
+                    end,
+                    self.path.display(),
+                    self.file_size
+                )));
+            }
+        }
+        backend().write_batch(&self.file, writes)?;
+        for &(offset, data) in writes {
+            self.mark_completed(offset..offset + data.len() as u64);
+            self.unsaved_bytes += data.len() as u64;
+        }
+
+        if self.unsaved_bytes >= CHECKPOINT_BYTES {
+            self.checkpoint()?;
+        }
+        Ok(())
+    }
+
+    fn mark_completed(&mut self, range: Range<u64>) {
+        if range.is_empty() {
+            return;
+        }
+        let (mut start, mut end) = (range.start, range.end);
+        let overlapping: Vec<u64> = self
+            .completed
+            .range(..=end)
+            .rev()
+            .take_while(|(_, &e)| e >= start)
+            .map(|(&s, _)| s)
+            .collect();
+        for s in overlapping {
+            let e = self.completed.remove(&s).unwrap();
+            start = start.min(s);
+            end = end.max(e);
+        }
+        self.completed.insert(start, end);
+    }
+
+    /// Flush written data to disk and record the completed ranges, so a
+    /// later [`PartialFile::open`] resumes from here.
+    pub fn checkpoint(&mut self) -> Result<(), ManifestError> {
+        self.file.sync_data()?;
+        let progress = Progress {
+            file_size: self.file_size,
+            sha_hash: self.sha_hash.clone(),
+            completed: self.completed.iter().map(|(&s, &e)| (s, e)).collect(),
+        };
+        let sidecar = progress_path(&self.path);
+        let tmp = sidecar.with_extension("progress.tmp");
+        fs::write(&tmp, serde_json::to_vec(&progress)?)?;
+        fs::rename(&tmp, &sidecar)?;
+        self.unsaved_bytes = 0;
+        Ok(())
+    }
+
+    /// Finish a complete file: sync it and remove the progress sidecar.
+    pub fn finish(self) -> Result<(), ManifestError> {
+        if let Some(missing) = self.missing_ranges().first() {
+            return Err(ManifestError::Invalid(format!(
+                "{} is incomplete: {}..{} not written",
+                self.path.display(),
+                missing.start,
+                missing.end
+            )));
+        }
+        self.file.sync_all()?;
+        match fs::remove_file(progress_path(&self.path)) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e.into()),
+            _ => Ok(()),
+        }
+    }
+}
+
+pub(crate) fn progress_path(path: &Path) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(PROGRESS_SUFFIX);
+    PathBuf::from(name)
+}
+
+/// Reconstruct `entry` at `path` from `provider`, skipping parts a previous
+/// interrupted run already wrote. Returns the number of bytes written now.
+pub fn assemble_file<P: ChunkProvider + ?Sized>(
+    provider: &P,
+    entry: &FileManifest,
+    path: impl AsRef<Path>,
+) -> Result<u64, ManifestError> {
+    let mut out = PartialFile::open(path, entry)?;
+    let mut written = 0;
+
+    // Missing parts, grouped into runs from the same chunk so each chunk is
+    // fetched once per run and its parts go out in one batch.
+    let missing: Vec<_> = entry
+        .chunk_parts
+        .iter()
+        .zip(part_offsets(entry))
+        .filter(|(part, offset)| !out.contains(&(*offset..*offset + part.size as u64)))
+        .collect();
+    for run in missing.chunk_by(|(a, _), (b, _)| a.parent_guid == b.parent_guid) {
+        let guid = &run[0].0.parent_guid;
+        let data = provider.chunk_data(guid)?;
+        let writes = run
+            .iter()
+            .map(|(part, offset)| {
+                let bytes = data
+                    .get(part.offset as usize..part.offset as usize + part.size as usize)
+                    .ok_or_else(|| {
+                        ManifestError::Invalid(format!(
+                            "chunk part {}..{} exceeds chunk {} of {} bytes",
+                            part.offset,
+                            part.offset as u64 + part.size as u64,
+                            guid,
+                            data.len()
+                        ))
+                    })?;
+                Ok((*offset, bytes))
+            })
+            .collect::<Result<Vec<_>, ManifestError>>()?;
+        out.write_batch(&writes)?;
+        written += writes.iter().map(|(_, bytes)| bytes.len() as u64).sum::<u64>();
+    }
+
+    out.finish()?;
+    Ok(written)
+}
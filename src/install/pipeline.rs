@@ -0,0 +1,331 @@
+//! Bounded download → verify → write pipeline.
+//!
+//! Each stage runs concurrently and hands chunks to the next through a
+//! channel of fixed depth, so when the network outpaces the disk the
+//! downloader blocks instead of buffering the install in memory. At most
+//! `download_concurrency + download_depth + write_depth + 2` chunks are held
+//! at once. [`PipelineMetrics`] counts each stage's progress and queue for
+//! display while the pipeline runs.
+
+use log::debug;
+use serde::Serialize;
+use sha1::{Digest, Sha1};
+use std::collections::HashMap;
+use std::future::Future;
+use std::path::Path;
+use std::sync::atomic::{AtomicI64, AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Instant;
+use tokio::sync::{mpsc, Semaphore};
+use tokio::task::JoinSet;
+
+use crate::error::ManifestError;
+use crate::crypto::AesKey;
+use crate::install::actions::{chunk_downloads, Action};
+use crate::install::apply::target_path;
+use crate::install::assemble::{part_offsets, PartialFile};
+use crate::types::chunk_file::decode_chunk_file_with_key;
+use crate::types::file::FileManifest;
+use crate::types::manifest::Manifest;
+
+#[derive(Debug, Clone)]
+pub struct PipelineOptions {
+    /// Chunk downloads in flight at once.
+    pub download_concurrency: usize,
+    /// Downloaded chunks waiting for verification.
+    pub download_depth: usize,
+    /// Verified chunks waiting to be written.
+    pub write_depth: usize,
+    /// Decrypts chunks flagged encrypted.
+    pub key: Option<AesKey>,
+}
+
+impl Default for PipelineOptions {
+    fn default() -> Self {
+        Self {
+            download_concurrency: 4,
+            download_depth: 8,
+            write_depth: 4,
+            key: None,
+        }
+    }
+}
+
+/// Live counters of one stage.
+#[derive(Debug, Default)]
+pub struct StageMetrics {
+    items: AtomicU64,
+    bytes: AtomicU64,
+    busy_nanos: AtomicU64,
+    /// Chunks waiting in this stage's input channel.
+    queued: AtomicI64,
+}
+
+impl StageMetrics {
+    fn record(&self, bytes: usize, started: Instant) {
+        self.items.fetch_add(1, Ordering::Relaxed);
+        self.bytes.fetch_add(bytes as u64, Ordering::Relaxed);
+        self.busy_nanos
+            .fetch_add(started.elapsed().as_nanos() as u64, Ordering::Relaxed);
+    }
+
+    pub fn snapshot(&self) -> StageSnapshot {
+        StageSnapshot {
+            items: self.items.load(Ordering::Relaxed),
+            bytes: self.bytes.load(Ordering::Relaxed),
+            busy_ms: self.busy_nanos.load(Ordering::Relaxed) / 1_000_000,
+            queued: self.queued.load(Ordering::Relaxed).max(0) as u64,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StageSnapshot {
+    /// Chunks the stage has finished.
+    pub items: u64,
+    /// Bytes it has handled: downloaded, decoded or written.
+    pub bytes: u64,
+    /// Time spent working, summed over concurrent workers.
+    pub busy_ms: u64,
+    /// Chunks waiting for this stage.
+    pub queued: u64,
+}
+
+/// Shared with the pipeline; poll [`PipelineMetrics::snapshot`] for progress.
+#[derive(Debug, Default)]
+pub struct PipelineMetrics {
+    pub download: StageMetrics,
+    pub verify: StageMetrics,
+    pub write: StageMetrics,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct PipelineSnapshot {
+    pub download: StageSnapshot,
+    pub verify: StageSnapshot,
+    pub write: StageSnapshot,
+}
+
+impl PipelineMetrics {
+    pub fn snapshot(&self) -> PipelineSnapshot {
+        PipelineSnapshot {
+            download: self.download.snapshot(),
+            verify: self.verify.snapshot(),
+            write: self.write.snapshot(),
+        }
+    }
+}
+
+/// Decode a chunk file and check its data against the manifest's SHA-1 for
+/// the chunk, or the chunk header's when the manifest has none.
+fn verify_chunk(
+    guid: &str,
+    stored_sha: Option<&str>,
+    key: Option<&AesKey>,
+    file: &[u8],
+) -> Result<Vec<u8>, ManifestError> {
+    let (header, data) = decode_chunk_file_with_key(file, key)?;
+    let expected = stored_sha.or(header.sha_hash.as_deref());
+    if let Some(expected) = expected {
+        let actual = hex::encode(Sha1::digest(&data));
+        if !actual.eq_ignore_ascii_case(expected) {
+            return Err(ManifestError::Invalid(format!(
+                "chunk {} hashes to {}, expected {}",
+                guid, actual, expected
+            )));
+        }
+    }
+    Ok(data)
+}
+
+/// Where one chunk part goes: file index, offset in the file, and the range
+/// of the chunk it copies.
+struct Placement {
+    file: usize,
+    file_offset: u64,
+    chunk_offset: usize,
+    size: usize,
+}
+
+/// Install every regular file of `manifest` into `target_dir`, fetching
+/// chunk files (as served by the CDN) with `source`.
+///
+/// Downloads run `download_concurrency` at a time; a stage only accepts new
+/// work when the next one has room. Returns the final metrics.
+pub async fn run_pipeline<S, Fut>(
+    manifest: &Manifest,
+    target_dir: impl AsRef<Path>,
+    source: S,
+    options: &PipelineOptions,
+    metrics: Arc<PipelineMetrics>,
+) -> Result<PipelineSnapshot, ManifestError>
+where
+    S: Fn(String) -> Fut + Send + Sync + 'static,
+    Fut: Future<Output = Result<Vec<u8>, ManifestError>> + Send + 'static,
+{
+    let target_dir = target_dir.as_ref();
+    let files: Vec<FileManifest> = manifest
+        .file_list
+        .as_ref()
+        .map(|f| f.file_manifest_list.as_slice())
+        .unwrap_or_default()
+        .iter()
+        .filter(|f| f.symlink_target.trim_end_matches('\0').is_empty())
+        .cloned()
+        .collect();
+    let targets = files
+        .iter()
+        .map(|f| target_path(target_dir, f))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let mut placements: HashMap<String, Vec<Placement>> = HashMap::new();
+    for (i, file) in files.iter().enumerate() {
+        for (part, file_offset) in file.chunk_parts.iter().zip(part_offsets(file)) {
+            placements.entry(part.parent_guid.clone()).or_default().push(Placement {
+                file: i,
+                file_offset,
+                chunk_offset: part.offset as usize,
+                size: part.size as usize,
+            });
+        }
+    }
+    let guids: Vec<String> = chunk_downloads(manifest, &files)
+        .into_iter()
+        .filter_map(|a| match a {
+            Action::Download { guid, .. } => Some(guid),
+            _ => None,
+        })
+        .collect();
+    // SHA-1s to check downloaded chunks against, where the manifest has them.
+    let chunks: HashMap<String, String> = manifest
+        .chunk_list
+        .iter()
+        .flat_map(|list| list.elements.iter().filter_map(move |c| Some((c.guid.clone(), list.stored_sha_hash(c)?.to_string()))))
+        .collect();
+    debug!("Pipeline: {} files from {} chunks", files.len(), guids.len());
+
+    let (downloaded_tx, mut downloaded_rx) = mpsc::channel::<(String, Vec<u8>)>(options.download_depth.max(1));
+    let (verified_tx, mut verified_rx) = mpsc::channel::<(String, Vec<u8>)>(options.write_depth.max(1));
+
+    // A stage whose output channel is closed stops quietly: the stage that
+    // closed it reports the actual error.
+    let download = {
+        let metrics = metrics.clone();
+        let source = Arc::new(source);
+        let permits = Arc::new(Semaphore::new(options.download_concurrency.max(1)));
+        tokio::spawn(async move {
+            let mut tasks = JoinSet::new();
+            for guid in guids {
+                let permit = permits.clone().acquire_owned().await.expect("semaphore closed");
+                if downloaded_tx.is_closed() {
+                    break;
+                }
+                let (source, tx, metrics) = (source.clone(), downloaded_tx.clone(), metrics.clone());
+                tasks.spawn(async move {
+                    let started = Instant::now();
+                    let data = source(guid.clone()).await?;
+                    metrics.download.record(data.len(), started);
+                    metrics.verify.queued.fetch_add(1, Ordering::Relaxed);
+                    let _ = tx.send((guid, data)).await;
+                    drop(permit);
+                    Ok::<(), ManifestError>(())
+                });
+                // Surface failures without waiting for the whole list.
+                while let Some(done) = tasks.try_join_next() {
+                    done.map_err(|e| ManifestError::Invalid(e.to_string()))??;
+                }
+            }
+            while let Some(done) = tasks.join_next().await {
+                done.map_err(|e| ManifestError::Invalid(e.to_string()))??;
+            }
+            Ok::<(), ManifestError>(())
+        })
+    };
+
+    let verify = {
+        let metrics = metrics.clone();
+        let key = Arc::new(options.key.clone());
+        tokio::spawn(async move {
+            let chunks = Arc::new(chunks);
+            while let Some((guid, file)) = downloaded_rx.recv().await {
+                metrics.verify.queued.fetch_sub(1, Ordering::Relaxed);
+                let started = Instant::now();
+                let chunks = chunks.clone();
+                let key = key.clone();
+                let (guid, data) = tokio::task::spawn_blocking(move || {
+                    let data = verify_chunk(&guid, chunks.get(&guid).map(String::as_str), key.as_ref().as_ref(), &file)?;
+                    Ok::<_, ManifestError>((guid, data))
+                })
+                .await
+                .map_err(|e| ManifestError::Invalid(e.to_string()))??;
+                metrics.verify.record(data.len(), started);
+                metrics.write.queued.fetch_add(1, Ordering::Relaxed);
+                if verified_tx.send((guid, data)).await.is_err() {
+                    break;
+                }
+            }
+            Ok::<(), ManifestError>(())
+        })
+    };
+
+    let write = {
+        let metrics = metrics.clone();
+        tokio::task::spawn_blocking(move || {
+            let mut remaining: Vec<usize> = files.iter().map(|f| f.chunk_parts.len()).collect();
+            let mut open: HashMap<usize, PartialFile> = HashMap::new();
+            for (i, file) in files.iter().enumerate() {
+                if remaining[i] == 0 {
+                    PartialFile::open(&targets[i], file)?.finish()?;
+                }
+            }
+
+            while let Some((guid, data)) = verified_rx.blocking_recv() {
+                metrics.write.queued.fetch_sub(1, Ordering::Relaxed);
+                let started = Instant::now();
+                let mut written = 0;
+                for placement in placements.get(&guid).map(Vec::as_slice).unwrap_or_default() {
+                    let bytes = data
+                        .get(placement.chunk_offset..placement.chunk_offset + placement.size)
+                        .ok_or_else(|| {
+                            ManifestError::Invalid(format!(
+                                "chunk part {}..{} exceeds chunk {} of {} bytes",
+                                placement.chunk_offset,
+                                placement.chunk_offset + placement.size,
+                                guid,
+                                data.len()
+                            ))
+                        })?;
+                    let out = match open.entry(placement.file) {
+                        std::collections::hash_map::Entry::Occupied(e) => e.into_mut(),
+                        std::collections::hash_map::Entry::Vacant(e) => {
+                            e.insert(PartialFile::open(&targets[placement.file], &files[placement.file])?)
+                        }
+                    };
+                    out.write_at(placement.file_offset, bytes)?;
+                    written += bytes.len();
+
+                    remaining[placement.file] -= 1;
+                    if remaining[placement.file] == 0 {
+                        open.remove(&placement.file).unwrap().finish()?;
+                    }
+                }
+                metrics.write.record(written, started);
+            }
+
+            // Anything still open missed chunks; keep its progress for a retry.
+            for out in open.values_mut() {
+                out.checkpoint()?;
+            }
+            Ok::<(), ManifestError>(())
+        })
+    };
+
+    let (download, verify, write) = tokio::join!(download, verify, write);
+    let join = |e: tokio::task::JoinError| ManifestError::Invalid(format!("pipeline stage failed: {}", e));
+    download.map_err(join)??;
+    verify.map_err(join)??;
+    write.map_err(join)??;
+
+    Ok(metrics.snapshot())
+}
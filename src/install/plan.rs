@@ -0,0 +1,96 @@
+//! Up-front accounting for an install: how much space the files need, how
+//! much temporary space chunk staging needs, and whether the target has it.
+
+use log::debug;
+use std::collections::HashSet;
+use std::path::Path;
+
+use crate::error::ManifestError;
+use crate::platform::available_space;
+use crate::types::manifest::Manifest;
+
+#[derive(Debug, Clone, Default)]
+pub struct PlanOptions {
+    /// Allocation unit for [`Manifest::size_on_disk`]; 0 means the default.
+    pub cluster_size: u64,
+    /// Query the target filesystem and fail with
+    /// [`ManifestError::InsufficientSpace`] if the install would not fit.
+    pub check_free_space: bool,
+}
+
+/// Space requirements of installing a manifest.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InstallPlan {
+    pub files_total: u32,
+    /// Size on disk of the installed files.
+    pub required_bytes: u64,
+    /// Temporary space for downloaded chunks while files are assembled: the
+    /// compressed size of every chunk the files reference.
+    pub staging_bytes: u64,
+    /// Free space found on the target, when it was checked.
+    pub available_bytes: Option<u64>,
+}
+
+impl InstallPlan {
+    /// Total space the install needs at its peak.
+    pub fn peak_bytes(&self) -> u64 {
+        self.required_bytes + self.staging_bytes
+    }
+}
+
+/// Plan installing `manifest` into `target_dir`, optionally checking free
+/// space there first.
+pub fn plan_install(
+    manifest: &Manifest,
+    target_dir: impl AsRef<Path>,
+    options: &PlanOptions,
+) -> Result<InstallPlan, ManifestError> {
+    let files = manifest
+        .file_list
+        .as_ref()
+        .map(|f| f.file_manifest_list.as_slice())
+        .unwrap_or_default();
+
+    let referenced: HashSet<&str> = files
+        .iter()
+        .flat_map(|f| &f.chunk_parts)
+        .map(|p| p.parent_guid.as_str())
+        .collect();
+    let staging_bytes = manifest
+        .chunk_list
+        .as_ref()
+        .map(|c| c.elements.as_slice())
+        .unwrap_or_default()
+        .iter()
+        .filter(|c| referenced.contains(c.guid.as_str()))
+        .map(|c| c.file_size.parse::<u64>().unwrap_or(0))
+        .sum();
+
+    let mut plan = InstallPlan {
+        files_total: files.len() as u32,
+        required_bytes: manifest.size_on_disk(options.cluster_size),
+        staging_bytes,
+        available_bytes: None,
+    };
+
+    if options.check_free_space {
+        let available = available_space(target_dir.as_ref())?;
+        debug!(
+            "Install preflight: {} bytes needed ({} + {} staging), {} available",
+            plan.peak_bytes(),
+            plan.required_bytes,
+            plan.staging_bytes,
+            available
+        );
+        if available < plan.peak_bytes() {
+            return Err(ManifestError::InsufficientSpace {
+                required: plan.required_bytes,
+                staging: plan.staging_bytes,
+                available,
+            });
+        }
+        plan.available_bytes = Some(available);
+    }
+
+    Ok(plan)
+}
@@ -0,0 +1,278 @@
+//! Integrity seals for archived manifest collections.
+//!
+//! A preservation mirror seals its archive once: every manifest (and
+//! optionally every chunk file) is hashed, and the hashes are folded into a
+//! Merkle root and a hash chain. Publishing the root lets anyone check later
+//! that a copy of the archive is complete and unmodified, or with a
+//! [`MerkleProof`], that one manifest belongs to it without fetching the rest.
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use crate::error::ManifestError;
+
+/// Seal format version.
+const SEAL_VERSION: u32 = 1;
+
+/// Domain separation between leaf and interior Merkle nodes.
+const LEAF_PREFIX: u8 = 0x00;
+const NODE_PREFIX: u8 = 0x01;
+
+/// One archived file.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ArchiveEntry {
+    /// Path relative to the archive root, `/`-separated.
+    pub name: String,
+    pub size: u64,
+    /// Hex SHA-256 of the file contents.
+    pub sha256: String,
+}
+
+impl ArchiveEntry {
+    pub fn from_bytes(name: impl Into<String>, bytes: &[u8]) -> Self {
+        Self {
+            name: name.into(),
+            size: bytes.len() as u64,
+            sha256: hex::encode(Sha256::digest(bytes)),
+        }
+    }
+
+    /// The Merkle leaf: binds name, size and content hash together.
+    fn leaf(&self) -> [u8; 32] {
+        let mut sha = Sha256::new();
+        sha.update([LEAF_PREFIX]);
+        sha.update((self.name.len() as u64).to_le_bytes());
+        sha.update(self.name.as_bytes());
+        sha.update(self.size.to_le_bytes());
+        sha.update(self.sha256.to_ascii_lowercase().as_bytes());
+        sha.finalize().into()
+    }
+}
+
+/// Which files [`ArchiveSeal::scan`] includes.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SealOptions {
+    /// Also seal `.chunk` files, not just `.manifest` and `.json` manifests.
+    pub include_chunks: bool,
+}
+
+/// Published summary of an archive: its entries, Merkle root and chain head.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ArchiveSeal {
+    pub version: u32,
+    /// Entries sorted by name.
+    pub entries: Vec<ArchiveEntry>,
+    /// Hex Merkle root over the entries' leaves.
+    pub merkle_root: String,
+    /// Hex head of the hash chain `h(i) = SHA-256(h(i-1) || leaf(i))`,
+    /// starting from 32 zero bytes. When a newer seal's entries only add to
+    /// the end of an older seal's list, its chain passes through the older head.
+    pub chain_head: String,
+}
+
+/// One sibling on the path from a leaf to the root.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProofStep {
+    pub sibling: String,
+    /// Whether the sibling is the left child.
+    pub left: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MerkleProof {
+    pub entry: ArchiveEntry,
+    pub steps: Vec<ProofStep>,
+}
+
+impl MerkleProof {
+    /// Whether this proof places its entry under `merkle_root`.
+    pub fn verify(&self, merkle_root: &str) -> bool {
+        let mut hash = self.entry.leaf();
+        for step in &self.steps {
+            let mut sibling = [0u8; 32];
+            if hex::decode_to_slice(&step.sibling, &mut sibling).is_err() {
+                return false;
+            }
+            hash = if step.left { node(&sibling, &hash) } else { node(&hash, &sibling) };
+        }
+        hex::encode(hash).eq_ignore_ascii_case(merkle_root)
+    }
+}
+
+/// Differences between an archive on disk and its seal.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IntegrityReport {
+    /// Sealed entries no longer present.
+    pub missing: Vec<String>,
+    /// Sealed entries whose size or hash changed.
+    pub modified: Vec<String>,
+    /// Files present but not in the seal.
+    pub unexpected: Vec<String>,
+    /// Whether the seal's own root and chain head match its entries.
+    pub seal_consistent: bool,
+}
+
+impl IntegrityReport {
+    pub fn is_ok(&self) -> bool {
+        self.seal_consistent && self.missing.is_empty() && self.modified.is_empty() && self.unexpected.is_empty()
+    }
+}
+
+impl ArchiveSeal {
+    pub fn from_entries(mut entries: Vec<ArchiveEntry>) -> Self {
+        entries.sort_by(|a, b| a.name.cmp(&b.name));
+        entries.dedup_by(|a, b| a.name == b.name);
+        let leaves: Vec<[u8; 32]> = entries.iter().map(ArchiveEntry::leaf).collect();
+        Self {
+            version: SEAL_VERSION,
+            merkle_root: hex::encode(merkle_root(&leaves)),
+            chain_head: hex::encode(chain_head(&leaves)),
+            entries,
+        }
+    }
+
+    /// Hash the archive under `root`.
+    pub fn scan(root: impl AsRef<Path>, options: &SealOptions) -> Result<Self, ManifestError> {
+        let root = root.as_ref();
+        let mut entries = Vec::new();
+        for name in sealed_files(root, options)? {
+            let bytes = fs::read(root.join(&name))?;
+            entries.push(ArchiveEntry::from_bytes(name, &bytes));
+        }
+        Ok(Self::from_entries(entries))
+    }
+
+    /// Whether `merkle_root` and `chain_head` are those of `entries`.
+    pub fn is_consistent(&self) -> bool {
+        let expected = Self::from_entries(self.entries.clone());
+        expected.merkle_root == self.merkle_root && expected.chain_head == self.chain_head
+    }
+
+    /// Inclusion proof for entry `name`.
+    pub fn proof(&self, name: &str) -> Option<MerkleProof> {
+        let position = self.entries.iter().position(|e| e.name == name)?;
+        let mut index = position;
+        let mut level: Vec<[u8; 32]> = self.entries.iter().map(ArchiveEntry::leaf).collect();
+        let mut steps = Vec::new();
+        while level.len() > 1 {
+            let sibling = index ^ 1;
+            if let Some(hash) = level.get(sibling) {
+                steps.push(ProofStep {
+                    sibling: hex::encode(hash),
+                    left: sibling < index,
+                });
+            }
+            level = next_level(&level);
+            index /= 2;
+        }
+        Some(MerkleProof {
+            entry: self.entries[position].clone(),
+            steps,
+        })
+    }
+
+    /// Compare the archive under `root` against this seal.
+    pub fn verify(&self, root: impl AsRef<Path>, options: &SealOptions) -> Result<IntegrityReport, ManifestError> {
+        let root = root.as_ref();
+        let mut report = IntegrityReport {
+            seal_consistent: self.is_consistent(),
+            ..Default::default()
+        };
+        for entry in &self.entries {
+            match fs::read(root.join(&entry.name)) {
+                Ok(bytes) => {
+                    if ArchiveEntry::from_bytes(entry.name.as_str(), &bytes) != *entry {
+                        report.modified.push(entry.name.clone());
+                    }
+                }
+                Err(e) if e.kind() == io::ErrorKind::NotFound => report.missing.push(entry.name.clone()),
+                Err(e) => return Err(e.into()),
+            }
+        }
+        for name in sealed_files(root, options)? {
+            if self.entries.binary_search_by(|e| e.name.as_str().cmp(&name)).is_err() {
+                report.unexpected.push(name);
+            }
+        }
+        Ok(report)
+    }
+}
+
+fn node(left: &[u8; 32], right: &[u8; 32]) -> [u8; 32] {
+    let mut sha = Sha256::new();
+    sha.update([NODE_PREFIX]);
+    sha.update(left);
+    sha.update(right);
+    sha.finalize().into()
+}
+
+/// Pair up nodes; an odd one out is promoted unchanged.
+fn next_level(level: &[[u8; 32]]) -> Vec<[u8; 32]> {
+    level
+        .chunks(2)
+        .map(|pair| match pair {
+            [left, right] => node(left, right),
+            [single] => *single,
+            _ => unreachable!(),
+        })
+        .collect()
+}
+
+fn merkle_root(leaves: &[[u8; 32]]) -> [u8; 32] {
+    if leaves.is_empty() {
+        return Sha256::digest([]).into();
+    }
+    let mut level = leaves.to_vec();
+    while level.len() > 1 {
+        level = next_level(&level);
+    }
+    level[0]
+}
+
+fn chain_head(leaves: &[[u8; 32]]) -> [u8; 32] {
+    leaves.iter().fold([0u8; 32], |head, leaf| {
+        let mut sha = Sha256::new();
+        sha.update(head);
+        sha.update(leaf);
+        sha.finalize().into()
+    })
+}
+
+/// Relative `/`-separated names of the files under `root` a seal covers,
+/// sorted.
+fn sealed_files(root: &Path, options: &SealOptions) -> io::Result<Vec<String>> {
+    let mut names = Vec::new();
+    let mut pending = vec![root.to_path_buf()];
+    while let Some(dir) = pending.pop() {
+        for entry in fs::read_dir(&dir)? {
+            let entry = entry?;
+            let path = entry.path();
+            if entry.file_type()?.is_dir() {
+                pending.push(path);
+                continue;
+            }
+            let extension = path.extension().and_then(|e| e.to_str()).unwrap_or_default();
+            let sealed = match extension.to_ascii_lowercase().as_str() {
+                "manifest" | "json" => true,
+                "chunk" => options.include_chunks,
+                _ => false,
+            };
+            if sealed {
+                if let Ok(relative) = path.strip_prefix(root) {
+                    let parts: Vec<_> = relative.components().map(|c| c.as_os_str().to_string_lossy()).collect();
+                    names.push(parts.join("/"));
+                }
+            }
+        }
+    }
+    names.sort();
+    Ok(names)
+}
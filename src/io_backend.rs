@@ -0,0 +1,728 @@
+//! Positional file IO for reconstruction and verification.
+//!
+//! [`IoBackend`] reads and writes batches of `(offset, buffer)` pairs. The
+//! portable [`StdIo`] backend performs them one after another. With the
+//! `platform-io` feature, Linux submits a whole batch through io_uring and
+//! Windows issues it as overlapped IO, so the kernel can keep several
+//! requests in flight; [`backend`] falls back to [`StdIo`] when the platform
+//! facility is unavailable (e.g. io_uring disabled by seccomp).
+
+use std::fs::File;
+use std::io;
+use std::sync::OnceLock;
+
+/// Batched positional IO on an open file.
+pub trait IoBackend: Send + Sync {
+    fn name(&self) -> &'static str;
+
+    /// Write every `(offset, data)` pair in full before returning.
+    fn write_batch(&self, file: &File, writes: &[(u64, &[u8])]) -> io::Result<()>;
+
+    /// Fill each `(offset, buffer)` pair from the file, returning how many
+    /// bytes each received; fewer than the buffer length means end of file.
+    fn read_batch(&self, file: &File, reads: &mut [(u64, &mut [u8])]) -> io::Result<Vec<usize>>;
+}
+
+/// Sequential positional reads and writes through `std`.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct StdIo;
+
+impl IoBackend for StdIo {
+    fn name(&self) -> &'static str {
+        "std"
+    }
+
+    fn write_batch(&self, file: &File, writes: &[(u64, &[u8])]) -> io::Result<()> {
+        for &(offset, data) in writes {
+            write_all_at(file, offset, data)?;
+        }
+        Ok(())
+    }
+
+    fn read_batch(&self, file: &File, reads: &mut [(u64, &mut [u8])]) -> io::Result<Vec<usize>> {
+        reads
+            .iter_mut()
+            .map(|(offset, buf)| read_full_at(file, *offset, buf))
+            .collect()
+    }
+}
+
+#[cfg(unix)]
+fn read_at(file: &File, offset: u64, buf: &mut [u8]) -> io::Result<usize> {
+    std::os::unix::fs::FileExt::read_at(file, buf, offset)
+}
+
+#[cfg(unix)]
+fn write_at(file: &File, offset: u64, data: &[u8]) -> io::Result<usize> {
+    std::os::unix::fs::FileExt::write_at(file, data, offset)
+}
+
+#[cfg(windows)]
+fn read_at(file: &File, offset: u64, buf: &mut [u8]) -> io::Result<usize> {
+    std::os::windows::fs::FileExt::seek_read(file, buf, offset)
+}
+
+#[cfg(windows)]
+fn write_at(file: &File, offset: u64, data: &[u8]) -> io::Result<usize> {
+    std::os::windows::fs::FileExt::seek_write(file, data, offset)
+}
+
+#[cfg(not(any(unix, windows)))]
+fn read_at(file: &File, offset: u64, buf: &mut [u8]) -> io::Result<usize> {
+    use std::io::{Read, Seek, SeekFrom};
+    let mut file = file;
+    file.seek(SeekFrom::Start(offset))?;
+    file.read(buf)
+}
+
+#[cfg(not(any(unix, windows)))]
+fn write_at(file: &File, offset: u64, data: &[u8]) -> io::Result<usize> {
+    use std::io::{Seek, SeekFrom, Write};
+    let mut file = file;
+    file.seek(SeekFrom::Start(offset))?;
+    file.write(data)
+}
+
+fn write_all_at(file: &File, mut offset: u64, mut data: &[u8]) -> io::Result<()> {
+    while !data.is_empty() {
+        match write_at(file, offset, data) {
+            Ok(0) => return Err(io::ErrorKind::WriteZero.into()),
+            Ok(n) => {
+                offset += n as u64;
+                data = &data[n..];
+            }
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(())
+}
+
+/// Read until `buf` is full or EOF; returns the bytes read.
+fn read_full_at(file: &File, offset: u64, buf: &mut [u8]) -> io::Result<usize> {
+    let mut filled = 0;
+    while filled < buf.len() {
+        match read_at(file, offset + filled as u64, &mut buf[filled..]) {
+            Ok(0) => break,
+            Ok(n) => filled += n,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(filled)
+}
+
+/// [`std::io::Read`] over a file that fetches `depth` blocks per
+/// [`IoBackend::read_batch`], keeping the backend's queue full for
+/// sequential scans such as hashing.
+pub struct BatchedReader<'a> {
+    file: File,
+    backend: &'a dyn IoBackend,
+    offset: u64,
+    block_size: usize,
+    depth: usize,
+    buf: Vec<u8>,
+    pos: usize,
+    len: usize,
+    eof: bool,
+}
+
+impl<'a> BatchedReader<'a> {
+    pub fn new(file: File, backend: &'a dyn IoBackend, block_size: usize, depth: usize) -> Self {
+        Self {
+            file,
+            backend,
+            offset: 0,
+            block_size: block_size.max(1),
+            depth: depth.max(1),
+            buf: Vec::new(),
+            pos: 0,
+            len: 0,
+            eof: false,
+        }
+    }
+
+    fn refill(&mut self) -> io::Result<()> {
+        self.buf.resize(self.block_size * self.depth, 0);
+        let offset = self.offset;
+        let block_size = self.block_size;
+        let mut reads: Vec<(u64, &mut [u8])> = self
+            .buf
+            .chunks_mut(block_size)
+            .enumerate()
+            .map(|(i, block)| (offset + (i * block_size) as u64, block))
+            .collect();
+        let lengths = self.backend.read_batch(&self.file, &mut reads)?;
+
+        // Data is contiguous up to the first short block.
+        let mut len = 0;
+        for n in lengths {
+            len += n;
+            if n < block_size {
+                self.eof = true;
+                break;
+            }
+        }
+        self.offset += len as u64;
+        self.pos = 0;
+        self.len = len;
+        Ok(())
+    }
+}
+
+impl io::Read for BatchedReader<'_> {
+    fn read(&mut self, out: &mut [u8]) -> io::Result<usize> {
+        if self.pos == self.len {
+            if self.eof {
+                return Ok(0);
+            }
+            self.refill()?;
+        }
+        let n = out.len().min(self.len - self.pos);
+        out[..n].copy_from_slice(&self.buf[self.pos..self.pos + n]);
+        self.pos += n;
+        Ok(n)
+    }
+}
+
+/// The backend used by the install and verification code: the platform one
+/// when the `platform-io` feature is enabled and it initializes, else
+/// [`StdIo`]. Chosen once per process.
+pub fn backend() -> &'static dyn IoBackend {
+    static BACKEND: OnceLock<Box<dyn IoBackend>> = OnceLock::new();
+    BACKEND
+        .get_or_init(|| {
+            let backend = platform::backend().unwrap_or_else(|| Box::new(StdIo));
+            log::debug!("Using {} IO backend", backend.name());
+            backend
+        })
+        .as_ref()
+}
+
+#[cfg(not(any(
+    all(target_os = "linux", feature = "platform-io"),
+    all(windows, feature = "platform-io")
+)))]
+mod platform {
+    pub fn backend() -> Option<Box<dyn super::IoBackend>> {
+        None
+    }
+}
+
+#[cfg(all(target_os = "linux", feature = "platform-io"))]
+mod platform {
+    pub fn backend() -> Option<Box<dyn super::IoBackend>> {
+        match super::uring::UringIo::new() {
+            Ok(uring) => Some(Box::new(uring)),
+            Err(e) => {
+                log::debug!("io_uring unavailable ({}); using std IO", e);
+                None
+            }
+        }
+    }
+}
+
+#[cfg(all(windows, feature = "platform-io"))]
+mod platform {
+    pub fn backend() -> Option<Box<dyn super::IoBackend>> {
+        Some(Box::new(super::overlapped::OverlappedIo))
+    }
+}
+
+#[cfg(all(target_os = "linux", feature = "platform-io"))]
+pub mod uring {
+    //! Minimal io_uring driver: one ring, shared behind a mutex, used
+    //! synchronously (submit a batch, wait for all of it).
+
+    use rustix::fd::{AsRawFd, OwnedFd};
+    use rustix::io_uring::{
+        io_uring_cqe, io_uring_enter, io_uring_params, io_uring_ptr, io_uring_setup, io_uring_sqe,
+        io_uring_user_data, IoringEnterFlags, IoringOp, IORING_OFF_CQ_RING, IORING_OFF_SQES,
+        IORING_OFF_SQ_RING,
+    };
+    use rustix::mm::{mmap, munmap, MapFlags, ProtFlags};
+    use std::ffi::c_void;
+    use std::fs::File;
+    use std::io;
+    use std::sync::atomic::{AtomicU32, Ordering};
+    use std::sync::Mutex;
+
+    use super::{read_full_at, write_all_at, IoBackend, StdIo};
+
+    const RING_ENTRIES: u32 = 64;
+
+    struct Mapping {
+        ptr: *mut c_void,
+        len: usize,
+    }
+
+    impl Drop for Mapping {
+        fn drop(&mut self) {
+            // SAFETY: `ptr`/`len` came from a successful mmap.
+            unsafe {
+                let _ = munmap(self.ptr, self.len);
+            }
+        }
+    }
+
+    struct Ring {
+        fd: OwnedFd,
+        sq: Mapping,
+        cq: Mapping,
+        sqes: Mapping,
+        params: io_uring_params,
+        /// Set when a submit fails. Entries the kernel never took may still
+        /// sit in the SQ ring pointing at freed buffers, so the ring is not
+        /// entered again.
+        broken: bool,
+        #[cfg(test)]
+        fail_next_enter: bool,
+    }
+
+    // SAFETY: the mappings are only touched while holding the mutex.
+    unsafe impl Send for Ring {}
+
+    impl Ring {
+        fn new() -> io::Result<Self> {
+            let mut params = io_uring_params::default();
+            // SAFETY: plain syscall with a valid params struct.
+            let fd = unsafe { io_uring_setup(RING_ENTRIES, &mut params)? };
+            let map = |len: usize, offset: u64| -> io::Result<Mapping> {
+                // SAFETY: mapping a region the kernel provides for this ring.
+                let ptr = unsafe {
+                    mmap(
+                        std::ptr::null_mut(),
+                        len,
+                        ProtFlags::READ | ProtFlags::WRITE,
+                        MapFlags::SHARED | MapFlags::POPULATE,
+                        &fd,
+                        offset,
+                    )?
+                };
+                Ok(Mapping { ptr, len })
+            };
+            let sq = map(
+                params.sq_off.array as usize + params.sq_entries as usize * 4,
+                IORING_OFF_SQ_RING,
+            )?;
+            let cq = map(
+                params.cq_off.cqes as usize + params.cq_entries as usize * size_of::<io_uring_cqe>(),
+                IORING_OFF_CQ_RING,
+            )?;
+            let sqes = map(params.sq_entries as usize * size_of::<io_uring_sqe>(), IORING_OFF_SQES)?;
+            Ok(Self {
+                fd,
+                sq,
+                cq,
+                sqes,
+                params,
+                broken: false,
+                #[cfg(test)]
+                fail_next_enter: false,
+            })
+        }
+
+        fn sq_u32(&self, offset: u32) -> &AtomicU32 {
+            // SAFETY: offsets come from the kernel and lie inside the mapping.
+            unsafe { &*(self.sq.ptr.add(offset as usize) as *const AtomicU32) }
+        }
+
+        fn cq_u32(&self, offset: u32) -> &AtomicU32 {
+            // SAFETY: as above.
+            unsafe { &*(self.cq.ptr.add(offset as usize) as *const AtomicU32) }
+        }
+
+        /// Submit `sqes` (at most the ring size) and wait for all of them,
+        /// returning each result indexed by its `user_data`.
+        ///
+        /// If the kernel refuses a submit, the entries it already took are
+        /// still reaped before returning, since they point at the caller's
+        /// buffers, and the ring is marked `broken`.
+        fn run(&mut self, sqes: &[io_uring_sqe]) -> io::Result<Vec<i32>> {
+            let off = self.params.sq_off;
+            let mask = self.params.sq_entries - 1;
+            let head = self.sq_u32(off.head).load(Ordering::Acquire);
+            let mut tail = self.sq_u32(off.tail).load(Ordering::Acquire);
+            for sqe in sqes {
+                let index = tail & mask;
+                // SAFETY: `index` is within both the SQE array and the
+                // index array, which the kernel does not read until the
+                // tail is published below.
+                unsafe {
+                    (self.sqes.ptr as *mut io_uring_sqe).add(index as usize).write(*sqe);
+                    (self.sq.ptr.add(off.array as usize) as *mut u32)
+                        .add(index as usize)
+                        .write(index);
+                }
+                tail = tail.wrapping_add(1);
+            }
+            self.sq_u32(off.tail).store(tail, Ordering::Release);
+
+            let mut results = vec![0i32; sqes.len()];
+            let mut reaped = 0;
+            let mut to_submit = sqes.len() as u32;
+            while reaped < sqes.len() {
+                // Interrupted waits are retried: submitted requests still
+                // point at the caller's buffers and must be reaped.
+                match self.enter(to_submit) {
+                    Ok(submitted) => to_submit = to_submit.saturating_sub(submitted),
+                    Err(rustix::io::Errno::INTR) => {}
+                    Err(e) => {
+                        self.broken = true;
+                        let taken = self.sq_u32(off.head).load(Ordering::Acquire).wrapping_sub(head) as usize;
+                        while reaped < taken {
+                            // SAFETY: as in `enter`. A ring that cannot wait
+                            // still posts completions when the thread returns
+                            // to user space, so keep yielding until it has.
+                            if unsafe { io_uring_enter(&self.fd, 0, 1, IoringEnterFlags::GETEVENTS) }.is_err() {
+                                std::thread::yield_now();
+                            }
+                            reaped += self.reap(&mut results);
+                        }
+                        return Err(e.into());
+                    }
+                }
+                reaped += self.reap(&mut results);
+            }
+            Ok(results)
+        }
+
+        fn enter(&mut self, to_submit: u32) -> rustix::io::Result<u32> {
+            // SAFETY: the ring fd is valid for the lifetime of `self`.
+            let entered = unsafe { io_uring_enter(&self.fd, to_submit, 1, IoringEnterFlags::GETEVENTS) };
+            #[cfg(test)]
+            if std::mem::take(&mut self.fail_next_enter) {
+                return entered.and(Err(rustix::io::Errno::IO));
+            }
+            entered
+        }
+
+        /// Move completions into `results` by `user_data`, returning how
+        /// many were taken off the CQ ring.
+        fn reap(&self, results: &mut [i32]) -> usize {
+            let coff = &self.params.cq_off;
+            let cmask = self.params.cq_entries - 1;
+            let mut head = self.cq_u32(coff.head).load(Ordering::Acquire);
+            let cq_tail = self.cq_u32(coff.tail).load(Ordering::Acquire);
+            let mut reaped = 0;
+            while head != cq_tail {
+                // SAFETY: entries between head and tail are owned by us.
+                let (user_data, res) = unsafe {
+                    let cqe = (self.cq.ptr.add(coff.cqes as usize) as *const io_uring_cqe).add((head & cmask) as usize);
+                    ((*cqe).user_data.u64_(), (*cqe).res)
+                };
+                if let Some(slot) = results.get_mut(user_data as usize) {
+                    *slot = res;
+                }
+                head = head.wrapping_add(1);
+                reaped += 1;
+            }
+            self.cq_u32(coff.head).store(head, Ordering::Release);
+            reaped
+        }
+    }
+
+    /// io_uring backend. Short transfers are completed with plain
+    /// positional IO, and once a submit fails every batch goes through
+    /// [`StdIo`].
+    pub struct UringIo {
+        ring: Mutex<Ring>,
+    }
+
+    impl UringIo {
+        pub fn new() -> io::Result<Self> {
+            Ok(Self {
+                ring: Mutex::new(Ring::new()?),
+            })
+        }
+
+        fn sqe(op: IoringOp, file: &File, offset: u64, ptr: *mut c_void, len: usize, index: usize) -> io_uring_sqe {
+            let mut sqe = io_uring_sqe {
+                opcode: op,
+                fd: file.as_raw_fd(),
+                ..Default::default()
+            };
+            sqe.off_or_addr2.off = offset;
+            sqe.addr_or_splice_off_in.addr = io_uring_ptr::new(ptr);
+            sqe.len.len = len as u32;
+            sqe.user_data = io_uring_user_data::from_u64(index as u64);
+            sqe
+        }
+
+        /// Make the next submit fail as if the kernel refused it.
+        #[cfg(test)]
+        pub(crate) fn fail_next_submit(&self) {
+            self.ring.lock().unwrap().fail_next_enter = true;
+        }
+
+        /// Whether batches still go through the ring.
+        pub fn is_usable(&self) -> bool {
+            !self.ring.lock().unwrap_or_else(|e| e.into_inner()).broken
+        }
+
+        fn check(res: i32) -> io::Result<usize> {
+            if res < 0 {
+                Err(io::Error::from_raw_os_error(-res))
+            } else {
+                Ok(res as usize)
+            }
+        }
+    }
+
+    impl IoBackend for UringIo {
+        fn name(&self) -> &'static str {
+            "io_uring"
+        }
+
+        fn write_batch(&self, file: &File, writes: &[(u64, &[u8])]) -> io::Result<()> {
+            let mut ring = self.ring.lock().unwrap_or_else(|e| e.into_inner());
+            for batch in writes.chunks(ring.params.sq_entries as usize) {
+                if ring.broken {
+                    StdIo.write_batch(file, batch)?;
+                    continue;
+                }
+                let sqes: Vec<io_uring_sqe> = batch
+                    .iter()
+                    .enumerate()
+                    .map(|(i, &(offset, data))| {
+                        Self::sqe(IoringOp::Write, file, offset, data.as_ptr() as *mut c_void, data.len(), i)
+                    })
+                    .collect();
+                let results = match ring.run(&sqes) {
+                    Ok(results) => results,
+                    Err(e) => {
+                        log::warn!("io_uring submit failed ({}); using std IO", e);
+                        StdIo.write_batch(file, batch)?;
+                        continue;
+                    }
+                };
+                for (&(offset, data), res) in batch.iter().zip(results) {
+                    let done = Self::check(res)?;
+                    write_all_at(file, offset + done as u64, &data[done..])?;
+                }
+            }
+            Ok(())
+        }
+
+        fn read_batch(&self, file: &File, reads: &mut [(u64, &mut [u8])]) -> io::Result<Vec<usize>> {
+            let mut ring = self.ring.lock().unwrap_or_else(|e| e.into_inner());
+            let mut lengths = Vec::with_capacity(reads.len());
+            for batch in reads.chunks_mut(ring.params.sq_entries as usize) {
+                if ring.broken {
+                    lengths.extend(StdIo.read_batch(file, batch)?);
+                    continue;
+                }
+                let sqes: Vec<io_uring_sqe> = batch
+                    .iter_mut()
+                    .enumerate()
+                    .map(|(i, (offset, buf))| {
+                        Self::sqe(IoringOp::Read, file, *offset, buf.as_mut_ptr() as *mut c_void, buf.len(), i)
+                    })
+                    .collect();
+                let results = match ring.run(&sqes) {
+                    Ok(results) => results,
+                    Err(e) => {
+                        log::warn!("io_uring submit failed ({}); using std IO", e);
+                        lengths.extend(StdIo.read_batch(file, batch)?);
+                        continue;
+                    }
+                };
+                for ((offset, buf), res) in batch.iter_mut().zip(results) {
+                    let done = Self::check(res)?;
+                    let rest = if done == 0 || done == buf.len() {
+                        0
+                    } else {
+                        read_full_at(file, *offset + done as u64, &mut buf[done..])?
+                    };
+                    lengths.push(done + rest);
+                }
+            }
+            Ok(lengths)
+        }
+    }
+}
+
+#[cfg(all(windows, feature = "platform-io"))]
+pub mod overlapped {
+    //! Overlapped IO: the file is reopened with `FILE_FLAG_OVERLAPPED`, every
+    //! request of a batch is issued, then all are awaited.
+
+    use std::fs::File;
+    use std::io;
+    use std::os::windows::io::AsRawHandle;
+    use windows_sys::Win32::Foundation::{
+        CloseHandle, GetLastError, ERROR_HANDLE_EOF, ERROR_IO_PENDING, HANDLE, INVALID_HANDLE_VALUE,
+    };
+    use windows_sys::Win32::Storage::FileSystem::{
+        ReOpenFile, ReadFile, WriteFile, FILE_FLAG_OVERLAPPED, FILE_GENERIC_READ, FILE_GENERIC_WRITE,
+        FILE_SHARE_DELETE, FILE_SHARE_READ, FILE_SHARE_WRITE,
+    };
+    use windows_sys::Win32::System::Threading::CreateEventW;
+    use windows_sys::Win32::System::IO::{GetOverlappedResult, OVERLAPPED};
+
+    use super::{read_full_at, write_all_at, IoBackend};
+
+    /// Requests in flight per batch.
+    const MAX_IN_FLIGHT: usize = 64;
+
+    struct OwnedHandle(HANDLE);
+
+    impl Drop for OwnedHandle {
+        fn drop(&mut self) {
+            // SAFETY: the handle was returned by a successful Win32 call.
+            unsafe {
+                CloseHandle(self.0);
+            }
+        }
+    }
+
+    fn reopen(file: &File, access: u32) -> io::Result<OwnedHandle> {
+        // SAFETY: `file` is a valid open handle.
+        let handle = unsafe {
+            ReOpenFile(
+                file.as_raw_handle() as HANDLE,
+                access,
+                FILE_SHARE_READ | FILE_SHARE_WRITE | FILE_SHARE_DELETE,
+                FILE_FLAG_OVERLAPPED,
+            )
+        };
+        if handle == INVALID_HANDLE_VALUE {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(OwnedHandle(handle))
+    }
+
+    struct Request {
+        overlapped: Box<OVERLAPPED>,
+        _event: OwnedHandle,
+    }
+
+    fn request(offset: u64) -> io::Result<Request> {
+        // SAFETY: creating an unnamed manual-reset event.
+        let event = unsafe { CreateEventW(std::ptr::null(), 1, 0, std::ptr::null()) };
+        if event == 0 {
+            return Err(io::Error::last_os_error());
+        }
+        // SAFETY: OVERLAPPED is plain data; zero is its documented initial state.
+        let mut overlapped: Box<OVERLAPPED> = Box::new(unsafe { std::mem::zeroed() });
+        overlapped.Anonymous.Anonymous.Offset = offset as u32;
+        overlapped.Anonymous.Anonymous.OffsetHigh = (offset >> 32) as u32;
+        overlapped.hEvent = event;
+        Ok(Request {
+            overlapped,
+            _event: OwnedHandle(event),
+        })
+    }
+
+    /// Issue result: `Ok(())` once queued or completed inline; EOF on reads
+    /// completes as zero bytes.
+    fn issued(ok: i32) -> io::Result<()> {
+        if ok != 0 {
+            return Ok(());
+        }
+        // SAFETY: reads the calling thread's last error.
+        match unsafe { GetLastError() } {
+            ERROR_IO_PENDING | ERROR_HANDLE_EOF => Ok(()),
+            code => Err(io::Error::from_raw_os_error(code as i32)),
+        }
+    }
+
+    fn wait(handle: &OwnedHandle, request: &Request) -> io::Result<usize> {
+        let mut transferred = 0u32;
+        // SAFETY: `request.overlapped` was used for an operation on `handle`.
+        let ok = unsafe { GetOverlappedResult(handle.0, &*request.overlapped, &mut transferred, 1) };
+        if ok == 0 {
+            // SAFETY: reads the calling thread's last error.
+            let code = unsafe { GetLastError() };
+            if code != ERROR_HANDLE_EOF {
+                return Err(io::Error::from_raw_os_error(code as i32));
+            }
+        }
+        Ok(transferred as usize)
+    }
+
+    pub struct OverlappedIo;
+
+    impl IoBackend for OverlappedIo {
+        fn name(&self) -> &'static str {
+            "overlapped"
+        }
+
+        fn write_batch(&self, file: &File, writes: &[(u64, &[u8])]) -> io::Result<()> {
+            let handle = reopen(file, FILE_GENERIC_WRITE)?;
+            for batch in writes.chunks(MAX_IN_FLIGHT) {
+                let mut requests = Vec::with_capacity(batch.len());
+                let mut failed = None;
+                for &(offset, data) in batch {
+                    let mut req = match request(offset) {
+                        Ok(req) => req,
+                        Err(e) => {
+                            failed = Some(e);
+                            break;
+                        }
+                    };
+                    // SAFETY: `data` and `req.overlapped` outlive the wait below.
+                    let ok = unsafe {
+                        WriteFile(handle.0, data.as_ptr(), data.len() as u32, std::ptr::null_mut(), &mut *req.overlapped)
+                    };
+                    if let Err(e) = issued(ok) {
+                        failed = Some(e);
+                        break;
+                    }
+                    requests.push(req);
+                }
+                // Everything issued is awaited before its buffer can go away.
+                let done: Vec<io::Result<usize>> = requests.iter().map(|req| wait(&handle, req)).collect();
+                if let Some(e) = failed {
+                    return Err(e);
+                }
+                for (&(offset, data), done) in batch.iter().zip(done) {
+                    let done = done?;
+                    write_all_at(file, offset + done as u64, &data[done..])?;
+                }
+            }
+            Ok(())
+        }
+
+        fn read_batch(&self, file: &File, reads: &mut [(u64, &mut [u8])]) -> io::Result<Vec<usize>> {
+            let handle = reopen(file, FILE_GENERIC_READ)?;
+            let mut lengths = Vec::with_capacity(reads.len());
+            for batch in reads.chunks_mut(MAX_IN_FLIGHT) {
+                let mut requests = Vec::with_capacity(batch.len());
+                let mut failed = None;
+                for (offset, buf) in batch.iter_mut() {
+                    let mut req = match request(*offset) {
+                        Ok(req) => req,
+                        Err(e) => {
+                            failed = Some(e);
+                            break;
+                        }
+                    };
+                    // SAFETY: `buf` and `req.overlapped` outlive the wait below.
+                    let ok = unsafe {
+                        ReadFile(handle.0, buf.as_mut_ptr(), buf.len() as u32, std::ptr::null_mut(), &mut *req.overlapped)
+                    };
+                    if let Err(e) = issued(ok) {
+                        failed = Some(e);
+                        break;
+                    }
+                    requests.push(req);
+                }
+                let done: Vec<io::Result<usize>> = requests.iter().map(|req| wait(&handle, req)).collect();
+                if let Some(e) = failed {
+                    return Err(e);
+                }
+                for ((offset, buf), done) in batch.iter_mut().zip(done) {
+                    let done = done?;
+                    let rest = if done == 0 || done == buf.len() {
+                        0
+                    } else {
+                        read_full_at(file, *offset + done as u64, &mut buf[done..])?
+                    };
+                    lengths.push(done + rest);
+                }
+            }
+            Ok(lengths)
+        }
+    }
+}
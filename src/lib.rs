@@ -1,97 +1,338 @@
 pub mod types {
+    pub mod cache;
     pub mod chunk;
+    pub mod custom_fields;
     pub mod file;
     pub mod flags;
+    pub mod footprint;
     pub mod header;
     pub mod manifest;
     pub mod meta;
     pub mod json_manifest;
+    pub mod chunk_file;
 }
 
 pub mod parser {
+    pub(crate) mod fast;
+    pub mod reader;
+    pub(crate) mod resync;
+}
+
+#[cfg(feature = "archives")]
+pub mod archive;
+pub mod audit;
+pub mod crypto;
+pub mod detect;
+pub mod fingerprint;
+
+pub mod diff {
+    pub mod changelog;
+    pub mod compare;
+    pub mod render;
+}
+
+/// Stable, versioned views of a parsed manifest for JavaScript and storage.
+pub mod dto {
+    pub mod v1;
+}
+
+pub mod download {
+    pub mod cdn_check;
+    pub mod config;
+    pub mod downloader;
+    pub mod mirrors;
+    pub mod retry;
+}
+
+pub mod format;
+pub mod health;
+pub mod history;
+pub mod hooks;
+pub mod integrity;
+
+pub mod export {
+    pub mod cas;
+    pub mod checksums;
+    pub mod composition;
+    pub mod json;
+    pub mod lockfile;
+    pub mod torrent;
+}
+
+pub mod index {
+    pub mod bloom;
+    pub mod directory;
+    pub mod manifests;
+}
+
+pub mod install {
+    pub mod actions;
+    pub mod apply;
+    pub mod assemble;
+    #[cfg(feature = "async")]
+    pub mod pipeline;
+    pub mod plan;
+}
+
+pub mod io_backend;
+pub mod options;
+pub mod parallelism;
+pub mod parsers;
+pub mod platform;
+pub mod prereq;
+pub mod pretty;
+pub mod probe;
+pub mod provider;
+pub mod report;
+pub mod schema;
+pub mod stream;
+pub mod truncation;
+pub mod validate;
+pub mod writer;
+
+pub mod store {
+    pub mod availability;
+    pub mod chunks;
+    pub mod manifests;
+}
+
+pub mod verify {
+    pub mod install;
     pub mod reader;
 }
 
 pub mod error;
 
+#[cfg(feature = "napi")]
+pub mod node;
+
+/// The types most consumers need, for `use egdata_manifests_parser::prelude::*`.
+pub mod prelude {
+    pub use crate::detect::{detect_format, scan_for_manifest, ManifestFormat};
+    pub use crate::error::ManifestError;
+    pub use crate::options::{ParseOptions, StringPolicy};
+    pub use crate::parser::reader::ReadExt;
+    pub use crate::provider::ChunkProvider;
+    pub use crate::report::{FileIssue, GuidIssue, MetaCheck, ParseReport, PayloadHashCheck};
+    pub use crate::store::availability::ChunkAvailability;
+    pub use crate::store::chunks::ChunkStore;
+    pub use crate::store::manifests::{DirManifestStore, ManifestStore, MemoryManifestStore};
+    pub use crate::stream::{parse_all, ManifestStream};
+    pub use crate::writer::{normalize, ManifestWriter};
+    pub use crate::types::chunk::{Chunk, ChunkDataList, ChunkPart, GuidFormat};
+    pub use crate::types::custom_fields::CustomFields;
+    pub use crate::types::file::{FileManifest, FileManifestList};
+    pub use crate::types::header::ManifestHeader;
+    pub use crate::types::manifest::Manifest;
+    pub use crate::types::meta::ManifestMeta;
+    pub use crate::{load, load_meta, load_with_key, load_with_options, parse_with_report};
+    #[cfg(feature = "async")]
+    pub use crate::{load_async, load_async_with_options};
+}
+
 // Re-export commonly used types
-pub use types::chunk::ChunkDataList;
-pub use types::file::FileManifestList;
+pub use error::ManifestError;
+pub use types::chunk::{Chunk, ChunkDataList, ChunkPart, GuidFormat};
+pub use types::custom_fields::CustomFields;
+pub use types::file::{FileManifest, FileManifestList};
 pub use types::header::ManifestHeader;
 pub use types::manifest::Manifest;
 pub use types::meta::ManifestMeta;
+pub use detect::{detect_format, scan_for_manifest, ManifestFormat};
+pub use diff::compare::ManifestDiff;
+pub use options::{ByteTrace, MetaRecovery, ParseLimits, ParseOptions, StringPolicy};
+pub use parallelism::Parallelism;
+pub use parsers::{ManifestParser, ParserRegistry};
+pub use parser::reader::ReadExt;
+pub use prereq::PrereqRegistry;
+pub use pretty::Verbosity;
+pub use probe::{probe, ManifestProbe};
+pub use provider::ChunkProvider;
+pub use hooks::{NoHooks, ParserHooks};
+pub use report::{MetaCheck, ParseReport, PayloadHashCheck};
+pub use stream::{parse_all, ManifestStream};
+pub use writer::{normalize, ManifestWriter};
+pub use store::availability::ChunkAvailability;
+pub use store::chunks::ChunkStore;
+pub use store::manifests::{DirManifestStore, ManifestStore, MemoryManifestStore};
 
 use std::{
     fs,
     io::{Cursor, Seek},
+    ops::ControlFlow,
     path::Path,
 };
 
-use error::ManifestError;
-use types::json_manifest::{JsonManifest, is_json_manifest};
+use types::json_manifest::JsonManifest;
+use crypto::AesKey;
+use hooks::{ParseWarning, Section};
+use options::check_deadline;
 
-use hex;
 use log::{debug, error, info, warn};
 use miniz_oxide::inflate::decompress_to_vec_zlib;
-use napi_derive::napi;
 use sha1::{Digest, Sha1};
+#[cfg(feature = "async")]
 use tokio::fs as tokio_fs;
 
 /// Read → verify → parse
 pub fn load(path: impl AsRef<Path>) -> Result<Manifest, ManifestError> {
-    let buf = fs::read(&path)?;
-    process_manifest_data(buf)
+    load_with_options(path, &ParseOptions::default())
 }
 
 /// Async version of load
+#[cfg(feature = "async")]
 pub async fn load_async(path: impl AsRef<Path>) -> Result<Manifest, ManifestError> {
+    load_async_with_options(path, &ParseOptions::default()).await
+}
+
+/// [`load`] with explicit parse options
+pub fn load_with_options(path: impl AsRef<Path>, options: &ParseOptions) -> Result<Manifest, ManifestError> {
+    let buf = fs::read(&path)?;
+    process_manifest_data_with(buf, options)
+}
+
+/// [`load`] of a manifest stored encrypted, decrypted with `key`. Other
+/// manifests load as with [`load`].
+pub fn load_with_key(path: impl AsRef<Path>, key: &AesKey) -> Result<Manifest, ManifestError> {
+    let options = ParseOptions {
+        decryption_key: Some(key.clone()),
+        ..ParseOptions::default()
+    };
+    load_with_options(path, &options)
+}
+
+/// The header and meta of the manifest at `path`, for listing app names and
+/// build versions without reading the chunk and file lists; see
+/// [`parse_meta`].
+pub fn load_meta(path: impl AsRef<Path>) -> Result<(ManifestHeader, ManifestMeta), ManifestError> {
+    parse_meta(&fs::read(&path)?, &ParseOptions::default())
+}
+
+/// The header and meta of the manifest in `buf`. Binary manifests are
+/// decompressed (and decrypted) but read no further than the meta section,
+/// which fails as [`ManifestError::Meta`] when unreadable. Other formats are
+/// parsed whole. Of `options`, the string policy, decryption key and
+/// payload hash requirement apply.
+pub fn parse_meta(buf: &[u8], options: &ParseOptions) -> Result<(ManifestHeader, ManifestMeta), ManifestError> {
+    if !matches!(detect_format(buf), ManifestFormat::BinaryV1 | ManifestFormat::BinaryV2Plus { .. }) {
+        let (manifest, _) = parse_with_report(buf, options)?;
+        let meta = manifest
+            .meta
+            .ok_or_else(|| ManifestError::Invalid("manifest has no meta".to_string()))?;
+        return Ok((manifest.header, meta));
+    }
+
+    let header = ManifestHeader::read(Cursor::new(buf))?;
+    let (payload, _) = manifest_payload(buf, &header, options.byte_trace(), options.decryption_key.as_ref())?;
+    if (options.require_payload_hash || options.strict) && header.payload_hash_is_sha1() {
+        let actual = hex::encode(Sha1::digest(&payload));
+        if actual != header.sha1_hash {
+            return Err(ManifestError::Sha1Mismatch {
+                expected: header.sha1_hash,
+                actual,
+            });
+        }
+    }
+    let (mut meta, _) = ManifestMeta::read_meta_with_options(&mut Cursor::new(&payload), options)
+        .map_err(|e| ManifestError::Meta {
+            offset: 0,
+            source: Box::new(e),
+        })?;
+    meta.ensure_build_id();
+    Ok((header, meta))
+}
+
+/// [`load_async`] with explicit parse options
+#[cfg(feature = "async")]
+pub async fn load_async_with_options(
+    path: impl AsRef<Path>,
+    options: &ParseOptions,
+) -> Result<Manifest, ManifestError> {
     let buf = tokio_fs::read(&path).await?;
-    process_manifest_data(buf)
+    process_manifest_data_with(buf, options)
 }
 
 /// Process manifest data from a buffer
+#[cfg(test)]
 fn process_manifest_data(buf: Vec<u8>) -> Result<Manifest, ManifestError> {
-    // Check if this is a JSON manifest first
-    if is_json_manifest(&buf) {
-        info!("Detected JSON manifest format");
-        let json_str = std::str::from_utf8(&buf)
-            .map_err(|e| ManifestError::Invalid(format!("Invalid UTF-8 in JSON manifest: {}", e)))?;
-        
-        let json_manifest = JsonManifest::from_str(json_str)?;
-        return json_manifest.to_manifest();
-    }
+    process_manifest_data_with(buf, &ParseOptions::default())
+}
 
-    // Otherwise, process as binary manifest
-    info!("Processing as binary manifest format");
-    let mut rdr = Cursor::new(&buf);
-    let header = ManifestHeader::read(&mut rdr)?;
+fn process_manifest_data_with(buf: Vec<u8>, options: &ParseOptions) -> Result<Manifest, ManifestError> {
+    parse_with_report(&buf, options).map(|(manifest, _)| manifest)
+}
 
-    // ---------------------------------------------------------------- body
-    let payload_compressed = {
+/// Parse a manifest from memory, also returning what was noticed on the way
+pub fn parse_with_report(
+    buf: &[u8],
+    options: &ParseOptions,
+) -> Result<(Manifest, ParseReport), ManifestError> {
+    parse_with_hooks(buf, options, &mut NoHooks)
+}
+
+/// [`parse_with_report`], calling `hooks` as sections are read
+pub fn parse_with_hooks<H: ParserHooks + ?Sized>(
+    buf: &[u8],
+    options: &ParseOptions,
+    hooks: &mut H,
+) -> Result<(Manifest, ParseReport), ManifestError> {
+    let mut report = ParseReport::default();
+    let parsers = ParserRegistry::global();
+    if options.scan_for_manifest && parsers.find(buf).is_none() {
+        report.offset = scan_for_manifest(buf)
+            .ok_or_else(|| ManifestError::Invalid("no manifest found in input".to_string()))?;
+        info!("Found embedded manifest at offset {}", report.offset);
+        emit_warning(hooks, options, ParseWarning::EmbeddedManifest { offset: report.offset })?;
+    }
+    let buf = &buf[report.offset..];
+    let mut hooks = hooks;
+    let mut manifest = parsers.parser_for(buf).parse(buf, options, &mut report, &mut hooks)?;
+    if options.guid_format != GuidFormat::Hyphenated {
+        manifest.set_guid_format(options.guid_format);
+    }
+    if options.file_flags {
+        manifest.fill_file_flags();
+    }
+    Ok((manifest, report))
+}
+
+/// The manifest payload after the header: decrypted with `key` when
+/// encrypted, decompressed when stored with zlib, plus the number of bytes
+/// it occupied in `buf`.
+pub(crate) fn manifest_payload(
+    buf: &[u8],
+    header: &ManifestHeader,
+    trace: ByteTrace,
+    key: Option<&AesKey>,
+) -> Result<(Vec<u8>, usize), ManifestError> {
+    let stored = {
         let start = header.header_size as usize;
-        let size = if header.is_compressed() {
-            header.data_size_compressed
-        } else {
-            header.data_size_uncompressed
-        };
-        let end = start + size as usize;
+        let end = start + header.stored_size() as usize;
         if start >= buf.len() || end > buf.len() {
             return Err(ManifestError::Invalid("payload out of bounds".to_string()));
         }
         &buf[start..end]
     };
 
-    if header.is_encrypted() {
-        return Err(ManifestError::EncryptedManifest);
-    }
+    let decrypted;
+    let payload_compressed = if header.is_encrypted() {
+        let key = key.ok_or(ManifestError::EncryptedManifest)?;
+        info!("Decrypting payload...");
+        decrypted = key.decrypt(stored)?;
+        if header.is_compressed() {
+            decrypted.as_slice()
+        } else {
+            // Block padding is not part of the payload.
+            &decrypted[..decrypted.len().min(header.data_size_uncompressed as usize)]
+        }
+    } else {
+        stored
+    };
 
     let payload = if header.is_compressed() {
         info!("Decompressing data...");
         debug!("  Compressed size: {}", payload_compressed.len());
-        debug!(
-            "  Compressed data starts with: {:02x?}",
-            &payload_compressed[..std::cmp::min(16, payload_compressed.len())]
-        );
+        trace.dump("  Compressed data", payload_compressed);
 
         // Try to find zlib header
         let mut offset = 0;
@@ -139,10 +380,7 @@ fn process_manifest_data(buf: Vec<u8>) -> Result<Manifest, ManifestError> {
             debug!("  Found zlib header at offset 9 in uncompressed data");
             let compressed_data = &payload_compressed[9..];
             debug!("  Decompressing {} bytes of data", compressed_data.len());
-            debug!(
-                "  Compressed data starts with: {:02x?}",
-                &compressed_data[..std::cmp::min(16, compressed_data.len())]
-            );
+            trace.dump("  Compressed data", compressed_data);
             // FIX: Use explicit match instead of ?
             match decompress_to_vec_zlib(compressed_data) {
                 Ok(data) => data,
@@ -158,25 +396,165 @@ fn process_manifest_data(buf: Vec<u8>) -> Result<Manifest, ManifestError> {
             payload_compressed.to_vec()
         }
     };
+    Ok((payload, stored.len()))
+}
 
-    debug!("Payload length: {}", payload.len());
-    debug!(
-        "Payload starts with: {:02x?}",
-        &payload[..std::cmp::min(16, payload.len())]
-    );
+/// Report sections laid out beyond what [`schema`] describes.
+fn warn_unknown_layout<H: ParserHooks + ?Sized>(
+    hooks: &mut H,
+    options: &ParseOptions,
+    section: Section,
+    data_version: u8,
+    extra_data: &Option<String>,
+) -> Result<(), ManifestError> {
+    let latest = schema::SectionSchema::for_section(section).map_or(0, |s| s.latest_version());
+    if data_version > latest {
+        warn!("{} data version {} is newer than {}; reading the known fields", section, data_version, latest);
+        emit_warning(hooks, options, ParseWarning::NewerSectionVersion { section, data_version })?;
+    }
+    if let Some(extra) = extra_data {
+        warn!("{} has {} bytes past the known fields", section, extra.len() / 2);
+        emit_warning(
+            hooks,
+            options,
+            ParseWarning::UnknownSectionData {
+                section,
+                bytes: extra.len() / 2,
+            },
+        )?;
+    }
+    Ok(())
+}
+
+/// Pass `warning` to `hooks`, then fail with it under
+/// [`ParseOptions::warnings_as_errors`].
+fn emit_warning<H: ParserHooks + ?Sized>(
+    hooks: &mut H,
+    options: &ParseOptions,
+    warning: ParseWarning,
+) -> Result<(), ManifestError> {
+    hooks.on_warning(&warning);
+    if options.warnings_as_errors {
+        return Err(ManifestError::Warning(warning));
+    }
+    Ok(())
+}
+
+/// Fill in what was parsed before a [`ManifestError::Timeout`] raised
+/// without it; other errors pass through.
+fn with_partial(e: ManifestError, partial: impl FnOnce() -> Manifest) -> ManifestError {
+    match e {
+        ManifestError::Timeout { section, partial: None } => ManifestError::Timeout {
+            section,
+            partial: Some(Box::new(partial())),
+        },
+        e => e,
+    }
+}
 
-    // Calculate SHA-1 of the payload
-    let mut hasher = Sha1::new();
-    hasher.update(&payload);
-    let payload_sha = hasher.finalize();
-    debug!("Payload SHA-1: {}", hex::encode(payload_sha));
-    debug!("Header SHA-1: {}", header.sha1_hash);
+/// Turn a hook's answer into an abort error.
+fn hook_flow(flow: ControlFlow<()>, section: Section) -> Result<(), ManifestError> {
+    match flow {
+        ControlFlow::Continue(()) => Ok(()),
+        ControlFlow::Break(()) => Err(ManifestError::Aborted(section)),
+    }
+}
 
-    if hex::encode(payload_sha) != header.sha1_hash {
-        warn!("Warning: Payload SHA-1 does not match header SHA-1");
+/// [`parsers::JsonParser`]'s parse.
+pub(crate) fn parse_json_bytes<H: ParserHooks + ?Sized>(
+    buf: &[u8],
+    options: &ParseOptions,
+    report: &mut ParseReport,
+    hooks: &mut H,
+) -> Result<Manifest, ManifestError> {
+    info!("Detected JSON manifest format");
+    check_deadline(options.deadline, Section::Json)?;
+    hook_flow(hooks.on_section_start(Section::Json, 0), Section::Json)?;
+    let json_manifest = JsonManifest::from_slice(buf)?;
+    let issues_before = report.invalid_guids.len();
+    let mut manifest = json_manifest.to_manifest_with_issues(&options.json, &mut report.invalid_guids)?;
+    for issue in &report.invalid_guids[issues_before..] {
+        hooks.on_recovered_error(Section::Json, &format!("{}: invalid GUID {}", issue.path, issue.guid));
+    }
+    if options.strings.trim_nul {
+        manifest.trim_nul_padding();
+    }
+    if let Some(meta) = &mut manifest.meta {
+        meta.ensure_build_id();
     }
+    hook_flow(hooks.on_section_end(Section::Json, buf.len() as u64), Section::Json)?;
+    Ok(manifest)
+}
+
+/// [`parsers::BinaryParser`]'s parse.
+pub(crate) fn parse_binary_bytes<H: ParserHooks + ?Sized>(
+    buf: &[u8],
+    options: &ParseOptions,
+    report: &mut ParseReport,
+    hooks: &mut H,
+) -> Result<Manifest, ManifestError> {
+    info!("Processing as binary manifest format");
+    hook_flow(hooks.on_section_start(Section::Header, 0), Section::Header)?;
+    let mut rdr = Cursor::new(buf);
+    let header = ManifestHeader::read(&mut rdr)?;
+    hook_flow(hooks.on_section_end(Section::Header, header.header_size as u64), Section::Header)?;
+    let header_only = || Manifest {
+        header: header.clone(),
+        ..Manifest::default()
+    };
+    check_deadline(options.deadline, Section::Payload).map_err(|e| with_partial(e, header_only))?;
+    hook_flow(hooks.on_section_start(Section::Payload, header.header_size as u64), Section::Payload)?;
+
+    // ---------------------------------------------------------------- body
+    let trace = options.byte_trace();
+    let (payload, stored_size) = manifest_payload(buf, &header, trace, options.decryption_key.as_ref())?;
+
+    debug!("Payload length: {}", payload.len());
+    trace.dump("Payload", &payload);
+
+    // Calculate SHA-1 of the payload, unless the header says it was hashed
+    // with something else: comparing against the wrong digest proves nothing.
+    report.payload_hash = if header.payload_hash_is_sha1() {
+        let mut hasher = Sha1::new();
+        hasher.update(&payload);
+        let payload_sha = hasher.finalize();
+        debug!("Payload SHA-1: {}", hex::encode(payload_sha));
+        debug!("Header SHA-1: {}", header.sha1_hash);
+
+        if hex::encode(payload_sha) != header.sha1_hash {
+            if options.require_payload_hash || options.strict {
+                return Err(ManifestError::Sha1Mismatch {
+                    expected: header.sha1_hash.clone(),
+                    actual: hex::encode(payload_sha),
+                });
+            }
+            warn!("Warning: Payload SHA-1 does not match header SHA-1");
+            emit_warning(hooks, options, ParseWarning::PayloadHashMismatch)?;
+            PayloadHashCheck::Mismatched
+        } else {
+            PayloadHashCheck::Matched
+        }
+    } else {
+        warn!(
+            "Warning: payload hash type {} is not supported; payload not verified",
+            header.hash_type
+        );
+        emit_warning(
+            hooks,
+            options,
+            ParseWarning::PayloadHashUnsupported {
+                hash_type: header.hash_type,
+            },
+        )?;
+        PayloadHashCheck::Unsupported {
+            hash_type: header.hash_type,
+        }
+    };
 
-    let mut cur = Cursor::new(payload.clone());
+    hook_flow(hooks.on_section_end(Section::Payload, stored_size as u64), Section::Payload)?;
+
+    let mut cur = Cursor::new(payload);
+    let fast_path = options.trusted && options.strings.strict;
 
     // --- Metadata Reading ---
     let meta_start_pos = cur.position();
@@ -186,25 +564,65 @@ fn process_manifest_data(buf: Vec<u8>) -> Result<Manifest, ManifestError> {
     );
 
     // Read metadata and process the result
-    let meta_result = ManifestMeta::read_meta(&mut cur);
+    check_deadline(options.deadline, Section::Meta).map_err(|e| with_partial(e, header_only))?;
+    hook_flow(hooks.on_section_start(Section::Meta, meta_start_pos), Section::Meta)?;
+    let meta_result = ManifestMeta::read_meta_with_options(&mut cur, options);
 
     // Map the result directly to Option<ManifestMeta> and handle side-effects
     let meta: Option<ManifestMeta> = match meta_result {
-        Ok((parsed_meta, _)) => {
+        Ok((mut parsed_meta, _)) => {
             info!(
                 "Successfully parsed metadata. Data size: {} (0x{:x})",
                 parsed_meta.data_size, parsed_meta.data_size
             );
+            parsed_meta.ensure_build_id();
+            report.meta = MetaCheck::Parsed;
             Some(parsed_meta)
         }
+        Err(e) if options.require_meta || options.strict => {
+            return Err(ManifestError::Meta {
+                offset: meta_start_pos,
+                source: Box::new(e),
+            })
+        }
         Err(e) => {
             error!("Failed to parse metadata: {}", e);
+            // Not `e.as_ref()`: that is only the error kind.
+            let message = e.to_string();
+            hooks.on_recovered_error(Section::Meta, &message);
+            // The meta reader stops after the declared section size, or
+            // after the size field if that was implausible; neither need be
+            // where the chunk list starts.
+            let resynced_at = match options.meta_recovery {
+                MetaRecovery::Resync if !parser::resync::is_chunk_list(cur.get_ref(), cur.position() as usize) => {
+                    parser::resync::find_chunk_list(cur.get_ref(), meta_start_pos as usize + 1)
+                }
+                _ => None,
+            };
+            if let Some(offset) = resynced_at {
+                warn!("Resynchronized: chunk list found at payload offset {}", offset);
+                emit_warning(
+                    hooks,
+                    options,
+                    ParseWarning::Resynchronized {
+                        section: Section::ChunkList,
+                        offset: offset as u64,
+                    },
+                )?;
+                cur.set_position(offset as u64);
+            }
+            report.meta = MetaCheck::Failed {
+                offset: meta_start_pos,
+                error: message,
+                resynced_at: resynced_at.map(|offset| offset as u64),
+            };
             None
         }
     };
 
     // Always seek to the end of the metadata section based on the reported data size
     if let Some(meta) = &meta {
+        warn_unknown_layout(hooks, options, Section::Meta, meta.data_version, &meta.extra_data)?;
         let expected_meta_end_pos = meta_start_pos + meta.data_size as u64;
         let current_pos = cur.position();
         info!(
@@ -221,7 +639,31 @@ fn process_manifest_data(buf: Vec<u8>) -> Result<Manifest, ManifestError> {
         chunk_list_start_pos, chunk_list_start_pos
     );
 
-    let chunk_list = ChunkDataList::read(&mut cur)?;
+    hook_flow(hooks.on_section_end(Section::Meta, chunk_list_start_pos - meta_start_pos), Section::Meta)?;
+    let up_to_meta = || Manifest {
+        meta: meta.clone(),
+        ..header_only()
+    };
+    check_deadline(options.deadline, Section::ChunkList).map_err(|e| with_partial(e, up_to_meta))?;
+    hook_flow(hooks.on_section_start(Section::ChunkList, chunk_list_start_pos), Section::ChunkList)?;
+    let fast_chunk_list = fast_path
+        .then(|| {
+            let start = chunk_list_start_pos as usize;
+            parser::fast::read_chunk_list(cur.get_ref(), start, &options.limits, options.deadline)
+        })
+        .flatten();
+    let chunk_list = match fast_chunk_list {
+        Some((chunk_list, end)) => {
+            cur.set_position(end as u64);
+            chunk_list
+        }
+        None => ChunkDataList::read_with_options(&mut cur, options).map_err(|e| with_partial(e, up_to_meta))?,
+    };
+    warn_unknown_layout(hooks, options, Section::ChunkList, chunk_list.data_version, &chunk_list.extra_data)?;
+    hook_flow(
+        hooks.on_section_end(Section::ChunkList, cur.position() - chunk_list_start_pos),
+        Section::ChunkList,
+    )?;
 
     // --- File List Reading ---
     let file_list_start_pos = cur.position();
@@ -230,46 +672,130 @@ fn process_manifest_data(buf: Vec<u8>) -> Result<Manifest, ManifestError> {
         file_list_start_pos, file_list_start_pos
     );
 
-    let file_list = FileManifestList::read(&mut cur, &chunk_list)?;
+    let up_to_chunk_list = || Manifest {
+        chunk_list: Some(chunk_list.clone()),
+        ..up_to_meta()
+    };
+    check_deadline(options.deadline, Section::FileList).map_err(|e| with_partial(e, up_to_chunk_list))?;
+    hook_flow(hooks.on_section_start(Section::FileList, file_list_start_pos), Section::FileList)?;
+    let issues_before = report.corrupt_files.len();
+    let fast_file_list = fast_path
+        .then(|| {
+            let start = file_list_start_pos as usize;
+            parser::fast::read_file_list(cur.get_ref(), start, &chunk_list, &options.strings, &options.limits, options.deadline)
+        })
+        .flatten();
+    let file_list = match fast_file_list {
+        Some((file_list, end)) => {
+            cur.set_position(end as u64);
+            file_list
+        }
+        None => FileManifestList::read_with_options(&mut cur, &chunk_list, options, report)
+            .map_err(|e| with_partial(e, up_to_chunk_list))?,
+    };
+    for issue in &report.corrupt_files[issues_before..] {
+        hooks.on_recovered_error(Section::FileList, &format!("{}: {}", issue.filename, issue.reason));
+    }
+    warn_unknown_layout(hooks, options, Section::FileList, file_list.data_version, &file_list.extra_data)?;
+    hook_flow(
+        hooks.on_section_end(Section::FileList, cur.position() - file_list_start_pos),
+        Section::FileList,
+    )?;
+
+    // --- Custom Fields Reading ---
+    // Older manifests end after the file list.
+    let mut custom_fields = CustomFields::default();
+    let custom_fields_start_pos = cur.position();
+    if custom_fields_start_pos < cur.get_ref().len() as u64 {
+        check_deadline(options.deadline, Section::CustomFields).map_err(|e| {
+            with_partial(e, || Manifest {
+                file_list: Some(file_list.clone()),
+                ..up_to_chunk_list()
+            })
+        })?;
+        hook_flow(
+            hooks.on_section_start(Section::CustomFields, custom_fields_start_pos),
+            Section::CustomFields,
+        )?;
+        match CustomFields::read(&mut cur, options) {
+            Ok((fields, data_version, extra_data)) => {
+                warn_unknown_layout(hooks, options, Section::CustomFields, data_version, &extra_data)?;
+                for key in fields.duplicate_keys() {
+                    warn!("Custom field {} is stored more than once; using the first value", key);
+                    emit_warning(hooks, options, ParseWarning::DuplicateCustomField { key: key.to_string() })?;
+                }
+                custom_fields = fields;
+            }
+            Err(e) if options.strict => {
+                return Err(ManifestError::Strict {
+                    section: Section::CustomFields,
+                    message: e.to_string(),
+                })
+            }
+            Err(e) => {
+                error!("Failed to parse custom fields: {}", e);
+                // Not `e.as_ref()`: that is only the error kind.
+                let message = e.to_string();
+                hooks.on_recovered_error(Section::CustomFields, &message);
+            }
+        }
+        hook_flow(
+            hooks.on_section_end(Section::CustomFields, cur.position() - custom_fields_start_pos),
+            Section::CustomFields,
+        )?;
+    }
 
     Ok(Manifest {
         header,
         meta,
         chunk_list: Some(chunk_list),
         file_list: Some(file_list),
+        custom_fields,
     })
 }
 
-// NAPI-RS exports
-use napi::{bindgen_prelude::Buffer, Result as NapiResult};
-
-/// Parse an Epic Games manifest file synchronously
-#[napi]
-pub fn parse_manifest_sync(path: String) -> NapiResult<Manifest> {
-    load(path).map_err(|e| napi::Error::from_reason(e.to_string()))
-}
-
-/// Parse an Epic Games manifest file asynchronously
-#[napi]
-pub async fn parse_manifest_async(path: String) -> NapiResult<Manifest> {
-    load_async(path)
-        .await
-        .map_err(|e| napi::Error::from_reason(e.to_string()))
-}
-
-/// Parse manifest data from a buffer
-#[napi]
-pub fn parse_manifest_buffer(buffer: Buffer) -> NapiResult<Manifest> {
-    let data: Vec<u8> = buffer.to_vec();
-    process_manifest_data(data).map_err(|e| napi::Error::from_reason(e.to_string()))
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
     use std::path::PathBuf;
+    use types::json_manifest::JsonConversion;
     use std::error::Error;
 
+    /// Counts the bytes each thread allocates, so tests can bound what
+    /// hostile input makes the parser reserve.
+    struct CountingAlloc;
+
+    thread_local! {
+        static ALLOCATED: std::cell::Cell<usize> = const { std::cell::Cell::new(0) };
+    }
+
+    // SAFETY: forwards to the system allocator unchanged.
+    unsafe impl std::alloc::GlobalAlloc for CountingAlloc {
+        unsafe fn alloc(&self, layout: std::alloc::Layout) -> *mut u8 {
+            let _ = ALLOCATED.try_with(|n| n.set(n.get() + layout.size()));
+            std::alloc::System.alloc(layout)
+        }
+
+        unsafe fn dealloc(&self, ptr: *mut u8, layout: std::alloc::Layout) {
+            std::alloc::System.dealloc(ptr, layout)
+        }
+
+        unsafe fn realloc(&self, ptr: *mut u8, layout: std::alloc::Layout, new_size: usize) -> *mut u8 {
+            let _ = ALLOCATED.try_with(|n| n.set(n.get() + new_size.saturating_sub(layout.size())));
+            std::alloc::System.realloc(ptr, layout, new_size)
+        }
+    }
+
+    #[global_allocator]
+    static ALLOCATOR: CountingAlloc = CountingAlloc;
+
+    /// Bytes the current thread allocates while running `f`.
+    fn allocated_by<T>(f: impl FnOnce() -> T) -> (T, usize) {
+        let before = ALLOCATED.with(|n| n.get());
+        let result = f();
+        (result, ALLOCATED.with(|n| n.get()) - before)
+    }
+
     #[test]
     fn test_parse_manifest() {
         let manifest_path = PathBuf::from("test-manifests/valid-small.manifest");
@@ -379,6 +905,7 @@ mod tests {
         }
     }
 
+    #[cfg(feature = "async")]
     #[tokio::test]
     async fn test_parse_manifest_async() {
         let manifest_path = PathBuf::from("test-manifests/valid-small.manifest");
@@ -406,6 +933,7 @@ mod tests {
         }
     }
 
+    #[cfg(feature = "async")]
     #[tokio::test]
     async fn test_sync_vs_async_manifest_loading() {
         let manifest_path = PathBuf::from("test-manifests/valid-small.manifest");
@@ -624,5 +1152,3705 @@ mod tests {
         assert!(successful > 0, "At least one manifest should parse successfully");
     }
 
+    #[test]
+    fn test_canonical_json() {
+        let a = load("test-manifests/valid-small.manifest").expect("Failed to load manifest");
+        let b = load("test-manifests/valid-small.manifest").expect("Failed to load manifest");
+        let json = a.canonical_json().unwrap();
+        assert_eq!(json, b.canonical_json().unwrap());
+
+        // Top-level keys are sorted and nothing is pretty-printed.
+        assert!(json.starts_with("{\"chunk_list\":{"));
+        assert!(!json.contains(": "));
+        assert!(!json.contains('\n'));
+        let parsed: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed, serde_json::to_value(&a).unwrap());
+    }
+
+    #[test]
+    fn test_sorted_path_lookups() {
+        let manifest = load("test-manifests/valid-small.manifest").expect("Failed to load manifest");
+        let file_list = manifest.file_list.as_ref().unwrap();
+
+        let order = file_list.sorted_by_path();
+        assert_eq!(order.len(), file_list.file_manifest_list.len());
+        let names: Vec<&str> = order
+            .iter()
+            .map(|&i| file_list.file_manifest_list[i as usize].filename.trim_end_matches('\0'))
+            .collect();
+        assert!(names.windows(2).all(|w| w[0] <= w[1]));
+
+        let some_file = file_list.file_manifest_list.last().unwrap();
+        let path = some_file.filename.trim_end_matches('\0');
+        assert_eq!(file_list.find_by_path(path).map(|f| &f.sha_hash), Some(&some_file.sha_hash));
+
+        if let Some((dir, _)) = path.rsplit_once('/') {
+            let prefix = format!("{}/", dir);
+            let expected = names.iter().filter(|n| n.starts_with(&prefix)).count();
+            assert_eq!(file_list.files_with_prefix(&prefix).count(), expected);
+        }
+
+        // Adding or removing files after a lookup, without invalidating,
+        // neither panics nor hides files.
+        let mut edited = file_list.clone();
+        edited.sorted_by_path();
+        edited.file_manifest_list.truncate(1);
+        let kept = edited.file_manifest_list[0].filename.trim_end_matches('\0').to_string();
+        assert!(edited.find_by_path(path).is_none() || path == kept);
+        assert_eq!(edited.files_with_prefix("").count(), 1);
+        edited.file_manifest_list.push(FileManifest {
+            filename: "zz/added".to_string(),
+            ..Default::default()
+        });
+        assert!(edited.find_by_path("zz/added").is_some());
+        assert_eq!(edited.files_with_prefix("zz/").count(), 1);
+    }
+
+    #[test]
+    fn test_directory_index() {
+        use index::directory::DirectoryIndex;
+
+        let manifest = load("test-manifests/valid-small.manifest").expect("Failed to load manifest");
+        let file_list = manifest.file_list.as_ref().unwrap();
+        let index = DirectoryIndex::build(file_list);
+
+        let total: i64 = file_list.file_manifest_list.iter().map(|f| f.file_size).sum();
+        assert_eq!(index.dir_size(""), total);
+        assert_eq!(index.files_under("").len(), file_list.file_manifest_list.len());
+
+        for dir in index.subdirs("") {
+            let prefix = format!("{}/", dir);
+            let expected: i64 = file_list
+                .files_with_prefix(&prefix)
+                .map(|f| f.file_size)
+                .sum();
+            assert_eq!(index.dir_size(&dir), expected);
+            assert_eq!(index.dir_size(&format!("/{}/", dir)), expected);
+            assert_eq!(index.files_under(&dir).len(), file_list.files_with_prefix(&prefix).count());
+        }
+        assert!(index.files_under("does/not/exist").is_empty());
+    }
+
+    #[test]
+    fn test_read_ext_utf16_and_arrays() {
+        use std::io::Cursor;
+
+        let mut data = Vec::new();
+        data.extend_from_slice(&2u32.to_le_bytes());
+        data.extend_from_slice(&7u64.to_le_bytes());
+        data.extend_from_slice(&9u64.to_le_bytes());
+        for unit in "héllo\0".encode_utf16() {
+            data.extend_from_slice(&unit.to_le_bytes());
+        }
+
+        let mut rdr = Cursor::new(data);
+        assert_eq!(ReadExt::read_array(&mut rdr, |r| r.u64()).unwrap(), vec![7, 9]);
+        assert_eq!(rdr.fstring_utf16(6).unwrap(), "héllo\0");
+        assert!(rdr.fstring_utf16(1).is_err());
+
+        // Negative FString lengths switch to UTF-16.
+        let mut data = Vec::new();
+        data.extend_from_slice(&(-4i32).to_le_bytes());
+        for unit in "日本\u{1F600}".encode_utf16() {
+            data.extend_from_slice(&unit.to_le_bytes());
+        }
+        data.extend_from_slice(&3i32.to_le_bytes());
+        data.extend_from_slice(b"ab\0");
+        let mut rdr = Cursor::new(data);
+        assert_eq!(rdr.fstring().unwrap(), "日本\u{1F600}");
+        assert_eq!(rdr.fstring().unwrap(), "ab\0");
+
+        // Counts past the limit or the bytes left fail before allocating.
+        let mut rdr = Cursor::new(u32::MAX.to_le_bytes().to_vec());
+        let err = ReadExt::read_array(&mut rdr, |r| r.u8()).unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+        let mut data = 3u32.to_le_bytes().to_vec();
+        data.extend_from_slice(&[0; 8]);
+        let mut rdr = Cursor::new(data);
+        assert!(rdr.read_count(10, 4).is_err());
+        rdr.set_position(0);
+        assert_eq!(rdr.read_count(10, 2).unwrap(), 3);
+    }
+
+    #[test]
+    fn test_detect_format() {
+        let binary = fs::read("test-manifests/valid-small.manifest").unwrap();
+        assert!(matches!(
+            detect_format(&binary),
+            ManifestFormat::BinaryV2Plus { feature_level: Some(_) }
+        ));
+        assert!(matches!(
+            detect_format(&binary[..20]),
+            ManifestFormat::BinaryV2Plus { feature_level: None }
+        ));
+
+        let mut v1 = binary[..41].to_vec();
+        v1[4..8].copy_from_slice(&37i32.to_le_bytes());
+        assert_eq!(detect_format(&v1), ManifestFormat::BinaryV1);
+
+        let json = b"\xEF\xBB\xBF\n {\"ManifestFileVersion\": \"013000000000\"}";
+        assert_eq!(detect_format(json), ManifestFormat::Json);
+        assert_eq!(detect_format(b"{\"foo\": 1}"), ManifestFormat::Unknown);
+        assert_eq!(detect_format(b""), ManifestFormat::Unknown);
+    }
+
+    #[test]
+    fn test_scan_for_embedded_manifest() {
+        let manifest = fs::read("test-manifests/valid-small.manifest").unwrap();
+        let mut dump = b"HTTP/1.1 200 OK\r\n\r\n".to_vec();
+        // A stray magic with an absurd header size must be skipped.
+        dump.extend_from_slice(&format::MANIFEST_MAGIC.to_le_bytes());
+        dump.extend_from_slice(&i32::MAX.to_le_bytes());
+        let offset = dump.len();
+        dump.extend_from_slice(&manifest);
+        assert_eq!(scan_for_manifest(&dump), Some(offset));
+
+        assert!(parse_with_report(&dump, &ParseOptions::default()).is_err());
+        let options = ParseOptions {
+            scan_for_manifest: true,
+            ..Default::default()
+        };
+        let (parsed, report) = parse_with_report(&dump, &options).unwrap();
+        assert_eq!(report.offset, offset);
+        assert_eq!(parsed.header.sha1_hash, load("test-manifests/valid-small.manifest").unwrap().header.sha1_hash);
+
+        let json = b"garbage{ \n\"ManifestFileVersion\": \"1\"}";
+        assert_eq!(scan_for_manifest(json), Some(7));
+        assert_eq!(scan_for_manifest(b"nothing here"), None);
+    }
+
+    #[test]
+    fn test_concatenated_manifest_stream() {
+        let binary = fs::read("test-manifests/valid-small.manifest").unwrap();
+        let json = fs::read("test-manifests/valid-json-format.manifest").unwrap();
+        let mut dump = binary.clone();
+        dump.extend_from_slice(b"\n\n");
+        dump.extend_from_slice(&json);
+        dump.extend_from_slice(&binary);
+
+        let items: Vec<_> = parse_all(&dump, ParseOptions::default())
+            .collect::<Result<_, _>>()
+            .unwrap();
+        let ranges: Vec<_> = items.iter().map(|(r, _)| r.clone()).collect();
+        let json_start = binary.len() + 2;
+        let second = json_start + json.len();
+        assert_eq!(
+            ranges,
+            vec![0..binary.len(), json_start..second, second..dump.len()]
+        );
+        assert_eq!(items[0].1.header.sha1_hash, items[2].1.header.sha1_hash);
+        assert!(items[1].1.file_list.as_ref().is_some_and(|f| f.count > 0));
+
+        // A truncated trailing manifest is reported, not silently dropped.
+        let cut = &dump[..dump.len() - 10];
+        let results: Vec<_> = parse_all(cut, ParseOptions::default()).collect();
+        assert_eq!(results.len(), 3);
+        assert!(results[2].is_err());
+    }
+
+    #[test]
+    fn test_build_id_computed_when_absent() {
+        let binary = load("test-manifests/valid-small.manifest").unwrap();
+        let meta = binary.meta.as_ref().unwrap();
+        assert_eq!(meta.data_version, 0);
+        assert_eq!(meta.build_id.as_deref(), Some("JU-lxmUVml3jtbUYmxX9hxuiNy0"));
+
+        let json = load("test-manifests/valid-json-format.manifest").unwrap();
+        let meta = json.meta.unwrap();
+        assert_eq!(meta.build_id.as_deref(), Some(meta.computed_build_id().as_str()));
+
+        let mut stored = meta.clone();
+        stored.build_id = Some("stored-id\0".to_string());
+        assert_eq!(stored.build_id(), "stored-id");
+        stored.ensure_build_id();
+        assert_eq!(stored.build_id.as_deref(), Some("stored-id\0"));
+    }
+
+    #[test]
+    fn test_guid_formats() {
+        let manifest = load("test-manifests/valid-small.manifest").unwrap();
+        let chunk = &manifest.chunk_list.as_ref().unwrap().elements[0];
+        let epic = chunk.guid_epic_format();
+        assert_eq!(epic.len(), 32);
+        assert!(epic.chars().all(|c| c.is_ascii_digit() || c.is_ascii_uppercase()));
+        assert_eq!(types::chunk::normalize_guid(&epic).as_deref(), Some(chunk.guid.as_str()));
+        assert_eq!(types::chunk::normalize_guid(&chunk.guid.to_uppercase()).as_deref(), Some(chunk.guid.as_str()));
+        assert_eq!(types::chunk::normalize_guid("not-a-guid"), None);
+
+        let options = ParseOptions {
+            guid_format: GuidFormat::Epic,
+            ..Default::default()
+        };
+        let epic_manifest = load_with_options("test-manifests/valid-small.manifest", &options).unwrap();
+        let chunk_list = epic_manifest.chunk_list.as_ref().unwrap();
+        assert_eq!(chunk_list.elements[0].guid, epic);
+        assert!(chunk_list.chunk_lookup.contains_key(&epic));
+        for part in epic_manifest.file_list.as_ref().unwrap().file_manifest_list.iter().flat_map(|f| &f.chunk_parts) {
+            assert!(chunk_list.chunk_lookup.contains_key(&part.parent_guid));
+        }
+
+        // JSON manifests accept both spellings and agree with the binary form.
+        let json = fs::read_to_string("test-manifests/valid-json-format.manifest").unwrap();
+        let mut json_manifest = JsonManifest::from_str(&json).unwrap();
+        let part = &mut json_manifest.file_manifest_list[0].file_chunk_parts[0];
+        let original = part.guid.clone();
+        let expected = types::chunk::normalize_guid(&original).unwrap();
+        part.guid = expected.clone();
+        let converted = json_manifest.to_manifest().unwrap();
+        let first = &converted.file_list.unwrap().file_manifest_list[0].chunk_parts[0];
+        assert_eq!(first.parent_guid, expected);
+        assert_eq!(types::chunk::format_guid(&expected, GuidFormat::Epic), original.to_uppercase());
+    }
+
+    #[test]
+    fn test_chunk_part_data_size() {
+        use types::chunk::{Chunk, ChunkPart};
+
+        let guid = [7u8; 16];
+        let chunks = vec![Chunk {
+            guid: uuid::Uuid::from_bytes(guid).to_string(),
+            ..Default::default()
+        }];
+        let lookup = std::collections::HashMap::from([(chunks[0].guid.clone(), 0u32)]);
+        let part = |data_size: u32, extra: &[u8], guid: [u8; 16]| {
+            let mut bytes = data_size.to_le_bytes().to_vec();
+            bytes.extend_from_slice(&guid);
+            bytes.extend_from_slice(&5u32.to_le_bytes());
+            bytes.extend_from_slice(&10u32.to_le_bytes());
+            bytes.extend_from_slice(extra);
+            bytes
+        };
+
+        let mut data = part(32, &[1, 2, 3, 4], guid);
+        data.extend(part(28, &[], [9u8; 16]));
+        data.extend(part(28, &[], guid));
+        let mut rdr = Cursor::new(data);
+        let first = ChunkPart::read(&mut rdr, &lookup, &chunks).unwrap();
+        assert_eq!((first.offset, first.size), (5, 10));
+        assert_eq!(first.extra_data.as_deref(), Some("01020304"));
+        // An unknown parent is rejected without losing alignment.
+        assert!(ChunkPart::read(&mut rdr, &lookup, &chunks).is_err());
+        let third = ChunkPart::read(&mut rdr, &lookup, &chunks).unwrap();
+        assert_eq!(third.extra_data, None);
+        assert!(third.chunk.is_some());
+
+        let mut rdr = Cursor::new(part(12, &[], guid));
+        assert!(ChunkPart::read(&mut rdr, &lookup, &chunks).is_err());
+    }
+
+    /// File list section with one chunk-part record per entry of `records`
+    /// (each a list of (guid, size)), version 0.
+    fn file_list_bytes(records: &[Vec<([u8; 16], u32)>], corrupt_count: Option<(usize, u32)>) -> Vec<u8> {
+        file_list_bytes_at(0, records, corrupt_count)
+    }
+
+    /// [`file_list_bytes`] at `version`; version 2 adds MD5, MIME type and
+    /// SHA-256 columns.
+    fn file_list_bytes_at(version: u8, records: &[Vec<([u8; 16], u32)>], corrupt_count: Option<(usize, u32)>) -> Vec<u8> {
+        let mut body = Vec::new();
+        let fstring = |body: &mut Vec<u8>, s: &str| {
+            body.extend_from_slice(&((s.len() + 1) as i32).to_le_bytes());
+            body.extend_from_slice(s.as_bytes());
+            body.push(0);
+        };
+        for i in 0..records.len() {
+            fstring(&mut body, &format!("file{}.bin", i));
+        }
+        for _ in records {
+            body.extend_from_slice(&0i32.to_le_bytes());
+        }
+        for _ in records {
+            body.extend_from_slice(&[0u8; 20]);
+        }
+        body.extend(std::iter::repeat_n(0u8, records.len()));
+        for _ in records {
+            body.extend_from_slice(&0u32.to_le_bytes());
+        }
+        for (i, parts) in records.iter().enumerate() {
+            let count = match corrupt_count {
+                Some((bad, value)) if bad == i => value,
+                _ => parts.len() as u32,
+            };
+            body.extend_from_slice(&count.to_le_bytes());
+            for (guid, size) in parts {
+                body.extend_from_slice(&28u32.to_le_bytes());
+                body.extend_from_slice(guid);
+                body.extend_from_slice(&0u32.to_le_bytes());
+                body.extend_from_slice(&size.to_le_bytes());
+            }
+        }
+        if version >= 2 {
+            for _ in records {
+                body.extend_from_slice(&1u32.to_le_bytes());
+                body.extend_from_slice(&[0xab; 16]);
+            }
+            for _ in records {
+                fstring(&mut body, "application/octet-stream");
+            }
+            for _ in records {
+                body.extend_from_slice(&[0xcd; 32]);
+            }
+        }
+        let mut data = ((body.len() + 9) as u32).to_le_bytes().to_vec();
+        data.push(version);
+        data.extend_from_slice(&(records.len() as u32).to_le_bytes());
+        data.extend(body);
+        data
+    }
+
+    #[test]
+    fn test_file_list_resyncs_after_corrupt_entry() {
+        let guids = [[1u8; 16], [2u8; 16]];
+        let mut chunk_list = ChunkDataList::default();
+        for (i, guid) in guids.iter().enumerate() {
+            let guid = uuid::Uuid::from_bytes(*guid).to_string();
+            chunk_list.chunk_lookup.insert(guid.clone(), i as u32);
+            chunk_list.elements.push(types::chunk::Chunk {
+                guid,
+                ..Default::default()
+            });
+        }
+        let records = vec![
+            vec![(guids[0], 10), (guids[1], 20)],
+            vec![(guids[1], 30)],
+            vec![(guids[0], 40), (guids[0], 50)],
+            vec![(guids[1], 60)],
+        ];
+
+        let clean = file_list_bytes(&records, None);
+        let mut report = ParseReport::default();
+        let list = FileManifestList::read_with(&mut Cursor::new(&clean), &chunk_list, &StringPolicy::default(), &mut report).unwrap();
+        assert!(report.corrupt_files.is_empty());
+        let sizes: Vec<i64> = list.file_manifest_list.iter().map(|f| f.file_size).collect();
+        assert_eq!(sizes, vec![30, 30, 90, 60]);
+
+        // A garbage chunk count on file 1 no longer shifts files 2 and 3.
+        let corrupt = file_list_bytes(&records, Some((1, 0x0102_0304)));
+        let mut report = ParseReport::default();
+        let list = FileManifestList::read_with(&mut Cursor::new(&corrupt), &chunk_list, &StringPolicy::default(), &mut report).unwrap();
+        let sizes: Vec<i64> = list.file_manifest_list.iter().map(|f| f.file_size).collect();
+        assert_eq!(sizes, vec![30, 0, 90, 60]);
+        let unknown: Vec<bool> = list.file_manifest_list.iter().map(|f| f.size_unknown).collect();
+        assert_eq!(unknown, vec![false, true, false, false]);
+        assert_eq!(report.corrupt_files.len(), 1);
+        assert_eq!(report.corrupt_files[0].index, 1);
+        assert_eq!(report.corrupt_files[0].filename, "file1.bin");
+
+        // A part with an unknown parent is dropped; the rest of the file stays.
+        let mut records = records;
+        records[2][0].0 = [9u8; 16];
+        let unknown = file_list_bytes(&records, None);
+        let mut report = ParseReport::default();
+        let list = FileManifestList::read_with(&mut Cursor::new(&unknown), &chunk_list, &StringPolicy::default(), &mut report).unwrap();
+        let sizes: Vec<i64> = list.file_manifest_list.iter().map(|f| f.file_size).collect();
+        assert_eq!(sizes, vec![30, 30, 50, 60]);
+        assert_eq!(report.corrupt_files.iter().map(|f| f.index).collect::<Vec<_>>(), vec![2]);
+
+        // Totals can tell they are a lower bound.
+        let manifest = Manifest {
+            file_list: Some(list),
+            ..Default::default()
+        };
+        assert_eq!(manifest.install_size(), 170);
+        let unknown: Vec<&str> = manifest.files_with_unknown_size().map(|f| f.filename.as_str()).collect();
+        assert_eq!(unknown, ["file2.bin"]);
+        let dto = crate::dto::v1::Manifest::from(manifest.clone());
+        let file = &dto.file_list.as_ref().unwrap().file_manifest_list[2];
+        assert_eq!(file.size_unknown, Some(true));
+        assert_eq!(Manifest::from(dto).files_with_unknown_size().count(), 1);
+    }
+
+    #[test]
+    fn test_file_list_resync_keeps_empty_files_and_trailing_columns() {
+        let guids = [[1u8; 16], [2u8; 16]];
+        let mut chunk_list = ChunkDataList::default();
+        for (i, guid) in guids.iter().enumerate() {
+            let guid = uuid::Uuid::from_bytes(*guid).to_string();
+            chunk_list.chunk_lookup.insert(guid.clone(), i as u32);
+            chunk_list.elements.push(types::chunk::Chunk {
+                guid,
+                ..Default::default()
+            });
+        }
+        let read = |data: &[u8]| {
+            let mut report = ParseReport::default();
+            let list = FileManifestList::read_with(&mut Cursor::new(data), &chunk_list, &StringPolicy::default(), &mut report).unwrap();
+            (list.file_manifest_list, report)
+        };
+
+        // The empty file after the corrupt record keeps its place instead
+        // of taking the next file's parts.
+        let records = vec![
+            vec![(guids[0], 10)],
+            vec![(guids[1], 20)],
+            vec![],
+            vec![(guids[0], 30)],
+            vec![(guids[1], 40)],
+        ];
+        for version in [0, 2] {
+            let (files, report) = read(&file_list_bytes_at(version, &records, Some((1, 0x0102_0304))));
+            let sizes: Vec<i64> = files.iter().map(|f| f.file_size).collect();
+            assert_eq!(sizes, vec![10, 0, 0, 30, 40], "version {}", version);
+            assert_eq!(report.corrupt_files.iter().map(|f| f.index).collect::<Vec<_>>(), vec![1]);
+            assert!(files.iter().all(|f| f.mime_type == if version == 2 { "application/octet-stream" } else { "" }));
+        }
+
+        // With nothing to resynchronize on, the columns after the chunk
+        // parts are still read.
+        let records = vec![vec![(guids[0], 10)], vec![(guids[1], 20)], vec![([9u8; 16], 30)]];
+        let (files, report) = read(&file_list_bytes_at(2, &records, Some((1, 0x0102_0304))));
+        assert_eq!(report.corrupt_files.iter().map(|f| f.index).collect::<Vec<_>>(), vec![1, 2]);
+        assert!(files.iter().all(|f| f.mime_type == "application/octet-stream"));
+    }
+
+    /// Full parsed output of every synthetic fixture in
+    /// `test-manifests/fixtures` (see `generate.py` there), plus the parse
+    /// report. Review changes with `cargo insta review`.
+    #[test]
+    fn test_fixture_snapshots() {
+        for name in [
+            "minimal",
+            "uncompressed-v2",
+            "corrupt-chunk-count",
+            "small.json",
+        ] {
+            let data = fs::read(format!("test-manifests/fixtures/{}.manifest", name)).unwrap();
+            let (manifest, report) = parse_with_report(&data, &ParseOptions::default()).unwrap();
+            let corrupt_files: Vec<_> = report
+                .corrupt_files
+                .iter()
+                .map(|f| serde_json::json!({ "index": f.index, "filename": f.filename, "reason": f.reason }))
+                .collect();
+            let parsed = serde_json::json!({
+                "manifest": manifest,
+                "report": { "offset": report.offset, "corruptFiles": corrupt_files },
+            });
+            insta::assert_json_snapshot!(format!("fixture_{}", name.replace('.', "_")), parsed);
+        }
+    }
+
+    #[test]
+    fn test_header_guid() {
+        let original = fs::read("test-manifests/valid-small.manifest").unwrap();
+        let header = ManifestHeader::read(Cursor::new(&original)).unwrap();
+        assert_eq!(header.guid(), None);
+
+        // Same manifest with a 57-byte header carrying a GUID.
+        let guid = [0xABu8; 16];
+        let mut data = original[..41].to_vec();
+        data[4..8].copy_from_slice(&57i32.to_le_bytes());
+        data.extend_from_slice(&guid);
+        data.extend_from_slice(&original[41..]);
+        let (manifest, _) = parse_with_report(&data, &ParseOptions::default()).unwrap();
+        assert_eq!(manifest.header.guid(), Some(uuid::Uuid::from_bytes(guid).to_string().as_str()));
+        assert_eq!(manifest.header.version, header.version);
+        assert!(manifest.file_list.unwrap().count > 0);
+        let json = serde_json::to_value(&manifest.header).unwrap();
+        assert_eq!(json["guid"], uuid::Uuid::from_bytes(guid).to_string());
+    }
+
+    #[test]
+    fn test_header_hash_type() {
+        let original = fs::read("test-manifests/valid-small.manifest").unwrap();
+        let (_, report) = parse_with_report(&original, &ParseOptions::default()).unwrap();
+        assert_eq!(report.payload_hash, PayloadHashCheck::Matched);
+
+        // 66-byte header: version, GUID, rolling hash and hash type.
+        let with_hash_type = |hash_type: u8| {
+            let mut data = original[..41].to_vec();
+            data[4..8].copy_from_slice(&66i32.to_le_bytes());
+            data.extend_from_slice(&[0u8; 16]);
+            data.extend_from_slice(&7i64.to_le_bytes());
+            data.push(hash_type);
+            data.extend_from_slice(&original[41..]);
+            data
+        };
+
+        let (manifest, report) = parse_with_report(&with_hash_type(0x02), &ParseOptions::default()).unwrap();
+        assert_eq!((manifest.header.rolling_hash, manifest.header.hash_type), (7, 2));
+        assert_eq!(report.payload_hash, PayloadHashCheck::Matched);
+
+        let (manifest, report) = parse_with_report(&with_hash_type(0x04), &ParseOptions::default()).unwrap();
+        assert_eq!(manifest.header.hash_type, 4);
+        assert_eq!(report.payload_hash, PayloadHashCheck::Unsupported { hash_type: 4 });
+    }
+
+    #[test]
+    fn test_normalize_round_trip() {
+        for path in ["test-manifests/valid-small.manifest", "test-manifests/valid-json-format.manifest"] {
+            let data = fs::read(path).expect("Failed to read manifest");
+            let (original, _) = parse_with_report(&data, &ParseOptions::default()).expect("Failed to parse");
+
+            let normalized = normalize(&data).expect("Failed to normalize");
+            assert_eq!(detect_format(&normalized), ManifestFormat::BinaryV2Plus {
+                feature_level: Some(normalized_feature_level(&original)),
+            });
+            let (reparsed, report) = parse_with_report(&normalized, &ParseOptions::default()).expect("Failed to reparse");
+            assert_eq!(report.payload_hash, PayloadHashCheck::Matched, "{}", path);
+
+            let (a, b) = (original.meta.clone().unwrap(), reparsed.meta.clone().unwrap());
+            assert_eq!(a.app_name, b.app_name);
+            assert_eq!(a.build_version, b.build_version);
+            assert_eq!(a.build_id(), b.build_id());
+            assert_eq!(
+                serde_json::to_value(&original.chunk_list.as_ref().unwrap().elements).unwrap(),
+                serde_json::to_value(&reparsed.chunk_list.as_ref().unwrap().elements).unwrap(),
+            );
+            // JSON chunk parts have no data size; the binary form always does.
+            let files = |manifest: Manifest| {
+                let mut files = manifest.file_list.unwrap().file_manifest_list;
+                files.iter_mut().flat_map(|f| &mut f.chunk_parts).for_each(|p| p.data_size = 0);
+                serde_json::to_value(&files).unwrap()
+            };
+            assert_eq!(files(original), files(reparsed), "{}", path);
+
+            assert_eq!(normalize(&normalized).unwrap(), normalized, "normalize is not idempotent for {}", path);
+        }
+    }
+
+    fn normalized_feature_level(manifest: &Manifest) -> i32 {
+        match manifest.meta.as_ref().map(|m| m.feature_level) {
+            Some(level) if level > 0 => level,
+            _ => manifest.header.version,
+        }
+    }
+
+    #[test]
+    fn test_size_on_disk() {
+        let files = [0i64, 1, 4096, 4097]
+            .into_iter()
+            .map(|file_size| FileManifest {
+                file_size,
+                ..Default::default()
+            })
+            .collect();
+        let manifest = Manifest {
+            file_list: Some(FileManifestList {
+                file_manifest_list: files,
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+
+        assert_eq!(manifest.size_on_disk(0), 5 * 4096);
+        assert_eq!(manifest.size_on_disk(4096), 5 * 4096);
+        assert_eq!(manifest.size_on_disk(512), 512 + 512 + 4096 + 4608);
+        assert_eq!(Manifest::default().size_on_disk(0), 0);
+        assert_eq!(manifest.install_size(), 1 + 4096 + 4097);
+
+        let loaded = load("test-manifests/valid-small.manifest").expect("Failed to load manifest");
+        let chunk_bytes: u64 = loaded.chunk_list.as_ref().unwrap().elements.iter().map(|c| c.file_size.parse::<u64>().unwrap()).sum();
+        assert!(chunk_bytes > 0);
+        assert_eq!(loaded.download_size(), chunk_bytes);
+    }
+
+    #[test]
+    fn test_install_preflight() {
+        use crate::install::plan::{plan_install, PlanOptions};
+
+        let manifest = load("test-manifests/valid-small.manifest").expect("Failed to load manifest");
+        let target = std::env::temp_dir().join("egdata-preflight-missing-dir");
+        let plan = plan_install(&manifest, &target, &PlanOptions::default()).unwrap();
+        assert_eq!(plan.required_bytes, manifest.size_on_disk(0));
+        assert!(plan.staging_bytes > 0);
+        assert_eq!(plan.available_bytes, None);
+
+        let options = PlanOptions {
+            check_free_space: true,
+            ..Default::default()
+        };
+        let mut huge = manifest.clone();
+        huge.file_list.as_mut().unwrap().file_manifest_list[0].file_size = i64::MAX / 2;
+        match plan_install(&huge, &target, &options) {
+            Err(ManifestError::InsufficientSpace { required, available, .. }) => assert!(required > available),
+            other => panic!("expected InsufficientSpace, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_partial_file_resume() {
+        use crate::install::assemble::{assemble_file, PartialFile, PROGRESS_SUFFIX};
+
+        let chunk: Vec<u8> = (0..=255u8).cycle().take(1000).collect();
+        let guids = ["00000000-0000-0000-0000-000000000001", "00000000-0000-0000-0000-000000000002"];
+        let parts = [(guids[0], 0u32, 300u32), (guids[1], 100, 500), (guids[0], 300, 200)];
+        let expected: Vec<u8> = parts
+            .iter()
+            .flat_map(|&(_, offset, size)| chunk[offset as usize..(offset + size) as usize].to_vec())
+            .collect();
+        let entry = FileManifest {
+            filename: "out.bin".to_string(),
+            sha_hash: hex::encode(sha1::Sha1::digest(&expected)),
+            file_size: expected.len() as i64,
+            chunk_parts: parts
+                .iter()
+                .map(|&(guid, offset, size)| ChunkPart {
+                    parent_guid: guid.to_string(),
+                    offset,
+                    size,
+                    ..Default::default()
+                })
+                .collect(),
+            ..Default::default()
+        };
+
+        let dir = std::env::temp_dir().join(format!("egdata-assemble-{}", uuid::Uuid::new_v4()));
+        let path = dir.join("out.bin");
+
+        // Out of order, then interrupted after a checkpoint.
+        let mut partial = PartialFile::open(&path, &entry).unwrap();
+        assert_eq!(fs::metadata(&path).unwrap().len(), 1000);
+        #[cfg(target_os = "linux")]
+        {
+            use std::os::unix::fs::MetadataExt;
+            assert!(fs::metadata(&path).unwrap().blocks() * 512 >= 1000, "space is allocated, not sparse");
+        }
+        partial.write_at(800, &expected[800..]).unwrap();
+        partial.write_at(0, &expected[..300]).unwrap();
+        partial.checkpoint().unwrap();
+        drop(partial);
+
+        let resumed = PartialFile::open(&path, &entry).unwrap();
+        assert_eq!(resumed.missing_ranges(), vec![300..800]);
+        drop(resumed);
+
+        let fetched = std::cell::Cell::new(0);
+        let provider = |_: &str| {
+            fetched.set(fetched.get() + 1);
+            Ok(chunk.clone())
+        };
+        assert_eq!(assemble_file(&provider, &entry, &path).unwrap(), 500);
+        assert_eq!(fetched.get(), 1, "only the missing part's chunk is fetched");
+        assert_eq!(fs::read(&path).unwrap(), expected);
+        assert!(!dir.join(format!("out.bin{}", PROGRESS_SUFFIX)).exists());
+
+        // A part past the end of its chunk is an error, even near u32::MAX.
+        let mut past_end = entry.clone();
+        past_end.chunk_parts[1].offset = u32::MAX - 10;
+        past_end.sha_hash = "0".repeat(40);
+        let err = assemble_file(&provider, &past_end, dir.join("past_end.bin")).unwrap_err();
+        assert!(err.to_string().contains(&format!("{}..{}", u32::MAX - 10, u32::MAX as u64 + 490)), "{}", err);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_io_backend_batches() {
+        use crate::io_backend::{backend, BatchedReader, IoBackend, StdIo};
+        use std::io::Read;
+
+        let path = std::env::temp_dir().join(format!("egdata-io-{}", uuid::Uuid::new_v4()));
+        let data: Vec<u8> = (0..300_000u32).map(|i| (i % 251) as u8).collect();
+        for io in [&StdIo as &dyn IoBackend, backend()] {
+            let file = fs::OpenOptions::new().read(true).write(true).create(true).truncate(true).open(&path).unwrap();
+            file.set_len(data.len() as u64).unwrap();
+            // Out of order, as chunk parts may arrive.
+            let writes: Vec<(u64, &[u8])> = data.chunks(70_000).enumerate().rev().map(|(i, c)| ((i * 70_000) as u64, c)).collect();
+            io.write_batch(&file, &writes).unwrap();
+
+            let (mut a, mut b) = (vec![0u8; 10], vec![0u8; 100]);
+            let lengths = io.read_batch(&file, &mut [(5, &mut a[..]), (299_950, &mut b[..])]).unwrap();
+            assert_eq!(lengths, vec![10, 50], "{}", io.name());
+            assert_eq!(a, data[5..15]);
+            assert_eq!(b[..50], data[299_950..]);
+
+            let mut read_back = Vec::new();
+            BatchedReader::new(file, io, 65_536, 3).read_to_end(&mut read_back).unwrap();
+            assert!(read_back == data, "{}", io.name());
+        }
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_export_checksums() {
+        use crate::export::checksums::{checksums_string, ChecksumFormat};
+
+        let file = |filename: &str, symlink_target: &str| FileManifest {
+            filename: filename.to_string(),
+            symlink_target: symlink_target.to_string(),
+            sha_hash: "DA39A3EE5E6B4B0D3255BFEF95601890AFD80709".to_string(),
+            ..Default::default()
+        };
+        let manifest = Manifest {
+            file_list: Some(FileManifestList {
+                file_manifest_list: vec![
+                    file("Game/Binaries/game.exe\0", ""),
+                    file("odd\\name", ""),
+                    file("link", "Game/Binaries/game.exe"),
+                ],
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            checksums_string(&manifest, ChecksumFormat::Sha1Sum).unwrap(),
+            "da39a3ee5e6b4b0d3255bfef95601890afd80709  Game/Binaries/game.exe\n\
+             \\da39a3ee5e6b4b0d3255bfef95601890afd80709  odd\\\\name\n"
+        );
+        assert_eq!(
+            checksums_string(&manifest, ChecksumFormat::Bsd).unwrap(),
+            "SHA1 (Game/Binaries/game.exe) = da39a3ee5e6b4b0d3255bfef95601890afd80709\n\
+             SHA1 (odd\\name) = da39a3ee5e6b4b0d3255bfef95601890afd80709\n"
+        );
+    }
+
+    #[test]
+    fn test_install_and_repair_dry_run() {
+        use crate::install::actions::Action;
+        use crate::install::apply::{install_files, repair_install, InstallOptions};
+
+        let guids = ["00000000-0000-0000-0000-00000000000a", "00000000-0000-0000-0000-00000000000b"];
+        let chunk_data = |guid: &str| -> Vec<u8> {
+            let seed = if guid == guids[0] { 1u8 } else { 7 };
+            (0..1000u32).map(|i| (i as u8).wrapping_mul(seed)).collect()
+        };
+        let chunk_list = ChunkDataList {
+            elements: guids
+                .iter()
+                .map(|guid| Chunk {
+                    guid: guid.to_string(),
+                    file_size: "600".to_string(),
+                    ..Default::default()
+                })
+                .collect(),
+            chunk_lookup: guids.iter().enumerate().map(|(i, g)| (g.to_string(), i as u32)).collect(),
+            ..Default::default()
+        };
+        let file = |filename: &str, parts: &[(&str, u32, u32)]| {
+            let content: Vec<u8> = parts
+                .iter()
+                .flat_map(|&(guid, offset, size)| chunk_data(guid)[offset as usize..(offset + size) as usize].to_vec())
+                .collect();
+            FileManifest {
+                filename: filename.to_string(),
+                sha_hash: hex::encode(sha1::Sha1::digest(&content)),
+                file_size: content.len() as i64,
+                chunk_parts: parts
+                    .iter()
+                    .map(|&(guid, offset, size)| ChunkPart {
+                        parent_guid: guid.to_string(),
+                        offset,
+                        size,
+                        ..Default::default()
+                    })
+                    .collect(),
+                ..Default::default()
+            }
+        };
+        let manifest = Manifest {
+            chunk_list: Some(chunk_list),
+            file_list: Some(FileManifestList {
+                file_manifest_list: vec![
+                    file("a.bin", &[(guids[0], 0, 400), (guids[1], 0, 100)]),
+                    file("sub/b.bin", &[(guids[1], 100, 900)]),
+                ],
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        let provider = |guid: &str| Ok(chunk_data(guid));
+        let target = std::env::temp_dir().join(format!("egdata-install-{}", uuid::Uuid::new_v4()));
+        let dry = InstallOptions { dry_run: true };
+
+        let plan = install_files(&manifest, &provider, &target, &dry).unwrap();
+        assert!(plan.dry_run);
+        assert_eq!((plan.download_bytes(), plan.write_bytes()), (1200, 1400));
+        assert_eq!(plan.actions.len(), 4);
+        assert!(!target.exists(), "dry run must not touch disk");
+
+        let done = install_files(&manifest, &provider, &target, &InstallOptions::default()).unwrap();
+        assert_eq!(done.actions, plan.actions);
+        assert!(verify::install::verify_install(&manifest, &target, None, |_| {}).unwrap().is_ok());
+
+        fs::write(target.join("sub/b.bin"), b"corrupt").unwrap();
+        let repair = repair_install(&manifest, &provider, &target, &dry).unwrap();
+        assert_eq!(
+            repair.actions,
+            vec![
+                Action::Download { guid: guids[1].to_string(), bytes: 600 },
+                Action::Write { path: "sub/b.bin".to_string(), bytes: 900 },
+            ]
+        );
+        assert_eq!(fs::read(target.join("sub/b.bin")).unwrap(), b"corrupt");
+
+        repair_install(&manifest, &provider, &target, &InstallOptions::default()).unwrap();
+        assert!(verify::install::verify_install(&manifest, &target, None, |_| {}).unwrap().is_ok());
+        fs::remove_dir_all(&target).unwrap();
+    }
+
+    /// A v3 CDN chunk file holding `data`, zlib-compressed.
+    fn chunk_file_bytes(guid: &str, data: &[u8]) -> Vec<u8> {
+        let compressed = miniz_oxide::deflate::compress_to_vec_zlib(data, 6);
+        let mut out = Vec::new();
+        out.extend_from_slice(&format::CHUNK_MAGIC.to_le_bytes());
+        out.extend_from_slice(&3u32.to_le_bytes());
+        out.extend_from_slice(&format::CHUNK_HEADER_SIZE_V3.to_le_bytes());
+        out.extend_from_slice(&(compressed.len() as u32).to_le_bytes());
+        out.extend_from_slice(uuid::Uuid::parse_str(guid).unwrap().as_bytes());
+        out.extend_from_slice(&0u64.to_le_bytes());
+        out.push(types::flags::STORED_COMPRESSED);
+        out.extend_from_slice(&sha1::Sha1::digest(data));
+        out.push(types::flags::HASH_SHA1 as u8);
+        out.extend_from_slice(&(data.len() as u32).to_le_bytes());
+        out.extend_from_slice(&compressed);
+        out
+    }
+
+    #[cfg(feature = "async")]
+    #[tokio::test]
+    async fn test_install_pipeline() {
+        use crate::install::pipeline::{run_pipeline, PipelineMetrics, PipelineOptions};
+        use std::sync::Arc;
+
+        let guids = ["00000000-0000-0000-0000-0000000000c1", "00000000-0000-0000-0000-0000000000c2"];
+        let data: Vec<Vec<u8>> = (1..=2u8).map(|seed| (0..5000u32).map(|i| (i as u8) ^ seed).collect()).collect();
+        let part = |chunk: usize, offset: u32, size: u32| ChunkPart {
+            parent_guid: guids[chunk].to_string(),
+            offset,
+            size,
+            ..Default::default()
+        };
+        let file = |filename: &str, parts: Vec<ChunkPart>| {
+            let content: Vec<u8> = parts
+                .iter()
+                .flat_map(|p| {
+                    let chunk = guids.iter().position(|g| *g == p.parent_guid).unwrap();
+                    data[chunk][p.offset as usize..(p.offset + p.size) as usize].to_vec()
+                })
+                .collect();
+            FileManifest {
+                filename: filename.to_string(),
+                sha_hash: hex::encode(sha1::Sha1::digest(&content)),
+                file_size: content.len() as i64,
+                chunk_parts: parts,
+                ..Default::default()
+            }
+        };
+        let manifest = Manifest {
+            chunk_list: Some(ChunkDataList {
+                elements: guids
+                    .iter()
+                    .zip(&data)
+                    .map(|(guid, data)| Chunk {
+                        guid: guid.to_string(),
+                        sha_hash: hex::encode(sha1::Sha1::digest(data)),
+                        file_size: "5066".to_string(),
+                        ..Default::default()
+                    })
+                    .collect(),
+                chunk_lookup: guids.iter().enumerate().map(|(i, g)| (g.to_string(), i as u32)).collect(),
+                ..Default::default()
+            }),
+            file_list: Some(FileManifestList {
+                file_manifest_list: vec![
+                    file("one.bin", vec![part(0, 0, 3000), part(1, 0, 2000)]),
+                    file("dir/two.bin", vec![part(1, 2000, 3000), part(0, 3000, 2000)]),
+                    file("empty.txt", vec![]),
+                ],
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        let options = PipelineOptions {
+            download_concurrency: 1,
+            download_depth: 1,
+            write_depth: 1,
+            ..Default::default()
+        };
+
+        let target = std::env::temp_dir().join(format!("egdata-pipeline-{}", uuid::Uuid::new_v4()));
+        let files: Vec<Vec<u8>> = guids.iter().zip(&data).map(|(g, d)| chunk_file_bytes(g, d)).collect();
+        let source = move |guid: String| {
+            let file = files[guids.iter().position(|g| *g == guid).unwrap()].clone();
+            async move { Ok(file) }
+        };
+        let metrics = Arc::new(PipelineMetrics::default());
+        let snapshot = run_pipeline(&manifest, &target, source, &options, metrics).await.unwrap();
+        assert_eq!(snapshot.download.items, 2);
+        assert_eq!(snapshot.verify.bytes, 10_000);
+        assert_eq!(snapshot.write.bytes, 10_000);
+        assert_eq!((snapshot.verify.queued, snapshot.write.queued), (0, 0));
+        assert!(verify::install::verify_install(&manifest, &target, None, |_| {}).unwrap().is_ok());
+        fs::remove_dir_all(&target).unwrap();
+
+        // A chunk whose content doesn't match the manifest fails verification.
+        let bad = chunk_file_bytes(guids[0], &[0u8; 5000]);
+        let source = move |_: String| {
+            let file = bad.clone();
+            async move { Ok(file) }
+        };
+        let result = run_pipeline(&manifest, &target, source, &options, Arc::default()).await;
+        assert!(matches!(result, Err(ManifestError::Invalid(ref e)) if e.contains("hashes to")), "{:?}", result);
+        fs::remove_dir_all(&target).ok();
+    }
+
+    #[test]
+    fn test_chunk_downloader_retries() {
+        use crate::download::downloader::ChunkDownloader;
+        use crate::download::retry::{FetchError, RetryPolicy};
+        use std::sync::Mutex;
+        use std::time::Duration;
+
+        let guid = "00000000-0000-0000-0000-0000000000d1";
+        let data: Vec<u8> = (0..3000u32).map(|i| (i % 251) as u8).collect();
+        let manifest = Manifest {
+            chunk_list: Some(ChunkDataList {
+                elements: vec![Chunk {
+                    guid: guid.to_string(),
+                    hash: "00000000000000AB".to_string(),
+                    sha_hash: hex::encode(sha1::Sha1::digest(&data)),
+                    group: 3,
+                    ..Default::default()
+                }],
+                chunk_lookup: [(guid.to_string(), 0)].into_iter().collect(),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        let good = chunk_file_bytes(guid, &data);
+        let mut bad = good.clone();
+        let last = bad.len() - 1;
+        bad[last] ^= 0xff;
+
+        // Scripted responses, one per request.
+        let run = |responses: Vec<Result<Vec<u8>, FetchError>>, policy: RetryPolicy| {
+            let responses = Mutex::new(responses.into_iter());
+            let urls = Mutex::new(Vec::new());
+            let transport = |url: &str| {
+                urls.lock().unwrap().push(url.to_string());
+                responses.lock().unwrap().next().unwrap()
+            };
+            let downloader = ChunkDownloader::new(transport, "https://a.example/Cloud/", manifest.clone())
+                .with_retry_policy(policy)
+                .with_sleep(|_| {});
+            let result = downloader.fetch(guid);
+            (result, urls.into_inner().unwrap())
+        };
+        let policy = RetryPolicy {
+            initial_backoff: Duration::ZERO,
+            alternate_base_urls: vec!["https://b.example/Cloud".to_string()],
+            ..Default::default()
+        };
+        let transient = || Err(FetchError::from_status("u", 503));
+
+        // Transient failure, then a corrupt chunk, then success on the alternate.
+        let (result, urls) = run(vec![transient(), Ok(bad.clone()), Ok(good.clone())], policy.clone());
+        assert_eq!(result.unwrap(), data);
+        assert_eq!(urls.len(), 3);
+        assert!(urls[0].starts_with("https://a.example/Cloud/Chunks"));
+        assert!(urls[1].starts_with("https://b.example/Cloud/"));
+        assert!(urls[0].ends_with("/03/00000000000000AB_000000000000000000000000D1000000.chunk"), "{}", urls[0]);
+
+        // A 404 moves on to the next host, but not past the last one.
+        let (result, urls) = run(vec![Err(FetchError::from_status("u", 404)); 2], policy.clone());
+        assert!(!result.unwrap_err().is_transient());
+        assert_eq!(urls.len(), 2);
+
+        // Without re-verification a corrupt chunk fails immediately.
+        let no_reverify = RetryPolicy {
+            reverify_on_corruption: false,
+            ..policy.clone()
+        };
+        let (result, urls) = run(vec![Ok(bad)], no_reverify);
+        assert!(result.unwrap_err().is_corrupt());
+        assert_eq!(urls.len(), 1);
+
+        // Transient failures stop at max_attempts.
+        let (result, urls) = run((0..5).map(|_| transient()).collect(), policy.clone());
+        assert!(result.unwrap_err().is_transient());
+        assert_eq!(urls.len(), policy.max_attempts as usize);
+
+        assert_eq!(policy.backoff(1), Duration::ZERO);
+        let default = RetryPolicy::default();
+        assert_eq!(default.backoff(2), Duration::from_millis(500));
+        assert_eq!(default.backoff(20), default.max_backoff);
+    }
+
+    #[test]
+    fn test_mirror_failover() {
+        use crate::download::downloader::ChunkDownloader;
+        use crate::download::mirrors::MirrorSet;
+        use crate::download::retry::{FetchError, RetryPolicy};
+        use std::sync::{Arc, Mutex};
+        use std::time::Duration;
+
+        let set = MirrorSet::from_base_url_field("https://a.example/, https://b.example,https://a.example,");
+        assert_eq!(set.ranked(), ["https://a.example", "https://b.example"]);
+        set.record_failure("https://a.example", &FetchError::from_status("u", 404));
+        assert_eq!(set.health()[0].failures, 0);
+        for _ in 0..3 {
+            set.record_failure("https://a.example", &FetchError::from_status("u", 502));
+        }
+        assert!(set.health()[0].resting);
+        assert_eq!(set.ranked(), ["https://b.example", "https://a.example"]);
+        set.record_success("https://a.example/");
+        assert_eq!(set.ranked()[0], "https://a.example");
+
+        // Host a is down; once it is rested, fetches go straight to b.
+        let guid = "00000000-0000-0000-0000-0000000000d2";
+        let data = vec![7u8; 1000];
+        let manifest = Manifest {
+            chunk_list: Some(ChunkDataList {
+                elements: vec![Chunk {
+                    guid: guid.to_string(),
+                    hash: "0000000000000001".to_string(),
+                    ..Default::default()
+                }],
+                chunk_lookup: [(guid.to_string(), 0)].into_iter().collect(),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        let file = chunk_file_bytes(guid, &data);
+        let urls = Mutex::new(Vec::new());
+        let transport = |url: &str| {
+            urls.lock().unwrap().push(url.to_string());
+            if url.starts_with("https://a.example") {
+                Err(FetchError::Transient { url: url.to_string(), message: "connection reset".to_string() })
+            } else {
+                Ok(file.clone())
+            }
+        };
+        let mirrors = Arc::new(MirrorSet::new(["https://a.example"]).with_cooldown(1, Duration::from_secs(60)));
+        let downloader = ChunkDownloader::new(transport, "", manifest)
+            .with_retry_policy(RetryPolicy {
+                initial_backoff: Duration::ZERO,
+                alternate_base_urls: vec!["https://b.example".to_string()],
+                ..Default::default()
+            })
+            .with_mirrors(mirrors.clone())
+            .with_sleep(|_| {});
+        assert_eq!(downloader.fetch(guid).unwrap(), data);
+        assert_eq!(downloader.fetch(guid).unwrap(), data);
+        let hosts: Vec<String> = urls.lock().unwrap().iter().map(|u| u[..17].to_string()).collect();
+        assert_eq!(hosts, ["https://a.example", "https://b.example", "https://b.example"]);
+        let health = mirrors.health();
+        assert_eq!((health[0].failures, health[1].successes), (1, 2));
+    }
+
+    #[test]
+    fn test_transport_config() {
+        use crate::download::config::{ProxyConfig, RootCertificate, TransportConfig, DEFAULT_USER_AGENT};
+        use crate::download::downloader::{ChunkDownloader, Transport};
+        use crate::download::retry::FetchError;
+
+        let proxy = ProxyConfig {
+            no_proxy: vec!["localhost".to_string(), ".corp.example".to_string()],
+            ..ProxyConfig::new("http://proxy.corp.example:3128")
+        };
+        let config = TransportConfig {
+            proxy: Some(proxy),
+            ..Default::default()
+        };
+        assert_eq!(config.user_agent, DEFAULT_USER_AGENT);
+        assert!(config.proxy_for("https://download.epicgames.com/Builds/x.chunk").is_some());
+        assert!(config.proxy_for("http://localhost:8080/Chunks").is_none());
+        assert!(config.proxy_for("https://user@mirror.corp.example/").is_none());
+        assert!(config.proxy_for("https://corp.example.org/").is_some());
+
+        let dir = scratch_dir("transport-config");
+        let pem = dir.join("ca.pem");
+        std::fs::write(&pem, "-----BEGIN CERTIFICATE-----\nMIIB\n-----END CERTIFICATE-----\n").unwrap();
+        assert!(matches!(RootCertificate::from_file(&pem).unwrap(), RootCertificate::Pem(_)));
+
+        #[derive(Default)]
+        struct Http {
+            user_agent: Option<String>,
+        }
+        impl Transport for Http {
+            fn get(&self, url: &str) -> Result<Vec<u8>, FetchError> {
+                Err(FetchError::from_status(url, 404))
+            }
+            fn configure(&mut self, config: &TransportConfig) -> Result<(), FetchError> {
+                self.user_agent = Some(config.user_agent.clone());
+                Ok(())
+            }
+        }
+        let config = TransportConfig {
+            user_agent: "EpicGamesLauncher/15.0".to_string(),
+            ..config
+        };
+        let downloader = ChunkDownloader::new(Http::default(), "https://a.example", Manifest::default())
+            .with_transport_config(&config)
+            .unwrap();
+        assert_eq!(downloader.transport().user_agent.as_deref(), Some("EpicGamesLauncher/15.0"));
+        // Closures take the default no-op.
+        let local = |_: &str| Ok(Vec::new());
+        assert!(ChunkDownloader::new(local, "file:///srv", Manifest::default())
+            .with_transport_config(&config)
+            .is_ok());
+    }
+
+    #[test]
+    fn test_verify_options() {
+        use crate::verify::install::{verify_install_with, VerifyOptions};
+
+        let file = |name: &str| FileManifest {
+            filename: name.to_string(),
+            file_size: 1,
+            ..Default::default()
+        };
+        let manifest = Manifest {
+            file_list: Some(FileManifestList {
+                file_manifest_list: vec![file("a.txt"), file("b.txt"), file("c.txt")],
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        let dir = scratch_dir("verify-options");
+        let options = VerifyOptions {
+            stop_on_corrupt: true,
+            ..Default::default()
+        };
+        let report = verify_install_with(&manifest, &dir, &options, None, |_| {}).unwrap();
+        assert_eq!((report.files_total, report.corrupt.len()), (3, 1));
+        let options = VerifyOptions {
+            files: vec!["b.txt".to_string(), "c.txt".to_string()],
+            ..Default::default()
+        };
+        let report = verify_install_with(&manifest, &dir, &options, None, |_| {}).unwrap();
+        assert_eq!((report.files_total, report.corrupt.len()), (2, 2));
+    }
+
+    #[test]
+    fn test_pretty_print() {
+        let manifest = load("test-manifests/valid-small.manifest").expect("Failed to load manifest");
+        let meta = manifest.meta.as_ref().unwrap();
+        let files = &manifest.file_list.as_ref().unwrap().file_manifest_list;
+
+        let summary = manifest.pretty_string(Verbosity::Summary);
+        assert!(summary.contains(&format!("App name:       {}", meta.app_name)));
+        assert!(summary.contains(&format!("Count:          {}", files.len())));
+        assert!(!summary.contains("Largest files"));
+
+        let normal = manifest.pretty_string(Verbosity::Normal);
+        assert!(normal.starts_with(&summary));
+        let largest = files.iter().max_by_key(|f| f.file_size).unwrap();
+        let listed = normal.split("Largest files\n").nth(1).unwrap();
+        assert!(listed.lines().next().unwrap().ends_with(&largest.filename));
+        assert!(listed.lines().count() <= 10);
+
+        let full = manifest.pretty_string(Verbosity::Full);
+        assert!(full.contains("All chunks"));
+        assert_eq!(full.split("All files\n").nth(1).unwrap().split("\n\n").next().unwrap().lines().count(), files.len());
+    }
+
+    #[test]
+    fn test_manifest_diff_renderers() {
+        use crate::diff::compare::ChangeKind;
+        use crate::diff::render::DiffFormat;
+
+        let old = load("test-manifests/valid-small.manifest").expect("Failed to load manifest");
+        let mut new = old.clone();
+        new.meta.as_mut().unwrap().build_version = "1.5.2".to_string();
+        let files = &mut new.file_list.as_mut().unwrap().file_manifest_list;
+        let total = files.len() as u32;
+        let removed = files.remove(0).filename;
+        files[0].sha_hash = "00".repeat(20);
+        files[0].file_size += 100;
+        let modified = files[0].filename.clone();
+        files.push(FileManifest {
+            filename: "mods/<new> & improved.pak".to_string(),
+            file_size: 2048,
+            sha_hash: "11".repeat(20),
+            ..Default::default()
+        });
+        let chunks = &mut new.chunk_list.as_mut().unwrap().elements;
+        chunks.push(Chunk {
+            guid: "00000000-0000-0000-0000-0000000000e1".to_string(),
+            file_size: "4096".to_string(),
+            ..Default::default()
+        });
+
+        let diff = ManifestDiff::between(&old, &new);
+        assert_eq!(diff.changes.len(), 3);
+        assert_eq!(diff.unchanged_files, total - 2);
+        assert_eq!((diff.new_chunks, diff.download_bytes), (1, 4096));
+        assert_eq!(diff.of_kind(ChangeKind::Removed).next().unwrap().path, removed);
+        let change = diff.of_kind(ChangeKind::Modified).next().unwrap();
+        assert_eq!((change.path.as_str(), change.size_delta()), (modified.as_str(), 100));
+        assert!(ManifestDiff::between(&old, &old).is_empty());
+
+        let json = diff.render_string(DiffFormat::Json).unwrap();
+        assert_eq!(serde_json::from_str::<ManifestDiff>(&json).unwrap(), diff);
+
+        let text = diff.render_string(DiffFormat::Text).unwrap();
+        let lines: Vec<&str> = text.lines().collect();
+        assert_eq!(lines[..2], ["--- 1.5.1", "+++ 1.5.2"]);
+        assert!(lines[2].starts_with("@@ 1 added, 1 removed, 1 modified"));
+        assert_eq!(lines[3], "+ mods/<new> & improved.pak  (2.00 KiB)");
+        assert!(lines[4].starts_with(&format!("- {}", removed)));
+        assert!(lines[5].starts_with(&format!("~ {}", modified)) && lines[5].ends_with(", +100 B)"));
+
+        let html = diff.render_string(DiffFormat::Html).unwrap();
+        assert!(html.starts_with("<!DOCTYPE html>"));
+        assert!(html.contains("mods/&lt;new&gt; &amp; improved.pak"));
+        assert_eq!(html.matches("<details").count(), 3);
+    }
+
+    #[test]
+    fn test_summarize_changes() {
+        let file = |path: &str, size: i64, sha: &str| FileManifest {
+            filename: path.to_string(),
+            file_size: size,
+            sha_hash: sha.repeat(20),
+            ..Default::default()
+        };
+        let manifest = |files: Vec<FileManifest>| Manifest {
+            file_list: Some(FileManifestList {
+                file_manifest_list: files,
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        let shaders = |sha: &'static str| (0..12).map(move |i| file(&format!("Game/Content/Shaders/s{}.ushaderbytecode", i), 10, sha));
+        let old = manifest(
+            shaders("aa")
+                .chain([
+                    file("Game/Binaries/Win64/Game.exe", 100, "aa"),
+                    file("Game/Content/Localization/Game/en/Game.locres", 5, "aa"),
+                    file("Game/Content/Localization/Game/fr/Game.locres", 5, "aa"),
+                ])
+                .collect(),
+        );
+        let new = manifest(
+            shaders("bb")
+                .chain([
+                    file("Game/Binaries/Win64/Game.exe", 120, "bb"),
+                    file("Game/Content/Localization/Game/en/Game.locres", 5, "aa"),
+                    file("Game/Content/Localization/Game/ja/Game.locres", 7, "bb"),
+                    file("Game/Content/Localization/Game/ja/Game.locmeta", 1, "bb"),
+                    file("Game/Content/Movies/intro.bk2", 50, "bb"),
+                    file("Game/Content/Movies/outro.bk2", 50, "bb"),
+                ])
+                .collect(),
+        );
+
+        let diff = ManifestDiff::between(&old, &new);
+        assert_eq!(diff.added_directories, ["Game/Content/Localization/Game/ja", "Game/Content/Movies"]);
+        assert_eq!(diff.removed_directories, ["Game/Content/Localization/Game/fr"]);
+
+        let notes: Vec<String> = diff.summarize_changes().iter().map(|n| n.to_string()).collect();
+        assert_eq!(
+            notes,
+            [
+                "New language pack: ja",
+                "Removed language pack: fr",
+                "12 shader files updated in Game/Content/Shaders",
+                "2 movies added in Game/Content/Movies",
+                "Updated Game/Binaries/Win64/Game.exe",
+            ]
+        );
+        let ja = &diff.summarize_changes()[0];
+        assert_eq!((ja.files, ja.size_delta), (2, 8));
+    }
+
+    #[test]
+    fn test_build_history() {
+        use crate::history::{BuildHistory, BuildRecord};
+
+        let manifest = load("test-manifests/valid-small.manifest").expect("Failed to load manifest");
+        let build = |id: &str, at: i64, chunks: &[(&str, u64)]| BuildRecord {
+            build_id: id.to_string(),
+            recorded_at: at,
+            content_hash: id.to_string(),
+            chunks: chunks.iter().map(|&(g, s)| (g.to_string(), s)).collect(),
+            ..Default::default()
+        };
+
+        let mut history = BuildHistory::new("Game");
+        history.append(build("b3", 300, &[("a", 10), ("c", 30), ("d", 40)])).unwrap();
+        history.append(build("b1", 100, &[("a", 10), ("b", 20)])).unwrap();
+        history.append(build("b2", 200, &[("a", 10), ("c", 30)])).unwrap();
+        assert_eq!(history.latest().unwrap().build_id, "b3");
+        assert_eq!(history.between(150, 301).len(), 2);
+        assert_eq!(history.patch_size("b1", "b3"), Some(70));
+        assert_eq!(history.cumulative_patch_size("b1", "b3"), Some(30 + 40));
+        assert_eq!(history.cumulative_patch_size("b1", "b1"), Some(0));
+        assert_eq!(history.cumulative_patch_size("b3", "b1"), None);
+
+        // Re-recording the same build is a no-op; a conflicting one is not.
+        history.append(build("b2", 250, &[])).unwrap();
+        assert_eq!(history.len(), 3);
+        let mut conflict = build("b2", 250, &[]);
+        conflict.content_hash = "other".to_string();
+        assert!(history.append(conflict).is_err());
+
+        let record = history.record(&manifest, 400).unwrap().clone();
+        let meta = manifest.meta.as_ref().unwrap();
+        assert_eq!(record.build_id, meta.build_id());
+        assert_eq!(record.file_count as usize, manifest.file_list.as_ref().unwrap().file_manifest_list.len());
+        assert_eq!(record.download_size, record.patch_size_from(&BuildRecord::default()));
+        assert_eq!(record.content_hash.len(), 64);
+        assert_eq!(BuildRecord::from_manifest(&manifest, 0).unwrap().content_hash, record.content_hash);
+
+        let restored = BuildHistory::from_json(&history.to_json().unwrap()).unwrap();
+        assert_eq!(restored, history);
+    }
+
+    #[test]
+    fn test_archive_seal() {
+        use crate::integrity::{ArchiveSeal, SealOptions};
+
+        let root = scratch_dir("archive-seal");
+        std::fs::create_dir_all(root.join("Fortnite/ChunksV4/01")).unwrap();
+        for i in 0..5 {
+            std::fs::write(root.join(format!("Fortnite/build-{}.manifest", i)), format!("manifest {}", i)).unwrap();
+        }
+        std::fs::write(root.join("Fortnite/ChunksV4/01/AB_CD.chunk"), b"chunk").unwrap();
+        std::fs::write(root.join("notes.txt"), b"ignored").unwrap();
+
+        let options = SealOptions::default();
+        let seal = ArchiveSeal::scan(&root, &options).unwrap();
+        assert_eq!(seal.entries.len(), 5);
+        assert!(seal.is_consistent());
+        assert!(seal.verify(&root, &options).unwrap().is_ok());
+        let with_chunks = SealOptions { include_chunks: true };
+        let full = ArchiveSeal::scan(&root, &with_chunks).unwrap();
+        assert_eq!(full.entries.len(), 6);
+        assert_ne!(full.merkle_root, seal.merkle_root);
+
+        // Every entry proves against the root, and only against it.
+        for entry in &seal.entries {
+            let proof = seal.proof(&entry.name).unwrap();
+            assert!(proof.verify(&seal.merkle_root));
+            assert!(!proof.verify(&full.merkle_root));
+        }
+        let mut forged = seal.proof("Fortnite/build-2.manifest").unwrap();
+        forged.entry.size += 1;
+        assert!(!forged.verify(&seal.merkle_root));
+
+        std::fs::write(root.join("Fortnite/build-1.manifest"), b"tampered").unwrap();
+        std::fs::remove_file(root.join("Fortnite/build-3.manifest")).unwrap();
+        std::fs::write(root.join("Fortnite/build-9.manifest"), b"new").unwrap();
+        let report = seal.verify(&root, &options).unwrap();
+        assert_eq!(report.modified, ["Fortnite/build-1.manifest"]);
+        assert_eq!(report.missing, ["Fortnite/build-3.manifest"]);
+        assert_eq!(report.unexpected, ["Fortnite/build-9.manifest"]);
+
+        let mut altered = seal.clone();
+        altered.entries.pop();
+        assert!(!altered.is_consistent());
+    }
+
+    #[test]
+    fn test_parser_hooks() {
+        use crate::hooks::{ParseWarning, Section};
+        use std::ops::ControlFlow;
+
+        #[derive(Default)]
+        struct Recorder {
+            events: Vec<String>,
+            stop_at: Option<Section>,
+        }
+        impl ParserHooks for Recorder {
+            fn on_section_start(&mut self, section: Section, _offset: u64) -> ControlFlow<()> {
+                self.events.push(format!("start {}", section));
+                if self.stop_at == Some(section) {
+                    return ControlFlow::Break(());
+                }
+                ControlFlow::Continue(())
+            }
+            fn on_section_end(&mut self, section: Section, size: u64) -> ControlFlow<()> {
+                assert!(size > 0, "{} has no size", section);
+                self.events.push(format!("end {}", section));
+                ControlFlow::Continue(())
+            }
+            fn on_warning(&mut self, warning: &ParseWarning) {
+                self.events.push(format!("warning {:?}", warning));
+            }
+            fn on_recovered_error(&mut self, section: Section, message: &str) {
+                self.events.push(format!("recovered {}: {}", section, message));
+            }
+        }
+
+        let data = fs::read("test-manifests/valid-small.manifest").unwrap();
+        let mut hooks = Recorder::default();
+        let (manifest, _) = parse_with_hooks(&data, &ParseOptions::default(), &mut hooks).unwrap();
+        assert!(manifest.file_list.is_some());
+        let sections = ["header", "payload", "meta", "chunk list", "file list", "custom fields"];
+        let expected: Vec<String> =
+            sections.iter().flat_map(|s| [format!("start {}", s), format!("end {}", s)]).collect();
+        assert_eq!(hooks.events, expected);
+
+        let mut hooks = Recorder {
+            stop_at: Some(Section::ChunkList),
+            ..Default::default()
+        };
+        let err = parse_with_hooks(&data, &ParseOptions::default(), &mut hooks).unwrap_err();
+        assert!(matches!(err, ManifestError::Aborted(Section::ChunkList)));
+        assert_eq!(hooks.events.last().unwrap(), "start chunk list");
+
+        let corrupt = fs::read("test-manifests/fixtures/corrupt-chunk-count.manifest").unwrap();
+        let mut hooks = Recorder::default();
+        let (_, report) = parse_with_hooks(&corrupt, &ParseOptions::default(), &mut hooks).unwrap();
+        let recovered: Vec<&String> = hooks.events.iter().filter(|e| e.starts_with("recovered file list")).collect();
+        assert_eq!(recovered.len(), report.corrupt_files.len());
+        assert!(!recovered.is_empty());
+
+        let mut preamble = b"launcher blob ".to_vec();
+        preamble.extend_from_slice(&data);
+        let options = ParseOptions {
+            scan_for_manifest: true,
+            ..Default::default()
+        };
+        let mut hooks = Recorder::default();
+        parse_with_hooks(&preamble, &options, &mut hooks).unwrap();
+        assert_eq!(hooks.events[0], "warning EmbeddedManifest { offset: 14 }");
+
+        let json = fs::read("test-manifests/fixtures/small.json.manifest").unwrap();
+        let mut hooks = Recorder::default();
+        parse_with_hooks(&json, &ParseOptions::default(), &mut hooks).unwrap();
+        assert_eq!(hooks.events, ["start JSON manifest", "end JSON manifest"]);
+    }
+
+    #[test]
+    fn test_parallel_verify_matches_sequential() {
+        use crate::verify::install::{verify_install_with, VerifyOptions};
+
+        let dir = scratch_dir("parallel-verify");
+        let mut files = Vec::new();
+        for i in 0..24u8 {
+            let name = format!("file{:02}.bin", i);
+            let content = vec![i; 1000 + i as usize];
+            // Every third file is damaged, every fifth missing.
+            if i % 5 != 0 {
+                let on_disk = if i % 3 == 0 { vec![!i; content.len()] } else { content.clone() };
+                std::fs::write(dir.join(&name), on_disk).unwrap();
+            }
+            files.push(FileManifest {
+                filename: name,
+                file_size: content.len() as i64,
+                sha_hash: hex::encode(sha1::Sha1::digest(&content)),
+                ..Default::default()
+            });
+        }
+        let manifest = Manifest {
+            file_list: Some(FileManifestList {
+                file_manifest_list: files,
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+
+        let verify = |parallelism: Parallelism| {
+            let options = VerifyOptions {
+                parallelism: Some(parallelism),
+                ..Default::default()
+            };
+            let mut events = 0;
+            let report = verify_install_with(&manifest, &dir, &options, None, |_| events += 1).unwrap();
+            (report, events)
+        };
+        let (sequential, sequential_events) = verify(Parallelism::sequential());
+        let (parallel, parallel_events) = verify(Parallelism::with_threads(4));
+        assert_eq!(sequential.files_ok, 24 - 5 - 6);
+        assert_eq!(sequential.corrupt, parallel.corrupt);
+        assert_eq!(sequential.bytes_verified, parallel.bytes_verified);
+        assert_eq!(sequential_events, parallel_events);
+        assert_eq!(Parallelism::with_threads(3).threads(), 3);
+        assert_eq!(Parallelism::sequential().threads(), 1);
+    }
+
+    #[test]
+    fn test_memory_footprint() {
+        let manifest = load("test-manifests/valid-small.manifest").expect("Failed to load manifest");
+        let footprint = manifest.memory_footprint();
+        assert!(footprint.strings > 0 && footprint.vectors > 0);
+        assert_eq!(footprint, manifest.clone().memory_footprint());
+        assert_eq!(Manifest::default().memory_footprint().total(), 0);
+
+        let mut bigger = manifest.clone();
+        let files = &mut bigger.file_list.as_mut().unwrap().file_manifest_list;
+        files[0].filename.push_str(&"x".repeat(1000));
+        let grown = bigger.memory_footprint();
+        assert_eq!(grown.strings, footprint.strings + 1000);
+        assert_eq!(grown.total(), footprint.total() + 1000);
+    }
+
+    #[test]
+    fn test_audit_filenames() {
+        use crate::audit::{audit_filenames, StringEncoding};
+
+        let mut manifest = load("test-manifests/valid-small.manifest").expect("Failed to load manifest");
+        let files = &mut manifest.file_list.as_mut().unwrap().file_manifest_list;
+        files[1].filename = "Game/bad_name.pak".to_string();
+        let clean = writer::write_manifest(&manifest).unwrap();
+        assert!(audit_filenames(&clean).unwrap().is_empty());
+
+        // Swap the `_` for a byte that is never valid UTF-8.
+        let mut payload = payload_of(&clean);
+        let at = payload.windows(8).position(|w| w == b"bad_name").unwrap() + 3;
+        payload[at] = 0xFF;
+        let damaged = with_payload(&clean, &payload);
+
+        let issues = audit_filenames(&damaged).unwrap();
+        assert_eq!(issues.len(), 1);
+        assert_eq!(issues[0].index, 1);
+        assert_eq!(issues[0].filename, "Game/bad\u{FFFD}name.pak");
+        assert_eq!(issues[0].raw, b"Game/bad\xFFname.pak\0");
+        assert_eq!((issues[0].encoding, issues[0].replacements), (StringEncoding::Utf8, 1));
+    }
+
+    #[test]
+    fn test_check_chunk_windows() {
+        use crate::validate::WindowAnomaly;
+
+        let buf = std::fs::read("test-manifests/valid-small.manifest").unwrap();
+        let mut manifest = process_manifest_data(buf.clone()).unwrap();
+        assert!(manifest.check_chunk_windows().is_clean());
+
+        let chunk_list = manifest.chunk_list.as_mut().unwrap();
+        chunk_list.elements[2].window_size = 512;
+        let report = manifest.check_chunk_windows();
+        assert!(matches!(
+            report.anomalies[0],
+            WindowAnomaly::NonStandardWindow { chunk_index: 2, window_size: 512, .. }
+        ));
+        assert!(report
+            .anomalies
+            .iter()
+            .skip(1)
+            .all(|a| matches!(a, WindowAnomaly::PartExceedsWindow { window_size: 512, .. })));
+
+        // The offset points at chunk 2's window size in the real payload.
+        let header = ManifestHeader::read(&mut Cursor::new(&buf[..])).unwrap();
+        let (payload, _) = manifest_payload(&buf, &header, ByteTrace::Off, None).unwrap();
+        let at = report.first_offset.unwrap() as usize;
+        assert_eq!(u32::from_le_bytes(payload[at..at + 4].try_into().unwrap()), format::CHUNK_WINDOW_SIZE);
+    }
+
+    #[test]
+    fn test_estimate_truncation() {
+        use crate::hooks::Section;
+        use crate::truncation::estimate_truncation;
+
+        let manifest = load("test-manifests/valid-small.manifest").expect("Failed to load manifest");
+        let buf = writer::write_manifest(&manifest).unwrap();
+        let complete = estimate_truncation(&buf).unwrap();
+        assert!(!complete.is_truncated(), "{:?}", complete);
+        let sections: Vec<Section> = complete.sections.iter().map(|s| s.section).collect();
+        assert_eq!(
+            sections,
+            [Section::Header, Section::Payload, Section::Meta, Section::ChunkList, Section::FileList]
+        );
+        let payload_size: u64 = complete.sections[2..].iter().map(|s| s.expected).sum();
+        assert_eq!(payload_size, u32::from_le_bytes(buf[8..12].try_into().unwrap()) as u64);
+
+        let cut = estimate_truncation(&buf[..buf.len() - 100]).unwrap();
+        assert!(cut.is_truncated());
+        assert_eq!(cut.missing_bytes, Some(100));
+        assert!(cut.missing_payload_bytes.unwrap() > 0, "{:?}", cut);
+        assert_eq!(cut.first_incomplete().unwrap().section, Section::Payload);
+        assert_eq!(cut.sections.last().unwrap().section, Section::FileList);
+        assert!(!cut.sections.last().unwrap().is_complete());
+
+        let header_only = estimate_truncation(&buf[..20]).unwrap();
+        assert_eq!(header_only.first_incomplete().unwrap().missing(), buf[4] as u64 - 20);
+        assert_eq!(header_only.missing_bytes, None);
+
+        let json = br#"{"ManifestFileVersion": "013000000000", "FileManifestList": ["#;
+        assert!(estimate_truncation(json).unwrap().json_ends_early);
+    }
+
+    #[test]
+    fn test_assess() {
+        use crate::health::{assess, assess_with, ManifestHealth};
+        use crate::hooks::Section;
+
+        let manifest = load("test-manifests/valid-small.manifest").expect("Failed to load manifest");
+        let buf = writer::write_manifest(&manifest).unwrap();
+        assert_eq!(assess(&buf), ManifestHealth::Ok);
+        assert_eq!(assess(&buf[..buf.len() - 100]), ManifestHealth::Truncated { missing: Some(100) });
+        assert_eq!(assess(&buf[..30]), ManifestHealth::Truncated { missing: Some(11) });
+        assert!(matches!(assess(b"PK\x03\x04"), ManifestHealth::Unsupported { .. }));
+
+        let mut bad_hash = buf.clone();
+        bad_hash[16] ^= 0xFF;
+        assert_eq!(assess(&bad_hash), ManifestHealth::Corrupt { section: Section::Payload });
+
+        // A desynchronized chunk list shows up as odd window sizes.
+        let mut odd_window = manifest.clone();
+        odd_window.chunk_list.as_mut().unwrap().elements[0].window_size = 7;
+        let health = assess(&writer::write_manifest(&odd_window).unwrap());
+        assert_eq!(health, ManifestHealth::Corrupt { section: Section::ChunkList });
+        assert!(health.needs_redownload());
+
+        // Builds over the limits, or slower than the deadline, are not damaged.
+        let limited = ParseOptions {
+            limits: ParseLimits {
+                max_chunks: 1,
+                ..ParseLimits::default()
+            },
+            ..ParseOptions::default()
+        };
+        let timed_out = ParseOptions {
+            deadline: Some(std::time::Instant::now()),
+            ..ParseOptions::default()
+        };
+        for options in [limited, timed_out] {
+            let health = assess_with(&buf, &options);
+            assert!(matches!(health, ManifestHealth::Unsupported { .. }), "{:?}", health);
+            assert!(!health.needs_redownload());
+        }
+    }
+
+    #[test]
+    fn test_newer_section_versions() {
+        use crate::hooks::{ParseWarning, ParserHooks, Section};
+
+        #[derive(Default)]
+        struct Warnings(Vec<ParseWarning>);
+        impl ParserHooks for Warnings {
+            fn on_warning(&mut self, warning: &ParseWarning) {
+                self.0.push(warning.clone());
+            }
+        }
+
+        let mut manifest = load("test-manifests/valid-small.manifest").expect("Failed to load manifest");
+        manifest.file_list.as_mut().unwrap().file_manifest_list[0].mime_type = "text/plain".to_string();
+        let buf = writer::write_manifest(&manifest).unwrap();
+        let payload = payload_of(&buf);
+        let meta_size = u32::from_le_bytes(payload[..4].try_into().unwrap()) as usize;
+        let chunk_size = u32::from_le_bytes(payload[meta_size..meta_size + 4].try_into().unwrap()) as usize;
+        let file_start = meta_size + chunk_size;
+
+        // A future version 9 meta and version 3 file list, each with fields
+        // appended at the end.
+        let mut meta = payload[..meta_size].to_vec();
+        meta[4] = 9;
+        meta.extend_from_slice(&[0xAA, 0xBB]);
+        let size = (meta.len() as u32).to_le_bytes();
+        meta[..4].copy_from_slice(&size);
+        let mut files = payload[file_start..].to_vec();
+        assert_eq!(files[4], 2);
+        files[4] = 3;
+        files.extend_from_slice(&[1, 2, 3]);
+        let size = (files.len() as u32).to_le_bytes();
+        files[..4].copy_from_slice(&size);
+        let newer = [meta, payload[meta_size..file_start].to_vec(), files].concat();
+
+        let mut warnings = Warnings::default();
+        let (parsed, _) = parse_with_hooks(&with_payload(&buf, &newer), &ParseOptions::default(), &mut warnings).unwrap();
+        let meta = parsed.meta.as_ref().unwrap();
+        assert_eq!(meta.build_id(), manifest.meta.as_ref().unwrap().build_id());
+        assert_eq!(meta.extra_data.as_deref(), Some("aabb"));
+        let file_list = parsed.file_list.as_ref().unwrap();
+        assert_eq!(file_list.extra_data.as_deref(), Some("010203"));
+        assert_eq!(file_list.file_manifest_list[0].mime_type, "text/plain");
+        assert_eq!(parsed.chunk_list.as_ref().unwrap().extra_data, None);
+        assert_eq!(
+            warnings.0,
+            [
+                ParseWarning::NewerSectionVersion { section: Section::Meta, data_version: 9 },
+                ParseWarning::UnknownSectionData { section: Section::Meta, bytes: 2 },
+                ParseWarning::NewerSectionVersion { section: Section::FileList, data_version: 3 },
+                ParseWarning::UnknownSectionData { section: Section::FileList, bytes: 3 },
+            ]
+        );
+        assert_eq!(schema::FILE_LIST.latest_version(), 2);
+        assert!(schema::META.has("build_id", 1) && !schema::META.has("build_id", 0));
+    }
+
+    #[test]
+    fn test_schema_layout() {
+        use crate::hooks::Section;
+
+        let mut manifest = load("test-manifests/valid-small.manifest").expect("Failed to load manifest");
+        manifest.file_list.as_mut().unwrap().file_manifest_list[0].mime_type = "text/plain".to_string();
+        let buf = writer::write_manifest(&manifest).unwrap();
+        let payload = payload_of(&buf);
+        let spans = schema::layout(&buf).unwrap();
+
+        // The spans tile the payload in order.
+        let mut offset = 0;
+        for span in &spans {
+            assert_eq!(span.offset, offset, "{:?}", span);
+            offset += span.size;
+        }
+        assert_eq!(offset, payload.len() as u64);
+
+        let span = |section: Section, field: &str| spans.iter().find(|s| s.section == section && s.field == field).unwrap();
+        let chunks = manifest.chunk_list.as_ref().unwrap().elements.len() as u64;
+        assert_eq!(span(Section::ChunkList, "guid").size, chunks * 16);
+        assert_eq!(span(Section::ChunkList, "window_size").size, chunks * 4);
+        assert!(span(Section::FileList, "mime_type").offset > span(Section::FileList, "chunk_parts").offset);
+        let meta_size = u32::from_le_bytes(payload[..4].try_into().unwrap()) as u64;
+        assert_eq!(span(Section::ChunkList, "data_size").offset, meta_size);
+    }
+
+    #[test]
+    fn test_file_flags_option() {
+        let plain = load("test-manifests/valid-small.manifest").unwrap();
+        let json = serde_json::to_value(&plain).unwrap();
+        assert!(json["file_list"]["file_manifest_list"][0].get("flags").is_none());
+
+        let options = ParseOptions {
+            file_flags: true,
+            ..Default::default()
+        };
+        let manifest = load_with_options("test-manifests/valid-small.manifest", &options).unwrap();
+        for file in &manifest.file_list.as_ref().unwrap().file_manifest_list {
+            let flags = file.flags.unwrap();
+            assert_eq!(flags, file.meta_flags());
+            assert_eq!(flags.unix_executable, file.file_meta_flags & 4 != 0);
+        }
+
+        let file = FileManifest {
+            file_meta_flags: 0b101,
+            ..Default::default()
+        };
+        let flags = file.meta_flags();
+        assert!(flags.readonly && !flags.compressed && flags.unix_executable);
+        let json = serde_json::to_value(&manifest).unwrap();
+        assert!(json["file_list"]["file_manifest_list"][0]["flags"]["unix_executable"].is_boolean());
+    }
+
+    #[test]
+    fn test_custom_fields() {
+        use crate::hooks::{ParseWarning, ParserHooks};
+        use crate::types::custom_fields::INCOMPLETE_INSTALL;
+
+        #[derive(Default)]
+        struct Warnings(Vec<ParseWarning>);
+        impl ParserHooks for Warnings {
+            fn on_warning(&mut self, warning: &ParseWarning) {
+                self.0.push(warning.clone());
+            }
+        }
+
+        let mut manifest = load("test-manifests/valid-small.manifest").expect("Failed to load manifest");
+        assert!(manifest.custom_fields.is_empty());
+        manifest.custom_fields.push("BaseUrl", "https://a.example/,https://b.example/");
+        manifest.custom_fields.push(INCOMPLETE_INSTALL, "true");
+        manifest.custom_fields.push("InstallSize", "1234");
+        manifest.custom_fields.push("baseurl", "https://c.example/");
+        let buf = writer::write_manifest(&manifest).unwrap();
+
+        let mut warnings = Warnings::default();
+        let (parsed, _) = parse_with_hooks(&buf, &ParseOptions::default(), &mut warnings).unwrap();
+        let fields = &parsed.custom_fields;
+        assert_eq!(fields, &manifest.custom_fields);
+        assert_eq!(fields.get("BASEURL"), Some("https://a.example/,https://b.example/"));
+        assert_eq!(fields.get_all("BaseUrl").count(), 2);
+        assert_eq!(fields.duplicate_keys(), ["BaseUrl"]);
+        assert!(fields.is_incomplete_install());
+        assert_eq!(fields.get_u64("InstallSize"), Some(1234));
+        assert_eq!(fields.get_bool("InstallSize"), None);
+        assert_eq!(
+            warnings.0,
+            [ParseWarning::DuplicateCustomField {
+                key: "BaseUrl".to_string()
+            }]
+        );
+
+        // JSON keeps order and duplicates both ways.
+        let json = r#"{"bIsIncompleteInstall":"false","Tag":"a","tag":"b","Count":3}"#;
+        let fields: CustomFields = serde_json::from_str(json).unwrap();
+        assert_eq!(fields.get("TAG"), Some("a"));
+        assert_eq!(fields.get_u64("count"), Some(3));
+        assert!(!fields.is_incomplete_install());
+        assert_eq!(serde_json::to_string(&fields).unwrap(), json.replace("3", "\"3\""));
+    }
+
+    /// Manifest URLs for [`test_live_cdn_manifests`]: whitespace-separated
+    /// in `EGDATA_LIVE_MANIFESTS`, plus one per line in the file named by
+    /// `EGDATA_LIVE_MANIFESTS_FILE` (`#` starts a comment).
+    fn live_manifest_urls() -> Option<Vec<String>> {
+        let mut urls: Vec<String> = std::env::var("EGDATA_LIVE_MANIFESTS")
+            .ok()?
+            .split_whitespace()
+            .map(str::to_string)
+            .collect();
+        if let Ok(path) = std::env::var("EGDATA_LIVE_MANIFESTS_FILE") {
+            let list = fs::read_to_string(&path).unwrap_or_else(|e| panic!("reading {}: {}", path, e));
+            urls.extend(
+                list.lines()
+                    .map(|line| line.split('#').next().unwrap().trim())
+                    .filter(|line| !line.is_empty())
+                    .map(str::to_string),
+            );
+        }
+        Some(urls)
+    }
+
+    /// Downloads real manifests and runs them through parsing, validation
+    /// and the stats helpers, to catch format drift that the fixtures miss.
+    /// Opt-in: set `EGDATA_LIVE_MANIFESTS` (see [`live_manifest_urls`]).
+    /// Fetches with the `curl` binary through a [`download::downloader::Transport`].
+    #[test]
+    fn test_live_cdn_manifests() {
+        use crate::download::downloader::Transport;
+        use crate::download::retry::FetchError;
+        use crate::health::{assess, ManifestHealth};
+
+        let Some(urls) = live_manifest_urls() else {
+            eprintln!("EGDATA_LIVE_MANIFESTS not set; skipping live CDN manifests");
+            return;
+        };
+        assert!(!urls.is_empty(), "EGDATA_LIVE_MANIFESTS lists no URLs");
+
+        let curl = |url: &str| -> Result<Vec<u8>, FetchError> {
+            let output = std::process::Command::new("curl")
+                .args(["--fail", "--silent", "--show-error", "--location", "--max-time", "120", url])
+                .output()
+                .map_err(|e| FetchError::Permanent {
+                    url: url.to_string(),
+                    message: format!("running curl: {}", e),
+                })?;
+            if !output.status.success() {
+                return Err(FetchError::Transient {
+                    url: url.to_string(),
+                    message: String::from_utf8_lossy(&output.stderr).trim().to_string(),
+                });
+            }
+            Ok(output.stdout)
+        };
+
+        for url in &urls {
+            let buf = curl.get(url).unwrap_or_else(|e| panic!("{}", e));
+
+            let (manifest, report) = parse_with_report(&buf, &ParseOptions::default()).unwrap();
+            assert!(report.corrupt_files.is_empty(), "{}: {:?}", url, report.corrupt_files);
+            assert!(audit::audit_filenames(&buf).unwrap().is_empty(), "{}", url);
+
+            let files = &manifest.file_list.as_ref().unwrap().file_manifest_list;
+            let chunk_list = manifest.chunk_list.as_ref().unwrap();
+            assert!(!files.is_empty() && !chunk_list.elements.is_empty(), "{}", url);
+            for part in files.iter().flat_map(|f| &f.chunk_parts) {
+                assert!(chunk_list.chunk_lookup.contains_key(&part.parent_guid), "{}", url);
+            }
+            assert!(manifest.download_size() > 0 && manifest.install_size() > 0, "{}", url);
+            assert_eq!(manifest.check_chunk_groups(), [], "{}", url);
+            assert!(manifest.memory_footprint().total() > 0);
+            let summary = manifest.pretty_string(Verbosity::Summary);
+
+            // Binary manifests must lay out exactly as the schema describes.
+            // JSON manifests carry no chunk window sizes (the converter
+            // assumes the standard window), so health and window checks
+            // only apply to binary ones.
+            if detect_format(&buf) != ManifestFormat::Json {
+                assert_eq!(assess(&buf), ManifestHealth::Ok, "{}", url);
+                let windows = manifest.check_chunk_windows();
+                assert!(windows.is_clean(), "{}: {:?}", url, windows.anomalies);
+                let spans = schema::layout(&buf).unwrap();
+                let end = spans.last().map_or(0, |s| s.offset + s.size);
+                let header = ManifestHeader::read(&mut std::io::Cursor::new(&buf)).unwrap();
+                assert_eq!(end, header.data_size_uncompressed as u64, "{}", url);
+            }
+
+            // Re-encoding keeps every file.
+            let (reparsed, _) = parse_with_report(&normalize(&buf).unwrap(), &ParseOptions::default()).unwrap();
+            assert_eq!(reparsed.file_list.unwrap().file_manifest_list.len(), files.len(), "{}", url);
+
+            eprintln!("{}\n{}", url, summary);
+        }
+    }
+
+    #[test]
+    fn test_hostile_counts_allocate_by_bytes_read() {
+        use crate::parser::reader::{bounded_capacity, MAX_ARRAY_LEN, MAX_PREALLOC_BYTES};
+
+        // Sizes far beyond the input read what is there instead of reserving
+        // the declared amount up front.
+        let mut rdr = Cursor::new(vec![1u8, 2, 3]);
+        assert_eq!(rdr.read_bytes_tolerant(usize::MAX / 2).unwrap(), [1, 2, 3]);
+        rdr.set_position(0);
+        assert_eq!(rdr.read_bytes_available(usize::MAX / 2).unwrap(), [1, 2, 3]);
+
+        let unbounded = StringPolicy {
+            max_len: usize::MAX,
+            ..StringPolicy::default()
+        };
+        for len in [i32::MAX, i32::MIN + 1] {
+            let mut data = len.to_le_bytes().to_vec();
+            data.extend_from_slice(b"abcd");
+            assert!(Cursor::new(data).fstring_with(&unbounded).is_err());
+        }
+
+        // Counts that pass the byte check still reserve a bounded amount.
+        assert_eq!(bounded_capacity::<u8>(10), 10);
+        assert!(bounded_capacity::<[u8; 256]>(MAX_ARRAY_LEN as usize) * 256 <= MAX_PREALLOC_BYTES);
+        let mut data = 10_000u32.to_le_bytes().to_vec();
+        data.resize(4 + 10_000, 7);
+        let items = ReadExt::read_array(&mut Cursor::new(data), |r| r.u8().map(|b| [b; 256])).unwrap();
+        assert_eq!(items.len(), 10_000);
+
+        // A meta section declaring a gigabyte in a payload of a few hundred
+        // bytes fails cleanly.
+        let buf = fs::read("test-manifests/fixtures/minimal.manifest").unwrap();
+        let mut payload = payload_of(&buf);
+        payload[..4].copy_from_slice(&(1024u32 * 1024 * 1024).to_le_bytes());
+        assert!(process_manifest_data(with_payload(&buf, &payload)).is_err());
+
+        // So does a chunk list whose count claims one element per byte left
+        // in the section, passing the count check.
+        let manifest = process_manifest_data(buf.clone()).unwrap();
+        let chunk_list_at = manifest.meta.as_ref().unwrap().data_size as usize;
+        let chunk_list_size = manifest.chunk_list.as_ref().unwrap().data_size;
+        let mut payload = payload_of(&buf);
+        let count_at = chunk_list_at + 5;
+        payload[count_at..count_at + 4].copy_from_slice(&(chunk_list_size - 9).to_le_bytes());
+        assert!(process_manifest_data(with_payload(&buf, &payload)).is_err());
+
+        // A file list claiming a file per byte, whose first name is already
+        // unreadable, fails having allocated about its own size rather than
+        // a million empty files.
+        let body_size = MAX_ARRAY_LEN as usize;
+        let mut section = ((body_size + 9) as u32).to_le_bytes().to_vec();
+        section.push(0);
+        section.extend_from_slice(&(body_size as u32).to_le_bytes());
+        section.resize(section.len() + body_size, 0x7f);
+        let chunk_list = ChunkDataList::default();
+        let (result, allocated) = allocated_by(|| {
+            let mut report = ParseReport::default();
+            FileManifestList::read_with(&mut Cursor::new(&section), &chunk_list, &StringPolicy::default(), &mut report)
+        });
+        assert!(result.is_err());
+        assert!(allocated < 4 * section.len(), "{} bytes allocated for a {} byte section", allocated, section.len());
+    }
+
+    #[test]
+    fn test_parse_deadline() {
+        use crate::hooks::Section;
+        use std::ops::ControlFlow;
+        use std::time::{Duration, Instant};
+
+        let data = fs::read("test-manifests/valid-small.manifest").unwrap();
+        let expired = ParseOptions {
+            deadline: Some(Instant::now()),
+            ..ParseOptions::default()
+        };
+        match parse_with_report(&data, &expired) {
+            Err(ManifestError::Timeout { section, partial }) => {
+                assert_eq!(section, Section::Payload);
+                let partial = partial.unwrap();
+                assert_eq!(partial.header.version, load("test-manifests/valid-small.manifest").unwrap().header.version);
+                assert!(partial.meta.is_none() && partial.chunk_list.is_none());
+            }
+            other => panic!("expected a timeout, got {:?}", other.map(|_| ())),
+        }
+
+        // The deadline passing while a section is read is caught inside its
+        // element loop, keeping the sections finished before it.
+        struct StallAt(Section, Duration);
+        impl ParserHooks for StallAt {
+            fn on_section_start(&mut self, section: Section, _offset: u64) -> ControlFlow<()> {
+                if section == self.0 {
+                    std::thread::sleep(self.1);
+                }
+                ControlFlow::Continue(())
+            }
+        }
+        let options = ParseOptions::default().with_timeout(Duration::from_millis(200));
+        let mut hooks = StallAt(Section::FileList, Duration::from_millis(400));
+        match parse_with_hooks(&data, &options, &mut hooks) {
+            Err(ManifestError::Timeout { section, partial }) => {
+                assert_eq!(section, Section::FileList);
+                let partial = partial.unwrap();
+                assert!(partial.meta.is_some() && partial.chunk_list.is_some());
+                assert!(partial.file_list.is_none());
+            }
+            other => panic!("expected a timeout, got {:?}", other.map(|_| ())),
+        }
+
+        let json = fs::read("test-manifests/valid-json-format.manifest").unwrap();
+        assert!(matches!(
+            parse_with_report(&json, &expired),
+            Err(ManifestError::Timeout { section: Section::Json, partial: None })
+        ));
+
+        // A generous deadline changes nothing.
+        let options = ParseOptions::default().with_timeout(Duration::from_secs(600));
+        let (manifest, _) = parse_with_report(&data, &options).unwrap();
+        assert_eq!(
+            serde_json::to_value(&manifest).unwrap(),
+            serde_json::to_value(process_manifest_data(data).unwrap()).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_trusted_fast_path_matches_regular_parse() {
+        let strict = ParseOptions {
+            strings: StringPolicy::strict(),
+            ..ParseOptions::default()
+        };
+        let trusted = ParseOptions {
+            trusted: true,
+            ..strict.clone()
+        };
+        let paths = [
+            "test-manifests/valid-small.manifest",
+            "test-manifests/truncated-recoverable.manifest",
+            "test-manifests/fixtures/minimal.manifest",
+            "test-manifests/fixtures/uncompressed-v2.manifest",
+            "test-manifests/fixtures/corrupt-chunk-count.manifest",
+        ];
+        for path in paths {
+            let data = fs::read(path).unwrap();
+            match (parse_with_report(&data, &strict), parse_with_report(&data, &trusted)) {
+                (Ok((regular, regular_report)), Ok((fast, fast_report))) => {
+                    assert_eq!(serde_json::to_value(&regular).unwrap(), serde_json::to_value(&fast).unwrap(), "{}", path);
+                    assert_eq!(regular_report, fast_report, "{}", path);
+                    let parents = |m: &Manifest| -> Vec<Option<String>> {
+                        m.file_list.iter()
+                            .flat_map(|l| &l.file_manifest_list)
+                            .flat_map(|f| &f.chunk_parts)
+                            .map(|p| p.chunk.as_ref().map(|c| c.guid.clone()))
+                            .collect()
+                    };
+                    assert_eq!(parents(&regular), parents(&fast), "{}", path);
+                }
+                (Err(regular), Err(fast)) => assert_eq!(regular.to_string(), fast.to_string(), "{}", path),
+                (regular, fast) => panic!("{}: {:?} vs {:?}", path, regular.map(|_| ()), fast.map(|_| ())),
+            }
+        }
+
+        // A well-formed manifest is read by the fast path itself...
+        let data = fs::read("test-manifests/valid-small.manifest").unwrap();
+        let payload = payload_of(&data);
+        let meta_size = u32::from_le_bytes(payload[..4].try_into().unwrap()) as usize;
+        let limits = ParseLimits::default();
+        let (chunk_list, end) = parser::fast::read_chunk_list(&payload, meta_size, &limits, None).unwrap();
+        let policy = StringPolicy::strict();
+        assert!(parser::fast::read_file_list(&payload, end, &chunk_list, &policy, &limits, None).is_some());
+
+        // Limits hold on the fast path too, and strict mode keeps it.
+        let few_files = ParseLimits {
+            max_files: 1,
+            ..ParseLimits::default()
+        };
+        assert!(parser::fast::read_file_list(&payload, end, &chunk_list, &policy, &few_files, None).is_none());
+        let limited = |options: &ParseOptions| ParseOptions {
+            limits: few_files,
+            ..options.clone()
+        };
+        let regular = parse_with_report(&data, &limited(&strict)).unwrap_err();
+        assert_eq!(parse_with_report(&data, &limited(&trusted)).unwrap_err().to_string(), regular.to_string());
+        let strict_trusted = ParseOptions {
+            strict: true,
+            ..trusted.clone()
+        };
+        let (manifest, _) = parse_with_report(&data, &strict_trusted).unwrap();
+        assert_eq!(
+            serde_json::to_value(&manifest).unwrap(),
+            serde_json::to_value(parse_with_report(&data, &strict).unwrap().0).unwrap()
+        );
+
+        // ...but not a section with bytes past the known fields.
+        let mut grown = payload[..end].to_vec();
+        let chunk_list_size = u32::from_le_bytes(grown[meta_size..meta_size + 4].try_into().unwrap());
+        grown[meta_size..meta_size + 4].copy_from_slice(&(chunk_list_size + 2).to_le_bytes());
+        grown.extend_from_slice(&[0xAB, 0xCD]);
+        assert!(parser::fast::read_chunk_list(&grown, meta_size, &limits, None).is_none());
+        let data = with_payload(&data, &[grown, payload[end..].to_vec()].concat());
+        let (manifest, _) = parse_with_report(&data, &trusted).unwrap();
+        assert_eq!(manifest.chunk_list.unwrap().extra_data.as_deref(), Some("abcd"));
+    }
+
+    #[test]
+    fn test_prerequisites() {
+        use crate::prereq::{PrereqInfo, PrereqKind};
+
+        let manifest = load("test-manifests/fixtures/minimal.manifest").unwrap();
+        let mut meta = manifest.meta.unwrap();
+        let prerequisites = meta.prerequisites();
+        assert_eq!(prerequisites.iter().map(|p| p.id.as_str()).collect::<Vec<_>>(), ["prereq-a", "prereq-b"]);
+        assert!(prerequisites.iter().all(|p| p.info.kind == PrereqKind::Unknown));
+        assert_eq!(prerequisites[0].info.name, "Fixture Prerequisites");
+
+        meta.prereq_path = "Engine\\Extras\\Redist\\en-us\\UEPrereqSetup_x64.exe".to_string();
+        assert!(meta.prerequisites().iter().all(|p| p.info.kind == PrereqKind::UnrealPrerequisites));
+        meta.prereq_path = "_CommonRedist/vcredist/2022/VC_redist.x64.exe".to_string();
+        let vc = &meta.prerequisites()[0].info;
+        assert_eq!(vc.kind, PrereqKind::VcRedist);
+        assert_eq!(vc.installer_url.as_deref(), Some("https://aka.ms/vs/17/release/vc_redist.x64.exe"));
+
+        // Catalogued IDs take precedence over the installer.
+        let mut registry = PrereqRegistry::builtin();
+        registry.insert("PREREQ-B", PrereqInfo::new(PrereqKind::DirectX, "DirectX", None));
+        let kinds: Vec<PrereqKind> = registry.resolve(&meta).iter().map(|p| p.info.kind).collect();
+        assert_eq!(kinds, [PrereqKind::VcRedist, PrereqKind::DirectX]);
+
+        // JSON manifests keep their prerequisites.
+        let json = fs::read_to_string("test-manifests/valid-json-format.manifest").unwrap();
+        let json = json
+            .replacen("\"PrereqIds\":[]", "\"PrereqIds\":[\"dx\"]", 1)
+            .replacen("\"PrereqPath\":\"\"", "\"PrereqPath\":\"Redist/DXSETUP.exe\"", 1);
+        let meta = process_manifest_data(json.into_bytes()).unwrap().meta.unwrap();
+        assert_eq!(meta.prerequisites()[0].info.kind, PrereqKind::DirectX);
+    }
+
+    #[test]
+    fn test_byte_trace() {
+        assert_eq!(ByteTrace::parse("FULL"), Some(ByteTrace::Full));
+        assert_eq!(ByteTrace::parse("1"), Some(ByteTrace::Full));
+        assert_eq!(ByteTrace::parse(" redacted "), Some(ByteTrace::Redacted));
+        assert_eq!(ByteTrace::parse(""), Some(ByteTrace::Off));
+        assert_eq!(ByteTrace::parse("verbose"), None);
+
+        // Dumps never change what is parsed.
+        let buf = std::fs::read("test-manifests/valid-small.manifest").unwrap();
+        let plain = parse_with_report(&buf, &ParseOptions::default()).unwrap().0;
+        for trace in [ByteTrace::Off, ByteTrace::Redacted, ByteTrace::Full] {
+            let options = ParseOptions {
+                trace_bytes: Some(trace),
+                ..ParseOptions::default()
+            };
+            let (manifest, _) = parse_with_report(&buf, &options).unwrap();
+            assert_eq!(serde_json::to_value(&manifest).unwrap(), serde_json::to_value(&plain).unwrap());
+        }
+    }
+
+    #[test]
+    fn test_dto_v1_matches_parsed_manifest() {
+        for path in ["test-manifests/valid-small.manifest", "test-manifests/valid-json-format.manifest"] {
+            let manifest = load(path).unwrap();
+            let dto = dto::v1::Manifest::from(&manifest);
+            assert_eq!(serde_json::to_value(&dto).unwrap(), serde_json::to_value(&manifest).unwrap(), "{}", path);
+
+            // Back and forth again loses nothing the contract holds.
+            let round_trip = dto::v1::Manifest::from(Manifest::from(dto.clone()));
+            assert_eq!(round_trip, dto, "{}", path);
+            let json = serde_json::to_value(&dto).unwrap();
+            let reparsed: dto::v1::Manifest = serde_json::from_value(json.clone()).unwrap();
+            assert_eq!(serde_json::to_value(&reparsed).unwrap(), json, "{}", path);
+        }
+
+        // NUL padding never reaches the contract.
+        let raw = ParseOptions {
+            strings: StringPolicy::RAW,
+            ..ParseOptions::default()
+        };
+        let manifest = load_with_options("test-manifests/valid-small.manifest", &raw).unwrap();
+        let dto = dto::v1::Manifest::from(manifest);
+        let files = &dto.file_list.unwrap().file_manifest_list;
+        assert!(files.iter().all(|f| !f.filename.contains('\0')));
+        assert!(!dto.meta.unwrap().app_name.contains('\0'));
+    }
+
+    #[cfg(feature = "archives")]
+    #[test]
+    fn test_archives() {
+        use archive::{visit_archive, ArchiveKind};
+        use std::io::Write;
+
+        let manifest = std::fs::read("test-manifests/valid-small.manifest").unwrap();
+        let json = std::fs::read("test-manifests/valid-json-format.manifest").unwrap();
+        let entries: [(&str, &[u8]); 4] = [
+            ("dump/a.manifest", &manifest),
+            ("dump/readme.txt", b"not a manifest"),
+            ("dump/nested/B.MANIFEST", &json),
+            ("dump/broken.manifest", &manifest[..100]),
+        ];
+
+        let mut zip = zip::ZipWriter::new(Cursor::new(Vec::new()));
+        let mut tar = tar::Builder::new(flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::fast()));
+        for (name, data) in entries {
+            zip.start_file(name, zip::write::SimpleFileOptions::default()).unwrap();
+            zip.write_all(data).unwrap();
+            let mut header = tar::Header::new_gnu();
+            header.set_size(data.len() as u64);
+            header.set_mode(0o644);
+            tar.append_data(&mut header, name, data).unwrap();
+        }
+        let zip = zip.finish().unwrap().into_inner();
+        let tar_gz = tar.into_inner().unwrap().finish().unwrap();
+        assert_eq!(ArchiveKind::detect(&zip), Some(ArchiveKind::Zip));
+        assert_eq!(ArchiveKind::detect(&tar_gz), Some(ArchiveKind::TarGz));
+
+        let expected = load("test-manifests/valid-small.manifest").unwrap();
+        for archive in [zip, tar_gz] {
+            let mut found = Vec::new();
+            visit_archive(Cursor::new(archive), &ParseOptions::default(), |entry| {
+                found.push(entry);
+                ControlFlow::Continue(())
+            })
+            .unwrap();
+            let names: Vec<&str> = found.iter().map(|e| e.name.as_str()).collect();
+            assert_eq!(names, ["dump/a.manifest", "dump/nested/B.MANIFEST", "dump/broken.manifest"]);
+            let first = found[0].manifest.as_ref().unwrap();
+            assert_eq!(serde_json::to_value(first).unwrap(), serde_json::to_value(&expected).unwrap());
+            assert!(found[1].manifest.is_ok());
+            assert!(found[2].manifest.is_err());
+        }
+
+        let err = visit_archive(Cursor::new(manifest), &ParseOptions::default(), |_| ControlFlow::Continue(()));
+        assert!(matches!(err, Err(ManifestError::Archive(_))));
+    }
+
+    #[test]
+    fn test_chunk_groups() {
+        for path in ["test-manifests/valid-small.manifest", "test-manifests/valid-json-format.manifest"] {
+            let mut manifest = load(path).unwrap();
+            assert_eq!(manifest.check_chunk_groups(), [], "{}", path);
+
+            let chunk = &mut manifest.chunk_list.as_mut().unwrap().elements[1];
+            let expected = chunk.group;
+            assert_eq!(Chunk::compute_group(&chunk.guid_epic_format()), Some(expected));
+            chunk.group = (expected + 1) % 100;
+            let guid = chunk.guid.clone();
+            assert_eq!(
+                manifest.check_chunk_groups(),
+                [validate::GroupMismatch {
+                    chunk_index: 1,
+                    guid,
+                    stored: (expected + 1) % 100,
+                    expected,
+                }]
+            );
+        }
+        assert_eq!(Chunk::compute_group("not a guid"), None);
+    }
+
+    #[test]
+    fn test_json_chunk_sizes() {
+        let buf = fs::read("test-manifests/valid-json-format.manifest").unwrap();
+        let manifest = process_manifest_data(buf.clone()).unwrap();
+        let chunk_list = manifest.chunk_list.unwrap();
+        assert_eq!(chunk_list.synthetic, ["window_size"]);
+        // ChunkFilesizeList stores "046044008000000000000000" for this chunk.
+        let chunk = &chunk_list.elements[chunk_list.chunk_lookup["4a865a33-515a-ee4a-89ee-1da254b2e66c"] as usize];
+        assert_eq!(chunk.file_size, (46 + 44 * 256 + 8 * 65536).to_string());
+        assert_eq!(chunk.window_size, format::CHUNK_WINDOW_SIZE);
+
+        let options = ParseOptions {
+            json: JsonConversion {
+                window_size: 512 * 1024,
+                ..JsonConversion::default()
+            },
+            ..ParseOptions::default()
+        };
+        let (manifest, _) = parse_with_report(&buf, &options).unwrap();
+        assert!(manifest.chunk_list.unwrap().elements.iter().all(|c| c.window_size == 512 * 1024));
+
+        // Without the list, file sizes are placeholders too.
+        let mut json: serde_json::Value = serde_json::from_slice(&buf).unwrap();
+        json.as_object_mut().unwrap().remove("ChunkFilesizeList");
+        let manifest = process_manifest_data(serde_json::to_vec(&json).unwrap()).unwrap();
+        let chunk_list = manifest.chunk_list.unwrap();
+        assert_eq!(chunk_list.synthetic, ["window_size", "file_size"]);
+        assert!(chunk_list.elements.iter().all(|c| c.file_size == "1048576"));
+    }
+
+    #[test]
+    fn test_json_synthetic_fields() {
+        let buf = fs::read("test-manifests/valid-json-format.manifest").unwrap();
+        let manifest = process_manifest_data(buf.clone()).unwrap();
+        let fields = manifest.synthetic_fields();
+        assert!(fields.contains(&"header.sha1_hash".to_string()));
+        assert!(fields.contains(&"chunk_list.window_size".to_string()));
+        // The hash and SHA lists are stored, so chunks carry real values.
+        assert!(!fields.iter().any(|f| f.ends_with(".hash") || f.ends_with(".sha_hash")));
+        let chunk_list = manifest.chunk_list.as_ref().unwrap();
+        let chunk = &chunk_list.elements[chunk_list.chunk_lookup["4a865a33-515a-ee4a-89ee-1da254b2e66c"] as usize];
+        assert_eq!(chunk_list.stored_sha_hash(chunk), Some("15f397e514bb5b275cc3d2eff71872539cadc629"));
+        let dto = dto::v1::Manifest::from(&manifest);
+        assert_eq!(dto.header.synthetic.as_deref(), Some(&manifest.header.synthetic[..]));
+
+        // Without the SHA list, nothing is verified against made-up hashes.
+        let mut json: serde_json::Value = serde_json::from_slice(&buf).unwrap();
+        json.as_object_mut().unwrap().remove("ChunkShaList");
+        let manifest = process_manifest_data(serde_json::to_vec(&json).unwrap()).unwrap();
+        assert!(manifest.synthetic_fields().contains(&"chunk_list.sha_hash".to_string()));
+        let chunk_list = manifest.chunk_list.unwrap();
+        assert!(chunk_list.elements.iter().all(|c| chunk_list.stored_sha_hash(c).is_none()));
+
+        // Binary manifests make nothing up.
+        assert!(load("test-manifests/valid-small.manifest").unwrap().synthetic_fields().is_empty());
+    }
+
+    #[test]
+    fn test_json_field_errors() {
+        let buf = fs::read("test-manifests/valid-json-format.manifest").unwrap();
+        let mut json: serde_json::Value = serde_json::from_slice(&buf).unwrap();
+        json["FileManifestList"][1]["FileChunkParts"][0]["Size"] = 5.into();
+        json["FileManifestList"][3].as_object_mut().unwrap().remove("FileHash");
+        let err = process_manifest_data(serde_json::to_vec(&json).unwrap()).unwrap_err();
+        let ManifestError::JsonFields(errors) = &err else { panic!("unexpected error: {}", err) };
+        let paths: Vec<_> = errors.iter().map(|e| (e.path.as_str(), e.file_index)).collect();
+        assert_eq!(paths, [("FileManifestList[1].FileChunkParts[0].Size", Some(1)), ("FileManifestList[3]", Some(3))]);
+        assert!(errors[1].message.contains("missing field `FileHash`"));
+        assert!(err.to_string().contains("FileManifestList[1].FileChunkParts[0].Size: invalid type"));
+
+        // Top-level problems are reported next to the file entries'.
+        json["AppID"] = serde_json::Value::Null;
+        let Err(ManifestError::JsonFields(errors)) = JsonManifest::from_str(&json.to_string()) else { panic!() };
+        assert_eq!(errors.len(), 3);
+        assert_eq!((errors[0].path.as_str(), errors[0].file_index), ("AppID", None));
+
+        // Syntax errors still carry the position.
+        let err = JsonManifest::from_str("{\"ManifestFileVersion\": }").unwrap_err();
+        assert!(err.to_string().contains("line 1 column"), "{}", err);
+    }
+
+    #[test]
+    fn test_json_extra_fields() {
+        let buf = fs::read("test-manifests/valid-json-format.manifest").unwrap();
+        let mut json: serde_json::Value = serde_json::from_slice(&buf).unwrap();
+        json["FileManifestList"][0]["InstallTags"] = serde_json::json!(["tag"]);
+        json["FileManifestList"][0]["FileChunkParts"][0]["Note"] = "kept".into();
+        let manifest = JsonManifest::from_str(&json.to_string()).unwrap();
+        assert!(manifest.extra.contains_key("DataGroupList"));
+        assert_eq!(manifest.file_manifest_list[0].extra["InstallTags"], serde_json::json!(["tag"]));
+        assert_eq!(manifest.file_manifest_list[0].file_chunk_parts[0].extra["Note"], "kept");
+        // Nothing is lost on the way back.
+        assert_eq!(serde_json::to_value(&manifest).unwrap(), json);
+    }
+
+    #[test]
+    fn test_json_flexible_types() {
+        let buf = fs::read("test-manifests/valid-json-format.manifest").unwrap();
+        let mut json: serde_json::Value = serde_json::from_slice(&buf).unwrap();
+        json["AppID"] = 42.into();
+        json["ManifestFileVersion"] = 13.into();
+        json["bIsFileData"] = 1.into();
+        json["FileManifestList"][0]["bIsUnixExecutable"] = 0.into();
+        let manifest = JsonManifest::from_str(&json.to_string()).unwrap();
+        assert_eq!((manifest.app_id.as_str(), manifest.manifest_file_version.as_str()), ("42", "13"));
+        assert!(manifest.is_file_data);
+        assert_eq!(manifest.file_manifest_list[0].is_unix_executable, Some(false));
+        let manifest = manifest.to_manifest().unwrap();
+        assert_eq!(manifest.meta.unwrap().app_id, 42);
+
+        json["bIsFileData"] = 2.into();
+        let Err(ManifestError::JsonFields(errors)) = JsonManifest::from_str(&json.to_string()) else { panic!() };
+        assert_eq!(errors[0].path, "bIsFileData");
+    }
+
+    #[test]
+    fn test_json_invalid_guids() {
+        use types::json_manifest::InvalidGuids;
+
+        let buf = fs::read("test-manifests/valid-json-format.manifest").unwrap();
+        let mut json: serde_json::Value = serde_json::from_slice(&buf).unwrap();
+        let file_count = json["FileManifestList"].as_array().unwrap().len();
+        json["FileManifestList"][2]["FileChunkParts"][0]["Guid"] = "not-a-guid".into();
+        json["ChunkShaList"].as_object_mut().unwrap().insert("XYZ".into(), "00".repeat(20).into());
+        let buf = serde_json::to_vec(&json).unwrap();
+        let err = process_manifest_data(buf.clone()).unwrap_err();
+        assert!(err.to_string().contains("Invalid GUID"), "{}", err);
+
+        let options = |invalid_guids| ParseOptions {
+            json: JsonConversion {
+                invalid_guids,
+                ..JsonConversion::default()
+            },
+            ..ParseOptions::default()
+        };
+        let (manifest, report) = parse_with_report(&buf, &options(InvalidGuids::Skip)).unwrap();
+        let files = manifest.file_list.unwrap().file_manifest_list;
+        assert_eq!(files.len(), file_count - 1);
+        let skipped = json["FileManifestList"][2]["Filename"].as_str().unwrap();
+        assert!(files.iter().all(|f| f.filename != skipped));
+        let issues: Vec<_> = report.invalid_guids.iter().map(|i| (i.path.as_str(), i.guid.as_str())).collect();
+        assert_eq!(issues, [("FileManifestList[2].FileChunkParts[0].Guid", "not-a-guid"), ("ChunkShaList", "XYZ")]);
+        assert!(report.invalid_guids.iter().all(|i| i.quarantined.is_none()));
+
+        let (_, report) = parse_with_report(&buf, &options(InvalidGuids::Quarantine)).unwrap();
+        let quarantined = report.invalid_guids[0].quarantined.as_ref().unwrap();
+        assert_eq!(quarantined.filename, skipped);
+        assert_eq!(quarantined.file_chunk_parts[0].guid, "not-a-guid");
+    }
+
+    #[test]
+    fn test_dto_v1_json_manifest() {
+        let buf = fs::read("test-manifests/valid-json-format.manifest").unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&buf).unwrap();
+        let dto = dto::v1::JsonManifest::from(JsonManifest::from_slice(&buf).unwrap());
+        // Values are surfaced as stored, not converted.
+        assert_eq!(dto.app_id, json["AppID"]);
+        let part = &dto.file_manifest_list[0].file_chunk_parts[0];
+        assert_eq!(part.guid, json["FileManifestList"][0]["FileChunkParts"][0]["Guid"]);
+        assert_eq!(part.size, json["FileManifestList"][0]["FileChunkParts"][0]["Size"]);
+        assert!(part.extra.is_none());
+        assert_eq!(dto.chunk_sha_list.len(), json["ChunkShaList"].as_object().unwrap().len());
+        assert!(dto.extra.unwrap().contains_key("DataGroupList"));
+    }
+
+    #[test]
+    fn test_cdn_availability_check() {
+        use crate::download::cdn_check::{check_cdn_availability, CdnCheckOptions};
+        use crate::download::downloader::Transport;
+        use crate::download::retry::{FetchError, RetryPolicy};
+        use std::sync::Mutex;
+        use std::time::Duration;
+
+        // HEAD only; chunk 1 is gone and chunk 2's host keeps timing out.
+        struct Cdn {
+            requests: Mutex<Vec<String>>,
+        }
+        impl Transport for Cdn {
+            fn get(&self, url: &str) -> Result<Vec<u8>, FetchError> {
+                panic!("GET {}", url);
+            }
+            fn head(&self, url: &str) -> Result<(), FetchError> {
+                self.requests.lock().unwrap().push(url.to_string());
+                match url {
+                    u if u.contains("0000000000000001_") => Err(FetchError::from_status(u, 404)),
+                    u if u.contains("0000000000000002_") => Err(FetchError::from_status(u, 503)),
+                    _ => Ok(()),
+                }
+            }
+        }
+
+        let chunks: Vec<Chunk> = (0..5u32)
+            .map(|i| Chunk {
+                guid: format!("00000000-0000-0000-0000-0000000000{:02x}", i),
+                hash: format!("{:016X}", i),
+                file_size: "100".to_string(),
+                ..Default::default()
+            })
+            .collect();
+        let manifest = Manifest {
+            chunk_list: Some(ChunkDataList {
+                elements: chunks,
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        let cdn = Cdn { requests: Mutex::default() };
+        let options = CdnCheckOptions {
+            parallelism: Parallelism::with_threads(3),
+            retry: RetryPolicy {
+                initial_backoff: Duration::ZERO,
+                ..Default::default()
+            },
+            sleep: |_| {},
+        };
+        let report = check_cdn_availability(&cdn, &manifest, "https://cdn.example/Cloud/", &options);
+        assert!(!report.is_complete());
+        assert_eq!(report.missing.iter().map(|c| c.index).collect::<Vec<_>>(), [1]);
+        assert_eq!(report.missing[0].guid, "00000000-0000-0000-0000-000000000001");
+        assert!(report.missing[0].error.to_string().contains("HTTP 404"));
+        assert_eq!(report.unchecked.iter().map(|c| c.index).collect::<Vec<_>>(), [2]);
+        assert_eq!(report.available.missing().collect::<Vec<_>>(), [1, 2]);
+        assert_eq!(report.available.summary(&manifest).unwrap().available_bytes, 300);
+
+        // One request per chunk, plus the retries of the transient failure.
+        let requests = cdn.requests.into_inner().unwrap();
+        assert_eq!(requests.len(), 4 + options.retry.max_attempts as usize);
+        assert!(requests.iter().all(|u| u.starts_with("https://cdn.example/Cloud/Chunks")), "{:?}", requests);
+    }
+
+    #[test]
+    fn test_chunk_ordering() {
+        let manifest = load("test-manifests/valid-small.manifest").unwrap();
+        let chunk_list = manifest.chunk_list.as_ref().unwrap();
+        let unique: Vec<&str> = manifest.unique_chunks().map(|c| c.guid.as_str()).collect();
+        let mut first_use = Vec::new();
+        for part in manifest.file_list.iter().flat_map(|f| &f.file_manifest_list).flat_map(|f| &f.chunk_parts) {
+            if !first_use.contains(&part.parent_guid.as_str()) {
+                first_use.push(part.parent_guid.as_str());
+            }
+        }
+        assert_eq!(unique, first_use);
+
+        let download: Vec<&Chunk> = chunk_list.iter_download_order().collect();
+        assert_eq!(download.len(), chunk_list.elements.len());
+        assert!(download.windows(2).all(|w| (w[0].group, &w[0].guid) <= (w[1].group, &w[1].guid)));
+        // The same order whatever format the GUIDs are in.
+        let mut epic = manifest.clone();
+        epic.set_guid_format(GuidFormat::Epic);
+        let epic_order: Vec<String> = epic.chunk_list.as_ref().unwrap().iter_download_order().map(|c| c.hash.clone()).collect();
+        assert_eq!(epic_order, download.iter().map(|c| c.hash.clone()).collect::<Vec<_>>());
+
+        // Converted JSON manifests list chunks in first-reference order.
+        let json = load("test-manifests/valid-json-format.manifest").unwrap();
+        let unique: Vec<&Chunk> = json.unique_chunks().collect();
+        assert_eq!(unique.len(), json.chunk_list.as_ref().unwrap().elements.len());
+        assert!(unique.iter().zip(&json.chunk_list.as_ref().unwrap().elements).all(|(a, b)| a.guid == b.guid));
+    }
+
+    #[test]
+    fn test_parser_registry() {
+        use crate::hooks::ParserHooks;
+        use crate::parsers::{BinaryParser, ManifestParser, ParserRegistry};
+
+        // A binary manifest behind an 8-byte corporate header.
+        struct Wrapped;
+        impl ManifestParser for Wrapped {
+            fn name(&self) -> &str {
+                "wrapped"
+            }
+            fn detect(&self, data: &[u8]) -> bool {
+                data.starts_with(b"CORPMF01")
+            }
+            fn parse(
+                &self,
+                data: &[u8],
+                options: &ParseOptions,
+                report: &mut ParseReport,
+                hooks: &mut dyn ParserHooks,
+            ) -> Result<Manifest, ManifestError> {
+                let mut manifest = BinaryParser.parse(&data[8..], options, report, hooks)?;
+                manifest.custom_fields.entries.push(types::custom_fields::CustomField {
+                    key: "Wrapper".to_string(),
+                    value: "CORPMF01".to_string(),
+                });
+                Ok(manifest)
+            }
+        }
+
+        let mut data = b"CORPMF01".to_vec();
+        data.extend(fs::read("test-manifests/valid-small.manifest").unwrap());
+        assert!(process_manifest_data(data.clone()).is_err());
+
+        let mut registry = ParserRegistry::builtin();
+        registry.register(Wrapped);
+        assert_eq!(registry.names(), ["wrapped", "json", "binary"]);
+        assert_eq!(registry.find(b"{}").map(|p| p.name()), None);
+        ParserRegistry::set_global(registry);
+        let manifest = process_manifest_data(data);
+        ParserRegistry::set_global(ParserRegistry::builtin());
+        let manifest = manifest.unwrap();
+        assert_eq!(manifest.custom_fields.get("Wrapper"), Some("CORPMF01"));
+        assert_eq!(manifest.file_list.unwrap().count, load("test-manifests/valid-small.manifest").unwrap().file_list.unwrap().count);
+    }
+
+    #[test]
+    fn test_fingerprint() {
+        use crate::fingerprint::{fingerprint, fingerprint_hex};
+
+        let binary = fs::read("test-manifests/valid-small.manifest").unwrap();
+        let json = fs::read("test-manifests/valid-json-format.manifest").unwrap();
+        assert_eq!(fingerprint(&binary), fingerprint(&binary.clone()));
+        assert_ne!(fingerprint(&binary), fingerprint(&json));
+        assert_eq!(fingerprint_hex(&binary).len(), 64);
+
+        // Trailing junk after the payload and whitespace around JSON don't count...
+        let mut padded = binary.clone();
+        padded.extend_from_slice(&[0; 16]);
+        assert_eq!(fingerprint(&padded), fingerprint(&binary));
+        let mut spaced = b"\xEF\xBB\xBF\n".to_vec();
+        spaced.extend_from_slice(&json);
+        spaced.extend_from_slice(b"  \n");
+        assert_eq!(fingerprint(&spaced), fingerprint(&json));
+
+        // ...but any byte of the header or payload does.
+        let header_size = u32::from_le_bytes(binary[4..8].try_into().unwrap()) as usize;
+        for at in [20, header_size + 10, binary.len() - 1] {
+            let mut changed = binary.clone();
+            changed[at] ^= 1;
+            assert_ne!(fingerprint(&changed), fingerprint(&binary), "byte {}", at);
+        }
+        assert_ne!(fingerprint(&binary[..binary.len() - 1]), fingerprint(&binary));
+        assert_ne!(fingerprint(b"not a manifest"), fingerprint(b"not a manifest!"));
+        assert_eq!(fingerprint(&binary[..10]), fingerprint(&binary[..10]));
+    }
+
+    #[test]
+    fn test_file_composition() {
+        use crate::export::composition::{composition, file_composition, ChunkRun};
+        use crate::types::chunk::ChunkPart;
+
+        let manifest = load("test-manifests/valid-small.manifest").unwrap();
+        let export = composition(&manifest);
+        let files = &manifest.file_list.as_ref().unwrap().file_manifest_list;
+        assert_eq!(export.files.len(), files.len());
+        assert_eq!(export.chunks.len(), manifest.unique_chunks().count());
+        for (file, encoded) in files.iter().zip(&export.files) {
+            assert_eq!(encoded.size, file.file_size as u64);
+            assert!(encoded.runs.windows(2).all(|w| w[0].chunk != w[1].chunk));
+            let first = file.chunk_parts.first().map(|p| p.parent_guid.as_str());
+            assert_eq!(encoded.runs.first().map(|r| export.chunks[r.chunk as usize].as_str()), first);
+        }
+
+        // Adjacent parts of one chunk merge; empty parts vanish.
+        let part = |guid: &str, size| ChunkPart {
+            parent_guid: guid.to_string(),
+            size,
+            ..ChunkPart::default()
+        };
+        let mut manifest = manifest;
+        let file = &mut manifest.file_list.as_mut().unwrap().file_manifest_list[0];
+        file.chunk_parts = vec![part("a", 10), part("a", 5), part("b", 0), part("b", 7), part("a", 1)];
+        let name = file.filename.trim_end_matches('\0').to_string();
+        let single = file_composition(&manifest, &name).unwrap();
+        assert_eq!(single.chunks, ["a", "b"]);
+        assert_eq!(
+            single.files[0].runs,
+            [
+                ChunkRun { chunk: 0, length: 15 },
+                ChunkRun { chunk: 1, length: 7 },
+                ChunkRun { chunk: 0, length: 1 }
+            ]
+        );
+        assert_eq!(single.files[0].size, 23);
+        assert!(file_composition(&manifest, "missing").is_none());
+        let json = serde_json::to_value(&single.files[0]).unwrap();
+        assert_eq!(json["runs"], serde_json::json!([[0, 15], [1, 7], [0, 1]]));
+    }
+
+    #[test]
+    fn test_manifest_index() {
+        use crate::index::manifests::ManifestIndex;
+        use crate::types::chunk::epic_guid;
+
+        let first = load("test-manifests/valid-small.manifest").unwrap();
+        let files = &first.file_list.as_ref().unwrap().file_manifest_list;
+        let path = files[0].filename.trim_end_matches('\0').to_string();
+        let guid = first.chunk_list.as_ref().unwrap().elements[0].guid.clone();
+
+        // A later build without the first file.
+        let mut second = first.clone();
+        second.meta.as_mut().unwrap().build_id = Some("second".to_string());
+        second.file_list.as_mut().unwrap().file_manifest_list.remove(0);
+
+        let mut index = ManifestIndex::new();
+        let first_id = index.add(&first).build_id.clone();
+        index.add(&second);
+        assert_eq!(index.len(), 2);
+        fn ids(builds: Vec<&crate::index::manifests::IndexedBuild>) -> Vec<&str> {
+            builds.into_iter().map(|b| b.build_id.as_str()).collect()
+        }
+        assert_eq!(ids(index.builds_with_file(&path)), [first_id.as_str()]);
+        assert_eq!(ids(index.builds_with_chunk(&guid)), [first_id.as_str(), "second"]);
+        assert_eq!(index.builds_with_chunk(&epic_guid(&guid)).len(), 2);
+        assert!(index.builds_with_file("missing").is_empty());
+        let under: Vec<_> = index.files_with_prefix("").map(|(p, _)| p.to_string()).collect();
+        assert!(under.windows(2).all(|w| w[0] < w[1]));
+        assert!(under.contains(&path));
+
+        // Round trip, then re-adding and removing keep the postings consistent.
+        let mut index = ManifestIndex::from_json(&index.to_json().unwrap()).unwrap();
+        index.add(&first);
+        assert_eq!(ids(index.builds_with_file(&path)), [first_id.as_str()]);
+        assert_eq!(index.builds()[0].build_id, "second");
+        assert!(index.remove("second"));
+        assert!(!index.remove("second"));
+        assert_eq!(ids(index.builds_with_chunk(&guid)), [first_id.as_str()]);
+
+        let dangling = r#"{"builds":[],"files":{"a":[0]},"chunks":{}}"#;
+        assert!(matches!(ManifestIndex::from_json(dangling), Err(ManifestError::Invalid(_))));
+    }
+
+    #[test]
+    fn test_chunk_bloom() {
+        use crate::index::bloom::{BloomParams, ChunkBloom};
+        use crate::types::chunk::epic_guid;
+
+        let params = BloomParams::for_capacity(2000, 0.01);
+        assert_eq!(params.bits % 64, 0);
+        assert!(params.hashes >= 6);
+
+        // Two builds sharing 1000 of their 1500 chunks.
+        let guids: Vec<String> = (0..2000u32)
+            .map(|i| {
+                let bytes = sha2::Sha256::digest(i.to_le_bytes());
+                uuid::Uuid::from_slice(&bytes[..16]).unwrap().to_string()
+            })
+            .collect();
+        let mut old = ChunkBloom::new(params);
+        let mut new = ChunkBloom::new(params);
+        guids[..1500].iter().for_each(|g| old.insert(g));
+        guids[500..].iter().for_each(|g| new.insert(g));
+        new.download_size = 1500 * 100;
+        assert!(guids[500..].iter().all(|g| new.contains(g)));
+        assert!(new.contains(&epic_guid(&guids[600])));
+        let false_positives = guids[..500].iter().filter(|g| new.contains(g)).count();
+        assert!(false_positives < 25, "{} false positives", false_positives);
+
+        let shared = new.estimate_intersection(&old).unwrap();
+        assert!((shared - 1000.0).abs() < 50.0, "estimated {}", shared);
+        let patch = new.estimate_patch_size(&old).unwrap();
+        assert!((patch as i64 - 500 * 100).abs() < 5000, "estimated {}", patch);
+        assert!(new.estimate_intersection(&ChunkBloom::new(BloomParams::for_capacity(10, 0.01))).is_none());
+
+        let manifest = load("test-manifests/valid-small.manifest").unwrap();
+        let filter = ChunkBloom::from_manifest(&manifest, params);
+        assert_eq!(filter.download_size, manifest.download_size());
+        assert_eq!(filter.estimate_patch_size(&filter), Some(0));
+        let restored = ChunkBloom::from_json(&filter.to_json().unwrap()).unwrap();
+        assert_eq!(restored, filter);
+        let guid = &manifest.chunk_list.as_ref().unwrap().elements[0].guid;
+        assert!(restored.contains(guid));
+        let mut broken: serde_json::Value = serde_json::from_str(&filter.to_json().unwrap()).unwrap();
+        broken["params"]["bits"] = 64.into();
+        assert!(ChunkBloom::from_json(&broken.to_string()).is_err());
+    }
+
+    #[test]
+    fn test_chunk_file_header() {
+        use crate::types::chunk::Chunk;
+        use crate::types::chunk_file::ChunkFileHeader;
+        use std::io::Read;
+
+        let guid = "1f6b7c2e-0a4d-4d8e-9b3a-6c1e2f3a4b5c";
+        let data = vec![7u8; 3000];
+        let file = chunk_file_bytes(guid, &data);
+
+        // A plain reader, left at the payload.
+        let mut rdr = &file[..];
+        let header = ChunkFileHeader::read(&mut rdr).unwrap();
+        assert_eq!(header.guid, guid);
+        assert_eq!(header.version, 3);
+        assert_eq!(header.data_size_uncompressed, 3000);
+        assert_eq!(header.file_size(), file.len() as u64);
+        let mut payload = Vec::new();
+        rdr.read_to_end(&mut payload).unwrap();
+        assert_eq!(payload.len(), header.data_size_compressed as usize);
+
+        // Prefixes: too short until the whole header is there.
+        let header_size = format::CHUNK_HEADER_SIZE_V3 as usize;
+        for len in [0, 3, 41, header_size - 1] {
+            assert!(ChunkFileHeader::from_prefix(&file[..len]).unwrap().is_none(), "{} bytes", len);
+        }
+        assert_eq!(ChunkFileHeader::from_prefix(&file[..header_size]).unwrap(), Some(header.clone()));
+        assert!(ChunkFileHeader::from_prefix(b"nope").is_err());
+
+        // Fields appended by a newer header version are skipped.
+        let mut longer = file.clone();
+        longer[8..12].copy_from_slice(&(header_size as u32 + 4).to_le_bytes());
+        longer.splice(header_size..header_size, [0xAA; 4]);
+        let mut rdr = &longer[..];
+        ChunkFileHeader::read(&mut rdr).unwrap();
+        assert_eq!(rdr, &payload[..]);
+        let mut shorter = file.clone();
+        shorter[8..12].copy_from_slice(&41u32.to_le_bytes());
+        assert!(matches!(ChunkFileHeader::read(&shorter[..]), Err(ManifestError::Invalid(_))));
+
+        let sha = header.sha_hash.clone().unwrap();
+        let chunk = Chunk {
+            guid: guid.to_string(),
+            file_size: file.len().to_string(),
+            ..Chunk::default()
+        };
+        header.verify_identity(&chunk, Some(&sha)).unwrap();
+        header.verify_identity(&Chunk { guid: types::chunk::epic_guid(guid), ..chunk.clone() }, None).unwrap();
+        let other = Chunk { guid: "00000000-0000-0000-0000-000000000001".to_string(), ..chunk.clone() };
+        assert!(header.verify_identity(&other, None).is_err());
+        assert!(header.verify_identity(&Chunk { file_size: "10".to_string(), ..chunk.clone() }, None).is_err());
+        assert!(header.verify_identity(&chunk, Some(&"ab".repeat(20))).is_err());
+    }
+
+    #[test]
+    fn test_encrypted_chunks() {
+        use crate::crypto::AesKey;
+        use crate::download::downloader::ChunkDownloader;
+        use crate::download::retry::FetchError;
+        use crate::provider::ChunkProvider;
+        use crate::store::chunks::ChunkStore;
+        use crate::types::chunk_file::{decode_chunk_file, decode_chunk_file_with_key};
+        use aes::cipher::{generic_array::GenericArray, BlockEncrypt, KeyInit};
+
+        let key_hex = "0x".to_string() + &"5a".repeat(32);
+        let key = AesKey::from_hex(&key_hex).unwrap();
+        assert_eq!(format!("{:?}", key), "AesKey(..)");
+        assert!(AesKey::from_hex("abcd").is_err());
+
+        // Epic's order: compress, then encrypt the padded result.
+        let encrypt = |file: &[u8]| {
+            let header_size = format::CHUNK_HEADER_SIZE_V3 as usize;
+            let mut payload = file[header_size..].to_vec();
+            payload.resize(payload.len().div_ceil(16) * 16, 0);
+            let cipher = aes::Aes256::new(GenericArray::from_slice(&[0x5a; 32]));
+            for block in payload.chunks_exact_mut(16) {
+                cipher.encrypt_block(GenericArray::from_mut_slice(block));
+            }
+            let mut out = file[..header_size].to_vec();
+            out[12..16].copy_from_slice(&(payload.len() as u32).to_le_bytes());
+            out[40] |= types::flags::STORED_ENCRYPTED;
+            out.extend_from_slice(&payload);
+            out
+        };
+        let guid = "3c1e2f3a-4b5c-4d8e-9b3a-1f6b7c2e0a4d";
+        let data: Vec<u8> = (0..5000u32).map(|i| (i % 251) as u8).collect();
+        let file = encrypt(&chunk_file_bytes(guid, &data));
+
+        assert!(matches!(decode_chunk_file(&file), Err(ManifestError::EncryptedChunk(g)) if g == guid));
+        let (header, decoded) = decode_chunk_file_with_key(&file, Some(&key)).unwrap();
+        assert!(header.is_encrypted() && header.is_compressed());
+        assert_eq!(decoded, data);
+        let wrong = AesKey::new([1; 32]);
+        assert!(decode_chunk_file_with_key(&file, Some(&wrong)).is_err());
+
+        let root = std::env::temp_dir().join(format!("egdata-encrypted-{}", uuid::Uuid::new_v4()));
+        let store = ChunkStore::open(&root, 21).unwrap();
+        let chunk = Chunk {
+            guid: guid.to_string(),
+            ..Default::default()
+        };
+        store.add(&chunk, &file).unwrap();
+        assert!(store.chunk_data(guid).is_err());
+        let store = store.with_key(key.clone());
+        assert_eq!(store.chunk_data(guid).unwrap(), data);
+        fs::remove_dir_all(&root).unwrap();
+
+        let manifest = Manifest {
+            chunk_list: Some(ChunkDataList {
+                elements: vec![chunk],
+                chunk_lookup: [(guid.to_string(), 0)].into_iter().collect(),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        let transport = |_: &str| Ok::<_, FetchError>(file.clone());
+        let downloader = ChunkDownloader::new(transport, "https://a.example", manifest).with_key(key);
+        assert_eq!(downloader.fetch(guid).unwrap(), data);
+    }
+
+    #[test]
+    fn test_manifest_writer() {
+        let mut manifest = load("test-manifests/valid-small.manifest").unwrap();
+
+        // Edit, then repackage: sizes and the SHA-1 follow the new contents.
+        manifest.meta.as_mut().unwrap().build_version = "1.5.1-patched".to_string();
+        let file_list = manifest.file_list.as_mut().unwrap();
+        file_list.file_manifest_list.remove(0);
+        file_list.file_manifest_list[0].filename = "Renamed/Ünïcode.bin".to_string();
+        let bytes = manifest.to_bytes().unwrap();
+        let (reparsed, report) = parse_with_report(&bytes, &ParseOptions::default()).unwrap();
+        assert_eq!(report.payload_hash, PayloadHashCheck::Matched);
+        assert_eq!(reparsed.meta.as_ref().unwrap().build_version, "1.5.1-patched");
+        let files = &reparsed.file_list.as_ref().unwrap().file_manifest_list;
+        assert_eq!(files.len(), manifest.file_list.as_ref().unwrap().file_manifest_list.len());
+        assert_eq!(files[0].filename, "Renamed/Ünïcode.bin");
+        assert_eq!(reparsed.install_size(), manifest.install_size());
+
+        let mut written = Vec::new();
+        manifest.write_binary(&mut written).unwrap();
+        assert_eq!(written, bytes);
+
+        let stored = ManifestWriter::new().uncompressed().to_bytes(&manifest).unwrap();
+        assert_eq!(stored[36], 0);
+        let header_size = format::MANIFEST_HEADER_SIZE_V2 as usize;
+        assert_eq!(&stored[8..12], &((stored.len() - header_size) as u32).to_le_bytes());
+        let (unpacked, report) = parse_with_report(&stored, &ParseOptions::default()).unwrap();
+        assert_eq!(report.payload_hash, PayloadHashCheck::Matched);
+        assert_eq!(unpacked.to_bytes().unwrap(), bytes);
+        let fast = ManifestWriter::new().with_compression_level(1).to_bytes(&manifest).unwrap();
+        assert_eq!(process_manifest_data(fast).unwrap().to_bytes().unwrap(), bytes);
+
+        manifest.chunk_list = None;
+        assert!(manifest.to_bytes().is_err());
+    }
+
+    #[test]
+    fn test_to_json_manifest() {
+        // Epic's own layout comes back value for value, plus the data groups
+        // the reader derives and an empty CustomFields.
+        let buf = fs::read("test-manifests/fixtures/small.json.manifest").unwrap();
+        let original: serde_json::Value = serde_json::from_slice(&buf).unwrap();
+        let manifest = process_manifest_data(buf).unwrap();
+        let mut exported = serde_json::to_value(manifest.to_json_manifest().unwrap()).unwrap();
+        assert!(exported.as_object_mut().unwrap().remove("DataGroupList").is_some());
+        assert_eq!(exported.as_object_mut().unwrap().remove("CustomFields"), Some(serde_json::json!({})));
+        assert_eq!(exported, original);
+
+        // Binary manifests survive the trip through JSON.
+        let binary = load("test-manifests/valid-small.manifest").unwrap();
+        let json = binary.to_json_manifest().unwrap();
+        assert_eq!(json.manifest_file_version, format!("{:03}000000000", binary.header.version));
+        let back = JsonManifest::from_str(&serde_json::to_string(&json).unwrap())
+            .unwrap()
+            .to_manifest()
+            .unwrap();
+        assert_eq!(back.header.version, binary.header.version);
+        assert!(back.chunk_list.as_ref().unwrap().synthetic.iter().all(|f| f == "window_size"));
+        let chunks = |m: &Manifest| {
+            let mut chunks: Vec<_> = m.chunk_list.as_ref().unwrap().elements.iter()
+                .map(|c| (c.guid.clone(), c.hash.clone(), c.sha_hash.clone(), c.group, c.file_size.clone()))
+                .collect();
+            chunks.sort();
+            chunks
+        };
+        assert_eq!(chunks(&back), chunks(&binary));
+        let files = |m: &Manifest| -> Vec<_> {
+            m.file_list.as_ref().unwrap().file_manifest_list.iter()
+                .map(|f| {
+                    let parts: Vec<_> = f.chunk_parts.iter().map(|p| (p.parent_guid.clone(), p.offset, p.size)).collect();
+                    (f.filename.trim_end_matches('\0').to_string(), f.sha_hash.clone(), f.file_size, parts)
+                })
+                .collect()
+        };
+        assert_eq!(files(&back), files(&binary));
+    }
+
+    #[test]
+    fn test_meta_failure() {
+        let manifest = load("test-manifests/valid-small.manifest").unwrap();
+        let buf = manifest.to_bytes().unwrap();
+        let (_, report) = parse_with_report(&buf, &ParseOptions::default()).unwrap();
+        assert_eq!(report.meta, MetaCheck::Parsed);
+        let json = fs::read("test-manifests/fixtures/small.json.manifest").unwrap();
+        assert_eq!(parse_with_report(&json, &ParseOptions::default()).unwrap().1.meta, MetaCheck::NotRead);
+
+        // An app name longer than the section; the rest still parses.
+        let mut payload = payload_of(&buf);
+        payload[14..18].copy_from_slice(&i32::MAX.to_le_bytes());
+        let broken = with_payload(&buf, &payload);
+        let (parsed, report) = parse_with_report(&broken, &ParseOptions::default()).unwrap();
+        assert!(parsed.meta.is_none());
+        assert_eq!(parsed.file_list.unwrap().count, manifest.file_list.as_ref().unwrap().count);
+        let MetaCheck::Failed { offset, error, resynced_at } = &report.meta else { panic!("{:?}", report.meta) };
+        assert_eq!((*offset, *resynced_at), (0, None));
+        assert!(!error.is_empty());
+
+        let options = ParseOptions {
+            require_meta: true,
+            ..ParseOptions::default()
+        };
+        let err = parse_with_report(&broken, &options).unwrap_err();
+        assert!(matches!(err, ManifestError::Meta { offset: 0, .. }), "{}", err);
+        assert!(err.to_string().contains(error.as_str()), "{}", err);
+        assert!(parse_with_report(&buf, &options).is_ok());
+    }
+
+    #[test]
+    fn test_meta_resync() {
+        use crate::hooks::{ParseWarning, ParserHooks, Section};
+
+        #[derive(Default)]
+        struct Warnings(Vec<ParseWarning>);
+        impl ParserHooks for Warnings {
+            fn on_warning(&mut self, warning: &ParseWarning) {
+                self.0.push(warning.clone());
+            }
+        }
+
+        let manifest = load("test-manifests/valid-small.manifest").unwrap();
+        let buf = manifest.to_bytes().unwrap();
+        let mut payload = payload_of(&buf);
+        let meta_size = u32::from_le_bytes(payload[..4].try_into().unwrap()) as u64;
+
+        // A meta size out of range leaves the reader right after the field,
+        // and one too small in the middle of the meta.
+        for size in [0u32, 10] {
+            payload[..4].copy_from_slice(&size.to_le_bytes());
+            let broken = with_payload(&buf, &payload);
+            let mut warnings = Warnings::default();
+            let (parsed, report) = parse_with_hooks(&broken, &ParseOptions::default(), &mut warnings).unwrap();
+            let MetaCheck::Failed { resynced_at, .. } = report.meta else { panic!("{:?}", report.meta) };
+            assert_eq!(resynced_at, Some(meta_size));
+            assert_eq!(
+                warnings.0,
+                [ParseWarning::Resynchronized { section: Section::ChunkList, offset: meta_size }]
+            );
+            assert_eq!(parsed.chunk_list.unwrap().count, manifest.chunk_list.as_ref().unwrap().count);
+            assert_eq!(parsed.file_list.unwrap().count, manifest.file_list.as_ref().unwrap().count);
+
+            let options = ParseOptions {
+                meta_recovery: MetaRecovery::Continue,
+                ..ParseOptions::default()
+            };
+            // Without the scan, the chunk list is read from meta bytes.
+            assert!(parse_with_report(&broken, &options).is_err());
+        }
+    }
+
+    #[test]
+    fn test_encrypted_manifest() {
+        use crate::crypto::AesKey;
+        use aes::cipher::{generic_array::GenericArray, BlockEncrypt, KeyInit};
+
+        let manifest = load("test-manifests/valid-small.manifest").unwrap();
+        let key = AesKey::new([0x3c; 32]);
+        let encrypt = |buf: &[u8]| {
+            let header_size = u32::from_le_bytes(buf[4..8].try_into().unwrap()) as usize;
+            let mut payload = buf[header_size..].to_vec();
+            payload.resize(payload.len().div_ceil(16) * 16, 0);
+            let cipher = aes::Aes256::new(GenericArray::from_slice(&[0x3c; 32]));
+            for block in payload.chunks_exact_mut(16) {
+                cipher.encrypt_block(GenericArray::from_mut_slice(block));
+            }
+            let mut out = buf[..header_size].to_vec();
+            out[12..16].copy_from_slice(&(payload.len() as u32).to_le_bytes());
+            out[36] |= types::flags::STORED_ENCRYPTED;
+            out.extend_from_slice(&payload);
+            out
+        };
+        let options = ParseOptions {
+            decryption_key: Some(key.clone()),
+            ..ParseOptions::default()
+        };
+
+        for stored in [ManifestWriter::new(), ManifestWriter::new().uncompressed()] {
+            let buf = encrypt(&stored.to_bytes(&manifest).unwrap());
+            assert!(matches!(process_manifest_data(buf.clone()), Err(ManifestError::EncryptedManifest)));
+            let (parsed, report) = parse_with_report(&buf, &options).unwrap();
+            assert_eq!(report.payload_hash, PayloadHashCheck::Matched);
+            assert_eq!(parsed.to_bytes().unwrap(), manifest.to_bytes().unwrap());
+            assert_eq!(stream::manifest_len(&buf), Some(buf.len()));
+
+            let path = std::env::temp_dir().join(format!("egdata-encrypted-{}.manifest", uuid::Uuid::new_v4()));
+            fs::write(&path, &buf).unwrap();
+            assert_eq!(load_with_key(&path, &key).unwrap().to_bytes().unwrap(), manifest.to_bytes().unwrap());
+            assert!(load_with_key(&path, &AesKey::new([1; 32])).is_err());
+            fs::remove_file(&path).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_meta_summary() {
+        let manifest = load_with_options("test-manifests/valid-small.manifest", &ParseOptions {
+            strings: StringPolicy::RAW,
+            ..ParseOptions::default()
+        })
+        .unwrap();
+        let meta = manifest.meta.as_ref().unwrap();
+        let summary = crate::dto::v1::MetaSummary::from(meta);
+        assert_eq!(summary.app_name, meta.app_name.trim_end_matches('\0'));
+        assert_eq!(summary.launch_exe, "launcher_epic.exe");
+        assert_eq!((summary.build_id, summary.feature_level), (meta.build_id(), meta.feature_level));
+        assert_eq!(summary.platform.as_deref(), Some("Windows"));
+        let json = serde_json::to_value(crate::dto::v1::MetaSummary::from(meta)).unwrap();
+        assert!(json.get("prereqIds").is_none() && json.get("prereq_ids").is_none());
+
+        let mut meta = meta.clone();
+        meta.launch_exe = "Game.app/Contents/MacOS/Game".to_string();
+        assert_eq!(meta.platform(), Some("Mac"));
+        meta.launch_exe = "bin/game".to_string();
+        assert_eq!(meta.platform(), None);
+    }
+
+    #[test]
+    fn test_strict_options() {
+        let buf = fs::read("test-manifests/valid-small.manifest").unwrap();
+        let strict = ParseOptions {
+            strict: true,
+            warnings_as_errors: true,
+            ..ParseOptions::default()
+        };
+        let (manifest, _) = parse_with_report(&buf, &strict).unwrap();
+        let files = manifest.file_list.unwrap().count;
+        assert!(files > 1);
+
+        // A flipped header hash byte: a warning, or with it the error.
+        let mut mismatched = buf.clone();
+        mismatched[16] ^= 0xff;
+        let (_, report) = parse_with_report(&mismatched, &ParseOptions::default()).unwrap();
+        assert_eq!(report.payload_hash, PayloadHashCheck::Mismatched);
+        let warnings_as_errors = ParseOptions {
+            warnings_as_errors: true,
+            ..ParseOptions::default()
+        };
+        let err = parse_with_report(&mismatched, &warnings_as_errors).unwrap_err();
+        assert!(matches!(err, ManifestError::Warning(ParseWarning::PayloadHashMismatch)), "{}", err);
+
+        let limited = ParseOptions {
+            limits: ParseLimits {
+                max_files: files - 1,
+                ..ParseLimits::default()
+            },
+            ..ParseOptions::default()
+        };
+        let err = parse_with_report(&buf, &limited).unwrap_err();
+        assert!(matches!(err, ManifestError::LimitExceeded { section: Section::FileList, .. }), "{}", err);
+        assert!(err.to_string().contains("over the limit"), "{}", err);
+
+        // A garbage chunk count is reported leniently and fails strictly.
+        let guid = [1u8; 16];
+        let mut chunk_list = ChunkDataList::default();
+        chunk_list.chunk_lookup.insert(uuid::Uuid::from_bytes(guid).to_string(), 0);
+        chunk_list.elements.push(types::chunk::Chunk {
+            guid: uuid::Uuid::from_bytes(guid).to_string(),
+            ..Default::default()
+        });
+        let records = vec![vec![(guid, 10)], vec![(guid, 20)]];
+        let corrupt = file_list_bytes(&records, Some((1, 0x0102_0304)));
+        let mut report = ParseReport::default();
+        let lenient = ParseOptions::default();
+        FileManifestList::read_with_options(&mut Cursor::new(&corrupt), &chunk_list, &lenient, &mut report).unwrap();
+        assert_eq!(report.corrupt_files.len(), 1);
+        let err = FileManifestList::read_with_options(&mut Cursor::new(&corrupt), &chunk_list, &strict, &mut report)
+            .unwrap_err();
+        let ManifestError::Strict { section, message } = &err else { panic!("{}", err) };
+        assert_eq!(*section, Section::FileList);
+        assert!(message.starts_with("file1.bin: "), "{}", message);
+
+        // So does a section shorter than its declared size.
+        let short = &corrupt[..corrupt.len() - 4];
+        let err = FileManifestList::read_with_options(&mut Cursor::new(short), &chunk_list, &strict, &mut report)
+            .unwrap_err();
+        assert!(matches!(err, ManifestError::Strict { section: Section::FileList, .. }), "{}", err);
+    }
+
+    #[test]
+    fn test_require_payload_hash() {
+        let buf = fs::read("test-manifests/valid-small.manifest").unwrap();
+        let options = ParseOptions {
+            require_payload_hash: true,
+            ..ParseOptions::default()
+        };
+        let (manifest, _) = parse_with_report(&buf, &options).unwrap();
+
+        let mut mismatched = buf.clone();
+        mismatched[16] ^= 0xff;
+        let (_, report) = parse_with_report(&mismatched, &ParseOptions::default()).unwrap();
+        assert_eq!(report.payload_hash, PayloadHashCheck::Mismatched);
+        for options in [options, ParseOptions { strict: true, ..ParseOptions::default() }] {
+            let err = parse_with_report(&mismatched, &options).unwrap_err();
+            let ManifestError::Sha1Mismatch { expected, actual } = &err else { panic!("{}", err) };
+            assert_eq!(*actual, manifest.header.sha1_hash);
+            assert_ne!(expected, actual);
+            assert_eq!(expected[2..], actual[2..]);
+        }
+    }
+
+    #[test]
+    fn test_manifest_store() {
+        let manifest = std::sync::Arc::new(load("test-manifests/valid-small.manifest").unwrap());
+        let hash = store::manifests::content_hash(&manifest).unwrap();
+        let dir = std::env::temp_dir().join(format!("egdata-manifest-store-{}", uuid::Uuid::new_v4()));
+        let stores: Vec<Box<dyn ManifestStore>> =
+            vec![Box::new(MemoryManifestStore::new()), Box::new(DirManifestStore::open(&dir).unwrap())];
+        for store in stores {
+            assert!(!store.contains(&hash).unwrap());
+            assert_eq!(store.put(manifest.clone()).unwrap(), hash);
+            assert_eq!(store.put(manifest.clone()).unwrap(), hash);
+            assert!(store.contains(&hash).unwrap());
+            let stored = store.get(&hash).unwrap().unwrap();
+            assert_eq!(stored.to_bytes().unwrap(), manifest.to_bytes().unwrap());
+            // What comes back stores under the same hash.
+            assert_eq!(store.put(stored).unwrap(), hash);
+            assert!(store.get(&"0".repeat(64)).unwrap().is_none());
+            assert!(store.get("../escape").unwrap().is_none());
+        }
+        assert!(dir.join(&hash[..2]).join(format!("{}.manifest", hash)).is_file());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_probe() {
+        let path = "test-manifests/valid-small.manifest";
+        let buf = fs::read(path).unwrap();
+        let manifest = load(path).unwrap();
+        let probed = probe(path).unwrap();
+        assert_eq!(probed.header.version, manifest.header.version);
+        assert_eq!(probed.header.sha1_hash, manifest.header.sha1_hash);
+        assert_eq!(probed.header.stored_as, manifest.header.stored_as);
+        assert_eq!(probed.file_size, buf.len() as u64);
+        assert!(probed.is_complete());
+        assert_eq!(probed.expected_size(), buf.len() as u64);
+
+        // A cut payload still probes, as incomplete.
+        let cut = probe::probe_bytes(&buf[..buf.len() / 2]).unwrap();
+        assert_eq!(cut.header.data_size_compressed, probed.header.data_size_compressed);
+        assert!(!cut.is_complete());
+
+        assert!(probe("test-manifests/valid-json-format.manifest").is_err());
+    }
+
+    #[test]
+    fn test_json_int64_formats() {
+        use export::json::{to_json_value, Int64Format};
+        use serde_json::json;
+
+        let manifest = load("test-manifests/valid-small.manifest").unwrap();
+        let chunk_size = manifest.chunk_list.as_ref().unwrap().elements[0].file_size.clone();
+        let file_size = manifest.file_list.as_ref().unwrap().file_manifest_list[0].file_size;
+        let rolling_hash = manifest.header.rolling_hash;
+        let at = |value: &serde_json::Value| {
+            (
+                value["chunk_list"]["elements"][0]["file_size"].clone(),
+                value["file_list"]["file_manifest_list"][0]["file_size"].clone(),
+                value["header"]["rolling_hash"].clone(),
+            )
+        };
+
+        let strings = to_json_value(&manifest, Int64Format::String).unwrap();
+        assert_eq!(at(&strings), (json!(chunk_size), json!(file_size.to_string()), json!(rolling_hash.to_string())));
+        let numbers = to_json_value(&manifest, Int64Format::Number).unwrap();
+        let chunk_number: u64 = chunk_size.parse().unwrap();
+        assert_eq!(at(&numbers), (json!(chunk_number), json!(file_size), json!(rolling_hash)));
+        let tagged = to_json_value(&manifest, Int64Format::Tagged).unwrap();
+        assert_eq!(
+            at(&tagged),
+            (
+                json!({ "$num": chunk_size }),
+                json!({ "$num": file_size.to_string() }),
+                json!({ "$num": rolling_hash.to_string() })
+            )
+        );
+
+        // Nothing else changes.
+        let mut plain = serde_json::to_value(&manifest).unwrap();
+        plain["chunk_list"]["elements"][0]["file_size"] = json!(chunk_number);
+        assert_eq!(plain["meta"], numbers["meta"]);
+        assert_eq!(plain["chunk_list"]["elements"][0], numbers["chunk_list"]["elements"][0]);
+        assert_eq!(manifest.to_json_with(Int64Format::Tagged).unwrap(), tagged.to_string());
+    }
+
+    #[test]
+    fn test_load_meta() {
+        let path = "test-manifests/valid-small.manifest";
+        let manifest = load(path).unwrap();
+        let as_json = |meta: &ManifestMeta| serde_json::to_value(meta).unwrap();
+        let (header, meta) = load_meta(path).unwrap();
+        assert_eq!(header.sha1_hash, manifest.header.sha1_hash);
+        assert_eq!(as_json(&meta), as_json(manifest.meta.as_ref().unwrap()));
+        let json = fs::read("test-manifests/valid-json-format.manifest").unwrap();
+        let (_, json_meta) = parse_meta(&json, &ParseOptions::default()).unwrap();
+        assert_eq!(as_json(&json_meta), as_json(&process_manifest_data(json).unwrap().meta.unwrap()));
+
+        // Nothing past the meta is read.
+        let buf = fs::read(path).unwrap();
+        let payload = payload_of(&buf);
+        let meta_only = with_payload(&buf, &payload[..meta.data_size as usize]);
+        let (_, cut_meta) = parse_meta(&meta_only, &ParseOptions::default()).unwrap();
+        assert_eq!(as_json(&cut_meta), as_json(&meta));
+
+        let mut broken = payload.clone();
+        broken[14..18].copy_from_slice(&i32::MAX.to_le_bytes());
+        let err = parse_meta(&with_payload(&buf, &broken), &ParseOptions::default()).unwrap_err();
+        assert!(matches!(err, ManifestError::Meta { offset: 0, .. }), "{}", err);
+    }
+
+    #[test]
+    fn test_lockfile() {
+        use export::lockfile::{LockedFile, Lockfile};
+
+        let manifest = load("test-manifests/valid-small.manifest").unwrap();
+        let lockfile = Lockfile::from_manifest(&manifest).unwrap();
+        assert_eq!(lockfile.files.len(), manifest.file_list.as_ref().unwrap().file_manifest_list.len());
+        assert_eq!(lockfile.chunks.len(), manifest.chunk_list.as_ref().unwrap().elements.len());
+        assert!(lockfile.files.windows(2).all(|w| w[0].path <= w[1].path));
+        assert!(lockfile.chunks.windows(2).all(|w| w[0].guid <= w[1].guid));
+        assert_eq!(lockfile.build_id, manifest.meta.as_ref().unwrap().build_id());
+
+        let text = lockfile.to_string();
+        let parsed = Lockfile::parse(&text).unwrap();
+        assert_eq!(parsed, lockfile);
+        assert_eq!(parsed.to_string(), text);
+
+        // Names that would break lines survive escaped.
+        let mut odd = lockfile.clone();
+        odd.app_name = "two\nlines\r\n".to_string();
+        odd.files.push(LockedFile {
+            path: "dir\\sub/new\nline name ".to_string(),
+            sha1: "00".repeat(20),
+            size: 0,
+        });
+        let odd_text = odd.to_string();
+        assert_eq!(odd_text.lines().count(), text.lines().count() + 1);
+        assert_eq!(Lockfile::parse(&odd_text).unwrap(), odd);
+
+        // One changed file is one changed line.
+        let mut changed = lockfile.clone();
+        changed.files[0].size += 1;
+        let changed_text = changed.to_string();
+        let differing = text.lines().zip(changed_text.lines()).filter(|(a, b)| a != b).count();
+        assert_eq!(differing, 1);
+
+        let err = Lockfile::parse(&text.replace("[chunks]", "[blobs]")).unwrap_err();
+        assert!(err.to_string().contains("unknown section"), "{}", err);
+        assert!(Lockfile::parse("files").is_err());
+
+        // Missing hashes keep their field and round-trip.
+        let mut unhashed = manifest.clone();
+        unhashed.file_list.as_mut().unwrap().file_manifest_list[0].sha_hash.clear();
+        let chunk = &mut unhashed.chunk_list.as_mut().unwrap().elements[0];
+        chunk.hash.clear();
+        chunk.sha_hash.clear();
+        let unhashed_lockfile = Lockfile::from_manifest(&unhashed).unwrap();
+        let unhashed_text = unhashed_lockfile.to_string();
+        assert_eq!(Lockfile::parse(&unhashed_text).unwrap(), unhashed_lockfile);
+        assert_eq!(unhashed_text.lines().filter(|line| line.starts_with("- ") || line.ends_with(" - -")).count(), 2);
+
+        // A chunk size that is not a number fails instead of becoming 0.
+        unhashed.chunk_list.as_mut().unwrap().elements[0].file_size = "big".to_string();
+        let err = Lockfile::from_manifest(&unhashed).unwrap_err();
+        assert!(err.to_string().contains("\"big\""), "{}", err);
+    }
+
+    #[cfg(all(target_os = "linux", feature = "platform-io"))]
+    #[test]
+    fn test_uring_submit_failure() {
+        use crate::io_backend::uring::UringIo;
+        use crate::io_backend::IoBackend;
+
+        let Ok(io) = UringIo::new() else {
+            return; // io_uring unavailable here
+        };
+        let path = std::env::temp_dir().join(format!("egdata-uring-{}", uuid::Uuid::new_v4()));
+        let data: Vec<u8> = (0..200_000u32).map(|i| (i % 251) as u8).collect();
+        let file = fs::OpenOptions::new().read(true).write(true).create(true).truncate(true).open(&path).unwrap();
+        let writes: Vec<(u64, &[u8])> = data.chunks(1000).enumerate().map(|(i, c)| ((i * 1000) as u64, c)).collect();
+        io.fail_next_submit();
+        io.write_batch(&file, &writes).unwrap();
+        assert!(!io.is_usable());
+        assert_eq!(fs::read(&path).unwrap(), data);
+
+        // Later batches skip the ring and still complete.
+        let mut buf = [0u8; 100];
+        assert_eq!(io.read_batch(&file, &mut [(199_950, &mut buf[..])]).unwrap(), vec![50]);
+        assert_eq!(buf[..50], data[199_950..]);
+
+        let io = UringIo::new().unwrap();
+        let mut bufs = vec![vec![0u8; 1000]; 150];
+        let mut reads: Vec<(u64, &mut [u8])> = bufs.iter_mut().enumerate().map(|(i, b)| ((i * 1000) as u64, &mut b[..])).collect();
+        io.fail_next_submit();
+        assert_eq!(io.read_batch(&file, &mut reads).unwrap(), vec![1000; 150]);
+        assert!(!io.is_usable());
+        assert_eq!(bufs.concat(), data[..150_000]);
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_install_paths_stay_inside_target() {
+        use crate::install::apply::target_path;
+
+        let target = std::path::Path::new("/srv/game");
+        let path = |name: &str| {
+            let file = FileManifest {
+                filename: name.to_string(),
+                ..Default::default()
+            };
+            target_path(target, &file).ok()
+        };
+        assert_eq!(path("Engine/Binaries/game.exe\0"), Some(target.join("Engine/Binaries/game.exe")));
+        assert_eq!(path("Engine\\Binaries\\game.exe"), Some(target.join("Engine/Binaries/game.exe")));
+        assert_eq!(path("./Engine//game.exe"), Some(target.join("Engine/game.exe")));
+        for escaping in ["/etc/passwd", "../x", "Engine/../../x", "\\Windows\\x", "C:x", "C:\\Windows\\x", "\\\\server\\share\\x", ""] {
+            assert_eq!(path(escaping), None, "{:?}", escaping);
+        }
+    }
+
+    #[test]
+    fn test_string_policy() {
+        let mut data = Vec::new();
+        data.extend_from_slice(&4i32.to_le_bytes());
+        data.extend_from_slice(b"a\xffb\0");
+        let policies = [StringPolicy::RAW, StringPolicy::default(), StringPolicy::strict()];
+
+        let mut rdr = Cursor::new(&data);
+        assert_eq!(rdr.fstring_with(&policies[0]).unwrap(), "a\u{FFFD}b\0");
+        rdr.set_position(0);
+        assert_eq!(rdr.fstring_with(&policies[1]).unwrap(), "a\u{FFFD}b");
+        rdr.set_position(0);
+        let err = rdr.fstring_with(&policies[2]).unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+
+        let capped = StringPolicy {
+            max_len: 3,
+            ..StringPolicy::default()
+        };
+        rdr.set_position(0);
+        assert!(rdr.fstring_with(&capped).is_err());
+
+        // Unpaired surrogate in a UTF-16 FString.
+        let mut data = Vec::new();
+        data.extend_from_slice(&(-2i32).to_le_bytes());
+        data.extend_from_slice(&[0x00, 0xD8, 0x00, 0x00]);
+        let mut rdr = Cursor::new(&data);
+        assert_eq!(rdr.fstring().unwrap(), "\u{FFFD}\0");
+        rdr.set_position(0);
+        assert!(rdr.fstring_with(&StringPolicy::strict()).is_err());
+    }
+
+    #[test]
+    fn test_nul_padding_trimmed_at_parse_time() {
+        let manifest = load("test-manifests/valid-small.manifest").expect("Failed to load manifest");
+        let meta = manifest.meta.as_ref().unwrap();
+        assert!(!meta.app_name.is_empty());
+        assert!(!meta.app_name.ends_with('\0'));
+        for file in &manifest.file_list.as_ref().unwrap().file_manifest_list {
+            assert!(!file.filename.ends_with('\0'));
+            assert!(file.install_tags.iter().all(|t| !t.ends_with('\0')));
+        }
+
+        let raw = load_with_options(
+            "test-manifests/valid-small.manifest",
+            &ParseOptions {
+                strings: StringPolicy::RAW,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        assert!(raw.meta.as_ref().unwrap().app_name.ends_with('\0'));
+        assert_eq!(raw.meta.unwrap().app_name.trim_end_matches('\0'), meta.app_name);
+    }
+
+    /// Provider serving deterministic synthetic data for every chunk.
+    fn synthetic_chunk(guid: &str) -> Result<Vec<u8>, error::ManifestError> {
+        let seed = guid.bytes().fold(0u8, |acc, b| acc.wrapping_add(b));
+        Ok((0..1024 * 1024).map(|i| seed.wrapping_add(i as u8)).collect())
+    }
+
+    #[test]
+    fn test_export_torrent() {
+        use export::torrent::{export_torrent, TorrentOptions};
+
+        let mut manifest = load("test-manifests/valid-small.manifest").expect("Failed to load manifest");
+        // Hashing every file is slow in debug builds; a few are enough.
+        manifest.file_list.as_mut().unwrap().file_manifest_list.truncate(3);
+        let options = TorrentOptions {
+            piece_length: 16 * 1024,
+            ..Default::default()
+        };
+        let torrent = export_torrent(&manifest, &synthetic_chunk, &options).expect("Export failed");
+
+        assert!(torrent.bytes.starts_with(b"d"));
+        let text = String::from_utf8_lossy(&torrent.bytes);
+        assert!(text.contains("12:meta versioni2e"));
+        assert!(text.contains("12:piece layers"));
+        let first = &manifest.file_list.as_ref().unwrap().file_manifest_list[0];
+        let leaf_name = first.filename.trim_end_matches('\0').rsplit('/').next().unwrap();
+        assert!(text.contains(leaf_name));
+
+        // Pieces below 16 KiB aren't valid BEP 52.
+        let bad = TorrentOptions {
+            piece_length: 1000,
+            ..Default::default()
+        };
+        assert!(export_torrent(&manifest, &synthetic_chunk, &bad).is_err());
+
+        // A file smaller than a piece gets a tree over its own two blocks,
+        // not a piece's worth of zero leaves. Root of 20000 bytes of 'a'.
+        let files = &mut manifest.file_list.as_mut().unwrap().file_manifest_list;
+        files.truncate(1);
+        files[0].chunk_parts.truncate(1);
+        files[0].chunk_parts[0].offset = 0;
+        files[0].chunk_parts[0].size = 20000;
+        let options = TorrentOptions {
+            piece_length: 64 * 1024,
+            ..Default::default()
+        };
+        let provider = |_: &str| Ok(vec![b'a'; 64 * 1024]);
+        let torrent = export_torrent(&manifest, &provider, &options).unwrap();
+        let key = b"11:pieces root32:";
+        let at = torrent.bytes.windows(key.len()).position(|w| w == key).unwrap() + key.len();
+        assert_eq!(
+            hex::encode(&torrent.bytes[at..at + 32]),
+            "4e15d8ed29f74ed4b4953e205673b4b46c63f7a717d693a6d27e7e8373354d48"
+        );
+    }
+
+    /// Manifest fixture trimmed to a few files whose hashes match
+    /// [`synthetic_chunk`] data.
+    fn synthetic_manifest() -> Manifest {
+        use sha1::{Digest, Sha1};
+
+        let mut manifest = load("test-manifests/valid-small.manifest").expect("Failed to load manifest");
+        let files = &mut manifest.file_list.as_mut().unwrap().file_manifest_list;
+        files.truncate(3);
+        for file in files.iter_mut() {
+            let mut hasher = Sha1::new();
+            provider::for_each_file_part(&synthetic_chunk, file, &mut None, |bytes| {
+                hasher.update(bytes);
+                Ok(())
+            })
+            .unwrap();
+            file.sha_hash = hex::encode(hasher.finalize());
+        }
+        manifest
+    }
+
+    #[test]
+    fn test_cas_round_trip() {
+        use export::cas::{export_cas, import_cas};
+
+        let manifest = synthetic_manifest();
+        let root = scratch_dir("cas");
+        let stats = export_cas(&manifest, &synthetic_chunk, root.join("cas")).expect("Export failed");
+        assert_eq!(stats.files, 3);
+
+        // Exporting again writes no new blobs.
+        let again = export_cas(&manifest, &synthetic_chunk, root.join("cas")).unwrap();
+        assert_eq!(again.blobs_written, 0);
+
+        let index = import_cas(root.join("cas"), root.join("out")).expect("Import failed");
+        for (entry, file) in index.files.iter().zip(&manifest.file_list.as_ref().unwrap().file_manifest_list) {
+            let data = std::fs::read(root.join("out").join(&entry.filename)).unwrap();
+            assert_eq!(data.len() as i64, file.file_size);
+            assert_eq!(entry.sha1, file.sha_hash);
+        }
+
+        // An index naming paths outside the target is refused.
+        for escaping in ["../escaped.bin", "\\escaped.bin", "C:escaped.bin"] {
+            let mut index = index.clone();
+            index.files[0].filename = escaping.to_string();
+            std::fs::write(root.join("cas").join(export::cas::CAS_INDEX_FILE), serde_json::to_vec(&index).unwrap()).unwrap();
+            assert!(matches!(import_cas(root.join("cas"), root.join("out")), Err(ManifestError::Invalid(_))), "{}", escaping);
+        }
+        assert!(!root.join("escaped.bin").exists());
+
+        // So is one whose SHA-1 would name a blob outside the CAS root.
+        std::fs::write(root.join("outside"), b"secret").unwrap();
+        for sha1 in ["../../outside", &"A".repeat(40), &"0".repeat(39)] {
+            let mut index = index.clone();
+            index.files[0].sha1 = sha1.to_string();
+            std::fs::write(root.join("cas").join(export::cas::CAS_INDEX_FILE), serde_json::to_vec(&index).unwrap()).unwrap();
+            let err = import_cas(root.join("cas"), root.join("out")).unwrap_err();
+            assert!(err.to_string().contains("not 40 lowercase hex digits"), "{}: {}", sha1, err);
+        }
+
+        std::fs::remove_dir_all(&root).ok();
+    }
+
+    #[test]
+    fn test_verifying_reader() {
+        use std::io::Read;
+        use verify::reader::{VerifyFailure, VerifyingReader};
+
+        let manifest = synthetic_manifest();
+        let file = &manifest.file_list.as_ref().unwrap().file_manifest_list[0];
+        let mut content = Vec::new();
+        provider::for_each_file_part(&synthetic_chunk, file, &mut None, |bytes| {
+            content.extend_from_slice(bytes);
+            Ok(())
+        })
+        .unwrap();
+
+        let mut out = Vec::new();
+        let mut reader = VerifyingReader::new(content.as_slice(), file);
+        reader.read_to_end(&mut out).expect("Valid content should verify");
+        assert_eq!(out, content);
+
+        if !content.is_empty() {
+            let mut corrupted = content.clone();
+            corrupted[0] ^= 0xff;
+            let mut reader = VerifyingReader::new(corrupted.as_slice(), file);
+            let err = reader.read_to_end(&mut Vec::new()).unwrap_err();
+            assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+            assert!(matches!(reader.check(), Err(VerifyFailure::Sha1 { .. })));
+
+            let truncated = VerifyingReader::new(&content[1..], file);
+            assert!(truncated.finish().is_err());
+        }
+
+        // The inner reader's own InvalidData comes through, not a mismatch.
+        #[derive(Debug)]
+        struct Corrupt<R>(R);
+        impl<R: Read> Read for Corrupt<R> {
+            fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+                match self.0.read(buf)? {
+                    0 => Err(std::io::Error::new(std::io::ErrorKind::InvalidData, "chunk failed to decompress")),
+                    n => Ok(n),
+                }
+            }
+        }
+        let partial = &content[..content.len() / 2];
+        let err = VerifyingReader::new(Corrupt(partial), file).finish().unwrap_err();
+        assert!(matches!(err, ManifestError::Io(_)), "{}", err);
+        assert!(err.to_string().contains("chunk failed to decompress"), "{}", err);
+    }
+
+    #[test]
+    fn test_verify_install() {
+        use verify::install::{verify_install, CorruptReason, VerifyEvent};
+
+        let manifest = synthetic_manifest();
+        let root = scratch_dir("verify");
+        export::cas::export_cas(&manifest, &synthetic_chunk, root.join("cas")).unwrap();
+        export::cas::import_cas(root.join("cas"), root.join("install")).unwrap();
+
+        let mut ok_events = 0;
+        let mut last_progress = None;
+        let report = verify_install(&manifest, root.join("install"), None, |event| match event {
+            VerifyEvent::FileOk { .. } => ok_events += 1,
+            VerifyEvent::Progress(progress) => last_progress = Some(serde_json::to_value(progress).unwrap()),
+            _ => {}
+        })
+        .unwrap();
+        assert!(report.is_ok());
+        assert_eq!(ok_events, 3);
+        // The `progress` event JS receives uses the same keys as its type.
+        let progress = last_progress.unwrap();
+        let mut keys: Vec<_> = progress.as_object().unwrap().keys().map(String::as_str).collect();
+        keys.sort();
+        assert_eq!(keys, ["bytesDone", "bytesTotal", "filesDone", "filesTotal"]);
+        assert_eq!(progress["filesDone"], 3);
+
+        let first = &manifest.file_list.as_ref().unwrap().file_manifest_list[0];
+        std::fs::remove_file(root.join("install").join(first.filename.trim_end_matches('\0'))).unwrap();
+        let report = verify_install(&manifest, root.join("install"), None, |_| {}).unwrap();
+        assert_eq!(report.corrupt.len(), 1);
+        assert_eq!(report.corrupt[0].reason, CorruptReason::Missing);
+
+        std::fs::remove_dir_all(&root).ok();
+    }
+
+    /// Decompressed payload of a manifest from [`writer::write_manifest`].
+    fn payload_of(buf: &[u8]) -> Vec<u8> {
+        let header_size = u32::from_le_bytes(buf[4..8].try_into().unwrap()) as usize;
+        miniz_oxide::inflate::decompress_to_vec_zlib(&buf[header_size..]).unwrap()
+    }
+
+    /// `buf` with its payload replaced by `payload`, sizes and SHA-1 updated.
+    fn with_payload(buf: &[u8], payload: &[u8]) -> Vec<u8> {
+        let header_size = u32::from_le_bytes(buf[4..8].try_into().unwrap()) as usize;
+        let compressed = miniz_oxide::deflate::compress_to_vec_zlib(payload, 6);
+        let mut out = buf[..header_size].to_vec();
+        out[8..12].copy_from_slice(&(payload.len() as u32).to_le_bytes());
+        out[12..16].copy_from_slice(&(compressed.len() as u32).to_le_bytes());
+        out[16..36].copy_from_slice(&Sha1::digest(payload));
+        out.extend(compressed);
+        out
+    }
+
+    /// Fresh scratch directory under the system temp dir.
+    fn scratch_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("egdata-{}-{}", name, uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).expect("Failed to create scratch dir");
+        dir
+    }
+
+    #[test]
+    fn test_chunk_store_layout_and_eviction() {
+        let manifest = load("test-manifests/valid-small.manifest").expect("Failed to load manifest");
+        let chunks = &manifest.chunk_list.as_ref().unwrap().elements;
+        assert!(chunks.len() >= 2, "Fixture should contain at least two chunks");
+
+        let root = scratch_dir("chunk-store");
+        let store = ChunkStore::open(&root, 21).expect("Failed to open store");
+        let first = store.add(&chunks[0], &[0u8; 100]).unwrap();
+        store.add(&chunks[1], &[0u8; 50]).unwrap();
+
+        assert!(first.starts_with(root.join("ChunksV4")));
+        assert!(first.to_string_lossy().ends_with(".chunk"));
+        assert_eq!(store.total_size(), 150);
+
+        // Re-opening indexes the files already on disk.
+        let reopened = ChunkStore::open(&root, 21).unwrap();
+        assert_eq!(reopened.len(), 2);
+        assert_eq!(reopened.locate(&chunks[0].guid), Some(first));
+
+        // chunks[0] was just used, so chunks[1] goes first.
+        let freed = reopened.evict_to_size(100).unwrap();
+        assert_eq!(freed, 50);
+        assert!(reopened.contains(&chunks[0].guid));
+        assert!(!reopened.contains(&chunks[1].guid));
+
+        // A chunk no manifest references is collected; referenced ones stay.
+        let stray = types::chunk::Chunk {
+            guid: uuid::Uuid::new_v4().to_string(),
+            hash: "0123456789abcdef".to_string(),
+            ..Default::default()
+        };
+        reopened.add(&stray, &[0u8; 10]).unwrap();
+        let dry_run = reopened
+            .gc_with(std::slice::from_ref(&manifest), &store::chunks::GcOptions { dry_run: true })
+            .unwrap();
+        assert_eq!((dry_run.removed_chunks, dry_run.reclaimed_bytes), (1, 10));
+        assert!(matches!(&dry_run.actions[..], [install::actions::Action::Delete { bytes: 10, .. }]));
+        assert!(reopened.contains(&stray.guid), "dry run must not delete");
+        let report = reopened.gc(std::slice::from_ref(&manifest)).unwrap();
+        assert_eq!(report.removed_chunks, 1);
+        assert_eq!(report.reclaimed_bytes, 10);
+        assert!(reopened.contains(&chunks[0].guid));
+
+        // Only chunks[0] is left, and the bitmap survives a round-trip.
+        let availability = ChunkAvailability::from_store(&manifest, &reopened);
+        assert_eq!(availability.len() as usize, chunks.len());
+        assert_eq!(availability.count_available(), 1);
+        assert!(availability.get(0));
+        let decoded = ChunkAvailability::from_bytes(&availability.to_bytes()).unwrap();
+        assert_eq!(decoded, availability);
+        let summary = decoded.summary(&manifest).unwrap();
+        assert_eq!(summary.available_chunks, 1);
+        assert_eq!(summary.total_chunks as usize, chunks.len());
+        assert!(summary.completeness > 0.0 && summary.completeness < 1.0);
+
+        // A bitmap made for another manifest is refused.
+        let other = ChunkAvailability::new(chunks.len() as u32 + 1);
+        let err = other.summary(&manifest).unwrap_err();
+        assert!(err.to_string().contains("covers"), "{}", err);
+
+        std::fs::remove_dir_all(&root).ok();
+    }
+
 
 }
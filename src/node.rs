@@ -0,0 +1,1116 @@
+//! NAPI-RS exports: the Node.js API.
+
+use napi::{bindgen_prelude::{Buffer, Either, Generator}, Result as NapiResult};
+use napi_derive::napi;
+use serde::Deserialize;
+use std::sync::atomic::{AtomicI64, Ordering};
+use std::time::Duration;
+
+use crate::crypto::AesKey;
+use crate::download::config::{ProxyConfig, RootCertificate, TransportConfig};
+use crate::download::retry::RetryPolicy;
+use crate::dto::v1 as dto;
+use crate::export::json::Int64Format;
+use crate::types::json_manifest::{InvalidGuids, JsonConversion, JsonManifest};
+use crate::types::manifest::Manifest;
+use crate::verify::install::VerifyOptions;
+use crate::{detect, export, fingerprint, health, index, prereq, probe, store, types, verify};
+use crate::{ChunkAvailability, ChunkStore};
+use crate::{detect_format, load_async_with_options, load_with_options, process_manifest_data_with};
+use crate::{ByteTrace, GuidFormat, ManifestError, MetaRecovery, Parallelism, ParseLimits, ParseOptions, StringPolicy};
+
+/// Options bag for the parse functions; omitted fields keep the defaults
+#[napi(object)]
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct ParseOptionsJs {
+    /// Reject malformed UTF-8/UTF-16 strings instead of substituting U+FFFD
+    pub strict_strings: Option<bool>,
+    /// Largest accepted string, in bytes as stored
+    pub max_string_length: Option<u32>,
+    /// Look for a manifest embedded further into the input
+    pub scan_for_manifest: Option<bool>,
+    /// `"hyphenated"` (default) or `"epic"`
+    pub guid_format: Option<String>,
+    /// Add a `flags` object (`readonly`, `compressed`, `unixExecutable`) to
+    /// every file
+    pub file_flags: Option<bool>,
+    /// Fail with a timeout error once parsing takes longer than this
+    pub timeout_ms: Option<u32>,
+    /// With `strictStrings`, read well-formed manifests through a faster path
+    pub trusted: Option<bool>,
+    /// Hex dumps of payload bytes in debug logs: `"off"`, `"redacted"` or
+    /// `"full"`; by default from `EGDATA_PARSER_TRACE_BYTES`
+    pub trace_bytes: Option<String>,
+    /// Window size given to chunks of JSON manifests, which don't store one
+    /// (default 1 MiB)
+    pub json_window_size: Option<u32>,
+    /// `"fail"` (default) or `"skip"`: whether JSON manifest files with
+    /// malformed chunk GUIDs fail the parse or are left out
+    pub json_invalid_guids: Option<String>,
+    /// Fail when the meta section can't be read, instead of returning the
+    /// manifest without `meta`
+    pub require_meta: Option<bool>,
+    /// `"resync"` (default) or `"continue"`: whether to scan for the chunk
+    /// list after an unreadable meta section
+    pub meta_recovery: Option<String>,
+    /// AES-256 key for encrypted manifests, as 64 hex digits (optionally
+    /// prefixed with `0x`)
+    pub decryption_key: Option<String>,
+    /// Fail on damage the parser would otherwise work around, such as
+    /// corrupt chunk parts or truncated sections
+    pub strict: Option<bool>,
+    /// Fail on the first parse warning, such as a payload hash mismatch
+    pub warnings_as_errors: Option<bool>,
+    /// Largest chunk count accepted
+    pub max_chunks: Option<u32>,
+    /// Largest file count accepted
+    pub max_files: Option<u32>,
+    /// Largest chunk part count accepted for one file
+    pub max_chunk_parts: Option<u32>,
+    /// Fail with a SHA-1 mismatch error when the payload doesn't match the
+    /// hash in its header. On unless set to `false`.
+    pub require_payload_hash: Option<bool>,
+}
+
+impl TryFrom<ParseOptionsJs> for ParseOptions {
+    type Error = napi::Error;
+
+    fn try_from(js: ParseOptionsJs) -> NapiResult<Self> {
+        let defaults = StringPolicy::default();
+        let limits = ParseLimits::default();
+        let guid_format = match js.guid_format.as_deref() {
+            None | Some("hyphenated") => GuidFormat::Hyphenated,
+            Some("epic") => GuidFormat::Epic,
+            Some(other) => return Err(napi::Error::from_reason(format!("unknown guidFormat: {}", other))),
+        };
+        let trace_bytes = match js.trace_bytes.as_deref() {
+            None => None,
+            Some(value) => Some(
+                ByteTrace::parse(value)
+                    .ok_or_else(|| napi::Error::from_reason(format!("unknown traceBytes: {}", value)))?,
+            ),
+        };
+        let invalid_guids = match js.json_invalid_guids.as_deref() {
+            None | Some("fail") => InvalidGuids::Fail,
+            Some("skip") => InvalidGuids::Skip,
+            Some(other) => return Err(napi::Error::from_reason(format!("unknown jsonInvalidGuids: {}", other))),
+        };
+        let meta_recovery = match js.meta_recovery.as_deref() {
+            None | Some("resync") => MetaRecovery::Resync,
+            Some("continue") => MetaRecovery::Continue,
+            Some(other) => return Err(napi::Error::from_reason(format!("unknown metaRecovery: {}", other))),
+        };
+        let decryption_key = js
+            .decryption_key
+            .as_deref()
+            .map(AesKey::from_hex)
+            .transpose()
+            .map_err(|e| napi::Error::from_reason(e.to_string()))?;
+        Ok(ParseOptions {
+            strings: StringPolicy {
+                strict: js.strict_strings.unwrap_or(defaults.strict),
+                // JS always gets NUL-free strings, as the JSON output does.
+                trim_nul: true,
+                max_len: js.max_string_length.map_or(defaults.max_len, |len| len as usize),
+            },
+            scan_for_manifest: js.scan_for_manifest.unwrap_or_default(),
+            guid_format,
+            file_flags: js.file_flags.unwrap_or_default(),
+            deadline: js
+                .timeout_ms
+                .map(|ms| std::time::Instant::now() + Duration::from_millis(ms as u64)),
+            trusted: js.trusted.unwrap_or_default(),
+            trace_bytes,
+            json: JsonConversion {
+                window_size: js.json_window_size.unwrap_or(JsonConversion::default().window_size),
+                invalid_guids,
+            },
+            require_meta: js.require_meta.unwrap_or_default(),
+            meta_recovery,
+            decryption_key,
+            limits: ParseLimits {
+                max_chunks: js.max_chunks.unwrap_or(limits.max_chunks),
+                max_files: js.max_files.unwrap_or(limits.max_files),
+                max_chunk_parts: js.max_chunk_parts.unwrap_or(limits.max_chunk_parts),
+            },
+            strict: js.strict.unwrap_or_default(),
+            warnings_as_errors: js.warnings_as_errors.unwrap_or_default(),
+            require_payload_hash: js.require_payload_hash.unwrap_or(true),
+        })
+    }
+}
+
+/// Run-length encoded file composition
+#[napi(object)]
+pub struct CompositionJs {
+    /// Chunk GUIDs; runs refer to them by index
+    pub chunks: Vec<String>,
+    pub files: Vec<FileCompositionJs>,
+}
+
+#[napi(object)]
+pub struct FileCompositionJs {
+    pub filename: String,
+    pub size: i64,
+    /// Flat `[chunkIndex, length, chunkIndex, length, ...]` pairs
+    pub runs: Vec<i64>,
+}
+
+impl From<export::composition::Composition> for CompositionJs {
+    fn from(composition: export::composition::Composition) -> Self {
+        Self {
+            chunks: composition.chunks,
+            files: composition
+                .files
+                .into_iter()
+                .map(|f| FileCompositionJs {
+                    filename: f.filename,
+                    size: f.size as i64,
+                    runs: f.runs.iter().flat_map(|r| [r.chunk as i64, r.length as i64]).collect(),
+                })
+                .collect(),
+        }
+    }
+}
+
+/// Options bag for install verification
+#[napi(object)]
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct VerifyOptionsJs {
+    /// Only verify these manifest filenames
+    pub files: Option<Vec<String>>,
+    /// Stop after the first corrupt file
+    pub stop_on_corrupt: Option<bool>,
+    /// Files hashed at once; 0 uses every core. Defaults to the global setting
+    pub threads: Option<u32>,
+}
+
+impl From<VerifyOptionsJs> for VerifyOptions {
+    fn from(js: VerifyOptionsJs) -> Self {
+        VerifyOptions {
+            files: js.files.unwrap_or_default(),
+            stop_on_corrupt: js.stop_on_corrupt.unwrap_or_default(),
+            parallelism: js.threads.map(|threads| Parallelism::with_threads(threads as usize)),
+        }
+    }
+}
+
+/// Options bag for chunk downloads: retries, mirrors and connection settings
+#[napi(object)]
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct DownloadOptionsJs {
+    pub max_attempts: Option<u32>,
+    pub initial_backoff_ms: Option<u32>,
+    pub max_backoff_ms: Option<u32>,
+    /// Fallback CDN base URLs, tried after the primary one
+    pub alternate_base_urls: Option<Vec<String>>,
+    /// Download a chunk again when it fails verification (default true)
+    pub reverify_on_corruption: Option<bool>,
+    pub user_agent: Option<String>,
+    /// Proxy URL; when omitted, `HTTPS_PROXY`/`HTTP_PROXY` are used
+    pub proxy: Option<String>,
+    /// Hosts that bypass the proxy
+    pub no_proxy: Option<Vec<String>>,
+    /// Paths of extra PEM or DER root certificates to trust
+    pub root_certificates: Option<Vec<String>>,
+}
+
+impl DownloadOptionsJs {
+    pub fn retry_policy(&self) -> RetryPolicy {
+        let defaults = RetryPolicy::default();
+        let millis = |ms: Option<u32>, default: Duration| ms.map_or(default, |ms| Duration::from_millis(ms as u64));
+        RetryPolicy {
+            max_attempts: self.max_attempts.unwrap_or(defaults.max_attempts),
+            initial_backoff: millis(self.initial_backoff_ms, defaults.initial_backoff),
+            max_backoff: millis(self.max_backoff_ms, defaults.max_backoff),
+            alternate_base_urls: self.alternate_base_urls.clone().unwrap_or_default(),
+            reverify_on_corruption: self.reverify_on_corruption.unwrap_or(defaults.reverify_on_corruption),
+            ..defaults
+        }
+    }
+
+    /// Connection settings; reads the listed root certificate files.
+    pub fn transport_config(&self) -> NapiResult<TransportConfig> {
+        let mut config = match &self.proxy {
+            Some(url) => TransportConfig {
+                proxy: Some(ProxyConfig::new(url.as_str())),
+                ..Default::default()
+            },
+            None => TransportConfig::from_env(),
+        };
+        if let (Some(proxy), Some(no_proxy)) = (config.proxy.as_mut(), &self.no_proxy) {
+            proxy.no_proxy = no_proxy.clone();
+        }
+        if let Some(user_agent) = &self.user_agent {
+            config.user_agent = user_agent.clone();
+        }
+        for path in self.root_certificates.iter().flatten() {
+            let certificate = RootCertificate::from_file(path)
+                .map_err(|e| napi::Error::from_reason(format!("{}: {}", path, e)))?;
+            config.root_certificates.push(certificate);
+        }
+        Ok(config)
+    }
+}
+
+fn parse_options(options: Option<ParseOptionsJs>) -> NapiResult<ParseOptions> {
+    ParseOptions::try_from(options.unwrap_or_default())
+}
+
+/// Process-wide counters of native objects owned by JS and of buffers
+/// copied out of JS, read by the soak test (`test/soak.mjs`) to tell
+/// native leaks from JS heap growth.
+struct NativeCounters {
+    manifest_handles: AtomicI64,
+    file_handles: AtomicI64,
+    chunk_handles: AtomicI64,
+    file_batches: AtomicI64,
+    verifiers: AtomicI64,
+    buffers_copied: AtomicI64,
+    buffer_bytes_copied: AtomicI64,
+}
+
+static COUNTERS: NativeCounters = NativeCounters {
+    manifest_handles: AtomicI64::new(0),
+    file_handles: AtomicI64::new(0),
+    chunk_handles: AtomicI64::new(0),
+    file_batches: AtomicI64::new(0),
+    verifiers: AtomicI64::new(0),
+    buffers_copied: AtomicI64::new(0),
+    buffer_bytes_copied: AtomicI64::new(0),
+};
+
+/// Counts one live object in `counter` until dropped, i.e. until JS
+/// garbage-collects the object that owns it.
+struct Live(&'static AtomicI64);
+
+impl Live {
+    fn new(counter: &'static AtomicI64) -> Self {
+        counter.fetch_add(1, Ordering::Relaxed);
+        Self(counter)
+    }
+}
+
+impl Drop for Live {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+/// Copy a JS buffer into Rust, counting the copy.
+fn copy_buffer(buffer: &Buffer) -> Vec<u8> {
+    COUNTERS.buffers_copied.fetch_add(1, Ordering::Relaxed);
+    COUNTERS.buffer_bytes_copied.fetch_add(buffer.len() as i64, Ordering::Relaxed);
+    buffer.to_vec()
+}
+
+/// Snapshot of the native counters
+#[napi(object)]
+pub struct NativeStats {
+    /// `ManifestHandle`s not yet garbage-collected
+    pub live_manifest_handles: i64,
+    pub live_file_handles: i64,
+    pub live_chunk_handles: i64,
+    /// `FileBatches` iterators not yet garbage-collected
+    pub live_file_batches: i64,
+    pub live_install_verifiers: i64,
+    /// Buffers copied out of JS since the module loaded
+    pub buffers_copied: i64,
+    pub buffer_bytes_copied: i64,
+}
+
+/// Read the native object and buffer counters, for leak testing
+#[napi]
+pub fn native_stats() -> NativeStats {
+    let read = |counter: &AtomicI64| counter.load(Ordering::Relaxed);
+    NativeStats {
+        live_manifest_handles: read(&COUNTERS.manifest_handles),
+        live_file_handles: read(&COUNTERS.file_handles),
+        live_chunk_handles: read(&COUNTERS.chunk_handles),
+        live_file_batches: read(&COUNTERS.file_batches),
+        live_install_verifiers: read(&COUNTERS.verifiers),
+        buffers_copied: read(&COUNTERS.buffers_copied),
+        buffer_bytes_copied: read(&COUNTERS.buffer_bytes_copied),
+    }
+}
+
+/// Parse an Epic Games manifest file synchronously
+#[napi]
+pub fn parse_manifest_sync(path: String, options: Option<ParseOptionsJs>) -> NapiResult<dto::Manifest> {
+    load_manifest(path, options).map(Into::into)
+}
+
+/// Parse an Epic Games manifest file asynchronously
+#[napi]
+pub async fn parse_manifest_async(path: String, options: Option<ParseOptionsJs>) -> NapiResult<dto::Manifest> {
+    load_async_with_options(path, &parse_options(options)?)
+        .await
+        .map(Into::into)
+        .map_err(|e| napi::Error::from_reason(e.to_string()))
+}
+
+/// Parse manifest data from a buffer
+#[napi]
+pub fn parse_manifest_buffer(buffer: Buffer, options: Option<ParseOptionsJs>) -> NapiResult<dto::Manifest> {
+    parse_buffer(buffer, options).map(Into::into)
+}
+
+fn load_manifest(path: String, options: Option<ParseOptionsJs>) -> NapiResult<Manifest> {
+    load_with_options(path, &parse_options(options)?).map_err(|e| napi::Error::from_reason(e.to_string()))
+}
+
+fn parse_buffer(buffer: Buffer, options: Option<ParseOptionsJs>) -> NapiResult<Manifest> {
+    let data = copy_buffer(&buffer);
+    process_manifest_data_with(data, &parse_options(options)?).map_err(|e| napi::Error::from_reason(e.to_string()))
+}
+
+/// The meta fields list views show, from a manifest buffer; null when the
+/// manifest has no readable meta
+#[napi]
+pub fn parse_meta_summary(buffer: Buffer, options: Option<ParseOptionsJs>) -> NapiResult<Option<dto::MetaSummary>> {
+    match crate::parse_meta(&buffer, &parse_options(options)?) {
+        Ok((_, meta)) => Ok(Some((&meta).into())),
+        Err(ManifestError::Meta { .. }) => Ok(None),
+        Err(e) => Err(napi::Error::from_reason(e.to_string())),
+    }
+}
+
+/// Read a JSON manifest from a path or buffer as stored, without converting
+/// it: file entries keep their blob strings and no chunk list is made up
+#[napi]
+pub fn parse_json_manifest(input: Either<String, Buffer>) -> NapiResult<dto::JsonManifest> {
+    let manifest = match input {
+        Either::A(path) => std::fs::read(path)
+            .map_err(ManifestError::from)
+            .and_then(|data| JsonManifest::from_slice(&data)),
+        Either::B(buffer) => JsonManifest::from_slice(&buffer),
+    };
+    manifest.map(Into::into).map_err(|e| napi::Error::from_reason(e.to_string()))
+}
+
+/// Hex SHA-256 fingerprint of a manifest buffer, equal for byte-identical
+/// manifests; computed without parsing, for rejecting duplicate uploads
+#[napi]
+pub fn manifest_fingerprint(buffer: Buffer) -> String {
+    fingerprint::fingerprint_hex(&buffer)
+}
+
+/// Identify a manifest buffer's format without parsing it
+#[napi(js_name = "detectFormat")]
+pub fn detect_format_napi(buffer: Buffer) -> detect::ManifestFormatInfo {
+    detect_format(&buffer).into()
+}
+
+/// Version, sizes and storage flags of the binary manifest at `path`, read
+/// from its header without touching the payload
+#[napi]
+pub fn probe_manifest(path: String) -> NapiResult<probe::ManifestProbeInfo> {
+    crate::probe(path).map(Into::into).map_err(|e| napi::Error::from_reason(e.to_string()))
+}
+
+/// Triage a manifest buffer: ok, truncated, corrupt or unsupported
+#[napi]
+pub fn assess_manifest(buffer: Buffer) -> health::ManifestHealthInfo {
+    health::assess(&buffer).into()
+}
+
+/// Build a chunk availability bitmap for a manifest from a local chunk store
+#[napi]
+pub fn chunk_availability_from_store(manifest: dto::Manifest, store_root: String) -> NapiResult<Buffer> {
+    let manifest = Manifest::from(manifest);
+    let feature_level = manifest.meta.as_ref().map(|m| m.feature_level).unwrap_or_default();
+    let store = ChunkStore::open(store_root, feature_level)
+        .map_err(|e| napi::Error::from_reason(e.to_string()))?;
+    Ok(ChunkAvailability::from_store(&manifest, &store).to_bytes().into())
+}
+
+/// Summarize how complete a manifest's download is according to a bitmap
+#[napi]
+pub fn chunk_availability_summary(
+    manifest: dto::Manifest,
+    bitmap: Buffer,
+) -> NapiResult<store::availability::ChunkAvailabilitySummary> {
+    let manifest = Manifest::from(manifest);
+    let availability = ChunkAvailability::from_bytes(&bitmap)
+        .map_err(|e| napi::Error::from_reason(e.to_string()))?;
+    availability
+        .summary(&manifest)
+        .map_err(|e| napi::Error::from_reason(e.to_string()))
+}
+
+/// A parsed manifest kept on the Rust side, with lazily built lookup
+/// structures shared across calls
+#[napi]
+pub struct ManifestHandle {
+    manifest: std::sync::Arc<Manifest>,
+    directory_index: std::sync::OnceLock<std::sync::Arc<index::directory::DirectoryIndex>>,
+    _live: Live,
+}
+
+impl ManifestHandle {
+    fn from_manifest(manifest: Manifest) -> Self {
+        Self::from_arc(std::sync::Arc::new(manifest))
+    }
+
+    fn from_arc(manifest: std::sync::Arc<Manifest>) -> Self {
+        Self {
+            manifest,
+            directory_index: std::sync::OnceLock::new(),
+            _live: Live::new(&COUNTERS.manifest_handles),
+        }
+    }
+
+    fn directory_index(&self) -> std::sync::Arc<index::directory::DirectoryIndex> {
+        self.directory_index
+            .get_or_init(|| {
+                let index = match &self.manifest.file_list {
+                    Some(file_list) => index::directory::DirectoryIndex::build(file_list),
+                    None => index::directory::DirectoryIndex::build(&Default::default()),
+                };
+                std::sync::Arc::new(index)
+            })
+            .clone()
+    }
+
+    fn file_list(&self) -> &[types::file::FileManifest] {
+        self.manifest
+            .file_list
+            .as_ref()
+            .map(|f| f.file_manifest_list.as_slice())
+            .unwrap_or_default()
+    }
+
+    fn chunks(&self) -> &[types::chunk::Chunk] {
+        self.manifest
+            .chunk_list
+            .as_ref()
+            .map(|c| c.elements.as_slice())
+            .unwrap_or_default()
+    }
+}
+
+#[napi]
+impl ManifestHandle {
+    /// Parse a manifest file into a handle
+    #[napi(factory)]
+    pub fn open(path: String, options: Option<ParseOptionsJs>) -> napi::Result<Self> {
+        Ok(Self::from_manifest(load_manifest(path, options)?))
+    }
+
+    /// Parse a manifest buffer into a handle
+    #[napi(factory)]
+    pub fn from_buffer(buffer: Buffer, options: Option<ParseOptionsJs>) -> napi::Result<Self> {
+        Ok(Self::from_manifest(parse_buffer(buffer, options)?))
+    }
+
+    /// Copy of the full parsed manifest
+    #[napi]
+    pub fn manifest(&self) -> dto::Manifest {
+        self.manifest.as_ref().into()
+    }
+
+    /// The meta fields list views show, without prerequisites and launch
+    /// commands
+    #[napi]
+    pub fn meta_summary(&self) -> Option<dto::MetaSummary> {
+        self.manifest.meta.as_ref().map(Into::into)
+    }
+
+    /// Filenames of every file below `dir`
+    #[napi]
+    pub fn files_under(&self, dir: String) -> Vec<String> {
+        let files = self.file_list();
+        self.directory_index()
+            .files_under(&dir)
+            .into_iter()
+            .map(|i| files[i as usize].filename.trim_end_matches('\0').to_string())
+            .collect()
+    }
+
+    /// Total size in bytes of every file below `dir`
+    #[napi]
+    pub fn dir_size(&self, dir: String) -> i64 {
+        self.directory_index().dir_size(&dir)
+    }
+
+    /// Immediate subdirectories of `dir`
+    #[napi]
+    pub fn subdirs(&self, dir: String) -> Vec<String> {
+        self.directory_index().subdirs(&dir)
+    }
+
+    /// Estimated heap bytes held by the parsed manifest
+    #[napi]
+    pub fn memory_footprint(&self) -> i64 {
+        self.manifest.memory_footprint().total_with_inline() as i64
+    }
+
+    /// Number of files in the manifest
+    #[napi(getter)]
+    pub fn file_count(&self) -> u32 {
+        self.file_list().len() as u32
+    }
+
+    /// Number of chunks in the manifest
+    #[napi(getter)]
+    pub fn chunk_count(&self) -> u32 {
+        self.chunks().len() as u32
+    }
+
+    /// Compressed size in bytes of every chunk, i.e. a full download
+    #[napi(getter)]
+    pub fn total_download_size(&self) -> i64 {
+        self.manifest.download_size() as i64
+    }
+
+    /// Sum of the installed files' sizes in bytes
+    #[napi(getter)]
+    pub fn total_install_size(&self) -> i64 {
+        self.manifest.install_size() as i64
+    }
+
+    /// Space the install takes with `clusterSize` byte allocation units
+    /// (4096 when omitted)
+    #[napi]
+    pub fn size_on_disk(&self, cluster_size: Option<i64>) -> i64 {
+        self.manifest.size_on_disk(cluster_size.unwrap_or_default().max(0) as u64) as i64
+    }
+
+    /// Feature level from the manifest meta
+    #[napi(getter)]
+    pub fn feature_level(&self) -> Option<i32> {
+        self.manifest.meta.as_ref().map(|m| m.feature_level)
+    }
+
+    /// Build ID, computed the way Epic does when the meta has none
+    #[napi(getter)]
+    pub fn build_id(&self) -> Option<String> {
+        self.manifest.meta.as_ref().map(|m| m.build_id())
+    }
+
+    /// Prerequisites the manifest asks to install, one per ID, described
+    /// from the built-in registry
+    #[napi]
+    pub fn prerequisites(&self) -> Vec<prereq::PrerequisiteInfo> {
+        let Some(meta) = &self.manifest.meta else {
+            return Vec::new();
+        };
+        meta.prerequisites().into_iter().map(Into::into).collect()
+    }
+
+    /// The file at `path`, if any
+    #[napi]
+    pub fn file(&self, path: String) -> Option<FileHandle> {
+        let file_list = self.manifest.file_list.as_ref()?;
+        let file = file_list.find_by_path(&path)?;
+        let index = file_list.file_manifest_list.iter().position(|f| std::ptr::eq(f, file))?;
+        Some(FileHandle::new(self.manifest.clone(), index))
+    }
+
+    /// Which chunk each byte range of every file comes from, run-length
+    /// encoded for visualizations
+    #[napi]
+    pub fn composition(&self) -> CompositionJs {
+        export::composition::composition(&self.manifest).into()
+    }
+
+    /// [`ManifestHandle::composition`] of the file at `path`, if any
+    #[napi]
+    pub fn file_composition(&self, path: String) -> Option<CompositionJs> {
+        export::composition::file_composition(&self.manifest, &path).map(Into::into)
+    }
+
+    /// The manifest as text in Epic's JSON manifest format
+    #[napi]
+    pub fn to_json_manifest(&self) -> napi::Result<String> {
+        self.manifest
+            .to_json_manifest()
+            .and_then(|json| Ok(serde_json::to_string(&json)?))
+            .map_err(|e| napi::Error::from_reason(e.to_string()))
+    }
+
+    /// The manifest as JSON, with 64-bit values (chunk and file sizes, the
+    /// rolling hash) as `"string"` (default), `"number"` or `"tagged"`
+    /// (`{"$num": "123"}`)
+    #[napi]
+    pub fn to_json(&self, int64: Option<String>) -> napi::Result<String> {
+        let format = match int64.as_deref() {
+            None | Some("string") => Int64Format::String,
+            Some("number") => Int64Format::Number,
+            Some("tagged") => Int64Format::Tagged,
+            Some(other) => return Err(napi::Error::from_reason(format!("unknown int64 format: {}", other))),
+        };
+        self.manifest.to_json_with(format).map_err(|e| napi::Error::from_reason(e.to_string()))
+    }
+
+    /// The manifest as a lockfile: sorted text lines for keeping builds in
+    /// git
+    #[napi]
+    pub fn to_lockfile(&self) -> napi::Result<String> {
+        export::lockfile::Lockfile::from_manifest(&self.manifest)
+            .map(|lockfile| lockfile.to_string())
+            .map_err(|e| napi::Error::from_reason(e.to_string()))
+    }
+
+    /// Every file in file list order, `batchSize` (default 1000) at a time.
+    /// Each batch is converted when it is asked for, so
+    /// `for await (const batch of handle.files())` never holds more than
+    /// one batch on the JS side
+    #[napi]
+    pub fn files(&self, batch_size: Option<u32>) -> FileBatches {
+        FileBatches {
+            manifest: self.manifest.clone(),
+            next: 0,
+            batch_size: batch_size.unwrap_or(DEFAULT_FILE_BATCH_SIZE).max(1) as usize,
+            _live: Live::new(&COUNTERS.file_batches),
+        }
+    }
+
+    /// The file at `index` in file list order, if any
+    #[napi]
+    pub fn file_at(&self, index: u32) -> Option<FileHandle> {
+        ((index as usize) < self.file_list().len()).then(|| FileHandle::new(self.manifest.clone(), index as usize))
+    }
+
+    /// The chunk with `guid`, given hyphenated or in Epic's 32-hex form
+    #[napi]
+    pub fn chunk(&self, guid: String) -> Option<ChunkHandle> {
+        let guid = types::chunk::normalize_guid(&guid)?;
+        let chunk_list = self.manifest.chunk_list.as_ref()?;
+        let index = *chunk_list.chunk_lookup.get(&guid)? as usize;
+        Some(ChunkHandle::new(self.manifest.clone(), index))
+    }
+}
+
+const DEFAULT_FILE_BATCH_SIZE: u32 = 1000;
+
+/// Files of a [`ManifestHandle`] as arrays of plain objects, from
+/// `handle.files()`; the position is kept on the Rust side
+#[napi(iterator)]
+pub struct FileBatches {
+    manifest: std::sync::Arc<Manifest>,
+    next: usize,
+    batch_size: usize,
+    _live: Live,
+}
+
+#[napi]
+impl Generator for FileBatches {
+    type Yield = Vec<dto::FileManifest>;
+    type Next = ();
+    type Return = ();
+
+    fn next(&mut self, _value: Option<()>) -> Option<Self::Yield> {
+        let files = self.manifest.file_list.as_ref()?.file_manifest_list.get(self.next..)?;
+        if files.is_empty() {
+            return None;
+        }
+        let batch: Vec<dto::FileManifest> = files.iter().take(self.batch_size).cloned().map(Into::into).collect();
+        self.next += batch.len();
+        Some(batch)
+    }
+}
+
+/// One file of a [`ManifestHandle`], read through getters instead of copied
+#[napi]
+pub struct FileHandle {
+    manifest: std::sync::Arc<Manifest>,
+    index: usize,
+    _live: Live,
+}
+
+impl FileHandle {
+    fn new(manifest: std::sync::Arc<Manifest>, index: usize) -> Self {
+        Self {
+            manifest,
+            index,
+            _live: Live::new(&COUNTERS.file_handles),
+        }
+    }
+
+    fn file(&self) -> &types::file::FileManifest {
+        &self.manifest.file_list.as_ref().expect("handle points into the file list").file_manifest_list[self.index]
+    }
+}
+
+#[napi]
+impl FileHandle {
+    #[napi(getter)]
+    pub fn filename(&self) -> String {
+        self.file().filename.trim_end_matches('\0').to_string()
+    }
+
+    #[napi(getter)]
+    pub fn symlink_target(&self) -> String {
+        self.file().symlink_target.trim_end_matches('\0').to_string()
+    }
+
+    #[napi(getter)]
+    pub fn sha_hash(&self) -> String {
+        self.file().sha_hash.clone()
+    }
+
+    #[napi(getter)]
+    pub fn file_size(&self) -> i64 {
+        self.file().file_size
+    }
+
+    /// Whether chunk parts of the file were lost, making `fileSize` a lower bound
+    #[napi(getter)]
+    pub fn size_unknown(&self) -> bool {
+        self.file().size_unknown
+    }
+
+    #[napi(getter)]
+    pub fn mime_type(&self) -> String {
+        self.file().mime_type.clone()
+    }
+
+    #[napi(getter)]
+    pub fn install_tags(&self) -> Vec<String> {
+        self.file().install_tags.clone()
+    }
+
+    #[napi(getter)]
+    pub fn file_meta_flags(&self) -> u8 {
+        self.file().file_meta_flags
+    }
+
+    #[napi(getter)]
+    pub fn is_readonly(&self) -> bool {
+        self.file().is_readonly()
+    }
+
+    #[napi(getter)]
+    pub fn is_compressed(&self) -> bool {
+        self.file().is_compressed()
+    }
+
+    #[napi(getter)]
+    pub fn is_unix_executable(&self) -> bool {
+        self.file().is_unix_executable()
+    }
+
+    /// Number of chunk parts the file is assembled from
+    #[napi(getter)]
+    pub fn chunk_part_count(&self) -> u32 {
+        self.file().chunk_parts.len() as u32
+    }
+
+    /// GUIDs of the chunks the file is assembled from, in part order
+    #[napi]
+    pub fn chunk_guids(&self) -> Vec<String> {
+        self.file().chunk_parts.iter().map(|p| p.parent_guid.clone()).collect()
+    }
+}
+
+/// One chunk of a [`ManifestHandle`], read through getters instead of copied
+#[napi]
+pub struct ChunkHandle {
+    manifest: std::sync::Arc<Manifest>,
+    index: usize,
+    _live: Live,
+}
+
+impl ChunkHandle {
+    fn new(manifest: std::sync::Arc<Manifest>, index: usize) -> Self {
+        Self {
+            manifest,
+            index,
+            _live: Live::new(&COUNTERS.chunk_handles),
+        }
+    }
+
+    fn chunk(&self) -> &types::chunk::Chunk {
+        &self.manifest.chunk_list.as_ref().expect("handle points into the chunk list").elements[self.index]
+    }
+}
+
+#[napi]
+impl ChunkHandle {
+    #[napi(getter)]
+    pub fn guid(&self) -> String {
+        self.chunk().guid()
+    }
+
+    /// The GUID as Epic tooling prints it (32 upper-case hex digits)
+    #[napi(getter)]
+    pub fn guid_epic(&self) -> String {
+        self.chunk().guid_epic_format()
+    }
+
+    #[napi(getter)]
+    pub fn hash(&self) -> String {
+        self.chunk().hash()
+    }
+
+    #[napi(getter)]
+    pub fn sha_hash(&self) -> String {
+        self.chunk().sha_hash()
+    }
+
+    #[napi(getter)]
+    pub fn group(&self) -> u8 {
+        self.chunk().group
+    }
+
+    #[napi(getter)]
+    pub fn window_size(&self) -> u32 {
+        self.chunk().window_size
+    }
+
+    /// Compressed size of the chunk file in bytes
+    #[napi(getter)]
+    pub fn file_size(&self) -> i64 {
+        self.chunk().file_size.parse().unwrap_or_default()
+    }
+
+    /// Relative CDN path of the chunk for manifests at `featureLevel`,
+    /// defaulting to the manifest's own
+    #[napi]
+    pub fn cdn_path(&self, feature_level: Option<i32>) -> String {
+        let level = feature_level
+            .or_else(|| self.manifest.meta.as_ref().map(|m| m.feature_level))
+            .unwrap_or_default();
+        self.chunk().cdn_path(level)
+    }
+}
+
+/// A build known to a `ManifestIndex`
+#[napi(object)]
+pub struct IndexedBuildJs {
+    pub build_id: String,
+    pub app_name: String,
+    pub build_version: String,
+}
+
+impl From<&index::manifests::IndexedBuild> for IndexedBuildJs {
+    fn from(build: &index::manifests::IndexedBuild) -> Self {
+        Self {
+            build_id: build.build_id.clone(),
+            app_name: build.app_name.clone(),
+            build_version: build.build_version.clone(),
+        }
+    }
+}
+
+/// Parsed manifests kept by content hash, in memory or in a directory
+#[napi(js_name = "ManifestStore")]
+pub struct ManifestStoreJs {
+    store: std::sync::Arc<dyn store::manifests::ManifestStore>,
+}
+
+#[napi]
+impl ManifestStoreJs {
+    /// A store in memory; handles opened from it share the stored manifest
+    #[napi(factory)]
+    pub fn memory() -> Self {
+        Self {
+            store: std::sync::Arc::new(store::manifests::MemoryManifestStore::new()),
+        }
+    }
+
+    /// A store of normalized manifest files below `root`, created if needed
+    #[napi(factory)]
+    pub fn open(root: String) -> napi::Result<Self> {
+        let store =
+            store::manifests::DirManifestStore::open(root).map_err(|e| napi::Error::from_reason(e.to_string()))?;
+        Ok(Self {
+            store: std::sync::Arc::new(store),
+        })
+    }
+
+    /// Store the handle's manifest, returning its content hash
+    #[napi]
+    pub fn put(&self, handle: &ManifestHandle) -> napi::Result<String> {
+        self.store
+            .put(handle.manifest.clone())
+            .map_err(|e| napi::Error::from_reason(e.to_string()))
+    }
+
+    /// A handle on the manifest stored under `hash`, or `null` if the store
+    /// has none
+    #[napi]
+    pub fn get(&self, hash: String) -> napi::Result<Option<ManifestHandle>> {
+        let manifest = self.store.get(&hash).map_err(|e| napi::Error::from_reason(e.to_string()))?;
+        Ok(manifest.map(ManifestHandle::from_arc))
+    }
+
+    #[napi]
+    pub fn has(&self, hash: String) -> napi::Result<bool> {
+        self.store.contains(&hash).map_err(|e| napi::Error::from_reason(e.to_string()))
+    }
+}
+
+/// File paths and chunk GUIDs of many builds, for cross-build search
+#[napi(js_name = "ManifestIndex")]
+pub struct ManifestIndexJs {
+    index: index::manifests::ManifestIndex,
+}
+
+#[napi]
+impl ManifestIndexJs {
+    #[napi(constructor)]
+    pub fn new() -> Self {
+        Self {
+            index: Default::default(),
+        }
+    }
+
+    /// Read an index saved with `toJson`
+    #[napi(factory)]
+    pub fn from_json(json: String) -> napi::Result<Self> {
+        let index =
+            index::manifests::ManifestIndex::from_json(&json).map_err(|e| napi::Error::from_reason(e.to_string()))?;
+        Ok(Self { index })
+    }
+
+    /// Index the manifest at `path`, replacing an earlier build with the same id
+    #[napi]
+    pub fn add_file(&mut self, path: String, options: Option<ParseOptionsJs>) -> napi::Result<IndexedBuildJs> {
+        Ok(self.index.add(&load_manifest(path, options)?).into())
+    }
+
+    /// Index a manifest buffer, replacing an earlier build with the same id
+    #[napi]
+    pub fn add_buffer(&mut self, buffer: Buffer, options: Option<ParseOptionsJs>) -> napi::Result<IndexedBuildJs> {
+        Ok(self.index.add(&parse_buffer(buffer, options)?).into())
+    }
+
+    /// Forget a build; returns whether it was indexed
+    #[napi]
+    pub fn remove(&mut self, build_id: String) -> bool {
+        self.index.remove(&build_id)
+    }
+
+    #[napi]
+    pub fn builds(&self) -> Vec<IndexedBuildJs> {
+        self.index.builds().iter().map(Into::into).collect()
+    }
+
+    /// Builds containing a file at exactly `path`
+    #[napi]
+    pub fn builds_with_file(&self, path: String) -> Vec<IndexedBuildJs> {
+        self.index.builds_with_file(&path).into_iter().map(Into::into).collect()
+    }
+
+    /// Builds whose chunk list has `guid`, hyphenated or in Epic's form
+    #[napi]
+    pub fn builds_with_chunk(&self, guid: String) -> Vec<IndexedBuildJs> {
+        self.index.builds_with_chunk(&guid).into_iter().map(Into::into).collect()
+    }
+
+    /// Indexed paths starting with `prefix`, in path order
+    #[napi]
+    pub fn files_with_prefix(&self, prefix: String) -> Vec<String> {
+        self.index.files_with_prefix(&prefix).map(|(path, _)| path.to_string()).collect()
+    }
+
+    #[napi]
+    pub fn to_json(&self) -> napi::Result<String> {
+        self.index.to_json().map_err(|e| napi::Error::from_reason(e.to_string()))
+    }
+}
+
+impl Default for ManifestIndexJs {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+type VerifyListener =
+    napi::threadsafe_function::ThreadsafeFunction<serde_json::Value, (), serde_json::Value, napi::Status, false>;
+
+/// Verifies an installation against a manifest on a background thread,
+/// emitting `progress`, `file-ok`, `file-corrupt` and `done` events
+#[napi]
+pub struct InstallVerifier {
+    manifest: std::sync::Arc<Manifest>,
+    install_dir: String,
+    options: VerifyOptions,
+    listeners: Vec<(String, std::sync::Arc<VerifyListener>)>,
+    cancel: std::sync::Arc<std::sync::atomic::AtomicBool>,
+    _live: Live,
+}
+
+#[napi]
+impl InstallVerifier {
+    #[napi(constructor)]
+    pub fn new(manifest: dto::Manifest, install_dir: String, options: Option<VerifyOptionsJs>) -> Self {
+        Self {
+            manifest: std::sync::Arc::new(manifest.into()),
+            install_dir,
+            options: options.unwrap_or_default().into(),
+            listeners: Vec::new(),
+            cancel: Default::default(),
+            _live: Live::new(&COUNTERS.verifiers),
+        }
+    }
+
+    /// Register a listener for `progress`, `file-ok`, `file-corrupt` or `done`
+    #[napi]
+    pub fn on(&mut self, event: String, listener: VerifyListener) -> NapiResult<()> {
+        if !["progress", "file-ok", "file-corrupt", "done"].contains(&event.as_str()) {
+            return Err(napi::Error::from_reason(format!("unknown event: {}", event)));
+        }
+        self.listeners.push((event, std::sync::Arc::new(listener)));
+        Ok(())
+    }
+
+    /// Start verifying; listeners registered so far receive the events
+    #[napi]
+    pub fn start(&mut self) {
+        use napi::threadsafe_function::ThreadsafeFunctionCallMode;
+        use serde_json::json;
+        use verify::install::{verify_install_with, VerifyEvent};
+
+        let listeners = std::mem::take(&mut self.listeners);
+        let manifest = self.manifest.clone();
+        let install_dir = self.install_dir.clone();
+        let options = self.options.clone();
+        let cancel = self.cancel.clone();
+
+        std::thread::spawn(move || {
+            let emit = |event: &str, payload: serde_json::Value| {
+                for (name, listener) in &listeners {
+                    if name == event {
+                        listener.call(payload.clone(), ThreadsafeFunctionCallMode::Blocking);
+                    }
+                }
+            };
+
+            let result = verify_install_with(&manifest, &install_dir, &options, Some(&cancel), |event| match event {
+                VerifyEvent::Progress(progress) => {
+                    emit("progress", serde_json::to_value(progress).unwrap_or_default())
+                }
+                VerifyEvent::FileOk { filename } => emit("file-ok", json!({ "filename": filename })),
+                VerifyEvent::FileCorrupt(corrupt) => emit(
+                    "file-corrupt",
+                    json!({ "filename": corrupt.filename, "reason": corrupt.reason.to_string() }),
+                ),
+                VerifyEvent::Done(report) => emit(
+                    "done",
+                    json!({
+                        "filesTotal": report.files_total,
+                        "filesOk": report.files_ok,
+                        "bytesVerified": report.bytes_verified,
+                        "corruptFiles": report.corrupt.iter().map(|c| &c.filename).collect::<Vec<_>>(),
+                        "cancelled": report.cancelled,
+                    }),
+                ),
+            });
+            if let Err(e) = result {
+                emit("done", json!({ "error": e.to_string() }));
+            }
+        });
+    }
+
+    /// Stop after the file currently being verified
+    #[napi]
+    pub fn cancel(&self) {
+        self.cancel.store(true, std::sync::atomic::Ordering::Relaxed);
+    }
+}
@@ -0,0 +1,223 @@
+use log::{debug, log_enabled, Level};
+use std::time::{Duration, Instant};
+
+use crate::crypto::AesKey;
+use crate::error::ManifestError;
+use crate::hooks::Section;
+use crate::parser::reader::MAX_ARRAY_LEN;
+use crate::types::chunk::GuidFormat;
+use crate::types::file::MAX_CHUNK_PARTS;
+use crate::types::json_manifest::JsonConversion;
+
+/// Elements read between deadline checks inside a section.
+pub(crate) const DEADLINE_CHECK_INTERVAL: u32 = 256;
+
+/// Environment variable choosing the [`ByteTrace`] for parses that don't set
+/// one: `full` (or `1`), `redacted`, or `off`.
+pub const TRACE_BYTES_ENV: &str = "EGDATA_PARSER_TRACE_BYTES";
+
+/// Bytes shown per dump.
+const TRACE_BYTES_LEN: usize = 16;
+
+/// Whether debug logs include raw manifest bytes, independent of the log
+/// level.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ByteTrace {
+    /// No dumps.
+    #[default]
+    Off,
+    /// Log how many bytes a dump would show, but not the bytes themselves.
+    Redacted,
+    /// Log the leading bytes in hex.
+    Full,
+}
+
+impl ByteTrace {
+    /// `"off"`, `"redacted"` or `"full"`, ignoring ASCII case; `"0"` and
+    /// `"1"` also work.
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "" | "0" | "off" | "false" => Some(ByteTrace::Off),
+            "redacted" | "redact" => Some(ByteTrace::Redacted),
+            "1" | "full" | "true" | "on" => Some(ByteTrace::Full),
+            _ => None,
+        }
+    }
+
+    /// From [`TRACE_BYTES_ENV`]; unset or unrecognized values mean off.
+    pub fn from_env() -> Self {
+        std::env::var(TRACE_BYTES_ENV)
+            .ok()
+            .and_then(|value| Self::parse(&value))
+            .unwrap_or_default()
+    }
+
+    /// Debug-log the start of `bytes` as `"{what} starts with: ..."`.
+    pub(crate) fn dump(self, what: &str, bytes: &[u8]) {
+        if self == ByteTrace::Off || !log_enabled!(Level::Debug) {
+            return;
+        }
+        let shown = &bytes[..bytes.len().min(TRACE_BYTES_LEN)];
+        match self {
+            ByteTrace::Full => debug!("{} starts with: {:02x?}", what, shown),
+            _ => debug!("{} starts with: <{} bytes redacted>", what, shown.len()),
+        }
+    }
+}
+
+/// How FString bytes are turned into Rust strings.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StringPolicy {
+    /// Fail with `InvalidData` on malformed UTF-8/UTF-16 instead of
+    /// substituting U+FFFD.
+    pub strict: bool,
+    /// Strip the trailing NUL terminators UE serializes into FStrings.
+    pub trim_nul: bool,
+    /// Largest accepted string body, in bytes as stored.
+    pub max_len: usize,
+}
+
+impl StringPolicy {
+    /// Exactly what is stored: lossy decoding, NULs kept, 1 GiB cap.
+    pub const RAW: StringPolicy = StringPolicy {
+        strict: false,
+        trim_nul: false,
+        max_len: 1024 * 1024 * 1024,
+    };
+
+    /// Like the default, but rejects malformed text.
+    pub fn strict() -> Self {
+        Self {
+            strict: true,
+            ..Self::default()
+        }
+    }
+}
+
+impl Default for StringPolicy {
+    fn default() -> Self {
+        Self {
+            trim_nul: true,
+            ..Self::RAW
+        }
+    }
+}
+
+/// Where parsing goes on after an unreadable meta section.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MetaRecovery {
+    /// Unless the chunk list plausibly starts where the meta reader stopped,
+    /// scan the payload for the offset whose section sizes chain through
+    /// the chunk list, file list and custom fields to its end, and go on
+    /// there. Without such an offset, go on where the reader stopped.
+    #[default]
+    Resync,
+    /// Go on where the meta reader stopped: after the meta's declared size,
+    /// or after its size field if that was out of range.
+    Continue,
+}
+
+/// Largest counts accepted from a manifest; a larger one fails the parse
+/// with [`ManifestError::Invalid`] before anything is allocated for it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ParseLimits {
+    pub max_chunks: u32,
+    pub max_files: u32,
+    /// Chunk parts of a single file. A file over the limit counts as
+    /// corrupt, like one with unreadable chunk parts.
+    pub max_chunk_parts: u32,
+}
+
+impl Default for ParseLimits {
+    fn default() -> Self {
+        Self {
+            max_chunks: MAX_ARRAY_LEN,
+            max_files: MAX_ARRAY_LEN,
+            max_chunk_parts: MAX_CHUNK_PARTS,
+        }
+    }
+}
+
+/// Knobs controlling how manifests are parsed.
+#[derive(Debug, Clone, Default)]
+pub struct ParseOptions {
+    /// Applied to every string read from a binary manifest. Its `trim_nul`
+    /// also governs JSON manifests, so parsed names compare equal to plain
+    /// Rust/JS strings either way.
+    pub strings: StringPolicy,
+    /// When the input doesn't start with a manifest, look for one further in
+    /// (see [`crate::detect::scan_for_manifest`]) and parse from there.
+    pub scan_for_manifest: bool,
+    /// Format of the chunk GUIDs in the parsed manifest.
+    pub guid_format: GuidFormat,
+    /// Decode each file's `file_meta_flags` into its `flags`. Off by default
+    /// to keep serialized manifests small.
+    pub file_flags: bool,
+    /// Give up with [`ManifestError::Timeout`] once this passes. Checked
+    /// between sections and every few hundred elements within them; JSON
+    /// manifests are parsed in one step and only checked before it.
+    pub deadline: Option<Instant>,
+    /// Read the chunk and file lists through a faster path for well-formed
+    /// manifests, such as those from Epic's CDN. Only takes effect together
+    /// with strict strings ([`StringPolicy::strict`]); `limits` and
+    /// `strict` apply on either path. Sections the fast path does not
+    /// expect (newer versions, unknown trailing data, corrupt chunk parts,
+    /// counts over the limits) are read the regular way, so the result is
+    /// the same either way; only malformed input gets slower.
+    pub trusted: bool,
+    /// Hex dumps of payload bytes in debug logs. `None` follows
+    /// [`TRACE_BYTES_ENV`].
+    pub trace_bytes: Option<ByteTrace>,
+    /// How values JSON manifests don't store are filled in.
+    pub json: JsonConversion,
+    /// Fail with [`ManifestError::Meta`] when the meta section can't be
+    /// read, instead of returning the manifest with `meta: None` and the
+    /// failure in [`crate::ParseReport::meta`].
+    pub require_meta: bool,
+    /// Where parsing goes on when the meta section can't be read.
+    pub meta_recovery: MetaRecovery,
+    /// Key for manifests stored encrypted, which fail with
+    /// [`ManifestError::EncryptedManifest`] without one.
+    pub decryption_key: Option<AesKey>,
+    pub limits: ParseLimits,
+    /// Fail with [`ManifestError::Strict`] on damage the parser otherwise
+    /// works around: sections shorter than their declared size, corrupt
+    /// chunk parts, unreadable custom fields, and fields of a newer layout
+    /// cut short. An unreadable meta section fails as with
+    /// [`ParseOptions::require_meta`], and a payload hash mismatch as with
+    /// [`ParseOptions::require_payload_hash`].
+    pub strict: bool,
+    /// Fail with [`ManifestError::Warning`] on the first
+    /// [`crate::hooks::ParseWarning`], such as a payload hash mismatch,
+    /// instead of passing it to the hooks and going on.
+    pub warnings_as_errors: bool,
+    /// Fail with [`ManifestError::Sha1Mismatch`] when the payload's SHA-1
+    /// is not the one in the header, instead of recording
+    /// [`crate::PayloadHashCheck::Mismatched`] and going on. Headers with
+    /// another hash type are not affected.
+    pub require_payload_hash: bool,
+}
+
+impl ParseOptions {
+    /// These options with a deadline `timeout` from now.
+    pub fn with_timeout(self, timeout: Duration) -> Self {
+        Self {
+            deadline: Some(Instant::now() + timeout),
+            ..self
+        }
+    }
+
+    /// The [`ByteTrace`] in effect.
+    pub(crate) fn byte_trace(&self) -> ByteTrace {
+        self.trace_bytes.unwrap_or_else(ByteTrace::from_env)
+    }
+}
+
+/// Fail with a [`ManifestError::Timeout`] carrying no partial result yet if
+/// `deadline` has passed.
+pub(crate) fn check_deadline(deadline: Option<Instant>, section: Section) -> Result<(), ManifestError> {
+    match deadline {
+        Some(deadline) if Instant::now() >= deadline => Err(ManifestError::Timeout { section, partial: None }),
+        _ => Ok(()),
+    }
+}
@@ -0,0 +1,147 @@
+//! How much CPU the crate's parallel work may use.
+//!
+//! Every parallel operation (currently install verification) takes its
+//! worker count from a [`Parallelism`]: explicitly via its options, or from
+//! the process-wide default set with [`Parallelism::set_global`]. Embedders
+//! in Node worker threads or constrained containers can bound it to a few
+//! threads, or with the `rayon` feature, hand over an existing pool so the
+//! crate never spawns threads of its own.
+
+use std::num::NonZeroUsize;
+use std::ops::ControlFlow;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{mpsc, RwLock};
+#[cfg(feature = "rayon")]
+use std::sync::Arc;
+
+static GLOBAL: RwLock<Option<Parallelism>> = RwLock::new(None);
+
+/// Worker configuration for parallel operations.
+#[derive(Debug, Clone, Default)]
+pub struct Parallelism {
+    /// Worker threads; `None` uses the available parallelism.
+    threads: Option<NonZeroUsize>,
+    #[cfg(feature = "rayon")]
+    pool: Option<Arc<rayon::ThreadPool>>,
+}
+
+impl PartialEq for Parallelism {
+    fn eq(&self, other: &Self) -> bool {
+        #[cfg(feature = "rayon")]
+        let same_pool = match (&self.pool, &other.pool) {
+            (Some(a), Some(b)) => Arc::ptr_eq(a, b),
+            (a, b) => a.is_none() && b.is_none(),
+        };
+        #[cfg(not(feature = "rayon"))]
+        let same_pool = true;
+        self.threads == other.threads && same_pool
+    }
+}
+
+impl Eq for Parallelism {}
+
+impl Parallelism {
+    /// One thread: everything runs on the caller's.
+    pub fn sequential() -> Self {
+        Self::with_threads(1)
+    }
+
+    /// At most `threads` workers (0 means the available parallelism).
+    pub fn with_threads(threads: usize) -> Self {
+        Self {
+            threads: NonZeroUsize::new(threads),
+            #[cfg(feature = "rayon")]
+            pool: None,
+        }
+    }
+
+    /// Run parallel work on `pool` instead of spawning threads; its size is
+    /// the worker count.
+    #[cfg(feature = "rayon")]
+    pub fn with_pool(pool: Arc<rayon::ThreadPool>) -> Self {
+        Self {
+            threads: NonZeroUsize::new(pool.current_num_threads()),
+            pool: Some(pool),
+        }
+    }
+
+    /// The process-wide default, used when an operation is not given one.
+    pub fn global() -> Self {
+        GLOBAL.read().unwrap_or_else(|e| e.into_inner()).clone().unwrap_or_default()
+    }
+
+    /// Replace the process-wide default.
+    pub fn set_global(parallelism: Parallelism) {
+        *GLOBAL.write().unwrap_or_else(|e| e.into_inner()) = Some(parallelism);
+    }
+
+    /// Effective worker count.
+    pub fn threads(&self) -> usize {
+        self.threads
+            .or_else(|| std::thread::available_parallelism().ok())
+            .map_or(1, NonZeroUsize::get)
+    }
+
+    /// Compute `work(0..count)` on the workers and hand each result to
+    /// `sink` on the calling thread, in completion order. Once `sink`
+    /// breaks, no new items are started; items already running finish.
+    pub(crate) fn run_indexed<T: Send>(
+        &self,
+        count: usize,
+        work: impl Fn(usize) -> T + Sync,
+        mut sink: impl FnMut(usize, T) -> ControlFlow<()>,
+    ) {
+        let workers = self.threads().min(count);
+        if workers <= 1 {
+            for index in 0..count {
+                if sink(index, work(index)).is_break() {
+                    break;
+                }
+            }
+            return;
+        }
+
+        let next = AtomicUsize::new(0);
+        let stop = AtomicBool::new(false);
+        let (tx, rx) = mpsc::channel();
+        let worker = |tx: mpsc::Sender<(usize, T)>| {
+            while !stop.load(Ordering::Relaxed) {
+                let index = next.fetch_add(1, Ordering::Relaxed);
+                if index >= count || tx.send((index, work(index))).is_err() {
+                    break;
+                }
+            }
+        };
+        let worker = &worker;
+        let mut drain = || {
+            for (index, result) in rx.iter() {
+                if sink(index, result).is_break() {
+                    stop.store(true, Ordering::Relaxed);
+                    break;
+                }
+            }
+        };
+
+        #[cfg(feature = "rayon")]
+        if let Some(pool) = &self.pool {
+            pool.in_place_scope(|scope| {
+                for _ in 0..workers {
+                    let tx = tx.clone();
+                    scope.spawn(move |_| worker(tx));
+                }
+                drop(tx);
+                drain();
+            });
+            return;
+        }
+
+        std::thread::scope(|scope| {
+            for _ in 0..workers {
+                let tx = tx.clone();
+                scope.spawn(move || worker(tx));
+            }
+            drop(tx);
+            drain();
+        });
+    }
+}
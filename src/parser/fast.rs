@@ -0,0 +1,285 @@
+//! The trusted fast path (see [`crate::ParseOptions::trusted`]).
+//!
+//! Reads the chunk and file lists straight out of the payload: no copy of
+//! the section body, no buffer per value and no intermediate columns. It
+//! only takes layouts [`crate::schema`] fully describes and returns `None`
+//! for anything else (a newer data version, bytes past the known fields, a
+//! chunk part the regular parser would report, malformed text, a passed
+//! deadline). The caller then reads the section the regular way, so the
+//! parsed manifest never depends on which path ran.
+
+use std::collections::HashMap;
+use std::io::Cursor;
+use std::time::Instant;
+use uuid::Uuid;
+
+use crate::format::CHUNK_PART_BASE_SIZE;
+use crate::options::{ParseLimits, StringPolicy, DEADLINE_CHECK_INTERVAL};
+use crate::parser::reader::{bounded_capacity, ReadExt, MAX_ARRAY_LEN};
+use crate::schema::{self, SectionSchema, MAX_SECTION_SIZE};
+use crate::types::cache::Cached;
+use crate::types::chunk::{Chunk, ChunkDataList, ChunkPart, MAX_CHUNK_PART_SIZE};
+use crate::types::file::{FileManifest, FileManifestList};
+
+/// Bounds-checked little-endian reads over a byte slice.
+struct Bytes<'a> {
+    buf: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Bytes<'a> {
+    fn remaining(&self) -> usize {
+        self.buf.len() - self.pos
+    }
+
+    fn take(&mut self, n: usize) -> Option<&'a [u8]> {
+        let bytes = self.buf.get(self.pos..self.pos.checked_add(n)?)?;
+        self.pos += n;
+        Some(bytes)
+    }
+
+    fn array<const N: usize>(&mut self) -> Option<[u8; N]> {
+        self.take(N)?.try_into().ok()
+    }
+
+    fn u8(&mut self) -> Option<u8> {
+        self.take(1).map(|b| b[0])
+    }
+
+    fn u32(&mut self) -> Option<u32> {
+        self.array().map(u32::from_le_bytes)
+    }
+
+    fn guid(&mut self) -> Option<String> {
+        self.array().map(|b| Uuid::from_bytes(b).to_string())
+    }
+
+    /// An element count passing the checks of [`ReadExt::check_count`].
+    fn count(&mut self, max: u32, min_element_size: u32) -> Option<u32> {
+        let count = self.u32()?;
+        (count <= max && count as u64 * min_element_size as u64 <= self.remaining() as u64).then_some(count)
+    }
+
+    /// [`ReadExt::fstring_with`] for strict policies.
+    fn fstring(&mut self, policy: &StringPolicy) -> Option<String> {
+        let len = i32::from_le_bytes(self.array()?);
+        if len < 0 {
+            // UTF-16 is rare in manifests; leave it to the regular reader.
+            let mut rdr = Cursor::new(self.buf);
+            rdr.set_position(self.pos as u64 - 4);
+            let value = rdr.fstring_with(policy).ok()?;
+            self.pos = rdr.position() as usize;
+            return Some(value);
+        }
+        if len as usize > policy.max_len {
+            return None;
+        }
+        let value = std::str::from_utf8(self.take(len as usize)?).ok()?;
+        let value = if policy.trim_nul { value.trim_end_matches('\0') } else { value };
+        Some(value.to_string())
+    }
+
+    fn fstring_array(&mut self, policy: &StringPolicy) -> Option<Vec<String>> {
+        let count = self.count(MAX_ARRAY_LEN, 1)?;
+        let mut items = Vec::with_capacity(bounded_capacity::<String>(count as usize));
+        for _ in 0..count {
+            items.push(self.fstring(policy)?);
+        }
+        Some(items)
+    }
+}
+
+/// A section opened on the fast path.
+struct Section<'a> {
+    body: Bytes<'a>,
+    data_size: u32,
+    data_version: u8,
+    count: u32,
+    deadline: Option<Instant>,
+}
+
+impl<'a> Section<'a> {
+    /// The section at `start` in `payload`, if it lies entirely inside it,
+    /// `schema` knows its data version and it has at most `max_count`
+    /// elements.
+    fn open(
+        payload: &'a [u8],
+        start: usize,
+        schema: &SectionSchema,
+        max_count: u32,
+        deadline: Option<Instant>,
+    ) -> Option<Self> {
+        let mut header = Bytes { buf: payload, pos: start };
+        let data_size = header.u32()?;
+        // The data size, data version and count alone take 9 bytes.
+        if !(9..=MAX_SECTION_SIZE).contains(&data_size) {
+            return None;
+        }
+        let end = start.checked_add(data_size as usize)?;
+        let mut body = Bytes {
+            buf: payload.get(..end)?,
+            pos: header.pos,
+        };
+        let data_version = body.u8()?;
+        if data_version > schema.latest_version() {
+            return None;
+        }
+        let count = body.count(max_count, 1)?;
+        Some(Self {
+            body,
+            data_size,
+            data_version,
+            count,
+            deadline,
+        })
+    }
+
+    /// Whether to give up before element `index`: checked as often as the
+    /// regular parser does.
+    fn expired(&self, index: usize) -> bool {
+        index.is_multiple_of(DEADLINE_CHECK_INTERVAL as usize) && self.deadline.is_some_and(|d| Instant::now() >= d)
+    }
+
+    /// Where the section ends, if every byte of it was read.
+    fn end(&self) -> Option<usize> {
+        (self.body.remaining() == 0).then_some(self.body.pos)
+    }
+}
+
+/// [`ChunkDataList::read`] of the section at `start`, with where it ends.
+pub(crate) fn read_chunk_list(
+    payload: &[u8],
+    start: usize,
+    limits: &ParseLimits,
+    deadline: Option<Instant>,
+) -> Option<(ChunkDataList, usize)> {
+    let mut section = Section::open(payload, start, &schema::CHUNK_LIST, limits.max_chunks, deadline)?;
+    let count = section.count as usize;
+    // Every field has a fixed size, so each column is one slice.
+    let guids = section.body.take(count.checked_mul(16)?)?;
+    let hashes = section.body.take(count * 8)?;
+    let sha_hashes = section.body.take(count * 20)?;
+    let groups = section.body.take(count)?;
+    let window_sizes = section.body.take(count * 4)?;
+    let file_sizes = section.body.take(count * 8)?;
+    let end = section.end()?;
+
+    let mut elements = Vec::with_capacity(count);
+    let mut chunk_lookup = HashMap::with_capacity(count);
+    for i in 0..count {
+        if section.expired(i) {
+            return None;
+        }
+        let guid = Uuid::from_bytes(guids[i * 16..][..16].try_into().ok()?).to_string();
+        chunk_lookup.insert(guid.clone(), i as u32);
+        elements.push(Chunk {
+            guid,
+            hash: format!("{:016x}", u64::from_le_bytes(hashes[i * 8..][..8].try_into().ok()?)),
+            sha_hash: hex::encode(&sha_hashes[i * 20..][..20]),
+            group: groups[i],
+            window_size: u32::from_le_bytes(window_sizes[i * 4..][..4].try_into().ok()?),
+            file_size: u64::from_le_bytes(file_sizes[i * 8..][..8].try_into().ok()?).to_string(),
+        });
+    }
+
+    let list = ChunkDataList {
+        data_size: section.data_size,
+        data_version: section.data_version,
+        count: section.count,
+        elements,
+        chunk_lookup,
+        extra_data: None,
+        synthetic: Vec::new(),
+    };
+    Some((list, end))
+}
+
+/// [`FileManifestList::read_with`] of the section at `start`, with where it
+/// ends. Files with chunk parts the regular parser would report as corrupt
+/// send the whole section back to it.
+pub(crate) fn read_file_list(
+    payload: &[u8],
+    start: usize,
+    chunk_list: &ChunkDataList,
+    policy: &StringPolicy,
+    limits: &ParseLimits,
+    deadline: Option<Instant>,
+) -> Option<(FileManifestList, usize)> {
+    let mut section = Section::open(payload, start, &schema::FILE_LIST, limits.max_files, deadline)?;
+    let count = section.count as usize;
+
+    let mut files = Vec::with_capacity(bounded_capacity::<FileManifest>(count));
+    for i in 0..count {
+        if section.expired(i) {
+            return None;
+        }
+        files.push(FileManifest {
+            filename: section.body.fstring(policy)?,
+            ..FileManifest::default()
+        });
+    }
+    for file in &mut files {
+        file.symlink_target = section.body.fstring(policy)?;
+    }
+    for file in &mut files {
+        file.sha_hash = hex::encode(section.body.take(20)?);
+    }
+    for file in &mut files {
+        file.file_meta_flags = section.body.u8()?;
+    }
+    for file in &mut files {
+        file.install_tags = section.body.fstring_array(policy)?;
+    }
+    for (i, file) in files.iter_mut().enumerate() {
+        if section.expired(i) {
+            return None;
+        }
+        let part_count = section.body.count(limits.max_chunk_parts, CHUNK_PART_BASE_SIZE)?;
+        let mut parts = Vec::with_capacity(bounded_capacity::<ChunkPart>(part_count as usize));
+        for _ in 0..part_count {
+            let data_size = section.body.u32()?;
+            if !(CHUNK_PART_BASE_SIZE..=MAX_CHUNK_PART_SIZE).contains(&data_size) {
+                return None;
+            }
+            let parent_guid = section.body.guid()?;
+            let offset = section.body.u32()?;
+            let size = section.body.u32()?;
+            let extra = section.body.take((data_size - CHUNK_PART_BASE_SIZE) as usize)?;
+            let &chunk_index = chunk_list.chunk_lookup.get(&parent_guid)?;
+            parts.push(ChunkPart {
+                data_size,
+                parent_guid,
+                offset,
+                size,
+                extra_data: (!extra.is_empty()).then(|| hex::encode(extra)),
+                chunk: chunk_list.elements.get(chunk_index as usize).cloned(),
+            });
+        }
+        if !parts.is_empty() {
+            file.file_size = parts.iter().map(|p| p.size as i64).sum();
+        }
+        file.chunk_parts = parts;
+    }
+    if schema::FILE_LIST.has("mime_type", section.data_version) {
+        for _ in 0..count {
+            let md5_count = section.body.count(MAX_ARRAY_LEN, 16)?;
+            section.body.take(md5_count as usize * 16)?;
+        }
+        for file in &mut files {
+            file.mime_type = section.body.fstring(policy)?;
+        }
+        section.body.take(count.checked_mul(32)?)?;
+    }
+    let end = section.end()?;
+
+    let list = FileManifestList {
+        data_size: section.data_size,
+        data_version: section.data_version,
+        count: section.count,
+        file_manifest_list: files,
+        path_order: Cached::default(),
+        extra_data: None,
+        synthetic: Vec::new(),
+    };
+    Some((list, end))
+}
@@ -1,11 +1,44 @@
 //! Tiny helpers for LE primitives and UE-style FStrings.
+//!
+//! [`ReadExt`] is public API: it is implemented for every `Read + Seek` and is
+//! meant for anyone writing their own tooling around Epic's formats (chunk
+//! files, catalogs, ...). All integers are little-endian. Reads that run out
+//! of data fail with [`io::ErrorKind::UnexpectedEof`] rather than panicking,
+//! except where a method documents tolerant behaviour.
+//!
+//! ```ignore
+//! use egdata_manifests_parser::ReadExt;
+//! use std::io::Cursor;
+//!
+//! let mut rdr = Cursor::new(bytes);
+//! let magic = rdr.u32()?;
+//! let name = rdr.fstring()?;
+//! let sizes = ReadExt::read_array(&mut rdr, |r| r.u64())?;
+//! ```
 
 use byteorder::{ByteOrder, LittleEndian};
+use crate::options::StringPolicy;
 use std::io::{self, Read, Seek, SeekFrom};
 use uuid::Uuid;
 
-/// Extension methods, implemented for every `Read`.
+/// Largest element count [`ReadExt::read_array`] accepts. Real manifests stay
+/// well below this; bigger counts come from corrupt or hostile input.
+pub const MAX_ARRAY_LEN: u32 = 1_000_000;
+
+/// Most bytes reserved up front for a count or size read from the input.
+/// Buffers for larger values grow as data actually arrives, so a corrupt
+/// count in a tiny file cannot reserve gigabytes.
+pub const MAX_PREALLOC_BYTES: usize = 64 * 1024;
+
+/// Capacity to reserve for `count` elements of `T` taken from untrusted
+/// input: `count`, capped at [`MAX_PREALLOC_BYTES`] worth of elements.
+pub(crate) fn bounded_capacity<T>(count: usize) -> usize {
+  count.min(MAX_PREALLOC_BYTES / std::mem::size_of::<T>().max(1))
+}
+
+/// Extension methods, implemented for every `Read + Seek`.
 pub trait ReadExt: Read + Seek {
+  /// Little-endian `i32`.
   fn i32(&mut self) -> io::Result<i32> {
     let bytes = self.read_bytes_tolerant(4)?;
     if bytes.len() < 4 {
@@ -16,6 +49,7 @@ pub trait ReadExt: Read + Seek {
     }
     Ok(LittleEndian::read_i32(&bytes))
   }
+  /// Single `u8`.
   fn u8(&mut self) -> io::Result<u8> {
     let bytes = self.read_bytes_tolerant(1)?;
     if bytes.is_empty() {
@@ -26,6 +60,7 @@ pub trait ReadExt: Read + Seek {
     }
     Ok(bytes[0])
   }
+  /// Little-endian `u32`.
   fn u32(&mut self) -> io::Result<u32> {
     let bytes = self.read_bytes_tolerant(4)?;
     if bytes.len() < 4 {
@@ -36,6 +71,7 @@ pub trait ReadExt: Read + Seek {
     }
     Ok(LittleEndian::read_u32(&bytes))
   }
+  /// Little-endian `i64`.
   fn i64(&mut self) -> io::Result<i64> {
     let bytes = self.read_bytes_tolerant(8)?;
     if bytes.len() < 8 {
@@ -46,6 +82,7 @@ pub trait ReadExt: Read + Seek {
     }
     Ok(LittleEndian::read_i64(&bytes))
   }
+  /// Little-endian `u64`.
   fn u64(&mut self) -> io::Result<u64> {
     let bytes = self.read_bytes_tolerant(8)?;
     if bytes.len() < 8 {
@@ -58,6 +95,7 @@ pub trait ReadExt: Read + Seek {
   }
 
   // Additional primitive type readers
+  /// Single `i8`.
   fn i8(&mut self) -> io::Result<i8> {
     let bytes = self.read_bytes_tolerant(1)?;
     if bytes.is_empty() {
@@ -68,6 +106,7 @@ pub trait ReadExt: Read + Seek {
     }
     Ok(bytes[0] as i8)
   }
+  /// Little-endian `i16`.
   fn i16(&mut self) -> io::Result<i16> {
     let bytes = self.read_bytes_tolerant(2)?;
     if bytes.len() < 2 {
@@ -78,6 +117,7 @@ pub trait ReadExt: Read + Seek {
     }
     Ok(LittleEndian::read_i16(&bytes))
   }
+  /// Little-endian `u16`.
   fn u16(&mut self) -> io::Result<u16> {
     let bytes = self.read_bytes_tolerant(2)?;
     if bytes.len() < 2 {
@@ -88,9 +128,11 @@ pub trait ReadExt: Read + Seek {
     }
     Ok(LittleEndian::read_u16(&bytes))
   }
+  /// Single byte, any non-zero value is `true`.
   fn bool(&mut self) -> io::Result<bool> {
     self.u8().map(|b| b != 0)
   }
+  /// Little-endian `f32`.
   fn f32(&mut self) -> io::Result<f32> {
     let bytes = self.read_bytes_tolerant(4)?;
     if bytes.len() < 4 {
@@ -101,6 +143,7 @@ pub trait ReadExt: Read + Seek {
     }
     Ok(LittleEndian::read_f32(&bytes))
   }
+  /// Little-endian `f64`.
   fn f64(&mut self) -> io::Result<f64> {
     let bytes = self.read_bytes_tolerant(8)?;
     if bytes.len() < 8 {
@@ -122,26 +165,32 @@ pub trait ReadExt: Read + Seek {
   }
 
   /// Read up to n bytes, returning whatever is available (like .NET BinaryReader.ReadBytes)
-  /// This method handles partial reads gracefully and doesn't fail on EOF
+  /// This method handles partial reads gracefully and doesn't fail on EOF.
+  /// A single read returns at most [`MAX_PREALLOC_BYTES`].
   fn read_bytes_available(&mut self, count: usize) -> io::Result<Vec<u8>> {
     if count == 0 {
       return Ok(Vec::new());
     }
-    let mut buf = vec![0u8; count];
+    let mut buf = vec![0u8; bounded_capacity::<u8>(count)];
     let bytes_read = self.read(&mut buf)?;
     buf.truncate(bytes_read);
     Ok(buf)
   }
 
-  /// Read exactly n bytes, but handle EOF gracefully by returning available bytes
+  /// Read exactly n bytes, but handle EOF gracefully by returning available bytes.
+  /// The buffer starts at [`MAX_PREALLOC_BYTES`] at most and doubles as it
+  /// fills, so memory tracks the bytes actually read rather than `count`.
   fn read_bytes_tolerant(&mut self, count: usize) -> io::Result<Vec<u8>> {
     if count == 0 {
       return Ok(Vec::new());
     }
-    let mut buf = vec![0u8; count];
+    let mut buf = vec![0u8; bounded_capacity::<u8>(count)];
     let mut total_read = 0;
     
     while total_read < count {
+      if total_read == buf.len() {
+        buf.resize(count.min(buf.len().saturating_mul(2)), 0);
+      }
       match self.read(&mut buf[total_read..]) {
         Ok(0) => break, // EOF reached
         Ok(n) => total_read += n,
@@ -157,7 +206,7 @@ pub trait ReadExt: Read + Seek {
   /// Read a GUID (UUID) stored as 4 uint32 segments in Big Endian
   fn guid(&mut self) -> io::Result<Uuid> {
     let mut data = [0u32; 4];
-    for i in 0..4 {
+    for (i, segment) in data.iter_mut().enumerate() {
       let bytes = self.read_bytes_tolerant(4)?;
       if bytes.len() < 4 {
         return Err(io::Error::new(
@@ -165,11 +214,11 @@ pub trait ReadExt: Read + Seek {
           format!("Expected 4 bytes for GUID segment {} but got {} bytes", i, bytes.len()),
         ));
       }
-      data[i] = byteorder::BigEndian::read_u32(&bytes);
+      *segment = byteorder::BigEndian::read_u32(&bytes);
     }
     let mut guid_bytes = [0u8; 16];
-    for i in 0..4 {
-      LittleEndian::write_u32(&mut guid_bytes[i * 4..(i + 1) * 4], data[i]);
+    for (i, segment) in data.iter().enumerate() {
+      LittleEndian::write_u32(&mut guid_bytes[i * 4..(i + 1) * 4], *segment);
     }
     Ok(Uuid::from_bytes(guid_bytes))
   }
@@ -182,27 +231,37 @@ pub trait ReadExt: Read + Seek {
     Ok(bytes)
   }
 
-  /// Unreal's FString (32-bit length, optionally null-terminated)
+  /// Unreal's FString: a signed 32-bit length, then that many bytes of
+  /// UTF-8/ANSI when positive, or `-length` UTF-16LE code units when negative.
+  /// Decoded with [`StringPolicy::RAW`], so the terminating NUL, if
+  /// serialized, is kept.
   fn fstring(&mut self) -> io::Result<String> {
-    let len = self.u32()?;
+    self.fstring_with(&StringPolicy::RAW)
+  }
+
+  /// [`ReadExt::fstring`] decoded according to `policy`.
+  fn fstring_with(&mut self, policy: &StringPolicy) -> io::Result<String> {
+    let len = self.i32()?;
     if len == 0 {
       return Ok(String::new());
     }
+    if len < 0 {
+      return self.fstring_utf16_with(len.unsigned_abs() as usize, policy);
+    }
+    let len = len as usize;
 
-    // Add reasonable size limit
-    const MAX_REASONABLE_STRING_LENGTH: u32 = 1024 * 1024 * 1024; // 1GB max string length
-    if len > MAX_REASONABLE_STRING_LENGTH {
+    if len > policy.max_len {
       return Err(io::Error::new(
         io::ErrorKind::InvalidData,
         format!(
           "String length ({}) exceeds maximum allowed size of {} bytes",
-          len, MAX_REASONABLE_STRING_LENGTH
+          len, policy.max_len
         ),
       ));
     }
 
-    let buf = self.read_bytes_tolerant(len as usize)?;
-    if buf.len() < len as usize {
+    let buf = self.read_bytes_tolerant(len)?;
+    if buf.len() < len {
       return Err(io::Error::new(
         io::ErrorKind::UnexpectedEof,
         format!("Expected {} bytes for string but got {} bytes", len, buf.len()),
@@ -211,18 +270,145 @@ pub trait ReadExt: Read + Seek {
 
     // Use the length field directly to determine string length
     // This handles both null-terminated and non-null-terminated strings
-    Ok(String::from_utf8_lossy(&buf).to_string())
+    let mut value = if policy.strict {
+      String::from_utf8(buf).map_err(|e| {
+        io::Error::new(io::ErrorKind::InvalidData, format!("Invalid UTF-8 in string: {}", e))
+      })?
+    } else {
+      String::from_utf8_lossy(&buf).into_owned()
+    };
+    if policy.trim_nul {
+      value.truncate(value.trim_end_matches('\0').len());
+    }
+    Ok(value)
+  }
+
+  /// UTF-16LE FString body of `char_count` code units (the terminator
+  /// included), as serialized by UE for negative FString lengths. The length
+  /// prefix must already have been consumed.
+  fn fstring_utf16(&mut self, char_count: usize) -> io::Result<String> {
+    self.fstring_utf16_with(char_count, &StringPolicy::RAW)
+  }
+
+  /// [`ReadExt::fstring_utf16`] decoded according to `policy`.
+  fn fstring_utf16_with(&mut self, char_count: usize, policy: &StringPolicy) -> io::Result<String> {
+    if char_count == 0 {
+      return Ok(String::new());
+    }
+
+    if char_count > policy.max_len / 2 {
+      return Err(io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!(
+          "UTF-16 string length ({}) exceeds maximum allowed size of {} characters",
+          char_count,
+          policy.max_len / 2
+        ),
+      ));
+    }
+
+    let buf = self.read_bytes_tolerant(char_count * 2)?;
+    if buf.len() < char_count * 2 {
+      return Err(io::Error::new(
+        io::ErrorKind::UnexpectedEof,
+        format!(
+          "Expected {} bytes for UTF-16 string but got {} bytes",
+          char_count * 2,
+          buf.len()
+        ),
+      ));
+    }
+
+    let units: Vec<u16> = buf
+      .chunks_exact(2)
+      .map(|pair| u16::from_le_bytes([pair[0], pair[1]]))
+      .collect();
+    let mut value = if policy.strict {
+      String::from_utf16(&units).map_err(|e| {
+        io::Error::new(io::ErrorKind::InvalidData, format!("Invalid UTF-16 in string: {}", e))
+      })?
+    } else {
+      String::from_utf16_lossy(&units)
+    };
+    if policy.trim_nul {
+      value.truncate(value.trim_end_matches('\0').len());
+    }
+    Ok(value)
+  }
+
+  /// Fail with [`io::ErrorKind::InvalidData`] unless `count` elements of at
+  /// least `min_element_size` bytes each could follow: `count` must be at
+  /// most `max` and fit in the bytes left in the stream. Checking before
+  /// allocating keeps a corrupt count from reserving gigabytes.
+  fn check_count(&mut self, count: u32, max: u32, min_element_size: u64) -> io::Result<()> {
+    if count > max {
+      return Err(io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("element count {} exceeds the limit of {}", count, max),
+      ));
+    }
+    let pos = self.stream_position()?;
+    let end = self.seek(SeekFrom::End(0))?;
+    self.seek(SeekFrom::Start(pos))?;
+    let needed = count as u64 * min_element_size;
+    let remaining = end.saturating_sub(pos);
+    if needed > remaining {
+      return Err(io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!(
+          "element count {} needs at least {} bytes but only {} remain",
+          count, needed, remaining
+        ),
+      ));
+    }
+    Ok(())
   }
 
-  fn fstring_array(&mut self) -> io::Result<Vec<String>> {
-    let len = self.u32()? as usize;
-    let mut strings = Vec::with_capacity(len);
+  /// A u32 element count, validated with [`ReadExt::check_count`].
+  fn read_count(&mut self, max: u32, min_element_size: u64) -> io::Result<u32> {
+    let count = self.u32()?;
+    self.check_count(count, max, min_element_size)?;
+    Ok(count)
+  }
+
+  /// UE `TArray`: a u32 element count followed by the elements, each read
+  /// with `read_element`. The count is checked against [`MAX_ARRAY_LEN`] and
+  /// the bytes left (one per element at least) before anything is
+  /// allocated, and the vector reserves at most [`MAX_PREALLOC_BYTES`] up
+  /// front, growing as elements are read.
+  ///
+  /// std has an unstable `Read::read_array`, so call this as
+  /// `ReadExt::read_array(&mut rdr, ..)` to avoid the name collision lint.
+  fn read_array<T, F>(&mut self, mut read_element: F) -> io::Result<Vec<T>>
+  where
+    Self: Sized,
+    F: FnMut(&mut Self) -> io::Result<T>,
+  {
+    let len = self.read_count(MAX_ARRAY_LEN, 1)?;
+    let mut items = Vec::with_capacity(bounded_capacity::<T>(len as usize));
     for _ in 0..len {
-      strings.push(self.fstring()?);
+      items.push(read_element(self)?);
     }
-    Ok(strings)
+    Ok(items)
+  }
+
+  /// `TArray<FString>`.
+  fn fstring_array(&mut self) -> io::Result<Vec<String>>
+  where
+    Self: Sized,
+  {
+    ReadExt::read_array(self, |r| r.fstring())
+  }
+
+  /// `TArray<FString>` decoded according to `policy`.
+  fn fstring_array_with(&mut self, policy: &StringPolicy) -> io::Result<Vec<String>>
+  where
+    Self: Sized,
+  {
+    ReadExt::read_array(self, |r| r.fstring_with(policy))
   }
 
+  /// Advance the position by `bytes`.
   fn skip(&mut self, bytes: u64) -> io::Result<()> {
     self.seek(SeekFrom::Current(bytes as i64))?;
     Ok(())
@@ -0,0 +1,42 @@
+//! Finding the chunk list again after an unreadable meta section (see
+//! [`crate::MetaRecovery::Resync`]).
+//!
+//! Every section starts with its `u32` size, which counts the size field
+//! itself, and a `u8` data version. After the meta come the chunk list, the
+//! file list and, from some feature level on, the custom fields, which end
+//! the payload. An offset is taken as the chunk list's only if the sizes
+//! found there chain through those sections to exactly the end of the
+//! payload; a few stray bytes inside the meta practically never do.
+
+/// Data versions above this are taken for garbage. Epic's sections are at
+/// version 2 at most; this leaves room for newer ones.
+const MAX_DATA_VERSION: u8 = 16;
+
+/// Size field, data version and element count.
+const MIN_SECTION_SIZE: usize = 9;
+
+/// First offset from `from` on where the chunk list plausibly starts.
+pub(crate) fn find_chunk_list(payload: &[u8], from: usize) -> Option<usize> {
+    (from..payload.len().saturating_sub(2 * MIN_SECTION_SIZE - 1)).find(|&at| is_chunk_list(payload, at))
+}
+
+/// Whether the sections starting at `at` chain to the end of `payload` as
+/// chunk list, file list and optional custom fields.
+pub(crate) fn is_chunk_list(payload: &[u8], at: usize) -> bool {
+    let Some(file_list) = section_end(payload, at) else {
+        return false;
+    };
+    match section_end(payload, file_list) {
+        Some(end) if end == payload.len() => true,
+        Some(custom_fields) => section_end(payload, custom_fields) == Some(payload.len()),
+        None => false,
+    }
+}
+
+/// End of a plausible section starting at `at`.
+fn section_end(payload: &[u8], at: usize) -> Option<usize> {
+    let header = payload.get(at..at + 5)?;
+    let size = u32::from_le_bytes(header[..4].try_into().expect("4 bytes")) as usize;
+    let end = at.checked_add(size)?;
+    (size >= MIN_SECTION_SIZE && header[4] <= MAX_DATA_VERSION && end <= payload.len()).then_some(end)
+}
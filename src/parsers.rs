@@ -0,0 +1,150 @@
+//! Manifest formats, as pluggable parsers.
+//!
+//! [`crate::parse_with_hooks`] and everything built on it hand the input to
+//! the first parser of the process-wide [`ParserRegistry`] whose
+//! [`ManifestParser::detect`] accepts it. The built-in registry knows JSON
+//! and binary manifests; applications with formats of their own (say, an
+//! internal variant with a different header) register a parser for them with
+//! [`ParserRegistry::set_global`] instead of dispatching themselves.
+
+use std::fmt;
+use std::sync::{Arc, OnceLock, RwLock};
+
+use crate::detect::{detect_format, ManifestFormat};
+use crate::error::ManifestError;
+use crate::hooks::ParserHooks;
+use crate::options::ParseOptions;
+use crate::report::ParseReport;
+use crate::types::json_manifest::is_json_manifest;
+use crate::types::manifest::Manifest;
+
+static GLOBAL: RwLock<Option<Arc<ParserRegistry>>> = RwLock::new(None);
+
+/// One manifest format.
+pub trait ManifestParser: Send + Sync {
+    /// Short name for logs, e.g. `"binary"`.
+    fn name(&self) -> &str;
+
+    /// Whether `data` is in this format. Called on every input, so it should
+    /// only look at the first bytes where it can.
+    fn detect(&self, data: &[u8]) -> bool;
+
+    /// Parse `data`, recording recoverable problems in `report` and calling
+    /// `hooks` as sections are read.
+    fn parse(
+        &self,
+        data: &[u8],
+        options: &ParseOptions,
+        report: &mut ParseReport,
+        hooks: &mut dyn ParserHooks,
+    ) -> Result<Manifest, ManifestError>;
+}
+
+/// Epic's binary manifests. Also parses input no other parser detects, so
+/// that garbage fails with the binary parser's errors.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct BinaryParser;
+
+impl ManifestParser for BinaryParser {
+    fn name(&self) -> &str {
+        "binary"
+    }
+
+    fn detect(&self, data: &[u8]) -> bool {
+        matches!(detect_format(data), ManifestFormat::BinaryV1 | ManifestFormat::BinaryV2Plus { .. })
+    }
+
+    fn parse(
+        &self,
+        data: &[u8],
+        options: &ParseOptions,
+        report: &mut ParseReport,
+        hooks: &mut dyn ParserHooks,
+    ) -> Result<Manifest, ManifestError> {
+        crate::parse_binary_bytes(data, options, report, hooks)
+    }
+}
+
+/// JSON manifests, converted per [`ParseOptions::json`].
+#[derive(Debug, Clone, Copy, Default)]
+pub struct JsonParser;
+
+impl ManifestParser for JsonParser {
+    fn name(&self) -> &str {
+        "json"
+    }
+
+    fn detect(&self, data: &[u8]) -> bool {
+        is_json_manifest(data)
+    }
+
+    fn parse(
+        &self,
+        data: &[u8],
+        options: &ParseOptions,
+        report: &mut ParseReport,
+        hooks: &mut dyn ParserHooks,
+    ) -> Result<Manifest, ManifestError> {
+        crate::parse_json_bytes(data, options, report, hooks)
+    }
+}
+
+/// Parsers in the order they are tried.
+#[derive(Clone)]
+pub struct ParserRegistry {
+    parsers: Vec<Arc<dyn ManifestParser>>,
+}
+
+impl fmt::Debug for ParserRegistry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.parsers.iter().map(|p| p.name())).finish()
+    }
+}
+
+impl Default for ParserRegistry {
+    fn default() -> Self {
+        Self::builtin()
+    }
+}
+
+impl ParserRegistry {
+    /// [`JsonParser`], then [`BinaryParser`].
+    pub fn builtin() -> Self {
+        Self {
+            parsers: vec![Arc::new(JsonParser), Arc::new(BinaryParser)],
+        }
+    }
+
+    /// Add `parser`, tried before every parser already registered
+    /// (including the built-ins).
+    pub fn register(&mut self, parser: impl ManifestParser + 'static) {
+        self.parsers.insert(0, Arc::new(parser));
+    }
+
+    /// Names of the parsers, in the order they are tried.
+    pub fn names(&self) -> Vec<&str> {
+        self.parsers.iter().map(|p| p.name()).collect()
+    }
+
+    /// The first parser detecting `data`.
+    pub fn find(&self, data: &[u8]) -> Option<&dyn ManifestParser> {
+        self.parsers.iter().map(|p| p.as_ref()).find(|p| p.detect(data))
+    }
+
+    /// [`ParserRegistry::find`], falling back to [`BinaryParser`].
+    pub(crate) fn parser_for(&self, data: &[u8]) -> &dyn ManifestParser {
+        self.find(data).unwrap_or(&BinaryParser)
+    }
+
+    /// The process-wide registry; [`ParserRegistry::builtin`] until replaced.
+    pub fn global() -> Arc<ParserRegistry> {
+        static BUILTIN: OnceLock<Arc<ParserRegistry>> = OnceLock::new();
+        let global = GLOBAL.read().unwrap_or_else(|e| e.into_inner()).clone();
+        global.unwrap_or_else(|| BUILTIN.get_or_init(|| Arc::new(Self::builtin())).clone())
+    }
+
+    /// Replace the process-wide registry.
+    pub fn set_global(registry: ParserRegistry) {
+        *GLOBAL.write().unwrap_or_else(|e| e.into_inner()) = Some(Arc::new(registry));
+    }
+}
@@ -0,0 +1,135 @@
+//! Thin OS layer for the few queries the install code needs.
+
+use std::fs::File;
+use std::io;
+use std::path::Path;
+
+/// Bytes available to the current user on the filesystem holding `path`.
+///
+/// `path` may not exist yet; the nearest existing ancestor is queried.
+pub fn available_space(path: &Path) -> io::Result<u64> {
+    let existing = path
+        .ancestors()
+        .find(|p| p.exists())
+        .filter(|p| !p.as_os_str().is_empty())
+        .unwrap_or(Path::new("."));
+    sys::available_space(existing)
+}
+
+/// Set the length of `file` to `len` with its blocks allocated up front, so
+/// writing it later can't run out of space halfway. Where the platform or
+/// filesystem can't allocate, the file is only extended (sparse).
+pub fn preallocate(file: &File, len: u64) -> io::Result<()> {
+    if len > 0 {
+        if let Err(e) = sys::allocate(file, len) {
+            log::debug!("Preallocation unavailable ({}); extending the file instead", e);
+        }
+    }
+    // Also shrinks a longer file, which allocating leaves as it is.
+    file.set_len(len)
+}
+
+#[cfg(unix)]
+mod sys {
+    use std::ffi::CString;
+    use std::fs::File;
+    use std::io;
+    use std::os::unix::ffi::OsStrExt;
+    use std::path::Path;
+
+    #[cfg(target_os = "linux")]
+    pub fn allocate(file: &File, len: u64) -> io::Result<()> {
+        use std::os::unix::io::AsRawFd;
+        let len = libc::off_t::try_from(len).map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+        // SAFETY: plain syscall on an open descriptor.
+        if unsafe { libc::fallocate(file.as_raw_fd(), 0, 0, len) } != 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(())
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    pub fn allocate(_file: &File, _len: u64) -> io::Result<()> {
+        Err(io::ErrorKind::Unsupported.into())
+    }
+
+    pub fn available_space(path: &Path) -> io::Result<u64> {
+        let c_path = CString::new(path.as_os_str().as_bytes())
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+        let mut stat = std::mem::MaybeUninit::<libc::statvfs>::uninit();
+        // SAFETY: `c_path` is NUL-terminated and `stat` is only read after
+        // statvfs reports success.
+        let stat = unsafe {
+            if libc::statvfs(c_path.as_ptr(), stat.as_mut_ptr()) != 0 {
+                return Err(io::Error::last_os_error());
+            }
+            stat.assume_init()
+        };
+        #[allow(clippy::unnecessary_cast)]
+        Ok(stat.f_bavail as u64 * stat.f_frsize as u64)
+    }
+}
+
+#[cfg(windows)]
+mod sys {
+    use std::fs::File;
+    use std::io;
+    use std::os::windows::ffi::OsStrExt;
+    use std::os::windows::io::AsRawHandle;
+    use std::path::Path;
+    use windows_sys::Win32::Foundation::HANDLE;
+    use windows_sys::Win32::Storage::FileSystem::{
+        FileAllocationInfo, GetDiskFreeSpaceExW, SetFileInformationByHandle, FILE_ALLOCATION_INFO,
+    };
+
+    pub fn allocate(file: &File, len: u64) -> io::Result<()> {
+        let info = FILE_ALLOCATION_INFO {
+            AllocationSize: i64::try_from(len).map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?,
+        };
+        // SAFETY: `info` is the struct FileAllocationInfo expects, and lives
+        // for the call.
+        let ok = unsafe {
+            SetFileInformationByHandle(
+                file.as_raw_handle() as HANDLE,
+                FileAllocationInfo,
+                &info as *const FILE_ALLOCATION_INFO as *const _,
+                std::mem::size_of::<FILE_ALLOCATION_INFO>() as u32,
+            )
+        };
+        if ok == 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(())
+    }
+
+    pub fn available_space(path: &Path) -> io::Result<u64> {
+        let wide: Vec<u16> = path.as_os_str().encode_wide().chain(Some(0)).collect();
+        let mut available = 0u64;
+        // SAFETY: `wide` is NUL-terminated; the other out-pointers may be null.
+        let ok = unsafe {
+            GetDiskFreeSpaceExW(wide.as_ptr(), &mut available, std::ptr::null_mut(), std::ptr::null_mut())
+        };
+        if ok == 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(available)
+    }
+}
+
+#[cfg(not(any(unix, windows)))]
+mod sys {
+    use std::fs::File;
+    use std::io;
+    use std::path::Path;
+
+    pub fn allocate(_file: &File, _len: u64) -> io::Result<()> {
+        Err(io::ErrorKind::Unsupported.into())
+    }
+
+    pub fn available_space(_path: &Path) -> io::Result<u64> {
+        Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "free space query not supported on this platform",
+        ))
+    }
+}
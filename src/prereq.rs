@@ -0,0 +1,203 @@
+//! Prerequisites a manifest asks the launcher to install.
+//!
+//! The meta names one installer (`prereq_path`, `prereq_args`,
+//! `prereq_name`) and the opaque IDs it provides (`prereq_ids`); the launcher
+//! skips the installer once every ID is installed. Epic publishes no list of
+//! those IDs, so [`PrereqRegistry::builtin`] recognizes the common installers
+//! by file name, and callers that have catalogued IDs add them with
+//! [`PrereqRegistry::insert`]. Anything unrecognized is passed through as
+//! [`PrereqKind::Unknown`].
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+#[cfg(feature = "napi")]
+use napi_derive::napi;
+
+use crate::types::meta::ManifestMeta;
+
+/// What a prerequisite installs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum PrereqKind {
+    /// Unreal Engine's bundled installer (`UEPrereqSetup`), which itself
+    /// installs the Visual C++ runtime and DirectX components.
+    UnrealPrerequisites,
+    /// A Microsoft Visual C++ Redistributable.
+    VcRedist,
+    /// The DirectX End-User Runtime.
+    DirectX,
+    /// The Epic Online Services runtime, including its overlay.
+    EpicOnlineServices,
+    Unknown,
+}
+
+impl std::fmt::Display for PrereqKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            PrereqKind::UnrealPrerequisites => "unrealPrerequisites",
+            PrereqKind::VcRedist => "vcRedist",
+            PrereqKind::DirectX => "directX",
+            PrereqKind::EpicOnlineServices => "epicOnlineServices",
+            PrereqKind::Unknown => "unknown",
+        })
+    }
+}
+
+/// What is known about a prerequisite.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PrereqInfo {
+    pub kind: PrereqKind,
+    /// Name to show users.
+    pub name: String,
+    /// Where users can download the installer themselves.
+    pub installer_url: Option<String>,
+}
+
+impl PrereqInfo {
+    pub fn new(kind: PrereqKind, name: impl Into<String>, installer_url: Option<&str>) -> Self {
+        Self {
+            kind,
+            name: name.into(),
+            installer_url: installer_url.map(str::to_string),
+        }
+    }
+}
+
+/// One of a manifest's prerequisite IDs, resolved against a registry.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Prerequisite {
+    /// The ID as stored.
+    pub id: String,
+    /// For unknown prerequisites, named after the manifest's `prereq_name`
+    /// (or the ID when that is empty).
+    pub info: PrereqInfo,
+}
+
+/// Known prerequisite IDs and installers.
+#[derive(Debug, Clone, Default)]
+pub struct PrereqRegistry {
+    /// Keyed by lower-cased ID.
+    ids: HashMap<String, PrereqInfo>,
+    /// Lower-cased installer file name prefixes, tried in order.
+    installers: Vec<(String, PrereqInfo)>,
+}
+
+impl PrereqRegistry {
+    /// Recognizes Unreal's prerequisite installer, the Visual C++
+    /// Redistributable, DirectX and Epic Online Services by installer name.
+    pub fn builtin() -> Self {
+        use PrereqKind::*;
+
+        const VC_NAME: &str = "Microsoft Visual C++ Redistributable";
+        let mut registry = Self::default();
+        let unreal = PrereqInfo::new(UnrealPrerequisites, "Unreal Engine Prerequisites", None);
+        registry.insert_installer("UEPrereqSetup", unreal.clone());
+        registry.insert_installer("UE4PrereqSetup", unreal);
+        // Microsoft's permanent links to the latest (2015 and later) runtime.
+        registry.insert_installer(
+            "VC_redist.x64",
+            PrereqInfo::new(VcRedist, VC_NAME, Some("https://aka.ms/vs/17/release/vc_redist.x64.exe")),
+        );
+        registry.insert_installer(
+            "VC_redist.x86",
+            PrereqInfo::new(VcRedist, VC_NAME, Some("https://aka.ms/vs/17/release/vc_redist.x86.exe")),
+        );
+        // Older runtimes (2013 and before) have no such link.
+        registry.insert_installer("vcredist", PrereqInfo::new(VcRedist, VC_NAME, None));
+        registry.insert_installer(
+            "DXSETUP",
+            PrereqInfo::new(
+                DirectX,
+                "DirectX End-User Runtime",
+                Some("https://www.microsoft.com/en-us/download/details.aspx?id=35"),
+            ),
+        );
+        let eos = PrereqInfo::new(EpicOnlineServices, "Epic Online Services", None);
+        registry.insert_installer("EpicOnlineServices", eos);
+        registry
+    }
+
+    /// Describe prerequisite `id` (compared ignoring ASCII case). Takes
+    /// precedence over the installer rules.
+    pub fn insert(&mut self, id: &str, info: PrereqInfo) {
+        self.ids.insert(id.to_ascii_lowercase(), info);
+    }
+
+    /// Describe installers whose file name starts with `prefix` (ignoring
+    /// ASCII case). Earlier rules win.
+    pub fn insert_installer(&mut self, prefix: &str, info: PrereqInfo) {
+        self.installers.push((prefix.to_ascii_lowercase(), info));
+    }
+
+    /// What is known about `id`, as a prerequisite installed by
+    /// `installer_path`.
+    pub fn lookup(&self, id: &str, installer_path: &str) -> Option<&PrereqInfo> {
+        if let Some(info) = self.ids.get(&id.to_ascii_lowercase()) {
+            return Some(info);
+        }
+        let file_name = installer_path
+            .trim_end_matches('\0')
+            .rsplit(['/', '\\'])
+            .next()
+            .unwrap_or_default()
+            .to_ascii_lowercase();
+        if file_name.is_empty() {
+            return None;
+        }
+        self.installers
+            .iter()
+            .find(|(prefix, _)| file_name.starts_with(prefix.as_str()))
+            .map(|(_, info)| info)
+    }
+
+    /// Every prerequisite ID of `meta`, in stored order.
+    pub fn resolve(&self, meta: &ManifestMeta) -> Vec<Prerequisite> {
+        let fallback_name = meta.prereq_name.trim_end_matches('\0');
+        meta.prereq_ids
+            .iter()
+            .map(|id| {
+                let id = id.trim_end_matches('\0');
+                let info = self.lookup(id, &meta.prereq_path).cloned().unwrap_or_else(|| {
+                    let name = if fallback_name.is_empty() { id } else { fallback_name };
+                    PrereqInfo::new(PrereqKind::Unknown, name, None)
+                });
+                Prerequisite {
+                    id: id.to_string(),
+                    info,
+                }
+            })
+            .collect()
+    }
+}
+
+impl ManifestMeta {
+    /// [`PrereqRegistry::resolve`] with [`PrereqRegistry::builtin`].
+    pub fn prerequisites(&self) -> Vec<Prerequisite> {
+        PrereqRegistry::builtin().resolve(self)
+    }
+}
+
+/// NAPI view of [`Prerequisite`]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[cfg_attr(feature = "napi", napi(object))]
+pub struct PrerequisiteInfo {
+    pub id: String,
+    /// `"unrealPrerequisites"`, `"vcRedist"`, `"directX"`,
+    /// `"epicOnlineServices"` or `"unknown"`
+    pub kind: String,
+    pub name: String,
+    pub installer_url: Option<String>,
+}
+
+impl From<Prerequisite> for PrerequisiteInfo {
+    fn from(prerequisite: Prerequisite) -> Self {
+        Self {
+            id: prerequisite.id,
+            kind: prerequisite.info.kind.to_string(),
+            name: prerequisite.info.name,
+            installer_url: prerequisite.info.installer_url,
+        }
+    }
+}
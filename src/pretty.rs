@@ -0,0 +1,221 @@
+//! Human-readable manifest reports.
+//!
+//! [`Manifest::pretty_print`] renders what people usually dig through the
+//! JSON for: the app and build, file and chunk totals, decoded flags and the
+//! largest files. The layout is for reading, not parsing; use the JSON
+//! serialization for tooling.
+
+use std::collections::{BTreeMap, HashSet};
+use std::io::{self, Write};
+
+use crate::types::file::FileManifest;
+use crate::types::flags::HASH_ROLLING_POLY64;
+use crate::types::manifest::{Manifest, DEFAULT_CLUSTER_SIZE};
+
+/// How much [`Manifest::pretty_print`] shows.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Verbosity {
+    /// Header, app and build details, and file and chunk totals.
+    Summary,
+    /// Also install tags, chunk groups and the largest files.
+    #[default]
+    Normal,
+    /// Also every file, sorted by path, and every chunk.
+    Full,
+}
+
+/// Files listed under "Largest files" at [`Verbosity::Normal`].
+const TOP_FILES: usize = 10;
+
+impl Manifest {
+    /// Write a readable report of this manifest to `out`.
+    pub fn pretty_print<W: Write>(&self, mut out: W, verbosity: Verbosity) -> io::Result<()> {
+        let out = &mut out;
+        let files = self
+            .file_list
+            .as_ref()
+            .map(|f| f.file_manifest_list.as_slice())
+            .unwrap_or_default();
+        let chunks = self.chunk_list.as_ref().map(|c| c.elements.as_slice()).unwrap_or_default();
+
+        let header = &self.header;
+        writeln!(out, "Manifest")?;
+        field(out, "Version", header.version)?;
+        field(out, "Stored as", storage_flags(header.stored_as))?;
+        if let Some(guid) = header.guid() {
+            field(out, "GUID", guid)?;
+        }
+        if header.hash_type != 0 {
+            let hash = if header.hash_type & HASH_ROLLING_POLY64 != 0 {
+                format!("rolling poly64 {:016X}", header.rolling_hash)
+            } else {
+                format!("type {:#x}", header.hash_type)
+            };
+            field(out, "Payload hash", hash)?;
+        }
+
+        if let Some(meta) = &self.meta {
+            writeln!(out, "\nBuild")?;
+            field(out, "App name", clean(&meta.app_name))?;
+            field(out, "App ID", meta.app_id)?;
+            field(out, "Build version", clean(&meta.build_version))?;
+            field(out, "Build ID", meta.build_id())?;
+            field(out, "Feature level", meta.feature_level)?;
+            field(out, "File data", meta.is_file_data)?;
+            if !clean(&meta.launch_exe).is_empty() {
+                field(out, "Launch", format!("{} {}", clean(&meta.launch_exe), clean(&meta.launch_command)).trim_end())?;
+            }
+            if !clean(&meta.prereq_name).is_empty() || !meta.prereq_ids.is_empty() {
+                field(out, "Prerequisite", clean(&meta.prereq_name))?;
+                let ids: Vec<&str> = meta.prereq_ids.iter().map(|id| clean(id)).collect();
+                field(out, "  IDs", ids.join(", "))?;
+                field(out, "  Command", format!("{} {}", clean(&meta.prereq_path), clean(&meta.prereq_args)).trim_end())?;
+            }
+        }
+
+        let total: u64 = files.iter().map(|f| f.file_size.max(0) as u64).sum();
+        writeln!(out, "\nFiles")?;
+        field(out, "Count", files.len())?;
+        field(out, "Total size", bytes(total))?;
+        field(out, "Size on disk", bytes(self.size_on_disk(DEFAULT_CLUSTER_SIZE)))?;
+        let count = |pred: fn(&FileManifest) -> bool| files.iter().filter(|f| pred(f)).count();
+        field(
+            out,
+            "Flags",
+            format!(
+                "{} read-only, {} compressed, {} executable, {} symlinks",
+                count(FileManifest::is_readonly),
+                count(FileManifest::is_compressed),
+                count(FileManifest::is_unix_executable),
+                count(|f| !clean(&f.symlink_target).is_empty()),
+            ),
+        )?;
+
+        let download: u64 = chunks.iter().filter_map(|c| c.file_size.parse::<u64>().ok()).sum();
+        let parts: usize = files.iter().map(|f| f.chunk_parts.len()).sum();
+        writeln!(out, "\nChunks")?;
+        field(out, "Count", chunks.len())?;
+        field(out, "Download size", bytes(download))?;
+        field(out, "Chunk parts", parts)?;
+        if !chunks.is_empty() {
+            let window: u64 = chunks.iter().map(|c| c.window_size as u64).sum();
+            field(out, "Average window", bytes(window / chunks.len() as u64))?;
+            if download > 0 {
+                field(out, "Compressed to", format!("{:.1}%", download as f64 * 100.0 / window.max(1) as f64))?;
+            }
+        }
+
+        if verbosity == Verbosity::Summary {
+            return Ok(());
+        }
+
+        let mut tags: BTreeMap<&str, usize> = BTreeMap::new();
+        for tag in files.iter().flat_map(|f| &f.install_tags) {
+            *tags.entry(clean(tag)).or_default() += 1;
+        }
+        if !tags.is_empty() {
+            writeln!(out, "\nInstall tags")?;
+            for (&tag, &files) in &tags {
+                let tag = if tag.is_empty() { "(untagged)" } else { tag };
+                field(out, tag, format!("{} file{}", files, if files == 1 { "" } else { "s" }))?;
+            }
+        }
+
+        let groups: HashSet<u8> = chunks.iter().map(|c| c.group).collect();
+        if !groups.is_empty() {
+            writeln!(out, "\nChunk groups")?;
+            field(out, "Used", format!("{} of 100", groups.len()))?;
+        }
+
+        let mut by_size: Vec<&FileManifest> = files.iter().collect();
+        if verbosity == Verbosity::Full {
+            by_size.sort_by(|a, b| clean(&a.filename).cmp(clean(&b.filename)));
+            writeln!(out, "\nAll files")?;
+        } else {
+            by_size.sort_by(|a, b| b.file_size.cmp(&a.file_size).then_with(|| a.filename.cmp(&b.filename)));
+            by_size.truncate(TOP_FILES);
+            writeln!(out, "\nLargest files")?;
+        }
+        for file in by_size {
+            write_file(out, file)?;
+        }
+
+        if verbosity == Verbosity::Full && !chunks.is_empty() {
+            writeln!(out, "\nAll chunks")?;
+            for chunk in chunks {
+                writeln!(
+                    out,
+                    "  {}  group {:02}  {:>10}  {}",
+                    chunk.guid,
+                    chunk.group,
+                    chunk.file_size,
+                    chunk.sha_hash
+                )?;
+            }
+        }
+        Ok(())
+    }
+
+    /// [`Manifest::pretty_print`] into a `String`.
+    pub fn pretty_string(&self, verbosity: Verbosity) -> String {
+        let mut out = Vec::new();
+        // Writing to a Vec cannot fail.
+        let _ = self.pretty_print(&mut out, verbosity);
+        String::from_utf8_lossy(&out).into_owned()
+    }
+}
+
+fn field(out: &mut impl Write, label: &str, value: impl std::fmt::Display) -> io::Result<()> {
+    writeln!(out, "  {:<16}{}", format!("{}:", label), value)
+}
+
+fn write_file(out: &mut impl Write, file: &FileManifest) -> io::Result<()> {
+    let flags = [
+        (file.is_readonly(), 'r'),
+        (file.is_compressed(), 'c'),
+        (file.is_unix_executable(), 'x'),
+    ]
+    .iter()
+    .map(|&(set, c)| if set { c } else { '-' })
+    .collect::<String>();
+    let name = clean(&file.filename);
+    match clean(&file.symlink_target) {
+        "" => writeln!(out, "  {:>12}  {}  {}", bytes(file.file_size.max(0) as u64), flags, name),
+        target => writeln!(out, "  {:>12}  {}  {} -> {}", "symlink", flags, name, target),
+    }
+}
+
+fn clean(value: &str) -> &str {
+    value.trim_end_matches('\0')
+}
+
+fn storage_flags(stored_as: u8) -> String {
+    use crate::types::flags::{STORED_COMPRESSED, STORED_ENCRYPTED};
+
+    let mut flags = Vec::new();
+    if stored_as & STORED_COMPRESSED != 0 {
+        flags.push("compressed");
+    }
+    if stored_as & STORED_ENCRYPTED != 0 {
+        flags.push("encrypted");
+    }
+    if flags.is_empty() {
+        flags.push("raw");
+    }
+    flags.join(", ")
+}
+
+/// `1.50 GiB`, or the plain byte count below 1 KiB.
+pub(crate) fn bytes(n: u64) -> String {
+    const UNITS: [&str; 5] = ["KiB", "MiB", "GiB", "TiB", "PiB"];
+    if n < 1024 {
+        return format!("{} B", n);
+    }
+    let mut value = n as f64 / 1024.0;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    format!("{:.2} {}", value, UNITS[unit])
+}
@@ -0,0 +1,94 @@
+//! Header-only look at binary manifests.
+//!
+//! Indexers that scan thousands of manifests for their version, size and
+//! storage flags need none of the payload. [`probe`] reads the
+//! [`ManifestHeader`] and the file's length and stops there: nothing is
+//! decompressed, decrypted or hashed.
+
+#[cfg(feature = "napi")]
+use napi_derive::napi;
+use serde::{Deserialize, Serialize};
+use std::fs::File;
+use std::io::{BufReader, Cursor};
+use std::path::Path;
+
+use crate::error::ManifestError;
+use crate::types::header::ManifestHeader;
+
+/// Output of [`probe`].
+#[derive(Debug, Clone)]
+pub struct ManifestProbe {
+    pub header: ManifestHeader,
+    /// Bytes in the file, header included.
+    pub file_size: u64,
+}
+
+impl ManifestProbe {
+    /// Bytes a complete file has: the header and the stored payload.
+    pub fn expected_size(&self) -> u64 {
+        self.header.header_size.max(0) as u64 + self.header.stored_size().max(0) as u64
+    }
+
+    /// Whether the file holds the whole stored payload. Trailing bytes
+    /// past it are allowed.
+    pub fn is_complete(&self) -> bool {
+        self.file_size >= self.expected_size()
+    }
+}
+
+/// Read the header of the binary manifest at `path`. JSON manifests have
+/// no header and fail with [`ManifestError::Invalid`].
+pub fn probe(path: impl AsRef<Path>) -> Result<ManifestProbe, ManifestError> {
+    let file = File::open(path)?;
+    let file_size = file.metadata()?.len();
+    let header = ManifestHeader::read(BufReader::new(file))?;
+    Ok(ManifestProbe { header, file_size })
+}
+
+/// [`probe`] of a manifest already in memory.
+pub fn probe_bytes(buf: &[u8]) -> Result<ManifestProbe, ManifestError> {
+    let header = ManifestHeader::read(Cursor::new(buf))?;
+    Ok(ManifestProbe {
+        header,
+        file_size: buf.len() as u64,
+    })
+}
+
+/// NAPI view of [`ManifestProbe`]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[cfg_attr(feature = "napi", napi(object))]
+pub struct ManifestProbeInfo {
+    /// Manifest version (feature level); 0 for headers without one
+    pub version: i32,
+    pub header_size: i32,
+    pub stored_as: u8,
+    pub compressed: bool,
+    pub encrypted: bool,
+    pub data_size_uncompressed: i32,
+    pub data_size_compressed: i32,
+    /// Hex SHA-1 of the payload, as recorded in the header
+    pub sha1_hash: String,
+    pub file_size: i64,
+    /// Bytes a complete file has
+    pub expected_size: i64,
+    /// Whether the file holds the whole stored payload
+    pub complete: bool,
+}
+
+impl From<ManifestProbe> for ManifestProbeInfo {
+    fn from(probe: ManifestProbe) -> Self {
+        Self {
+            expected_size: probe.expected_size() as i64,
+            complete: probe.is_complete(),
+            compressed: probe.header.is_compressed(),
+            encrypted: probe.header.is_encrypted(),
+            file_size: probe.file_size as i64,
+            version: probe.header.version,
+            header_size: probe.header.header_size,
+            stored_as: probe.header.stored_as,
+            data_size_uncompressed: probe.header.data_size_uncompressed,
+            data_size_compressed: probe.header.data_size_compressed,
+            sha1_hash: probe.header.sha1_hash,
+        }
+    }
+}
@@ -0,0 +1,64 @@
+use crate::error::ManifestError;
+use crate::store::chunks::ChunkStore;
+use crate::types::chunk_file::decode_chunk_file_with_key;
+
+/// Source of uncompressed chunk data, keyed by chunk GUID.
+///
+/// Exporters and the reconstruction code only need chunk bytes; where they
+/// come from (local store, CDN, test fixtures) is up to the implementation.
+pub trait ChunkProvider {
+    /// Uncompressed contents (the full window) of the chunk `guid`.
+    fn chunk_data(&self, guid: &str) -> Result<Vec<u8>, ManifestError>;
+}
+
+impl<F> ChunkProvider for F
+where
+    F: Fn(&str) -> Result<Vec<u8>, ManifestError>,
+{
+    fn chunk_data(&self, guid: &str) -> Result<Vec<u8>, ManifestError> {
+        self(guid)
+    }
+}
+
+impl ChunkProvider for ChunkStore {
+    fn chunk_data(&self, guid: &str) -> Result<Vec<u8>, ManifestError> {
+        let file = self
+            .read(guid)?
+            .ok_or_else(|| ManifestError::Invalid(format!("chunk {} not in store", guid)))?;
+        Ok(decode_chunk_file_with_key(&file, self.aes_key())?.1)
+    }
+}
+
+/// Reassemble one file from its chunk parts, handing each part's bytes to
+/// `sink` in order. The most recently used chunk is kept decoded, since
+/// consecutive parts usually come from the same chunk.
+pub(crate) fn for_each_file_part<P, F>(
+    provider: &P,
+    file: &crate::types::file::FileManifest,
+    cache: &mut Option<(String, Vec<u8>)>,
+    mut sink: F,
+) -> Result<(), ManifestError>
+where
+    P: ChunkProvider + ?Sized,
+    F: FnMut(&[u8]) -> Result<(), ManifestError>,
+{
+    for part in &file.chunk_parts {
+        if cache.as_ref().map(|(guid, _)| guid != &part.parent_guid).unwrap_or(true) {
+            *cache = Some((part.parent_guid.clone(), provider.chunk_data(&part.parent_guid)?));
+        }
+        let data = &cache.as_ref().unwrap().1;
+        let start = part.offset as usize;
+        let end = start + part.size as usize;
+        if end > data.len() {
+            return Err(ManifestError::Invalid(format!(
+                "chunk part {}..{} exceeds chunk {} of {} bytes",
+                start,
+                end,
+                part.parent_guid,
+                data.len()
+            )));
+        }
+        sink(&data[start..end])?;
+    }
+    Ok(())
+}
@@ -0,0 +1,60 @@
+pub use crate::types::json_manifest::GuidIssue;
+
+/// A file list entry that could not be parsed cleanly.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileIssue {
+    /// Index into the file list.
+    pub index: u32,
+    pub filename: String,
+    pub reason: String,
+}
+
+/// Outcome of checking a binary manifest's payload against its header hash.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum PayloadHashCheck {
+    /// Not checked (JSON manifests).
+    #[default]
+    NotChecked,
+    Matched,
+    Mismatched,
+    /// The header names a hash type this parser can't compute.
+    Unsupported { hash_type: u32 },
+}
+
+/// Outcome of reading a binary manifest's meta section.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum MetaCheck {
+    /// No meta section was read (JSON manifests, whose meta is always
+    /// present).
+    #[default]
+    NotRead,
+    Parsed,
+    /// The section could not be read and the manifest's `meta` is `None`.
+    /// Parsing went on with the chunk list; see
+    /// [`crate::ParseOptions::require_meta`] to fail instead.
+    Failed {
+        /// Offset of the meta section in the decompressed payload.
+        offset: u64,
+        error: String,
+        /// Where the chunk list was found by scanning, when parsing did not
+        /// go on where the meta reader stopped; see
+        /// [`crate::MetaRecovery`].
+        resynced_at: Option<u64>,
+    },
+}
+
+/// Diagnostics collected while parsing, returned next to the manifest.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ParseReport {
+    /// Byte offset of the manifest in the input; non-zero when it was found
+    /// by scanning past a preamble.
+    pub offset: usize,
+    /// Files whose chunk parts were corrupt. Their `chunk_parts` hold
+    /// whatever could be read; parsing resumed with the next file.
+    pub corrupt_files: Vec<FileIssue>,
+    /// JSON manifest entries dropped for malformed GUIDs; see
+    /// [`crate::types::json_manifest::InvalidGuids`].
+    pub invalid_guids: Vec<GuidIssue>,
+    pub payload_hash: PayloadHashCheck,
+    pub meta: MetaCheck,
+}
@@ -0,0 +1,526 @@
+//! Which fields each manifest section holds, by section data version.
+//!
+//! Each section starts with its data size and data version, and newer
+//! versions only ever append fields. The tables below list the fields in
+//! serialization order with their wire type and the version that introduced
+//! them, and [`SectionReader`] reads a section by walking its table, so a
+//! new version is a table change rather than another read loop. A version
+//! newer than the table knows is read as far as the known fields go, and
+//! whatever follows inside the section's data size is kept as hex in the
+//! section's `extra_data` rather than misread.
+//!
+//! [`layout`] uses the same tables to report where every field of a binary
+//! manifest sits in its payload.
+
+use log::debug;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::io::{Cursor, Read, Seek, SeekFrom};
+use std::time::Instant;
+use uuid::Uuid;
+
+use crate::error::ManifestError;
+use crate::format::CHUNK_PART_BASE_SIZE;
+use crate::hooks::Section;
+use crate::options::{check_deadline, ByteTrace, ParseOptions, StringPolicy, DEADLINE_CHECK_INTERVAL};
+use crate::parser::reader::{ReadExt, MAX_ARRAY_LEN};
+use crate::types::chunk::MAX_CHUNK_PART_SIZE;
+use crate::types::file::MAX_CHUNK_PARTS;
+use crate::types::header::ManifestHeader;
+
+/// Largest section data size accepted.
+pub(crate) const MAX_SECTION_SIZE: u32 = 1024 * 1024 * 1024;
+
+/// How a field is serialized.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum FieldKind {
+    U8,
+    /// A byte, any non-zero value is `true`.
+    Bool,
+    I32,
+    U32,
+    U64,
+    FString,
+    /// `TArray<FString>`.
+    FStringArray,
+    /// 16 raw bytes.
+    Guid,
+    /// A fixed number of bytes, zero-padded when the section ends early.
+    Bytes(u32),
+    /// A u32 count of entries of the given size.
+    CountedBytes(u32),
+    /// A u32 count of chunk parts, each prefixed with its own data size.
+    ChunkParts,
+}
+
+/// One serialized field (or column, for the list sections).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Field {
+    pub name: &'static str,
+    pub kind: FieldKind,
+    /// First section data version that serializes the field.
+    pub since: u8,
+}
+
+const fn field(name: &'static str, kind: FieldKind, since: u8) -> Field {
+    Field { name, kind, since }
+}
+
+/// The fields of one section.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SectionSchema {
+    pub section: Section,
+    /// Whether the section is a list stored column by column after a u32
+    /// element count: every element's first field, then every second field,
+    /// and so on.
+    pub columnar: bool,
+    /// In serialization order, after the data size, data version and, for
+    /// columnar sections, the count.
+    pub fields: &'static [Field],
+}
+
+impl SectionSchema {
+    /// Newest data version whose layout is fully known.
+    pub fn latest_version(&self) -> u8 {
+        self.fields.iter().map(|f| f.since).max().unwrap_or_default()
+    }
+
+    /// Whether sections of `version` serialize field `name`.
+    pub fn has(&self, name: &str, version: u8) -> bool {
+        self.fields.iter().any(|f| f.name == name && f.since <= version)
+    }
+
+    /// Fields serialized at `version`, in order.
+    pub fn fields_at(&self, version: u8) -> impl Iterator<Item = &'static Field> {
+        self.fields.iter().filter(move |f| f.since <= version)
+    }
+
+    pub fn for_section(section: Section) -> Option<&'static SectionSchema> {
+        [&META, &CHUNK_LIST, &FILE_LIST, &CUSTOM_FIELDS].into_iter().find(|s| s.section == section)
+    }
+}
+
+pub const META: SectionSchema = SectionSchema {
+    section: Section::Meta,
+    columnar: false,
+    fields: &[
+        field("feature_level", FieldKind::I32, 0),
+        field("is_file_data", FieldKind::Bool, 0),
+        field("app_id", FieldKind::I32, 0),
+        field("app_name", FieldKind::FString, 0),
+        field("build_version", FieldKind::FString, 0),
+        field("launch_exe", FieldKind::FString, 0),
+        field("launch_command", FieldKind::FString, 0),
+        field("prereq_ids", FieldKind::FStringArray, 0),
+        field("prereq_name", FieldKind::FString, 0),
+        field("prereq_path", FieldKind::FString, 0),
+        field("prereq_args", FieldKind::FString, 0),
+        field("build_id", FieldKind::FString, 1),
+    ],
+};
+
+pub const CHUNK_LIST: SectionSchema = SectionSchema {
+    section: Section::ChunkList,
+    columnar: true,
+    fields: &[
+        field("guid", FieldKind::Guid, 0),
+        field("hash", FieldKind::U64, 0),
+        field("sha_hash", FieldKind::Bytes(20), 0),
+        field("group", FieldKind::U8, 0),
+        field("window_size", FieldKind::U32, 0),
+        field("file_size", FieldKind::U64, 0),
+    ],
+};
+
+pub const FILE_LIST: SectionSchema = SectionSchema {
+    section: Section::FileList,
+    columnar: true,
+    fields: &[
+        field("filename", FieldKind::FString, 0),
+        field("symlink_target", FieldKind::FString, 0),
+        field("sha_hash", FieldKind::Bytes(20), 0),
+        field("file_meta_flags", FieldKind::U8, 0),
+        field("install_tags", FieldKind::FStringArray, 0),
+        field("chunk_parts", FieldKind::ChunkParts, 0),
+        field("md5_hashes", FieldKind::CountedBytes(16), 2),
+        field("mime_type", FieldKind::FString, 2),
+        field("sha256_hash", FieldKind::Bytes(32), 2),
+    ],
+};
+
+/// Stored column by column: every key, then every value.
+pub const CUSTOM_FIELDS: SectionSchema = SectionSchema {
+    section: Section::CustomFields,
+    columnar: true,
+    fields: &[field("key", FieldKind::FString, 0), field("value", FieldKind::FString, 0)],
+};
+
+/// Where one field (or column) of a section sits.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FieldSpan {
+    pub section: Section,
+    /// Schema field name, or `data_size`, `data_version`, `count` and
+    /// `extra_data` for the parts every section shares.
+    pub field: String,
+    /// Offset into the decompressed payload.
+    pub offset: u64,
+    pub size: u64,
+}
+
+/// A decoded field value.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum Value {
+    Unsigned(u64),
+    Signed(i64),
+    Bool(bool),
+    String(String),
+    Strings(Vec<String>),
+    Bytes(Vec<u8>),
+}
+
+impl Value {
+    pub(crate) fn as_u64(&self) -> u64 {
+        match *self {
+            Value::Unsigned(v) => v,
+            Value::Signed(v) => v as u64,
+            Value::Bool(v) => v as u64,
+            _ => 0,
+        }
+    }
+
+    pub(crate) fn as_i64(&self) -> i64 {
+        match *self {
+            Value::Signed(v) => v,
+            _ => self.as_u64() as i64,
+        }
+    }
+
+    pub(crate) fn as_bool(&self) -> bool {
+        self.as_u64() != 0
+    }
+
+    pub(crate) fn into_string(self) -> String {
+        match self {
+            Value::String(s) => s,
+            _ => String::new(),
+        }
+    }
+
+    pub(crate) fn into_strings(self) -> Vec<String> {
+        match self {
+            Value::Strings(s) => s,
+            _ => Vec::new(),
+        }
+    }
+
+    pub(crate) fn into_bytes(self) -> Vec<u8> {
+        match self {
+            Value::Bytes(b) => b,
+            _ => Vec::new(),
+        }
+    }
+}
+
+/// Values read so far, one column per field name.
+#[derive(Debug, Default)]
+pub(crate) struct Values(HashMap<&'static str, Vec<Value>>);
+
+impl Values {
+    /// The column for `name`, empty if it was not read.
+    pub(crate) fn take(&mut self, name: &str) -> Vec<Value> {
+        self.0.remove(name).unwrap_or_default()
+    }
+
+    /// The single value of a non-columnar field.
+    pub(crate) fn take_one(&mut self, name: &str) -> Option<Value> {
+        self.take(name).into_iter().next()
+    }
+}
+
+/// Reads one section by its schema.
+///
+/// [`SectionReader::open`] takes the section's declared data size from the
+/// outer reader, leaving it positioned after the section, and keeps the body
+/// (everything after the data size) in `rdr`.
+pub(crate) struct SectionReader {
+    pub(crate) schema: &'static SectionSchema,
+    pub(crate) data_size: u32,
+    pub(crate) data_version: u8,
+    /// Element count for columnar sections, 1 otherwise.
+    pub(crate) count: u32,
+    /// The section body; positions are relative to its start.
+    pub(crate) rdr: Cursor<Vec<u8>>,
+    /// Checked every [`DEADLINE_CHECK_INTERVAL`] elements while reading.
+    pub(crate) deadline: Option<Instant>,
+    /// [`ParseOptions::strict`]: a newer field cut short fails the read.
+    pub(crate) strict: bool,
+    /// Offset of the data size in the outer reader.
+    start: u64,
+    spans: Vec<FieldSpan>,
+}
+
+impl SectionReader {
+    pub(crate) fn open<R: Read + Seek>(rdr: &mut R, schema: &'static SectionSchema) -> Result<Self, ManifestError> {
+        Self::open_with(rdr, schema, &ParseOptions::default())
+    }
+
+    /// [`SectionReader::open`] under the deadline, strictness and count
+    /// limits of `options`.
+    pub(crate) fn open_with<R: Read + Seek>(
+        rdr: &mut R,
+        schema: &'static SectionSchema,
+        options: &ParseOptions,
+    ) -> Result<Self, ManifestError> {
+        let start = rdr.stream_position()?;
+        debug!("Reading {} at position: {} (0x{:x})", schema.section, start, start);
+
+        let data_size = rdr.u32()?;
+        debug!("  Data size: {} (0x{:x})", data_size, data_size);
+        if data_size == 0 || data_size > MAX_SECTION_SIZE {
+            return Err(ManifestError::Invalid(format!(
+                "Invalid data size: {} (0x{:x}). Must be between 1 and 1GB",
+                data_size, data_size
+            )));
+        }
+
+        // Use tolerant reading to handle cases where less data is available
+        // than the section declares.
+        let body_size = data_size.saturating_sub(4) as usize;
+        let body = rdr.read_bytes_tolerant(body_size)?;
+        if body.len() < body_size && options.strict {
+            return Err(ManifestError::Strict {
+                section: schema.section,
+                message: format!("{} of {} bytes present", body.len() + 4, data_size),
+            });
+        }
+        if body.len() < body_size {
+            debug!(
+                "Warning: Expected {} bytes but only {} bytes available for {}. Using available data.",
+                body_size,
+                body.len(),
+                schema.section
+            );
+        }
+
+        let mut section = Self {
+            schema,
+            data_size,
+            data_version: 0,
+            count: 1,
+            rdr: Cursor::new(body),
+            deadline: options.deadline,
+            strict: options.strict,
+            start,
+            spans: Vec::new(),
+        };
+        section.push_span("data_size", -4, 4);
+        section.data_version = section.rdr.u8()?;
+        debug!("  Data version: {} (0x{:x})", section.data_version, section.data_version);
+        section.push_span("data_version", 0, 1);
+
+        if schema.columnar {
+            let count = section.rdr.read_count(MAX_ARRAY_LEN, 1)?;
+            debug!("  Count: {} (0x{:x})", count, count);
+            let limit = match schema.section {
+                Section::ChunkList => options.limits.max_chunks,
+                Section::FileList => options.limits.max_files,
+                _ => MAX_ARRAY_LEN,
+            };
+            if count > limit {
+                return Err(ManifestError::LimitExceeded {
+                    section: schema.section,
+                    count,
+                    limit,
+                });
+            }
+            section.count = count;
+            section.push_span("count", 1, 4);
+        }
+        Ok(section)
+    }
+
+    fn push_span(&mut self, field: &str, body_offset: i64, size: u64) {
+        self.spans.push(FieldSpan {
+            section: self.schema.section,
+            field: field.to_string(),
+            offset: (self.start as i64 + 4 + body_offset) as u64,
+            size,
+        });
+    }
+
+    /// Fields the section's data version serializes, in order.
+    pub(crate) fn fields(&self) -> Vec<&'static Field> {
+        self.schema.fields_at(self.data_version).collect()
+    }
+
+    /// Read `field` for every element into `values`. A field newer than the
+    /// original layout that fails to read ends the section without error,
+    /// keeping what was read of it; the result says whether to go on.
+    pub(crate) fn read_into(
+        &mut self,
+        field: &'static Field,
+        policy: &StringPolicy,
+        values: &mut Values,
+    ) -> Result<bool, ManifestError> {
+        debug!("Reading {}...", field.name);
+        let begin = self.rdr.position();
+        let column = values.0.entry(field.name).or_default();
+        let mut result = Ok(true);
+        for index in 0..self.count {
+            if index % DEADLINE_CHECK_INTERVAL == 0 {
+                if let Err(e) = check_deadline(self.deadline, self.schema.section) {
+                    result = Err(e);
+                    break;
+                }
+            }
+            match read_value(&mut self.rdr, field.kind, policy) {
+                Ok(value) => column.push(value),
+                Err(e) if field.since > 0 && !self.strict => {
+                    debug!(
+                        "Warning: Failed to read {} for element {}: {}. Stopping at the known fields.",
+                        field.name, index, e
+                    );
+                    result = Ok(false);
+                    break;
+                }
+                Err(e) => {
+                    result = Err(e);
+                    break;
+                }
+            }
+        }
+        let end = self.rdr.position();
+        self.push_span(field.name, begin as i64, end - begin);
+        result
+    }
+
+    /// Read every field the data version serializes.
+    pub(crate) fn read_all(&mut self, policy: &StringPolicy) -> Result<Values, ManifestError> {
+        let mut values = Values::default();
+        for field in self.fields() {
+            if !self.read_into(field, policy, &mut values)? {
+                break;
+            }
+        }
+        Ok(values)
+    }
+
+    /// Record a field the caller read from `rdr` itself, from `begin` to the
+    /// current position.
+    pub(crate) fn record(&mut self, field: &'static Field, begin: u64) {
+        let end = self.rdr.position();
+        self.push_span(field.name, begin as i64, end.saturating_sub(begin));
+    }
+
+    /// Hex of the body bytes after the current position.
+    pub(crate) fn extra_data(&self) -> Option<String> {
+        extra_data(self.rdr.get_ref(), self.rdr.position() as usize)
+    }
+
+    /// Spans of everything read, closed with any unknown trailing bytes.
+    fn into_spans(mut self) -> Vec<FieldSpan> {
+        let position = self.rdr.position();
+        let len = self.rdr.get_ref().len() as u64;
+        if position < len {
+            self.push_span("extra_data", position as i64, len - position);
+        }
+        self.spans
+    }
+}
+
+/// End of `fields` read for `count` elements from `pos` of `data`, if every
+/// value parses without running past the data.
+pub(crate) fn columns_end(data: &[u8], pos: usize, count: u32, fields: &[&'static Field]) -> Option<usize> {
+    let mut rdr = Cursor::new(data);
+    rdr.set_position(pos as u64);
+    for field in fields {
+        for _ in 0..count {
+            let left = (data.len() as u64).checked_sub(rdr.position())?;
+            if let FieldKind::Bytes(size) = field.kind {
+                // Read tolerantly elsewhere, which would pad a short read.
+                if left < size as u64 {
+                    return None;
+                }
+            }
+            read_value(&mut rdr, field.kind, &StringPolicy::RAW).ok()?;
+        }
+    }
+    let end = rdr.position() as usize;
+    (end <= data.len()).then_some(end)
+}
+
+fn read_value<R: Read + Seek>(rdr: &mut R, kind: FieldKind, policy: &StringPolicy) -> Result<Value, ManifestError> {
+    Ok(match kind {
+        FieldKind::U8 => Value::Unsigned(rdr.u8()? as u64),
+        FieldKind::Bool => Value::Bool(rdr.bool()?),
+        FieldKind::I32 => Value::Signed(rdr.i32()? as i64),
+        FieldKind::U32 => Value::Unsigned(rdr.u32()? as u64),
+        FieldKind::U64 => Value::Unsigned(rdr.u64()?),
+        FieldKind::FString => Value::String(rdr.fstring_with(policy)?),
+        FieldKind::FStringArray => Value::Strings(rdr.fstring_array_with(policy)?),
+        FieldKind::Guid => {
+            let bytes = rdr.read_bytes_tolerant(16)?;
+            let guid: [u8; 16] = bytes.as_slice().try_into().map_err(|_| {
+                ManifestError::Invalid(format!("Expected 16 bytes for GUID but got {} bytes", bytes.len()))
+            })?;
+            Value::String(Uuid::from_bytes(guid).to_string())
+        }
+        FieldKind::Bytes(size) => {
+            let mut bytes = rdr.read_bytes_tolerant(size as usize)?;
+            if bytes.len() < size as usize {
+                debug!("Warning: Expected {} bytes but got {}; padding with zeros", size, bytes.len());
+                bytes.resize(size as usize, 0);
+            }
+            Value::Bytes(bytes)
+        }
+        FieldKind::CountedBytes(size) => {
+            let count = rdr.read_count(MAX_ARRAY_LEN, size as u64)?;
+            Value::Bytes(rdr.read_bytes(count as usize * size as usize)?)
+        }
+        FieldKind::ChunkParts => {
+            // Kept undecoded: resolving parts needs the chunk list.
+            let start = rdr.stream_position()?;
+            let count = rdr.read_count(MAX_CHUNK_PARTS, CHUNK_PART_BASE_SIZE as u64)?;
+            for _ in 0..count {
+                let size = rdr.u32()?;
+                if !(CHUNK_PART_BASE_SIZE..=MAX_CHUNK_PART_SIZE).contains(&size) {
+                    return Err(ManifestError::Invalid(format!("chunk part data size {} out of range", size)));
+                }
+                rdr.skip(size as u64 - 4)?;
+            }
+            let end = rdr.stream_position()?;
+            rdr.seek(SeekFrom::Start(start))?;
+            let bytes = rdr.read_bytes_tolerant((end - start) as usize)?;
+            if (bytes.len() as u64) < end - start {
+                return Err(ManifestError::Invalid("chunk parts run past the section".to_string()));
+            }
+            Value::Bytes(bytes)
+        }
+    })
+}
+
+/// Hex of `section[known..]`, the bytes after the fields this parser knows.
+pub(crate) fn extra_data(section: &[u8], known: usize) -> Option<String> {
+    section.get(known..).filter(|rest| !rest.is_empty()).map(hex::encode)
+}
+
+/// Where each field of the binary manifest in `buf` sits in its
+/// decompressed payload, section by section, following the schema tables.
+pub fn layout(buf: &[u8]) -> Result<Vec<FieldSpan>, ManifestError> {
+    let header = ManifestHeader::read(&mut Cursor::new(buf))?;
+    let (payload, _) = crate::manifest_payload(buf, &header, ByteTrace::from_env(), None)?;
+    let mut rdr = Cursor::new(payload);
+    let mut spans = Vec::new();
+    for schema in [&META, &CHUNK_LIST, &FILE_LIST, &CUSTOM_FIELDS] {
+        // Older manifests end after the file list.
+        if schema.section == Section::CustomFields && rdr.position() >= rdr.get_ref().len() as u64 {
+            break;
+        }
+        let mut section = SectionReader::open(&mut rdr, schema)?;
+        section.read_all(&StringPolicy::RAW)?;
+        spans.extend(section.into_spans());
+    }
+    Ok(spans)
+}
@@ -0,0 +1,172 @@
+#[cfg(feature = "napi")]
+use napi_derive::napi;
+use serde::{Deserialize, Serialize};
+
+use crate::error::ManifestError;
+use crate::store::chunks::ChunkStore;
+use crate::types::manifest::Manifest;
+
+/// Which chunks of a manifest are already present locally, as a bitset keyed
+/// by index into `ChunkDataList.elements`.
+///
+/// Serialized form: chunk count (u32 LE) followed by `ceil(count / 8)` bytes,
+/// least-significant bit first.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ChunkAvailability {
+    len: u32,
+    bits: Vec<u8>,
+}
+
+/// Completeness figures derived from a [`ChunkAvailability`].
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[cfg_attr(feature = "napi", napi(object))]
+pub struct ChunkAvailabilitySummary {
+    pub total_chunks: u32,
+    pub available_chunks: u32,
+    pub total_bytes: i64,
+    pub available_bytes: i64,
+    /// Fraction of download bytes available, 0.0..=1.0
+    pub completeness: f64,
+}
+
+impl ChunkAvailability {
+    /// An empty bitmap for `len` chunks.
+    pub fn new(len: u32) -> Self {
+        Self {
+            len,
+            bits: vec![0; (len as usize).div_ceil(8)],
+        }
+    }
+
+    /// Bitmap of the chunks of `manifest` present in `store`.
+    pub fn from_store(manifest: &Manifest, store: &ChunkStore) -> Self {
+        let elements = manifest
+            .chunk_list
+            .as_ref()
+            .map(|c| c.elements.as_slice())
+            .unwrap_or_default();
+        let mut availability = Self::new(elements.len() as u32);
+        for (i, chunk) in elements.iter().enumerate() {
+            if store.contains(&chunk.guid) {
+                availability.set(i as u32, true);
+            }
+        }
+        availability
+    }
+
+    pub fn len(&self) -> u32 {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn get(&self, index: u32) -> bool {
+        index < self.len && self.bits[(index / 8) as usize] & (1 << (index % 8)) != 0
+    }
+
+    pub fn set(&mut self, index: u32, available: bool) {
+        if index >= self.len {
+            return;
+        }
+        let mask = 1 << (index % 8);
+        if available {
+            self.bits[(index / 8) as usize] |= mask;
+        } else {
+            self.bits[(index / 8) as usize] &= !mask;
+        }
+    }
+
+    /// Number of chunks marked available.
+    pub fn count_available(&self) -> u32 {
+        self.bits.iter().map(|b| b.count_ones()).sum()
+    }
+
+    /// Indices of chunks still missing.
+    pub fn missing(&self) -> impl Iterator<Item = u32> + '_ {
+        (0..self.len).filter(|&i| !self.get(i))
+    }
+
+    /// Completeness of `manifest` according to this bitmap, weighted by each
+    /// chunk's download size. Fails if the bitmap was made for a manifest
+    /// with a different number of chunks.
+    pub fn summary(&self, manifest: &Manifest) -> Result<ChunkAvailabilitySummary, ManifestError> {
+        let elements = manifest
+            .chunk_list
+            .as_ref()
+            .map(|c| c.elements.as_slice())
+            .unwrap_or_default();
+        if self.len as usize != elements.len() {
+            return Err(ManifestError::Invalid(format!(
+                "chunk availability bitmap covers {} chunks, manifest has {}",
+                self.len,
+                elements.len()
+            )));
+        }
+
+        let mut total_bytes = 0i64;
+        let mut available_bytes = 0i64;
+        for (i, chunk) in elements.iter().enumerate() {
+            let size = chunk.file_size.parse::<i64>().unwrap_or(0);
+            total_bytes += size;
+            if self.get(i as u32) {
+                available_bytes += size;
+            }
+        }
+
+        let completeness = if total_bytes > 0 {
+            available_bytes as f64 / total_bytes as f64
+        } else if self.len > 0 {
+            self.count_available() as f64 / self.len as f64
+        } else {
+            1.0
+        };
+
+        Ok(ChunkAvailabilitySummary {
+            total_chunks: self.len,
+            available_chunks: self.count_available(),
+            total_bytes,
+            available_bytes,
+            completeness,
+        })
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut out = Vec::with_capacity(4 + self.bits.len());
+        out.extend_from_slice(&self.len.to_le_bytes());
+        out.extend_from_slice(&self.bits);
+        out
+    }
+
+    pub fn from_bytes(data: &[u8]) -> Result<Self, ManifestError> {
+        if data.len() < 4 {
+            return Err(ManifestError::Invalid(
+                "chunk availability bitmap is missing its length".to_string(),
+            ));
+        }
+        let len = u32::from_le_bytes([data[0], data[1], data[2], data[3]]);
+        let expected = (len as usize).div_ceil(8);
+        let bits = &data[4..];
+        if bits.len() != expected {
+            return Err(ManifestError::Invalid(format!(
+                "chunk availability bitmap has {} bytes, expected {} for {} chunks",
+                bits.len(),
+                expected,
+                len
+            )));
+        }
+
+        let mut availability = Self {
+            len,
+            bits: bits.to_vec(),
+        };
+        // Ignore stray bits past the end so equality and counts stay exact.
+        if !len.is_multiple_of(8) {
+            if let Some(last) = availability.bits.last_mut() {
+                *last &= (1u8 << (len % 8)) - 1;
+            }
+        }
+        Ok(availability)
+    }
+}
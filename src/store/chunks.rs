@@ -0,0 +1,307 @@
+use log::debug;
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::SystemTime;
+
+use crate::crypto::AesKey;
+use crate::error::ManifestError;
+use crate::install::actions::Action;
+use crate::types::chunk::{guid_from_epic, normalize_guid, Chunk};
+use crate::types::manifest::Manifest;
+
+/// A chunk file known to the store.
+#[derive(Debug, Clone)]
+pub struct StoredChunk {
+    pub guid: String,
+    /// Path relative to the store root.
+    pub relative_path: PathBuf,
+    pub size: u64,
+    pub last_used: SystemTime,
+}
+
+/// Outcome of [`ChunkStore::gc`]; in a dry run, what it would remove.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct GcReport {
+    pub removed_chunks: usize,
+    pub reclaimed_bytes: u64,
+    /// One [`Action::Delete`] per chunk file, relative to the store root.
+    pub actions: Vec<Action>,
+    pub dry_run: bool,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct GcOptions {
+    /// Only report what would be removed.
+    pub dry_run: bool,
+}
+
+/// Local chunk cache laid out like Epic's CDN
+/// (`ChunksV4/<group>/<hash>_<guid>.chunk`), so a store directory can be
+/// served or synced as-is.
+///
+/// All methods take `&self`; the index is guarded internally so one store can
+/// be shared (e.g. behind an `Arc`) by the downloader and the installer.
+#[derive(Debug)]
+pub struct ChunkStore {
+    root: PathBuf,
+    feature_level: i32,
+    index: Mutex<HashMap<String, StoredChunk>>,
+    key: Option<AesKey>,
+}
+
+impl ChunkStore {
+    /// Open (creating if needed) a store at `root` and index the chunk files
+    /// already present. `feature_level` selects the chunk directory new chunks
+    /// are written to.
+    pub fn open(root: impl AsRef<Path>, feature_level: i32) -> Result<Self, ManifestError> {
+        let root = root.as_ref().to_path_buf();
+        fs::create_dir_all(&root)?;
+
+        let mut index = HashMap::new();
+        for dir in ["Chunks", "ChunksV2", "ChunksV3", "ChunksV4"] {
+            let chunk_dir = root.join(dir);
+            if !chunk_dir.is_dir() {
+                continue;
+            }
+            for group in fs::read_dir(&chunk_dir)? {
+                let group = group?;
+                if !group.file_type()?.is_dir() {
+                    continue;
+                }
+                for entry in fs::read_dir(group.path())? {
+                    let entry = entry?;
+                    let path = entry.path();
+                    let Some(guid) = Self::guid_from_path(&path) else {
+                        continue;
+                    };
+                    let metadata = entry.metadata()?;
+                    let relative_path = path.strip_prefix(&root).unwrap_or(&path).to_path_buf();
+                    index.insert(
+                        guid.clone(),
+                        StoredChunk {
+                            guid,
+                            relative_path,
+                            size: metadata.len(),
+                            last_used: metadata.modified().unwrap_or(SystemTime::UNIX_EPOCH),
+                        },
+                    );
+                }
+            }
+        }
+        debug!("ChunkStore: indexed {} chunks under {:?}", index.len(), root);
+
+        Ok(Self {
+            root,
+            feature_level,
+            index: Mutex::new(index),
+            key: None,
+        })
+    }
+
+    /// Extract the parser-format GUID from a `<hash>_<guid>.chunk` file name.
+    fn guid_from_path(path: &Path) -> Option<String> {
+        if path.extension()? != "chunk" {
+            return None;
+        }
+        let stem = path.file_stem()?.to_str()?;
+        let (_, epic) = stem.split_once('_')?;
+        guid_from_epic(epic)
+    }
+
+    /// Index key for a GUID in either accepted format.
+    fn key(guid: &str) -> String {
+        normalize_guid(guid).unwrap_or_else(|| guid.to_string())
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    pub fn feature_level(&self) -> i32 {
+        self.feature_level
+    }
+
+    /// Decrypt chunks flagged encrypted with `key` when reading their data
+    /// through [`crate::provider::ChunkProvider`].
+    pub fn with_key(mut self, key: AesKey) -> Self {
+        self.key = Some(key);
+        self
+    }
+
+    pub fn aes_key(&self) -> Option<&AesKey> {
+        self.key.as_ref()
+    }
+
+    /// Number of chunks in the store.
+    pub fn len(&self) -> usize {
+        self.index.lock().unwrap().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Total bytes held by the store.
+    pub fn total_size(&self) -> u64 {
+        self.index.lock().unwrap().values().map(|c| c.size).sum()
+    }
+
+    pub fn contains(&self, guid: &str) -> bool {
+        self.index.lock().unwrap().contains_key(&Self::key(guid))
+    }
+
+    /// Snapshot of every stored chunk.
+    pub fn entries(&self) -> Vec<StoredChunk> {
+        self.index.lock().unwrap().values().cloned().collect()
+    }
+
+    /// Write `data` (the chunk file as served by the CDN) for `chunk`,
+    /// replacing any previous copy.
+    pub fn add(&self, chunk: &Chunk, data: &[u8]) -> Result<PathBuf, ManifestError> {
+        let relative_path = PathBuf::from(chunk.cdn_path(self.feature_level));
+        let path = self.root.join(&relative_path);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        // Write to a temporary file first so readers never see a partial chunk.
+        let tmp_path = path.with_extension("chunk.tmp");
+        {
+            let mut file = fs::File::create(&tmp_path)?;
+            file.write_all(data)?;
+            file.sync_all()?;
+        }
+        fs::rename(&tmp_path, &path)?;
+
+        let guid = Self::key(&chunk.guid);
+        let previous = self.index.lock().unwrap().insert(
+            guid.clone(),
+            StoredChunk {
+                guid,
+                relative_path: relative_path.clone(),
+                size: data.len() as u64,
+                last_used: SystemTime::now(),
+            },
+        );
+        // A chunk re-added under another feature level lives at a different path.
+        if let Some(previous) = previous {
+            if previous.relative_path != relative_path {
+                let _ = fs::remove_file(self.root.join(previous.relative_path));
+            }
+        }
+
+        Ok(path)
+    }
+
+    /// Absolute path of a stored chunk, marking it as recently used.
+    pub fn locate(&self, guid: &str) -> Option<PathBuf> {
+        let mut index = self.index.lock().unwrap();
+        let entry = index.get_mut(&Self::key(guid))?;
+        entry.last_used = SystemTime::now();
+        Some(self.root.join(&entry.relative_path))
+    }
+
+    /// Read a stored chunk file, marking it as recently used.
+    pub fn read(&self, guid: &str) -> Result<Option<Vec<u8>>, ManifestError> {
+        match self.locate(guid) {
+            Some(path) => Ok(Some(fs::read(path)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Remove a chunk, returning the bytes freed.
+    pub fn remove(&self, guid: &str) -> Result<u64, ManifestError> {
+        let entry = self.index.lock().unwrap().remove(&Self::key(guid));
+        match entry {
+            Some(entry) => {
+                match fs::remove_file(self.root.join(&entry.relative_path)) {
+                    Ok(()) => {}
+                    Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+                    Err(e) => return Err(e.into()),
+                }
+                Ok(entry.size)
+            }
+            None => Ok(0),
+        }
+    }
+
+    /// Evict least-recently-used chunks until the store holds at most
+    /// `max_bytes`. Returns the bytes freed.
+    pub fn evict_to_size(&self, max_bytes: u64) -> Result<u64, ManifestError> {
+        let mut by_age: Vec<(SystemTime, String, u64)> = self
+            .index
+            .lock()
+            .unwrap()
+            .values()
+            .map(|c| (c.last_used, c.guid.clone(), c.size))
+            .collect();
+        by_age.sort();
+
+        let mut total: u64 = by_age.iter().map(|(_, _, size)| size).sum();
+        let mut freed = 0;
+        for (_, guid, _) in by_age {
+            if total <= max_bytes {
+                break;
+            }
+            let size = self.remove(&guid)?;
+            total -= size;
+            freed += size;
+        }
+        debug!("ChunkStore: evicted {} bytes", freed);
+        Ok(freed)
+    }
+
+    /// Delete every chunk not referenced by any of `referenced_manifests`.
+    pub fn gc(&self, referenced_manifests: &[Manifest]) -> Result<GcReport, ManifestError> {
+        self.gc_with(referenced_manifests, &GcOptions::default())
+    }
+
+    /// [`ChunkStore::gc`] with options, e.g. a dry run.
+    pub fn gc_with(&self, referenced_manifests: &[Manifest], options: &GcOptions) -> Result<GcReport, ManifestError> {
+        let mut referenced = HashSet::new();
+        for manifest in referenced_manifests {
+            if let Some(chunk_list) = &manifest.chunk_list {
+                referenced.extend(chunk_list.elements.iter().map(|c| Self::key(&c.guid)));
+            }
+            if let Some(file_list) = &manifest.file_list {
+                for file in &file_list.file_manifest_list {
+                    referenced.extend(file.chunk_parts.iter().map(|p| Self::key(&p.parent_guid)));
+                }
+            }
+        }
+
+        let mut unreferenced: Vec<StoredChunk> = self
+            .index
+            .lock()
+            .unwrap()
+            .values()
+            .filter(|chunk| !referenced.contains(&chunk.guid))
+            .cloned()
+            .collect();
+        unreferenced.sort_by(|a, b| a.relative_path.cmp(&b.relative_path));
+
+        let mut report = GcReport {
+            dry_run: options.dry_run,
+            ..Default::default()
+        };
+        for chunk in unreferenced {
+            let bytes = if options.dry_run { chunk.size } else { self.remove(&chunk.guid)? };
+            report.reclaimed_bytes += bytes;
+            report.removed_chunks += 1;
+            report.actions.push(Action::Delete {
+                path: chunk.relative_path.to_string_lossy().replace('\\', "/"),
+                bytes,
+            });
+        }
+        debug!(
+            "ChunkStore: gc {} {} chunks ({} bytes)",
+            if options.dry_run { "would remove" } else { "removed" },
+            report.removed_chunks,
+            report.reclaimed_bytes
+        );
+        Ok(report)
+    }
+}
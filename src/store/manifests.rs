@@ -0,0 +1,146 @@
+//! Parsed manifests kept by content hash.
+//!
+//! Tools built on the crate tend to each grow their own manifest cache.
+//! [`ManifestStore`] is the one abstraction they can share: `put` a parsed
+//! manifest, keep the hash it returns, and `get` it back later. Stores hold
+//! [`Manifest`]s rather than any serialized form, so an implementation picks
+//! its own representation: [`MemoryManifestStore`] keeps the parsed values,
+//! [`DirManifestStore`] normalized binary manifests.
+
+use log::debug;
+use std::collections::HashMap;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use crate::error::ManifestError;
+use crate::fingerprint::fingerprint_hex;
+use crate::options::ParseOptions;
+use crate::types::manifest::Manifest;
+
+/// Storage for parsed manifests, addressed by [`content_hash`].
+pub trait ManifestStore: Send + Sync {
+    /// Store `manifest`, returning its content hash. Storing a manifest
+    /// with the same content again keeps one copy.
+    fn put(&self, manifest: Arc<Manifest>) -> Result<String, ManifestError>;
+
+    /// The manifest stored under `hash`, if any.
+    fn get(&self, hash: &str) -> Result<Option<Arc<Manifest>>, ManifestError>;
+
+    fn contains(&self, hash: &str) -> Result<bool, ManifestError> {
+        Ok(self.get(hash)?.is_some())
+    }
+}
+
+/// Hash a store files `manifest` under: the hex [`crate::fingerprint`] of
+/// its normalized binary form ([`Manifest::to_bytes`]), so it does not
+/// depend on how the manifest was compressed when it was read.
+pub fn content_hash(manifest: &Manifest) -> Result<String, ManifestError> {
+    Ok(fingerprint_hex(&manifest.to_bytes()?))
+}
+
+/// Whether `hash` looks like a [`content_hash`]; anything else is never
+/// stored, and must not reach a path.
+fn is_content_hash(hash: &str) -> bool {
+    hash.len() == 64 && hash.bytes().all(|b| matches!(b, b'0'..=b'9' | b'a'..=b'f'))
+}
+
+/// Manifests kept parsed in memory; `get` hands out the stored value
+/// without parsing anything.
+#[derive(Debug, Default)]
+pub struct MemoryManifestStore {
+    manifests: Mutex<HashMap<String, Arc<Manifest>>>,
+}
+
+impl MemoryManifestStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.manifests.lock().unwrap().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl ManifestStore for MemoryManifestStore {
+    fn put(&self, manifest: Arc<Manifest>) -> Result<String, ManifestError> {
+        let hash = content_hash(&manifest)?;
+        self.manifests.lock().unwrap().entry(hash.clone()).or_insert(manifest);
+        Ok(hash)
+    }
+
+    fn get(&self, hash: &str) -> Result<Option<Arc<Manifest>>, ManifestError> {
+        Ok(self.manifests.lock().unwrap().get(hash).cloned())
+    }
+
+    fn contains(&self, hash: &str) -> Result<bool, ManifestError> {
+        Ok(self.manifests.lock().unwrap().contains_key(hash))
+    }
+}
+
+/// Normalized binary manifests in a directory, as
+/// `<root>/<first two hash digits>/<hash>.manifest`. `get` parses the file.
+#[derive(Debug)]
+pub struct DirManifestStore {
+    root: PathBuf,
+}
+
+impl DirManifestStore {
+    /// Open (creating if needed) a store at `root`.
+    pub fn open(root: impl AsRef<Path>) -> Result<Self, ManifestError> {
+        let root = root.as_ref().to_path_buf();
+        fs::create_dir_all(&root)?;
+        Ok(Self { root })
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    fn path(&self, hash: &str) -> PathBuf {
+        self.root.join(&hash[..2]).join(format!("{}.manifest", hash))
+    }
+}
+
+impl ManifestStore for DirManifestStore {
+    fn put(&self, manifest: Arc<Manifest>) -> Result<String, ManifestError> {
+        let bytes = manifest.to_bytes()?;
+        let hash = fingerprint_hex(&bytes);
+        let path = self.path(&hash);
+        if path.is_file() {
+            return Ok(hash);
+        }
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        // Write to a temporary file first so readers never see a partial manifest.
+        let tmp_path = path.with_extension("manifest.tmp");
+        {
+            let mut file = fs::File::create(&tmp_path)?;
+            file.write_all(&bytes)?;
+            file.sync_all()?;
+        }
+        fs::rename(&tmp_path, &path)?;
+        debug!("DirManifestStore: stored {} ({} bytes)", hash, bytes.len());
+        Ok(hash)
+    }
+
+    fn get(&self, hash: &str) -> Result<Option<Arc<Manifest>>, ManifestError> {
+        if !self.contains(hash)? {
+            return Ok(None);
+        }
+        let bytes = fs::read(self.path(hash))?;
+        let (manifest, _) = crate::parse_with_report(&bytes, &ParseOptions::default())?;
+        Ok(Some(Arc::new(manifest)))
+    }
+
+    fn contains(&self, hash: &str) -> Result<bool, ManifestError> {
+        Ok(is_content_hash(hash) && self.path(hash).is_file())
+    }
+}
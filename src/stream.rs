@@ -0,0 +1,89 @@
+//! Streams of concatenated manifests, as found in bulk export dumps.
+
+use std::ops::Range;
+
+use byteorder::{ByteOrder, LittleEndian};
+use serde::de::IgnoredAny;
+
+use crate::detect::{detect_format, scan_for_manifest, ManifestFormat};
+use crate::error::ManifestError;
+use crate::format::MANIFEST_HEADER_SIZE_V1;
+use crate::options::ParseOptions;
+use crate::types::manifest::Manifest;
+
+/// Length in bytes of the manifest starting at `data[0]`, from its header
+/// sizes (binary) or the extent of the first JSON value.
+pub fn manifest_len(data: &[u8]) -> Option<usize> {
+    match detect_format(data) {
+        ManifestFormat::BinaryV1 | ManifestFormat::BinaryV2Plus { .. } => {
+            if data.len() < MANIFEST_HEADER_SIZE_V1 as usize {
+                return None;
+            }
+            let header_size = LittleEndian::read_i32(&data[4..8]);
+            let uncompressed = LittleEndian::read_i32(&data[8..12]);
+            let compressed = LittleEndian::read_i32(&data[12..16]);
+            let stored_as = data[36];
+            // Encrypted payloads are padded, so stored at their compressed size.
+            let stored_flags = crate::types::flags::STORED_COMPRESSED | crate::types::flags::STORED_ENCRYPTED;
+            let payload = if stored_as & stored_flags != 0 {
+                compressed
+            } else {
+                uncompressed
+            };
+            let len = usize::try_from(header_size).ok()? + usize::try_from(payload).ok()?;
+            (len <= data.len()).then_some(len)
+        }
+        ManifestFormat::Json => {
+            let mut values = serde_json::Deserializer::from_slice(data).into_iter::<IgnoredAny>();
+            values.next()?.ok()?;
+            Some(values.byte_offset())
+        }
+        ManifestFormat::Unknown => None,
+    }
+}
+
+/// Iterator over the manifests in a buffer, yielding each one with its byte
+/// range. Bytes between manifests are skipped.
+///
+/// A manifest that fails to parse is yielded as an error and skipped when its
+/// extent is known from its header; otherwise iteration ends after the error.
+pub struct ManifestStream<'a> {
+    data: &'a [u8],
+    pos: usize,
+    options: ParseOptions,
+}
+
+/// Iterate over every manifest in `data`.
+pub fn parse_all(data: &[u8], options: ParseOptions) -> ManifestStream<'_> {
+    ManifestStream {
+        data,
+        pos: 0,
+        options,
+    }
+}
+
+impl Iterator for ManifestStream<'_> {
+    type Item = Result<(Range<usize>, Manifest), ManifestError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let rest = self.data.get(self.pos..)?;
+        let start = self.pos + scan_for_manifest(rest)?;
+        let Some(len) = manifest_len(&self.data[start..]) else {
+            self.pos = self.data.len();
+            return Some(Err(ManifestError::Invalid(format!(
+                "manifest at offset {} is truncated or malformed",
+                start
+            ))));
+        };
+        let range = start..start + len;
+        self.pos = range.end;
+        let data = &self.data[range.clone()];
+        let mut report = crate::report::ParseReport::default();
+        Some(
+            crate::parsers::ParserRegistry::global()
+                .parser_for(data)
+                .parse(data, &self.options, &mut report, &mut crate::hooks::NoHooks)
+                .map(|m| (range, m)),
+        )
+    }
+}
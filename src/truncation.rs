@@ -0,0 +1,166 @@
+//! Estimating how much of a cut-short manifest is missing.
+//!
+//! Every binary section announces its size before its contents: the header
+//! gives the stored payload size, and meta, chunk list and file list each
+//! start with their data size. Comparing those with the bytes actually
+//! present tells whether a download stopped early, and by how much.
+
+use miniz_oxide::inflate::core::{decompress, inflate_flags, DecompressorOxide};
+use miniz_oxide::inflate::TINFLStatus;
+use serde::{Deserialize, Serialize};
+
+use crate::error::ManifestError;
+use crate::format::{MANIFEST_HEADER_SIZE_V1, MANIFEST_MAGIC};
+use crate::hooks::Section;
+use crate::types::header::ManifestHeader;
+
+/// Declared and available size of one section.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SectionExtent {
+    pub section: Section,
+    /// Offset into the input for [`Section::Header`] and
+    /// [`Section::Payload`], into the decompressed payload otherwise.
+    pub offset: u64,
+    /// Size the section declares.
+    pub expected: u64,
+    /// Bytes of it present, at most `expected`.
+    pub available: u64,
+}
+
+impl SectionExtent {
+    pub fn missing(&self) -> u64 {
+        self.expected - self.available
+    }
+
+    pub fn is_complete(&self) -> bool {
+        self.available == self.expected
+    }
+}
+
+/// Result of [`estimate_truncation`].
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TruncationReport {
+    /// Sections in file order, up to and including the first incomplete one.
+    pub sections: Vec<SectionExtent>,
+    /// Bytes missing from the end of the input. `None` when the header is
+    /// too short to give the payload size.
+    pub missing_bytes: Option<u64>,
+    /// Decompressed payload bytes missing, per the header's uncompressed size.
+    pub missing_payload_bytes: Option<u64>,
+    /// A JSON manifest whose text ends mid-document. JSON gives no sizes, so
+    /// the amount missing is unknown.
+    pub json_ends_early: bool,
+}
+
+impl TruncationReport {
+    pub fn is_truncated(&self) -> bool {
+        self.json_ends_early
+            || self.missing_bytes.is_some_and(|missing| missing > 0)
+            || self.sections.iter().any(|s| !s.is_complete())
+    }
+
+    /// The first section that is cut short.
+    pub fn first_incomplete(&self) -> Option<&SectionExtent> {
+        self.sections.iter().find(|s| !s.is_complete())
+    }
+}
+
+/// Compare the sizes `buf` declares with what it holds. Fails only when
+/// `buf` is neither a JSON nor a binary manifest.
+pub fn estimate_truncation(buf: &[u8]) -> Result<TruncationReport, ManifestError> {
+    let mut report = TruncationReport::default();
+    if buf.trim_ascii_start().starts_with(b"{") {
+        report.json_ends_early = serde_json::from_slice::<serde_json::Value>(buf).is_err_and(|e| e.is_eof());
+        return Ok(report);
+    }
+    if buf.len() < 4 || u32::from_le_bytes(buf[..4].try_into().unwrap()) != MANIFEST_MAGIC {
+        return Err(ManifestError::Invalid("not a manifest".to_string()));
+    }
+
+    let len = buf.len() as u64;
+    let header_size = buf
+        .get(4..8)
+        .map_or(MANIFEST_HEADER_SIZE_V1 as u64, |b| i32::from_le_bytes(b.try_into().unwrap()).max(0) as u64);
+    report.sections.push(SectionExtent {
+        section: Section::Header,
+        offset: 0,
+        expected: header_size,
+        available: len.min(header_size),
+    });
+    if len < header_size {
+        return Ok(report);
+    }
+
+    let header = ManifestHeader::read(std::io::Cursor::new(buf))?;
+    let stored_size = if header.is_compressed() {
+        header.data_size_compressed
+    } else {
+        header.data_size_uncompressed
+    }
+    .max(0) as u64;
+    let stored = &buf[header_size as usize..(header_size + stored_size).min(len) as usize];
+    let payload_extent = SectionExtent {
+        section: Section::Payload,
+        offset: header_size,
+        expected: stored_size,
+        available: stored.len() as u64,
+    };
+    report.sections.push(payload_extent);
+    report.missing_bytes = Some(payload_extent.missing());
+    if header.is_encrypted() {
+        return Ok(report);
+    }
+
+    let payload = if header.is_compressed() {
+        inflate_prefix(stored)
+    } else {
+        stored.to_vec()
+    };
+    let uncompressed_size = header.data_size_uncompressed.max(0) as u64;
+    report.missing_payload_bytes = Some(uncompressed_size.saturating_sub(payload.len() as u64));
+
+    let mut offset = 0u64;
+    for section in [Section::Meta, Section::ChunkList, Section::FileList, Section::CustomFields] {
+        let Some(size) = payload.get(offset as usize..offset as usize + 4) else {
+            break;
+        };
+        let expected = u32::from_le_bytes(size.try_into().unwrap()) as u64;
+        let extent = SectionExtent {
+            section,
+            offset,
+            expected,
+            available: expected.min(payload.len() as u64 - offset),
+        };
+        report.sections.push(extent);
+        if !extent.is_complete() || expected == 0 {
+            break;
+        }
+        offset += expected;
+    }
+    Ok(report)
+}
+
+/// Largest payload [`inflate_prefix`] produces.
+const MAX_PAYLOAD_SIZE: usize = 1024 * 1024 * 1024;
+
+/// Inflate as much of a zlib stream as is present; a cut-off stream yields
+/// the data up to where it stops.
+fn inflate_prefix(mut input: &[u8]) -> Vec<u8> {
+    let flags = inflate_flags::TINFL_FLAG_PARSE_ZLIB_HEADER | inflate_flags::TINFL_FLAG_USING_NON_WRAPPING_OUTPUT_BUF;
+    let mut decompressor = Box::<DecompressorOxide>::default();
+    let mut out = vec![0; input.len().saturating_mul(2).clamp(64, MAX_PAYLOAD_SIZE)];
+    let mut position = 0;
+    loop {
+        let (status, consumed, written) = decompress(&mut decompressor, input, &mut out, position, flags);
+        position += written;
+        input = &input[consumed.min(input.len())..];
+        if status != TINFLStatus::HasMoreOutput || out.len() >= MAX_PAYLOAD_SIZE {
+            break;
+        }
+        out.resize((out.len() * 2).min(MAX_PAYLOAD_SIZE), 0);
+    }
+    out.truncate(position);
+    out
+}
@@ -0,0 +1,40 @@
+use std::sync::OnceLock;
+
+/// Lazily computed value attached to a parsed structure, e.g. a sort order or
+/// lookup index derived from its public fields.
+///
+/// It is never serialized and starts empty again when cloned. Call [`Cached::invalidate`]
+/// after mutating the data it was derived from.
+pub struct Cached<T>(OnceLock<T>);
+
+impl<T> Default for Cached<T> {
+    fn default() -> Self {
+        Self(OnceLock::new())
+    }
+}
+
+impl<T> Cached<T> {
+    pub fn get_or_init(&self, init: impl FnOnce() -> T) -> &T {
+        self.0.get_or_init(init)
+    }
+
+    pub fn get(&self) -> Option<&T> {
+        self.0.get()
+    }
+
+    pub fn invalidate(&mut self) {
+        self.0 = OnceLock::new();
+    }
+}
+
+impl<T> Clone for Cached<T> {
+    fn clone(&self) -> Self {
+        Self(OnceLock::new())
+    }
+}
+
+impl<T> std::fmt::Debug for Cached<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(if self.0.get().is_some() { "Cached(..)" } else { "Cached(empty)" })
+    }
+}
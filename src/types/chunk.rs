@@ -1,15 +1,16 @@
 use hex;
 use log::debug;
 use serde::{Deserialize, Serialize};
-use std::io::{Read, Seek, SeekFrom};
+use std::io::{Read, Seek};
 use uuid::Uuid;
-use napi_derive::napi;
 
 use crate::error::ManifestError;
-use crate::parser::reader::ReadExt;
+use crate::format::CHUNK_PART_BASE_SIZE;
+use crate::parser::reader::{bounded_capacity, ReadExt};
+use crate::options::{ParseOptions, StringPolicy};
+use crate::schema::{self, Value};
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
-#[napi(object)]
 pub struct Chunk {
     pub guid: String,
     pub hash: String, // Store as hex string for NAPI compatibility
@@ -31,219 +32,218 @@ impl Chunk {
     pub fn sha_hash(&self) -> String {
         self.sha_hash.to_string()
     }
-}
 
-#[derive(Debug, Clone, Serialize, Deserialize, Default)]
-#[napi(object)]
-pub struct ChunkDataList {
-    pub data_size: u32,
-    pub data_version: u8,
-    pub count: u32,
-    pub elements: Vec<Chunk>,
-    #[serde(skip)]
-    pub chunk_lookup: std::collections::HashMap<String, u32>,
-}
+    /// This chunk's GUID as Epic tooling prints it (32 upper-case hex digits).
+    pub fn guid_epic_format(&self) -> String {
+        epic_guid(&self.guid)
+    }
 
-/// A wrapper that limits reading to a specific range of data
-struct LimitedReader<'a> {
-    data: &'a [u8],
-    position: usize,
-    limit: usize,
-}
+    /// The group Epic files the chunk with GUID `guid` under on its CDN:
+    /// the CRC-32 of the GUID's bytes, modulo 100. `None` if `guid` is not a
+    /// GUID in either accepted form (see [`normalize_guid`]).
+    pub fn compute_group(guid: &str) -> Option<u8> {
+        let uuid = Uuid::parse_str(&normalize_guid(guid)?).ok()?;
+        Some((crc32(uuid.as_bytes()) % 100) as u8)
+    }
 
-impl<'a> LimitedReader<'a> {
-    fn new(data: &'a [u8], limit: usize) -> Self {
-        Self {
-            data,
-            position: 0,
-            limit: std::cmp::min(limit, data.len()),
+    /// Directory Epic's CDN uses for chunks of manifests at `feature_level`.
+    pub fn chunk_dir(feature_level: i32) -> &'static str {
+        match feature_level {
+            l if l >= 15 => "ChunksV4",
+            l if l >= 6 => "ChunksV3",
+            l if l >= 3 => "ChunksV2",
+            _ => "Chunks",
         }
     }
+
+    /// Relative CDN path of this chunk, e.g.
+    /// `ChunksV4/42/0123456789ABCDEF_00112233445566778899AABBCCDDEEFF.chunk`
+    pub fn cdn_path(&self, feature_level: i32) -> String {
+        format!(
+            "{}/{:02}/{}_{}.chunk",
+            Self::chunk_dir(feature_level),
+            self.group,
+            self.hash.to_uppercase(),
+            epic_guid(&self.guid)
+        )
+    }
 }
 
-impl<'a> Read for LimitedReader<'a> {
-    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
-        let available = self.limit.saturating_sub(self.position);
-        if available == 0 {
-            return Ok(0);
-        }
-        
-        let to_read = std::cmp::min(buf.len(), available);
-        let end_pos = self.position + to_read;
-        
-        if end_pos <= self.data.len() {
-            buf[..to_read].copy_from_slice(&self.data[self.position..end_pos]);
-            self.position = end_pos;
-            Ok(to_read)
-        } else {
-            Ok(0)
+/// CRC-32 (IEEE, as zlib computes it). Only ever run over 16 bytes, so the
+/// bitwise form is fast enough.
+fn crc32(data: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for &byte in data {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            crc = (crc >> 1) ^ (0xEDB8_8320 & (crc & 1).wrapping_neg());
         }
     }
+    !crc
 }
 
-impl<'a> Seek for LimitedReader<'a> {
-    fn seek(&mut self, pos: SeekFrom) -> std::io::Result<u64> {
-        let new_pos = match pos {
-            SeekFrom::Start(offset) => offset as usize,
-            SeekFrom::End(offset) => {
-                if offset >= 0 {
-                    self.limit + offset as usize
-                } else {
-                    self.limit.saturating_sub((-offset) as usize)
-                }
-            }
-            SeekFrom::Current(offset) => {
-                if offset >= 0 {
-                    self.position + offset as usize
-                } else {
-                    self.position.saturating_sub((-offset) as usize)
-                }
-            }
-        };
-        
-        self.position = std::cmp::min(new_pos, self.limit);
-        Ok(self.position as u64)
+/// Format a parsed GUID the way Epic tooling does: four little-endian u32
+/// segments as 32 upper-case hex digits.
+pub(crate) fn epic_guid(guid: &str) -> String {
+    match Uuid::parse_str(guid) {
+        Ok(uuid) => uuid
+            .as_bytes()
+            .chunks_exact(4)
+            .map(|b| format!("{:08X}", u32::from_le_bytes([b[0], b[1], b[2], b[3]])))
+            .collect(),
+        Err(_) => guid.to_uppercase(),
     }
 }
 
-impl ChunkDataList {
-    pub fn read<R: Read + Seek>(mut rdr: R) -> Result<Self, ManifestError> {
-        let start_pos = rdr.stream_position()?;
-        debug!(
-            "Reading chunk list at position: {} (0x{:x})",
-            start_pos, start_pos
-        );
-
-        let data_size = rdr.u32()?;
-        debug!("  Data size: {} (0x{:x})", data_size, data_size);
-
-        if data_size == 0 || data_size > 1024 * 1024 * 1024 {
-            // 1GB max
-            return Err(ManifestError::Invalid(format!(
-                "Invalid data size: {} (0x{:x}). Must be between 1 and 1GB",
-                data_size, data_size
-            )));
-        }
+/// How GUID strings are emitted.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum GuidFormat {
+    /// Lower-case hyphenated UUID over the raw GUID bytes (the default).
+    #[default]
+    Hyphenated,
+    /// Upper-case 32-hex concatenation of the four u32 segments, as used by
+    /// Epic tooling and CDN chunk file names.
+    Epic,
+}
 
-        // Read remaining data into buffer and use LimitedReader
-        let adjusted_data_size = data_size.saturating_sub(4); // Subtract the 4 bytes we already read for data_size
-        // Use tolerant reading to handle cases where less data is available than expected
-        let remaining_data = rdr.read_bytes_tolerant(adjusted_data_size as usize)?;
-        let actual_size = remaining_data.len();
-        
-        if actual_size < adjusted_data_size as usize {
-            debug!(
-                "Warning: Expected {} bytes but only {} bytes available for chunk data. Using available data.",
-                adjusted_data_size, actual_size
-            );
-        }
-        
-        let mut limited_reader = LimitedReader::new(&remaining_data, actual_size);
-        let rdr = &mut limited_reader;
-        
-        debug!(
-            "ChunkDataList: using limited reader with {} bytes",
-            adjusted_data_size
-        );
+/// Render a GUID given in either accepted form (see [`normalize_guid`]) in
+/// `format`. Unparseable input is returned unchanged.
+pub fn format_guid(guid: &str, format: GuidFormat) -> String {
+    match (format, normalize_guid(guid)) {
+        (GuidFormat::Hyphenated, Some(canonical)) => canonical,
+        (GuidFormat::Epic, Some(canonical)) => epic_guid(&canonical),
+        (_, None) => guid.to_string(),
+    }
+}
 
-        let data_version = rdr.u8()?;
-        debug!("  Data version: {} (0x{:x})", data_version, data_version);
+/// Canonical hyphenated form of a GUID written either hyphenated or in
+/// Epic's 32-hex form, or `None` if it is neither.
+pub fn normalize_guid(guid: &str) -> Option<String> {
+    if guid.len() == 32 {
+        guid_from_epic(guid)
+    } else {
+        Uuid::try_parse(guid).ok().map(|uuid| uuid.to_string())
+    }
+}
 
-        let count = rdr.u32()?;
-        debug!("  Count: {} (0x{:x})", count, count);
+/// Inverse of [`epic_guid`]: turn a 32-hex Epic GUID back into the parser's
+/// hyphenated representation.
+pub(crate) fn guid_from_epic(epic: &str) -> Option<String> {
+    if epic.len() != 32 || !epic.is_ascii() {
+        return None;
+    }
+    let mut bytes = [0u8; 16];
+    for (i, segment) in bytes.chunks_exact_mut(4).enumerate() {
+        let value = u32::from_str_radix(&epic[i * 8..(i + 1) * 8], 16).ok()?;
+        segment.copy_from_slice(&value.to_le_bytes());
+    }
+    Some(Uuid::from_bytes(bytes).to_string())
+}
 
-        if count > 1_000_000 {
-            // Reasonable max chunk count
-            return Err(ManifestError::Invalid(format!(
-                "Invalid count: {} (0x{:x}). Must be less than 1,000,000",
-                count, count
-            )));
-        }
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct ChunkDataList {
+    pub data_size: u32,
+    pub data_version: u8,
+    pub count: u32,
+    /// In stored order for binary manifests, and in order of first reference
+    /// by the file list for converted JSON manifests. Either way the same
+    /// input always yields the same order, and indices into this list (as
+    /// used by `chunk_lookup` and availability bitmaps) are stable.
+    pub elements: Vec<Chunk>,
+    #[serde(skip)]
+    pub chunk_lookup: std::collections::HashMap<String, u32>,
+    /// Hex of any bytes past the fields known to this parser, present when a
+    /// newer manifest version grew the chunk list (per `data_size`).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub extra_data: Option<String>,
+    /// Fields of `elements` holding placeholders rather than stored data,
+    /// such as `window_size` for chunks converted from JSON manifests.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub synthetic: Vec<String>,
+}
+
+impl ChunkDataList {
+    /// `chunk`'s SHA-1 to verify its data against: `None` when it is empty,
+    /// all zeros, or made up (`sha_hash` in [`ChunkDataList::synthetic`],
+    /// which covers every chunk of the list).
+    pub fn stored_sha_hash<'a>(&self, chunk: &'a Chunk) -> Option<&'a str> {
+        let sha = chunk.sha_hash.as_str();
+        let placeholder = sha.is_empty() || sha.bytes().all(|b| b == b'0') || self.synthetic.iter().any(|f| f == "sha_hash");
+        (!placeholder).then_some(sha)
+    }
+
+    /// Every chunk sorted by group, then by GUID (in hyphenated form, whatever
+    /// [`GuidFormat`] the list uses): the order the CDN lays chunks out in,
+    /// independent of how the manifest stored them.
+    pub fn iter_download_order(&self) -> impl Iterator<Item = &Chunk> {
+        let mut chunks: Vec<&Chunk> = self.elements.iter().collect();
+        chunks.sort_by_cached_key(|c| (c.group, normalize_guid(&c.guid).unwrap_or_else(|| c.guid.clone())));
+        chunks.into_iter()
+    }
 
-        let mut elements = Vec::with_capacity(count as usize);
-        let mut chunk_lookup = std::collections::HashMap::with_capacity(count as usize);
+    pub fn read<R: Read + Seek>(rdr: R) -> Result<Self, ManifestError> {
+        Self::read_with_options(rdr, &ParseOptions::default())
+    }
 
-        debug!("\nReading GUIDs...");
+    /// [`ChunkDataList::read`] under the deadline, limits and strictness of
+    /// `options`.
+    pub fn read_with_options<R: Read + Seek>(mut rdr: R, options: &ParseOptions) -> Result<Self, ManifestError> {
+        let mut section = schema::SectionReader::open_with(&mut rdr, &schema::CHUNK_LIST, options)?;
+        let mut values = section.read_all(&StringPolicy::RAW)?;
+        let count = section.count;
+
+        let mut guids = values.take("guid").into_iter();
+        let mut hashes = values.take("hash").into_iter();
+        let mut sha_hashes = values.take("sha_hash").into_iter();
+        let mut groups = values.take("group").into_iter();
+        let mut window_sizes = values.take("window_size").into_iter();
+        let mut file_sizes = values.take("file_size").into_iter();
+
+        let mut elements = Vec::with_capacity(bounded_capacity::<Chunk>(count as usize));
+        let mut chunk_lookup =
+            std::collections::HashMap::with_capacity(bounded_capacity::<(String, u32)>(count as usize));
         for i in 0..count {
-            let guid_bytes = rdr.read_bytes_tolerant(16)?;
-            if guid_bytes.len() != 16 {
-                debug!("Warning: Expected 16 bytes for GUID but got {} bytes for chunk {}", guid_bytes.len(), i);
-                return Err(ManifestError::Invalid(format!(
-                    "Expected 16 bytes for GUID but got {} bytes for chunk {}", 
-                    guid_bytes.len(), i
-                )));
-            }
-            let mut guid_array = [0u8; 16];
-            guid_array.copy_from_slice(&guid_bytes);
-            let guid = Uuid::from_bytes(guid_array);
-            let guid_str = guid.to_string();
-            chunk_lookup.insert(guid_str.clone(), i);
+            let guid = guids.next().map(Value::into_string).unwrap_or_default();
+            chunk_lookup.insert(guid.clone(), i);
             elements.push(Chunk {
-                guid: guid_str,
-                hash: String::new(),
-                sha_hash: String::new(),
-                group: 0,
-                window_size: 0,
-                file_size: String::new(),
+                guid,
+                hash: format!("{:016x}", hashes.next().map_or(0, |v| v.as_u64())),
+                sha_hash: hex::encode(sha_hashes.next().map(Value::into_bytes).unwrap_or_default()),
+                group: groups.next().map_or(0, |v| v.as_u64() as u8),
+                window_size: window_sizes.next().map_or(0, |v| v.as_u64() as u32),
+                file_size: file_sizes.next().map_or(0, |v| v.as_u64()).to_string(),
             });
         }
 
-        debug!("\nReading hashes...");
-        for chunk in &mut elements {
-            let hash = rdr.u64()?;
-            chunk.hash = format!("{:016x}", hash);
-        }
-
-        debug!("\nReading SHA hashes...");
-        for (i, chunk) in elements.iter_mut().enumerate() {
-            let hash_bytes = rdr.read_bytes_tolerant(20)?;
-            if hash_bytes.len() == 20 {
-                chunk.sha_hash = hex::encode(hash_bytes);
-            } else {
-                debug!("Warning: Expected 20 bytes for SHA hash but got {} bytes for chunk {}", hash_bytes.len(), i);
-                let mut padded_hash = hash_bytes;
-                padded_hash.resize(20, 0);
-                chunk.sha_hash = hex::encode(padded_hash);
-            }
-        }
-
-        debug!("\nReading groups...");
-        for chunk in &mut elements {
-            chunk.group = rdr.u8()?;
-        }
-
-        debug!("\nReading window sizes...");
-        for chunk in &mut elements {
-            chunk.window_size = rdr.u32()?;
-        }
-
-        debug!("\nReading file sizes...");
-        for chunk in &mut elements {
-            let file_size = rdr.u64()?;
-            chunk.file_size = file_size.to_string();
-        }
-
         Ok(Self {
-            data_size,
-            data_version,
+            data_size: section.data_size,
+            data_version: section.data_version,
             count,
             elements,
             chunk_lookup,
+            extra_data: section.extra_data(),
+            synthetic: Vec::new(),
         })
     }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
-#[napi(object)]
 pub struct ChunkPart {
     pub data_size: u32,
     pub parent_guid: String,
     pub offset: u32,
     pub size: u32,
+    /// Hex of any bytes past the fields known to this parser, present when a
+    /// newer manifest version grew the chunk part (per `data_size`).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub extra_data: Option<String>,
     #[serde(skip)]
     pub chunk: Option<Chunk>, // Reference to parent chunk
 }
 
+/// Larger `data_size` values are treated as corruption rather than new fields.
+pub(crate) const MAX_CHUNK_PART_SIZE: u32 = 4096;
+
 impl ChunkPart {
     pub fn read<R: Read + Seek>(
         rdr: &mut R,
@@ -258,6 +258,13 @@ impl ChunkPart {
             ManifestError::Io(e)
         })?;
 
+        if !(CHUNK_PART_BASE_SIZE..=MAX_CHUNK_PART_SIZE).contains(&data_size) {
+            return Err(ManifestError::Invalid(format!(
+                "chunk part data size {} at position {} outside {}..={}",
+                data_size, current_pos, CHUNK_PART_BASE_SIZE, MAX_CHUNK_PART_SIZE
+            )));
+        }
+
         // Read GUID
         let guid_bytes = rdr.read_bytes_tolerant(16).map_err(|e| {
             debug!("Failed to read GUID at position {}: {}", rdr.stream_position().unwrap_or(0), e);
@@ -275,14 +282,6 @@ impl ChunkPart {
         guid_array.copy_from_slice(&guid_bytes);
         let parent_guid = Uuid::from_bytes(guid_array).to_string();
 
-        // Validate parent GUID exists in chunk lookup
-        if !chunk_lookup.contains_key(&parent_guid) {
-            return Err(ManifestError::Invalid(format!(
-                "Parent GUID {} not found in chunk lookup",
-                parent_guid
-            )));
-        }
-
         let offset = rdr.u32().map_err(|e| {
             debug!("Failed to read offset at position {}: {}", rdr.stream_position().unwrap_or(0), e);
             ManifestError::Io(e)
@@ -293,6 +292,32 @@ impl ChunkPart {
             ManifestError::Io(e)
         })?;
 
+        // Newer versions append fields; keep them and stay aligned with the
+        // next part.
+        let extra_len = (data_size - CHUNK_PART_BASE_SIZE) as usize;
+        let extra_data = if extra_len > 0 {
+            let extra = rdr.read_bytes_tolerant(extra_len)?;
+            if extra.len() < extra_len {
+                return Err(ManifestError::Invalid(format!(
+                    "Expected {} trailing bytes for chunk part but got {}",
+                    extra_len,
+                    extra.len()
+                )));
+            }
+            Some(hex::encode(extra))
+        } else {
+            None
+        };
+
+        // Validate parent GUID exists in chunk lookup. The part has been
+        // consumed in full, so the reader stays aligned on failure.
+        if !chunk_lookup.contains_key(&parent_guid) {
+            return Err(ManifestError::Invalid(format!(
+                "Parent GUID {} not found in chunk lookup",
+                parent_guid
+            )));
+        }
+
         // Get reference to parent chunk
         let chunk_idx = chunk_lookup[&parent_guid];
         let chunk = chunks.get(chunk_idx as usize).cloned();
@@ -302,6 +327,7 @@ impl ChunkPart {
             parent_guid,
             offset,
             size,
+            extra_data,
             chunk,
         })
     }
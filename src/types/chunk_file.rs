@@ -0,0 +1,232 @@
+use log::debug;
+use miniz_oxide::inflate::decompress_to_vec_zlib;
+use std::io::{Cursor, Read, Seek};
+
+use crate::crypto::AesKey;
+use crate::error::ManifestError;
+use crate::format::{CHUNK_HEADER_SIZE_V1, CHUNK_HEADER_SIZE_V2, CHUNK_HEADER_SIZE_V3, CHUNK_MAGIC, CHUNK_WINDOW_SIZE};
+use crate::parser::reader::ReadExt;
+use crate::types::chunk::{normalize_guid, Chunk};
+use crate::types::flags::*;
+
+/// Header of a `.chunk` file as served by Epic's CDN.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ChunkFileHeader {
+    pub version: u32,
+    pub header_size: u32,
+    pub data_size_compressed: u32,
+    pub guid: String,
+    pub rolling_hash: u64,
+    pub stored_as: u8,
+    /// Present from header version 2.
+    pub sha_hash: Option<String>,
+    pub hash_type: u8,
+    /// Present from header version 3; 1 MiB before that.
+    pub data_size_uncompressed: u32,
+}
+
+impl ChunkFileHeader {
+    /// Read the header from the start of a chunk file, leaving `rdr` at the
+    /// first byte of the payload. Only the header is read, so `rdr` can be
+    /// a response body still downloading.
+    pub fn read<R: Read>(mut rdr: R) -> Result<Self, ManifestError> {
+        let mut bytes = vec![0; CHUNK_HEADER_SIZE_V1 as usize];
+        // The magic first, so that anything else fails without reading on.
+        rdr.read_exact(&mut bytes[..4])?;
+        if u32::from_le_bytes(bytes[..4].try_into().expect("4 bytes")) != CHUNK_MAGIC {
+            return Err(ManifestError::Invalid("invalid chunk magic number".to_string()));
+        }
+        rdr.read_exact(&mut bytes[4..])?;
+        let version = u32::from_le_bytes(bytes[4..8].try_into().expect("4 bytes"));
+        let known_size = match version {
+            0 | 1 => CHUNK_HEADER_SIZE_V1,
+            2 => CHUNK_HEADER_SIZE_V2,
+            _ => CHUNK_HEADER_SIZE_V3,
+        };
+        bytes.resize(known_size as usize, 0);
+        rdr.read_exact(&mut bytes[CHUNK_HEADER_SIZE_V1 as usize..])?;
+        let header = Self::read_fields(Cursor::new(&bytes))?;
+
+        // Newer header versions may append fields; skip them.
+        let Some(extra) = header.header_size.checked_sub(known_size) else {
+            return Err(ManifestError::Invalid(format!(
+                "chunk header size {} is smaller than version {} needs",
+                header.header_size, version
+            )));
+        };
+        let skipped = std::io::copy(&mut rdr.by_ref().take(extra as u64), &mut std::io::sink())?;
+        if skipped != extra as u64 {
+            return Err(std::io::Error::from(std::io::ErrorKind::UnexpectedEof).into());
+        }
+        Ok(header)
+    }
+
+    /// [`ChunkFileHeader::read`] from the first bytes of a chunk file, or
+    /// `None` if `prefix` does not hold the whole header yet.
+    pub fn from_prefix(prefix: &[u8]) -> Result<Option<Self>, ManifestError> {
+        match Self::read(prefix) {
+            Ok(header) => Ok(Some(header)),
+            Err(ManifestError::Io(e)) if e.kind() == std::io::ErrorKind::UnexpectedEof => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    fn read_fields<R: Read + Seek>(mut rdr: R) -> Result<Self, ManifestError> {
+        let magic = rdr.u32()?;
+        if magic != CHUNK_MAGIC {
+            return Err(ManifestError::Invalid("invalid chunk magic number".to_string()));
+        }
+
+        let version = rdr.u32()?;
+        let header_size = rdr.u32()?;
+        let data_size_compressed = rdr.u32()?;
+
+        let guid_bytes = rdr.read_bytes_tolerant(16)?;
+        if guid_bytes.len() != 16 {
+            return Err(ManifestError::Invalid(format!(
+                "Expected 16 bytes for chunk GUID but got {} bytes",
+                guid_bytes.len()
+            )));
+        }
+        let mut guid_array = [0u8; 16];
+        guid_array.copy_from_slice(&guid_bytes);
+        let guid = uuid::Uuid::from_bytes(guid_array).to_string();
+
+        let rolling_hash = rdr.u64()?;
+        let stored_as = rdr.u8()?;
+
+        let (sha_hash, hash_type) = if version >= 2 {
+            let hash = rdr.read_bytes_tolerant(20)?;
+            if hash.len() != 20 {
+                return Err(ManifestError::Invalid(format!(
+                    "Expected 20 bytes for chunk SHA hash but got {} bytes",
+                    hash.len()
+                )));
+            }
+            (Some(hex::encode(hash)), rdr.u8()?)
+        } else {
+            (None, 0)
+        };
+
+        let data_size_uncompressed = if version >= 3 {
+            rdr.u32()?
+        } else {
+            CHUNK_WINDOW_SIZE
+        };
+
+        debug!(
+            "Chunk header: version={}, header_size={}, compressed={}, guid={}",
+            version, header_size, data_size_compressed, guid
+        );
+
+        Ok(Self {
+            version,
+            header_size,
+            data_size_compressed,
+            guid,
+            rolling_hash,
+            stored_as,
+            sha_hash,
+            hash_type,
+            data_size_uncompressed,
+        })
+    }
+
+    pub fn is_compressed(&self) -> bool {
+        self.stored_as & STORED_COMPRESSED != 0
+    }
+
+    pub fn is_encrypted(&self) -> bool {
+        self.stored_as & STORED_ENCRYPTED != 0
+    }
+
+    /// Size of the whole chunk file: header and stored payload.
+    pub fn file_size(&self) -> u64 {
+        self.header_size as u64 + self.data_size_compressed as u64
+    }
+
+    /// Check that this header belongs to `chunk`: same GUID, and the same
+    /// file size, rolling hash and SHA-1 where both sides record one.
+    /// `stored_sha` is the manifest's SHA-1 for the chunk, as returned by
+    /// [`crate::types::chunk::ChunkDataList::stored_sha_hash`].
+    pub fn verify_identity(&self, chunk: &Chunk, stored_sha: Option<&str>) -> Result<(), ManifestError> {
+        let mismatch = |field: &str, header: &dyn std::fmt::Display, manifest: &dyn std::fmt::Display| {
+            Err(ManifestError::Invalid(format!(
+                "chunk header {} {} does not match the manifest's {} for chunk {}",
+                field, header, manifest, chunk.guid
+            )))
+        };
+        if normalize_guid(&self.guid) != normalize_guid(&chunk.guid) {
+            return mismatch("GUID", &self.guid, &chunk.guid);
+        }
+        if let Ok(size) = chunk.file_size.parse::<u64>() {
+            if size != 0 && size != self.file_size() {
+                return mismatch("file size", &self.file_size(), &size);
+            }
+        }
+        if let Ok(hash) = u64::from_str_radix(&chunk.hash, 16) {
+            if hash != 0 && self.rolling_hash != 0 && hash != self.rolling_hash {
+                return mismatch("rolling hash", &format!("{:016x}", self.rolling_hash), &chunk.hash);
+            }
+        }
+        if let (Some(header), Some(manifest)) = (&self.sha_hash, stored_sha) {
+            if !header.eq_ignore_ascii_case(manifest) {
+                return mismatch("SHA-1", header, &manifest);
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Parse a complete chunk file and return its header and uncompressed data.
+/// Encrypted chunks fail with [`ManifestError::EncryptedChunk`]; see
+/// [`decode_chunk_file_with_key`].
+pub fn decode_chunk_file(data: &[u8]) -> Result<(ChunkFileHeader, Vec<u8>), ManifestError> {
+    decode_chunk_file_with_key(data, None)
+}
+
+/// [`decode_chunk_file`], decrypting encrypted chunks with `key` before
+/// decompressing them.
+pub fn decode_chunk_file_with_key(
+    data: &[u8],
+    key: Option<&AesKey>,
+) -> Result<(ChunkFileHeader, Vec<u8>), ManifestError> {
+    let header = ChunkFileHeader::read(Cursor::new(data))?;
+
+    let start = header.header_size as usize;
+    let end = start + header.data_size_compressed as usize;
+    if start > data.len() || end > data.len() {
+        return Err(ManifestError::Invalid("chunk data out of bounds".to_string()));
+    }
+    let payload = &data[start..end];
+
+    let decrypted;
+    let payload = if header.is_encrypted() {
+        let key = key.ok_or_else(|| ManifestError::EncryptedChunk(header.guid.clone()))?;
+        decrypted = key.decrypt(payload)?;
+        decrypted.as_slice()
+    } else {
+        payload
+    };
+
+    let decoded = if header.is_compressed() {
+        decompress_to_vec_zlib(payload)
+            .map_err(|e| ManifestError::Inflate(format!("chunk decompression failed: {}", e)))?
+    } else if header.is_encrypted() {
+        // Block padding is not part of the data.
+        payload[..payload.len().min(header.data_size_uncompressed as usize)].to_vec()
+    } else {
+        payload.to_vec()
+    };
+
+    if decoded.len() != header.data_size_uncompressed as usize {
+        debug!(
+            "Warning: chunk {} decoded to {} bytes, header says {}",
+            header.guid,
+            decoded.len(),
+            header.data_size_uncompressed
+        );
+    }
+
+    Ok((header, decoded))
+}
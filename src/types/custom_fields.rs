@@ -0,0 +1,166 @@
+//! A manifest's custom fields: free-form string pairs that build tooling
+//! attaches, such as `BaseUrl` or `bIsIncompleteInstall`.
+//!
+//! UE stores them in a `TMap<FString, FString>`, whose keys compare
+//! case-insensitively, yet Epic's tooling occasionally emits the same key
+//! twice. [`CustomFields`] therefore keeps every entry in stored order with
+//! its stored spelling; lookups ignore ASCII case and the first entry wins.
+
+use serde::de::{MapAccess, Visitor};
+use serde::ser::SerializeMap;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::fmt;
+use std::io::{Read, Seek};
+
+use crate::error::ManifestError;
+use crate::options::ParseOptions;
+use crate::schema;
+
+/// Set on manifests of builds that were not fully staged.
+pub const INCOMPLETE_INSTALL: &str = "bIsIncompleteInstall";
+
+/// One stored key/value pair.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct CustomField {
+    pub key: String,
+    pub value: String,
+}
+
+/// Custom fields in stored order, duplicates included. Serializes as a JSON
+/// object, repeating duplicate keys the way Epic's JSON manifests do.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct CustomFields {
+    pub entries: Vec<CustomField>,
+}
+
+impl CustomFields {
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn push(&mut self, key: impl Into<String>, value: impl Into<String>) {
+        self.entries.push(CustomField {
+            key: key.into(),
+            value: value.into(),
+        });
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.entries.iter().map(|e| (e.key.as_str(), e.value.as_str()))
+    }
+
+    /// Value of the first entry whose key matches `key`, ignoring ASCII case.
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.entries.iter().find(|e| e.key.eq_ignore_ascii_case(key)).map(|e| e.value.as_str())
+    }
+
+    /// Values of every entry whose key matches `key`, in stored order.
+    pub fn get_all<'a>(&'a self, key: &'a str) -> impl Iterator<Item = &'a str> + 'a {
+        self.entries
+            .iter()
+            .filter(move |e| e.key.eq_ignore_ascii_case(key))
+            .map(|e| e.value.as_str())
+    }
+
+    /// `key` as a boolean: `true`/`false` in any case, or `1`/`0`.
+    pub fn get_bool(&self, key: &str) -> Option<bool> {
+        let value = self.get(key)?.trim();
+        if value.eq_ignore_ascii_case("true") || value == "1" {
+            Some(true)
+        } else if value.eq_ignore_ascii_case("false") || value == "0" {
+            Some(false)
+        } else {
+            None
+        }
+    }
+
+    /// `key` as an unsigned integer.
+    pub fn get_u64(&self, key: &str) -> Option<u64> {
+        self.get(key)?.trim().parse().ok()
+    }
+
+    /// Keys stored more than once (ignoring ASCII case), each reported once
+    /// with the spelling of its first entry.
+    pub fn duplicate_keys(&self) -> Vec<&str> {
+        // Custom field lists are short, so the quadratic scan is fine.
+        let mut duplicates: Vec<&str> = Vec::new();
+        for (index, entry) in self.entries.iter().enumerate() {
+            let Some(first) = self.entries[..index].iter().find(|e| e.key.eq_ignore_ascii_case(&entry.key)) else {
+                continue;
+            };
+            if !duplicates.iter().any(|d| d.eq_ignore_ascii_case(&first.key)) {
+                duplicates.push(&first.key);
+            }
+        }
+        duplicates
+    }
+
+    /// Read the binary custom fields section, returning it with its data
+    /// version and any bytes past the known fields.
+    pub(crate) fn read<R: Read + Seek>(
+        rdr: &mut R,
+        options: &ParseOptions,
+    ) -> Result<(Self, u8, Option<String>), ManifestError> {
+        let mut section = schema::SectionReader::open_with(rdr, &schema::CUSTOM_FIELDS, options)?;
+        let mut values = section.read_all(&options.strings)?;
+        let entries = values
+            .take("key")
+            .into_iter()
+            .zip(values.take("value"))
+            .map(|(key, value)| CustomField {
+                key: key.into_string(),
+                value: value.into_string(),
+            })
+            .collect();
+        Ok((Self { entries }, section.data_version, section.extra_data()))
+    }
+
+    /// Whether the build was marked as not fully staged.
+    pub fn is_incomplete_install(&self) -> bool {
+        self.get_bool(INCOMPLETE_INSTALL).unwrap_or(false)
+    }
+}
+
+impl Serialize for CustomFields {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(Some(self.entries.len()))?;
+        for entry in &self.entries {
+            map.serialize_entry(&entry.key, &entry.value)?;
+        }
+        map.end()
+    }
+}
+
+impl<'de> Deserialize<'de> for CustomFields {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct EntriesVisitor;
+
+        impl<'de> Visitor<'de> for EntriesVisitor {
+            type Value = CustomFields;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("a map of custom fields")
+            }
+
+            fn visit_map<A: MapAccess<'de>>(self, mut access: A) -> Result<CustomFields, A::Error> {
+                let mut fields = CustomFields::default();
+                while let Some((key, value)) = access.next_entry::<String, serde_json::Value>()? {
+                    // Values are strings in practice; keep anything else as
+                    // its JSON text rather than rejecting the manifest.
+                    let value = match value {
+                        serde_json::Value::String(s) => s,
+                        other => other.to_string(),
+                    };
+                    fields.push(key, value);
+                }
+                Ok(fields)
+            }
+        }
+
+        deserializer.deserialize_map(EntriesVisitor)
+    }
+}
@@ -1,76 +1,21 @@
 use hex;
 use log::debug;
 use serde::{Deserialize, Serialize};
-use std::io::{Read, Seek, SeekFrom};
-use napi_derive::napi;
+use std::borrow::Cow;
+use std::io::{Cursor, Read, Seek, SeekFrom};
 
 use crate::error::ManifestError;
-use crate::parser::reader::ReadExt;
-use crate::types::chunk::{ChunkDataList, ChunkPart};
-
-/// A wrapper that limits reading to a specific range of data
-struct LimitedReader<'a> {
-    data: &'a [u8],
-    position: usize,
-    limit: usize,
-}
-
-impl<'a> LimitedReader<'a> {
-    fn new(data: &'a [u8], limit: usize) -> Self {
-        Self {
-            data,
-            position: 0,
-            limit: limit.min(data.len()),
-        }
-    }
-}
-
-impl<'a> Read for LimitedReader<'a> {
-    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
-        if self.position >= self.limit {
-            return Ok(0); // EOF
-        }
-        
-        let available = self.limit - self.position;
-        let to_read = buf.len().min(available);
-        
-        if to_read == 0 {
-            return Ok(0);
-        }
-        
-        buf[..to_read].copy_from_slice(&self.data[self.position..self.position + to_read]);
-        self.position += to_read;
-        Ok(to_read)
-    }
-}
-
-impl<'a> Seek for LimitedReader<'a> {
-    fn seek(&mut self, pos: SeekFrom) -> std::io::Result<u64> {
-        let new_pos = match pos {
-            SeekFrom::Start(offset) => offset as usize,
-            SeekFrom::End(offset) => {
-                if offset >= 0 {
-                    self.limit + offset as usize
-                } else {
-                    self.limit.saturating_sub((-offset) as usize)
-                }
-            }
-            SeekFrom::Current(offset) => {
-                if offset >= 0 {
-                    self.position + offset as usize
-                } else {
-                    self.position.saturating_sub((-offset) as usize)
-                }
-            }
-        };
-        
-        self.position = new_pos.min(self.limit);
-        Ok(self.position as u64)
-    }
-}
+use crate::hooks::Section;
+use crate::options::{check_deadline, ParseOptions, StringPolicy, DEADLINE_CHECK_INTERVAL};
+use crate::parser::reader::{bounded_capacity, ReadExt};
+use crate::schema::{self, Values};
+use crate::types::cache::Cached;
+use crate::report::{FileIssue, ParseReport};
+use crate::format::CHUNK_PART_BASE_SIZE;
+use crate::types::chunk::{ChunkDataList, ChunkPart, MAX_CHUNK_PART_SIZE};
+use crate::types::meta::trim_nul;
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
-#[napi(object)]
 pub struct FileManifest {
     #[serde(serialize_with = "trim_null_chars")]
     pub filename: String,
@@ -81,20 +26,50 @@ pub struct FileManifest {
     pub install_tags: Vec<String>,
     pub chunk_parts: Vec<ChunkPart>,
     pub file_size: i64,
+    /// Set when chunk parts of the file could not be read (see
+    /// [`ParseReport::corrupt_files`]); `file_size` then only counts the
+    /// parts that were, and is a lower bound.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub size_unknown: bool,
     #[serde(skip_serializing_if = "String::is_empty")]
     pub mime_type: String,
+    /// `file_meta_flags` decoded, filled in when parsing with
+    /// [`crate::ParseOptions::file_flags`] (see [`Manifest::fill_file_flags`]).
+    ///
+    /// [`Manifest::fill_file_flags`]: crate::types::manifest::Manifest::fill_file_flags
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub flags: Option<FileFlags>,
+}
+
+/// The bits of [`FileManifest::file_meta_flags`] as booleans.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Default, PartialEq, Eq)]
+pub struct FileFlags {
+    pub readonly: bool,
+    pub compressed: bool,
+    pub unix_executable: bool,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
-#[napi(object)]
 pub struct FileManifestList {
     pub data_size: u32,
     pub data_version: u8,
     pub count: u32,
     pub file_manifest_list: Vec<FileManifest>,
+    /// Indices into `file_manifest_list` ordered by filename; see
+    /// [`FileManifestList::sorted_by_path`].
+    #[serde(skip)]
+    pub path_order: Cached<Vec<u32>>,
+    /// Hex of any bytes past the fields known to this parser, present when a
+    /// newer manifest version grew the file list (per `data_size`).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub extra_data: Option<String>,
+    /// Fields holding placeholders rather than stored data, such as the
+    /// section and chunk part sizes of JSON manifests.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub synthetic: Vec<String>,
 }
 
-fn trim_null_chars<S>(value: &String, serializer: S) -> Result<S::Ok, S::Error>
+fn trim_null_chars<S>(value: &str, serializer: S) -> Result<S::Ok, S::Error>
 where
     S: serde::Serializer,
 {
@@ -102,7 +77,7 @@ where
     serializer.serialize_str(trimmed)
 }
 
-fn vector_trim_null_chars<S>(value: &Vec<String>, serializer: S) -> Result<S::Ok, S::Error>
+fn vector_trim_null_chars<S>(value: &[String], serializer: S) -> Result<S::Ok, S::Error>
 where
     S: serde::Serializer,
 {
@@ -123,6 +98,15 @@ pub enum EFileMetaFlags {
 }
 
 impl FileManifest {
+    /// Strip trailing NULs from the filename, symlink target, install tags
+    /// and MIME type.
+    pub fn trim_nul_padding(&mut self) {
+        trim_nul(&mut self.filename);
+        trim_nul(&mut self.symlink_target);
+        trim_nul(&mut self.mime_type);
+        self.install_tags.iter_mut().for_each(trim_nul);
+    }
+
     pub fn is_readonly(&self) -> bool {
         self.file_meta_flags & EFileMetaFlags::ReadOnly as u8 != 0
     }
@@ -134,252 +118,380 @@ impl FileManifest {
     pub fn is_unix_executable(&self) -> bool {
         self.file_meta_flags & EFileMetaFlags::UnixExecutable as u8 != 0
     }
+
+    /// `file_meta_flags` decoded.
+    pub fn meta_flags(&self) -> FileFlags {
+        FileFlags {
+            readonly: self.is_readonly(),
+            compressed: self.is_compressed(),
+            unix_executable: self.is_unix_executable(),
+        }
+    }
 }
 
-impl FileManifestList {
-    pub fn read<R: Read + Seek>(rdr: &mut R, chunk_list: &ChunkDataList) -> Result<Self, ManifestError> {
-        let start_pos = rdr.stream_position()?;
-        debug!(
-            "\nReading file list at position: {} (0x{:x})",
-            start_pos, start_pos
-        );
+/// Upper bound on chunk parts per file; larger counts are treated as corrupt.
+pub(crate) const MAX_CHUNK_PARTS: u32 = 10_000;
 
-        // Read data size (uint32 in Go)
-        let data_size = rdr.u32()?;
-        debug!("  Data size: {} (0x{:x})", data_size, data_size);
-
-        // Validate data size
-        if data_size == 0 || data_size > 1024 * 1024 * 1024 {
-            // 1GB max
-            return Err(ManifestError::Invalid(format!(
-                "Invalid data size: {} (0x{:x}). Must be between 1 and 1GB",
-                data_size, data_size
-            )));
+/// `data_size` of the chunk part at `pos`, if it is a plausible one.
+fn part_size_at(data: &[u8], pos: usize) -> Option<u64> {
+    let size = u32::from_le_bytes(data.get(pos..pos + 4)?.try_into().ok()?);
+    (CHUNK_PART_BASE_SIZE..=MAX_CHUNK_PART_SIZE).contains(&size).then_some(size as u64)
+}
+
+/// End of the chunk-part record (count followed by parts) at `pos`, if every
+/// part has a plausible size and a parent in `chunk_list`.
+fn chunk_record_end(data: &[u8], pos: usize, chunk_list: &ChunkDataList) -> Option<(usize, u32)> {
+    let count = u32::from_le_bytes(data.get(pos..pos + 4)?.try_into().ok()?);
+    if count > MAX_CHUNK_PARTS {
+        return None;
+    }
+    let mut at = pos + 4;
+    for _ in 0..count {
+        let size = part_size_at(data, at)? as usize;
+        let guid: [u8; 16] = data.get(at + 4..at + 20)?.try_into().ok()?;
+        if !chunk_list.chunk_lookup.contains_key(&uuid::Uuid::from_bytes(guid).to_string()) {
+            return None;
         }
+        at += size;
+    }
+    (at <= data.len()).then_some((at, count))
+}
 
-        // Read data version (uint8 in Go)
-        let data_version = rdr.u8()?;
-        debug!("  Data version: {} (0x{:x})", data_version, data_version);
+/// The columns after the chunk parts, which fix where the chunk-part column
+/// has to end.
+struct TrailingColumns<'a> {
+    fields: &'a [&'static schema::Field],
+    count: u32,
+    /// Whether the columns end the section body; newer data versions may
+    /// append fields the schema does not know.
+    ends_section: bool,
+}
 
-        // Validate data version
-        if data_version > 2 {
-            return Err(ManifestError::Invalid(format!(
-                "Invalid data version: {} (0x{:x}). Must be 0, 1, or 2",
-                data_version, data_version
-            )));
+impl TrailingColumns<'_> {
+    /// Where the chunk-part column ends: the last position from `from` that
+    /// the trailing columns parse from to the end of `data`. `None` if there
+    /// is none, or if unknown fields may follow the columns.
+    fn start(&self, data: &[u8], from: usize) -> Option<usize> {
+        if !self.ends_section {
+            return None;
         }
+        (from..=data.len())
+            .rev()
+            .find(|&pos| schema::columns_end(data, pos, self.count, self.fields) == Some(data.len()))
+    }
+}
 
-        // Read count (uint32 in Go)
-        let count = rdr.u32()?;
-        debug!("  Count: {} (0x{:x})", count, count);
-
-        // Read the remaining data into a buffer and use LimitedReader
-        // Use tolerant reading to handle cases where less data is available than expected
-        let remaining_data = rdr.read_bytes_tolerant(data_size as usize)?;
-        let actual_size = remaining_data.len();
-        
-        if actual_size < data_size as usize {
-            debug!(
-                "Warning: Expected {} bytes but only {} bytes available. Using available data.",
-                data_size, actual_size
-            );
+/// After a corrupt record, find where the next file's chunk-part record
+/// starts: the first position from `from` where the records of all
+/// `remaining_files` parse cleanly (empty ones included) and end exactly at
+/// `end`, the end of the chunk-part column.
+fn resync_chunk_records(
+    data: &[u8],
+    from: usize,
+    end: usize,
+    chunk_list: &ChunkDataList,
+    remaining_files: u32,
+) -> Option<usize> {
+    // Records between each position and `end`, so every position is
+    // parsed at most once however many candidates lead through it.
+    const UNKNOWN: u32 = u32::MAX;
+    const BROKEN: u32 = u32::MAX - 1;
+    let mut records_to_end = vec![UNKNOWN; end.checked_sub(from)? + 1];
+    let mut path = Vec::new();
+    (from..=end).find(|&candidate| {
+        let mut pos = candidate;
+        let mut records = loop {
+            match records_to_end[pos - from] {
+                UNKNOWN if pos == end => break 0,
+                UNKNOWN => {}
+                known => break known,
+            }
+            path.push(pos);
+            match chunk_record_end(data, pos, chunk_list) {
+                Some((next, _)) if next <= end => pos = next,
+                _ => break BROKEN,
+            }
+        };
+        for pos in path.drain(..).rev() {
+            if records != BROKEN {
+                records += 1;
+            }
+            records_to_end[pos - from] = records;
         }
-        
-        let mut limited_reader = LimitedReader::new(&remaining_data, actual_size);
-        let rdr = &mut limited_reader;
-        
-        debug!(
-            "FileManifestList: using limited reader with {} bytes",
-            data_size
-        );
+        records_to_end[candidate - from] == remaining_files
+    })
+}
 
-        // Validate count
-        if count > 1_000_000 {
-            return Err(ManifestError::Invalid(
-                "File count exceeds reasonable limit".to_string(),
-            ));
+impl FileManifestList {
+    /// Indices into `file_manifest_list` sorted by filename (byte order,
+    /// trailing NULs ignored). Computed on first use and cached. Files added
+    /// or removed since then are noticed by the length no longer matching,
+    /// and the order is recomputed for the call; renaming files in place
+    /// still needs `path_order.invalidate()`.
+    pub fn sorted_by_path(&self) -> Cow<'_, [u32]> {
+        let order = self.path_order.get_or_init(|| self.path_order_now());
+        if order.len() == self.file_manifest_list.len() {
+            Cow::Borrowed(order)
+        } else {
+            debug!("file list changed since its path order was cached; sorting again");
+            Cow::Owned(self.path_order_now())
         }
+    }
 
-        // Initialize file list with capacity
-        let mut files = Vec::with_capacity(count as usize);
+    fn path_order_now(&self) -> Vec<u32> {
+        let mut order: Vec<u32> = (0..self.file_manifest_list.len() as u32).collect();
+        order.sort_by(|&a, &b| {
+            self.file_manifest_list[a as usize]
+                .filename
+                .trim_end_matches('\0')
+                .cmp(self.file_manifest_list[b as usize].filename.trim_end_matches('\0'))
+        });
+        order
+    }
 
-        // Read filenames in batch
-        debug!("\nReading filenames...");
-        for _ in 0..count {
-            let mut file = FileManifest::default();
-            file.filename = rdr.fstring()?;
-            files.push(file);
-        }
+    /// Look up a file by exact path with a binary search over
+    /// [`FileManifestList::sorted_by_path`].
+    pub fn find_by_path(&self, path: &str) -> Option<&FileManifest> {
+        let order = self.sorted_by_path();
+        order
+            .binary_search_by(|&i| self.file_manifest_list[i as usize].filename.trim_end_matches('\0').cmp(path))
+            .ok()
+            .map(|pos| &self.file_manifest_list[order[pos] as usize])
+    }
 
-        // Read symlink targets in batch
-        debug!("\nReading symlink targets...");
-        for i in 0..count {
-            files[i as usize].symlink_target = rdr.fstring()?;
-        }
+    /// All files whose path starts with `prefix` (e.g. `Engine/Binaries/`),
+    /// in path order.
+    pub fn files_with_prefix<'a>(&'a self, prefix: &'a str) -> impl Iterator<Item = &'a FileManifest> + 'a {
+        let order = self.sorted_by_path();
+        let name = move |i: u32| self.file_manifest_list[i as usize].filename.trim_end_matches('\0');
+        let start = order.partition_point(|&i| name(i) < prefix);
+        (start..order.len())
+            .map(move |pos| order[pos])
+            .take_while(move |&i| name(i).starts_with(prefix))
+            .map(move |i| &self.file_manifest_list[i as usize])
+    }
+
+    pub fn read<R: Read + Seek>(rdr: &mut R, chunk_list: &ChunkDataList) -> Result<Self, ManifestError> {
+        Self::read_with(rdr, chunk_list, &StringPolicy::RAW, &mut ParseReport::default())
+    }
 
-        // Read SHA hashes in batch
-        debug!("\nReading file hashes...");
-        for i in 0..count {
-            let hash_bytes = rdr.read_bytes_tolerant(20)?;
-            if hash_bytes.len() == 20 {
-                files[i as usize].sha_hash = hex::encode(hash_bytes);
-            } else {
-                debug!("Warning: Expected 20 bytes for SHA hash but got {} bytes for file {}", hash_bytes.len(), i);
-                // Pad with zeros if needed or use empty hash
-                let mut padded_hash = hash_bytes;
-                padded_hash.resize(20, 0);
-                files[i as usize].sha_hash = hex::encode(padded_hash);
+    /// [`FileManifestList::read`] with strings decoded according to `policy`,
+    /// recording files with corrupt chunk parts in `report`.
+    pub fn read_with<R: Read + Seek>(
+        rdr: &mut R,
+        chunk_list: &ChunkDataList,
+        policy: &StringPolicy,
+        report: &mut ParseReport,
+    ) -> Result<Self, ManifestError> {
+        let options = ParseOptions {
+            strings: *policy,
+            ..ParseOptions::default()
+        };
+        Self::read_with_options(rdr, chunk_list, &options, report)
+    }
+
+    /// [`FileManifestList::read_with`] under the string policy, deadline,
+    /// limits and strictness of `options`.
+    pub fn read_with_options<R: Read + Seek>(
+        rdr: &mut R,
+        chunk_list: &ChunkDataList,
+        options: &ParseOptions,
+        report: &mut ParseReport,
+    ) -> Result<Self, ManifestError> {
+        let mut section = schema::SectionReader::open_with(rdr, &schema::FILE_LIST, options)?;
+        let count = section.count;
+        let mut values = schema::Values::default();
+        let mut files = Vec::new();
+        let fields = section.fields();
+        for (index, &field) in fields.iter().enumerate() {
+            if field.kind == schema::FieldKind::ChunkParts {
+                // Corruption reports name the files, so fill in what was read so far.
+                fill_columns(&mut files, &mut values);
+                let trailing = TrailingColumns {
+                    fields: &fields[index + 1..],
+                    count,
+                    ends_section: section.data_version <= schema::FILE_LIST.latest_version(),
+                };
+                let begin = section.rdr.position();
+                read_chunk_parts(&mut section.rdr, &mut files, chunk_list, &trailing, report, options)?;
+                section.record(field, begin);
+                continue;
+            }
+            if !section.read_into(field, &options.strings, &mut values)? {
+                debug!("Note: file list data past the original layout was incomplete; keeping what was read.");
+                break;
             }
         }
+        fill_columns(&mut files, &mut values);
 
-        // Read file meta flags in batch
-        debug!("\nReading file meta flags...");
-        for i in 0..count {
-            files[i as usize].file_meta_flags = rdr.u8()?;
-        }
+        debug!("FileManifestList parsing completed successfully");
+        Ok(Self {
+            data_size: section.data_size,
+            data_version: section.data_version,
+            count,
+            file_manifest_list: files,
+            path_order: Cached::default(),
+            extra_data: section.extra_data(),
+            synthetic: Vec::new(),
+        })
+    }
+}
 
-        // Read install tags in batch
-        debug!("\nReading install tags...");
-        for i in 0..count {
-            files[i as usize].install_tags = rdr.fstring_array()?;
+/// Move the columns in `values` into `files`. Files are created from the
+/// filename column, so they only take memory once their names were read.
+fn fill_columns(files: &mut Vec<FileManifest>, values: &mut Values) {
+    for value in values.take("filename") {
+        files.push(FileManifest {
+            filename: value.into_string(),
+            ..FileManifest::default()
+        });
+    }
+    for (file, value) in files.iter_mut().zip(values.take("symlink_target")) {
+        file.symlink_target = value.into_string();
+    }
+    for (file, value) in files.iter_mut().zip(values.take("sha_hash")) {
+        file.sha_hash = hex::encode(value.into_bytes());
+    }
+    for (file, value) in files.iter_mut().zip(values.take("file_meta_flags")) {
+        file.file_meta_flags = value.as_u64() as u8;
+    }
+    for (file, value) in files.iter_mut().zip(values.take("install_tags")) {
+        file.install_tags = value.into_strings();
+    }
+    for (file, value) in files.iter_mut().zip(values.take("mime_type")) {
+        file.mime_type = value.into_string();
+    }
+}
+
+/// Read the chunk-part column for `files`, skipping parts that fail to
+/// resolve and resynchronizing after corrupt records. Problems are recorded
+/// in `report`, or fail the read under [`ParseOptions::strict`].
+fn read_chunk_parts(
+    rdr: &mut Cursor<Vec<u8>>,
+    files: &mut [FileManifest],
+    chunk_list: &ChunkDataList,
+    trailing: &TrailingColumns,
+    report: &mut ParseReport,
+    options: &ParseOptions,
+) -> Result<(), ManifestError> {
+    let count = files.len() as u32;
+    debug!("\nReading chunk parts...");
+    let mut total_chunk_parts = 0;
+    let mut total_chunk_size = 0i64;
+    // Located on the first corrupt record.
+    let mut column_end = None;
+    let mut i = 0;
+    while i < count {
+        if i % DEADLINE_CHECK_INTERVAL == 0 {
+            check_deadline(options.deadline, Section::FileList)?;
         }
+        let record_pos = rdr.stream_position()?;
+        let chunk_count = rdr.u32()?;
+        let pos = rdr.stream_position()?;
+        debug!(
+            "File {}: Reading {} chunk parts at position {}",
+            i, chunk_count, pos
+        );
 
-        // Read chunk parts in batch
-        debug!("\nReading chunk parts...");
-        let mut total_chunk_parts = 0;
-        let mut total_chunk_size = 0i64;
-        for i in 0..count {
-            let chunk_count = rdr.u32()?;
-            let pos = rdr.stream_position()?;
-            debug!(
-                "File {}: Reading {} chunk parts at position {}",
-                i, chunk_count, pos
-            );
-
-            // Validate chunk count - use a reasonable limit
-            if chunk_count > 10_000 {
-                debug!(
-                    "   Warning: Invalid chunk count ({}) for file {} at position {}, skipping.",
-                    chunk_count, i, pos
-                );
-                files[i as usize].chunk_parts = Vec::new();
-                continue;
-            }
+        let mut file_chunk_size = 0i64;
+        let mut problem = None;
+        let mut in_sync = true;
 
-            // Read chunks
-            let mut chunks = Vec::with_capacity(chunk_count as usize);
-            let mut file_chunk_size = 0i64;
-            let mut valid_chunks = 0;
-
-            for j in 0..chunk_count {
-                let chunk_pos = rdr.stream_position()?;
-                match ChunkPart::read(rdr, &chunk_list.chunk_lookup, &chunk_list.elements) {
-                    Ok(chunk) => {
-                        file_chunk_size += chunk.size as i64;
-                        chunks.push(chunk);
-                        valid_chunks += 1;
-                        if j == 0 || j == chunk_count - 1 {
-                            debug!(
-                                "  Chunk part {}: size={}, offset={}, parent={} (at pos {})",
-                                j,
-                                chunks[j as usize].size,
-                                chunks[j as usize].offset,
-                                chunks[j as usize].parent_guid,
-                                chunk_pos
-                            );
-                        }
-                    }
-                    Err(e) => {
+        let max_chunk_parts = options.limits.max_chunk_parts;
+        if rdr.check_count(chunk_count, max_chunk_parts, CHUNK_PART_BASE_SIZE as u64).is_err() {
+            problem = Some(format!("implausible chunk part count {}", chunk_count));
+            in_sync = false;
+        }
+        let capacity = if in_sync { chunk_count as usize } else { 0 };
+        let mut chunks = Vec::with_capacity(bounded_capacity::<ChunkPart>(capacity));
+
+        let mut j = 0;
+        while in_sync && j < chunk_count {
+            let chunk_pos = rdr.stream_position()?;
+            match ChunkPart::read(rdr, &chunk_list.chunk_lookup, &chunk_list.elements) {
+                Ok(chunk) => {
+                    if j == 0 || j == chunk_count - 1 {
                         debug!(
-              "   Warning: Failed to read chunk part {} for file {}: {}. Skipping remaining chunks.",
-              j, i, e
-            );
-                        break;
+                            "  Chunk part {}: size={}, offset={}, parent={} (at pos {})",
+                            j, chunk.size, chunk.offset, chunk.parent_guid, chunk_pos
+                        );
                     }
+                    file_chunk_size += chunk.size as i64;
+                    chunks.push(chunk);
+                }
+                Err(e) => {
+                    // A part that was consumed in full (e.g. unknown parent)
+                    // is skipped; anything else means we lost our place.
+                    let consumed = rdr.stream_position()? - chunk_pos;
+                    in_sync = part_size_at(rdr.get_ref(), chunk_pos as usize) == Some(consumed);
+                    debug!(
+                        "   Warning: Failed to read chunk part {} for file {}: {}{}",
+                        j,
+                        i,
+                        e,
+                        if in_sync { ". Skipping it." } else { ". Resynchronizing." }
+                    );
+                    problem.get_or_insert_with(|| format!("chunk part {}: {}", j, e));
                 }
             }
-
-            if valid_chunks > 0 {
-                total_chunk_parts += valid_chunks;
-                total_chunk_size += file_chunk_size;
-                files[i as usize].chunk_parts = chunks;
-                files[i as usize].file_size = file_chunk_size; // Calculate file size from chunks
-            } else {
-                debug!(
-                    "   Warning: No valid chunks found for file {}, skipping.",
-                    i
-                );
-                files[i as usize].chunk_parts = Vec::new();
-            }
+            j += 1;
         }
 
-        // Handle version 2+ specific data with EOF tolerance
-        if data_version >= 2 {
-            debug!("\nReading version 2+ specific data...");
-            
-            // Try to read version 2+ data, but handle EOF gracefully
-            let mut version2_success = true;
-            
-            // Skip unknown arrays with EOF handling
-            for i in 0..count {
-                match rdr.u32() {
-                    Ok(array_size) => {
-                        if let Err(e) = rdr.seek(SeekFrom::Current(array_size as i64 * 16)) {
-                            debug!("Warning: Failed to seek past unknown array for file {}: {}. Stopping version 2+ parsing.", i, e);
-                            version2_success = false;
-                            break;
-                        }
-                    }
-                    Err(e) => {
-                        debug!("Warning: Failed to read array size for file {}: {}. Stopping version 2+ parsing.", i, e);
-                        version2_success = false;
-                        break;
-                    }
-                }
+        if !chunks.is_empty() {
+            total_chunk_parts += chunks.len();
+            total_chunk_size += file_chunk_size;
+            files[i as usize].file_size = file_chunk_size; // Calculate file size from chunks
+        }
+        files[i as usize].chunk_parts = chunks;
+
+        if let Some(reason) = problem {
+            if options.strict {
+                return Err(ManifestError::Strict {
+                    section: Section::FileList,
+                    message: format!("{}: {}", files[i as usize].filename.trim_end_matches('\0'), reason),
+                });
             }
+            files[i as usize].size_unknown = true;
+            report.corrupt_files.push(FileIssue {
+                index: i,
+                filename: files[i as usize].filename.clone(),
+                reason,
+            });
+        }
 
-            // Read MIME types with EOF handling
-            if version2_success {
-                for i in 0..count {
-                    match rdr.fstring() {
-                        Ok(mime_type) => {
-                            files[i as usize].mime_type = mime_type;
-                        }
-                        Err(e) => {
-                            debug!("Warning: Failed to read MIME type for file {}: {}. Stopping MIME type parsing.", i, e);
-                            break;
-                        }
-                    }
+        if !in_sync {
+            let remaining_files = count - i - 1;
+            let data = rdr.get_ref();
+            let from = record_pos as usize + 4;
+            let end = *column_end.get_or_insert_with(|| trailing.start(data, from));
+            match end.and_then(|end| resync_chunk_records(data, from, end, chunk_list, remaining_files)) {
+                Some(next) => {
+                    debug!("   Resynchronized at position {} after file {}", next, i);
+                    rdr.seek(SeekFrom::Start(next as u64))?;
                 }
-            }
-
-            // Skip unknown data with EOF handling
-            if version2_success {
-                for i in 0..count {
-                    if let Err(e) = rdr.seek(SeekFrom::Current(32)) {
-                        debug!("Warning: Failed to seek past unknown data for file {}: {}. Stopping unknown data parsing.", i, e);
-                        break;
+                None => {
+                    debug!("   Could not resynchronize after file {}; remaining files have no chunk parts.", i);
+                    for index in i + 1..count {
+                        files[index as usize].size_unknown = true;
+                        report.corrupt_files.push(FileIssue {
+                            index,
+                            filename: files[index as usize].filename.clone(),
+                            reason: "chunk parts lost after earlier corruption".to_string(),
+                        });
                     }
+                    // Skip to the columns after the chunk parts, if they can
+                    // be found, rather than dropping them too.
+                    rdr.seek(SeekFrom::Start(end.unwrap_or(data.len()) as u64))?;
+                    break;
                 }
             }
-            
-            if !version2_success {
-                debug!("Note: Version 2+ specific data parsing was incomplete due to EOF, but this is acceptable for corrupted/truncated manifests.");
-            }
         }
-
-        debug!(
-            "Total chunk parts: {}, Total chunk size: {} bytes",
-            total_chunk_parts, total_chunk_size
-        );
-
-        debug!("FileManifestList parsing completed successfully");
-
-        Ok(Self {
-            data_size,
-            data_version,
-            count,
-            file_manifest_list: files,
-        })
+        i += 1;
     }
+
+    debug!(
+        "Total chunk parts: {}, Total chunk size: {} bytes",
+        total_chunk_parts, total_chunk_size
+    );
+    Ok(())
 }
@@ -1,2 +1,7 @@
 pub const STORED_COMPRESSED: u8 = 0x01;
 pub const STORED_ENCRYPTED: u8 = 0x02;
+
+// Hash type flags (chunk headers, and manifest headers that carry one).
+// A manifest hash type of 0 means the legacy SHA-1 payload hash.
+pub const HASH_ROLLING_POLY64: u32 = 0x01;
+pub const HASH_SHA1: u32 = 0x02;
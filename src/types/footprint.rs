@@ -0,0 +1,140 @@
+//! Heap usage estimates for parsed manifests.
+//!
+//! The numbers are computed from lengths rather than allocator capacities,
+//! so the same manifest always reports the same footprint however its
+//! buffers grew while parsing or cloning. They are estimates: allocator
+//! overhead, spare capacity and lazily built caches are not counted.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::mem::{size_of, size_of_val};
+
+use crate::types::chunk::{Chunk, ChunkDataList, ChunkPart};
+use crate::types::file::{FileManifest, FileManifestList};
+use crate::types::manifest::Manifest;
+use crate::types::meta::ManifestMeta;
+
+/// Estimated heap bytes owned by a [`Manifest`], by kind of allocation.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MemoryFootprint {
+    /// String contents.
+    pub strings: u64,
+    /// Vector buffers, including the inline size of their elements.
+    pub vectors: u64,
+    /// Hash map tables.
+    pub maps: u64,
+}
+
+impl MemoryFootprint {
+    /// Heap bytes in total.
+    pub fn total(&self) -> u64 {
+        self.strings + self.vectors + self.maps
+    }
+
+    /// Heap bytes plus the `Manifest` value itself.
+    pub fn total_with_inline(&self) -> u64 {
+        self.total() + size_of::<Manifest>() as u64
+    }
+
+    fn string(&mut self, value: &str) {
+        self.strings += value.len() as u64;
+    }
+
+    fn vec<T>(&mut self, items: &[T]) {
+        self.vectors += size_of_val(items) as u64;
+    }
+
+    /// hashbrown's table for `len` entries: buckets at 7/8 load rounded to
+    /// a power of two, one control byte per bucket plus a group of padding.
+    fn map<K, V>(&mut self, map: &HashMap<K, V>) {
+        const GROUP_WIDTH: u64 = 16;
+        let len = map.len() as u64;
+        if len == 0 {
+            return;
+        }
+        let buckets = if len < 8 { (len + 1).next_power_of_two().max(4) } else { (len * 8 / 7).next_power_of_two() };
+        self.maps += buckets * size_of::<(K, V)>() as u64 + buckets + GROUP_WIDTH;
+    }
+}
+
+impl Manifest {
+    /// Estimate the heap memory this manifest holds, for enforcing memory
+    /// quotas or choosing between a full parse and the streaming APIs.
+    pub fn memory_footprint(&self) -> MemoryFootprint {
+        let mut footprint = MemoryFootprint::default();
+        footprint.string(&self.header.guid);
+        if let Some(meta) = &self.meta {
+            meta_footprint(meta, &mut footprint);
+        }
+        if let Some(chunk_list) = &self.chunk_list {
+            chunk_list_footprint(chunk_list, &mut footprint);
+        }
+        if let Some(file_list) = &self.file_list {
+            file_list_footprint(file_list, &mut footprint);
+        }
+        footprint.vec(&self.custom_fields.entries);
+        for entry in &self.custom_fields.entries {
+            footprint.string(&entry.key);
+            footprint.string(&entry.value);
+        }
+        footprint
+    }
+}
+
+fn meta_footprint(meta: &ManifestMeta, footprint: &mut MemoryFootprint) {
+    for value in [
+        &meta.app_name,
+        &meta.build_version,
+        &meta.launch_exe,
+        &meta.launch_command,
+        &meta.prereq_name,
+        &meta.prereq_path,
+        &meta.prereq_args,
+    ] {
+        footprint.string(value);
+    }
+    footprint.vec(&meta.prereq_ids);
+    meta.prereq_ids.iter().for_each(|id| footprint.string(id));
+    if let Some(build_id) = &meta.build_id {
+        footprint.string(build_id);
+    }
+}
+
+fn chunk_footprint(chunk: &Chunk, footprint: &mut MemoryFootprint) {
+    for value in [&chunk.guid, &chunk.hash, &chunk.sha_hash, &chunk.file_size] {
+        footprint.string(value);
+    }
+}
+
+fn chunk_list_footprint(chunk_list: &ChunkDataList, footprint: &mut MemoryFootprint) {
+    footprint.vec(&chunk_list.elements);
+    chunk_list.elements.iter().for_each(|c| chunk_footprint(c, footprint));
+    footprint.map(&chunk_list.chunk_lookup);
+    chunk_list.chunk_lookup.keys().for_each(|guid| footprint.string(guid));
+}
+
+fn part_footprint(part: &ChunkPart, footprint: &mut MemoryFootprint) {
+    footprint.string(&part.parent_guid);
+    if let Some(extra) = &part.extra_data {
+        footprint.string(extra);
+    }
+    if let Some(chunk) = &part.chunk {
+        chunk_footprint(chunk, footprint);
+    }
+}
+
+fn file_footprint(file: &FileManifest, footprint: &mut MemoryFootprint) {
+    for value in [&file.filename, &file.symlink_target, &file.sha_hash, &file.mime_type] {
+        footprint.string(value);
+    }
+    footprint.vec(&file.install_tags);
+    file.install_tags.iter().for_each(|tag| footprint.string(tag));
+    footprint.vec(&file.chunk_parts);
+    file.chunk_parts.iter().for_each(|p| part_footprint(p, footprint));
+}
+
+fn file_list_footprint(file_list: &FileManifestList, footprint: &mut MemoryFootprint) {
+    footprint.vec(&file_list.file_manifest_list);
+    file_list.file_manifest_list.iter().for_each(|f| file_footprint(f, footprint));
+}
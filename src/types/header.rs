@@ -2,15 +2,14 @@ use hex;
 use log::debug;
 use serde::{Deserialize, Serialize};
 use std::io::{Read, Seek};
-use napi_derive::napi;
 
+use crate::format::{
+    MANIFEST_HEADER_SIZE_GUID, MANIFEST_HEADER_SIZE_HASH_TYPE, MANIFEST_HEADER_SIZE_V1, MANIFEST_MAGIC,
+};
 use crate::parser::reader::ReadExt;
 use crate::{error::ManifestError, types::flags::*};
 
-const MANIFEST_MAGIC: u32 = 0x44BEC00C;
-
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
-#[napi(object)]
 pub struct ManifestHeader {
     pub header_size: i32,
     pub data_size_uncompressed: i32,
@@ -21,6 +20,10 @@ pub struct ManifestHeader {
     pub guid: String,
     pub rolling_hash: i64,
     pub hash_type: u32,
+    /// Fields holding placeholders rather than stored data, such as the
+    /// sizes and hash of headers made up for JSON manifests.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub synthetic: Vec<String>,
 }
 
 impl ManifestHeader {
@@ -52,18 +55,35 @@ impl ManifestHeader {
             padded_hash[..copy_len].copy_from_slice(&hash_bytes[..copy_len]);
             padded_hash
         };
-        debug!("Raw SHA-1 bytes from file: {:02x?}", hash);
 
         // Read stored_as flag
         let stored_as = rdr.u8()?;
 
-        // Read version if header size > 37 bytes
-        let version = if header_size > 37 {
+        // Read version if the header is larger than the original layout
+        let version = if header_size > MANIFEST_HEADER_SIZE_V1 as i32 {
             rdr.i32()?
         } else {
             0 // Default to 0 for older versions
         };
 
+        // Some serializations follow the version with a GUID, stored like
+        // chunk GUIDs (raw bytes).
+        let guid = if header_size >= MANIFEST_HEADER_SIZE_GUID as i32 {
+            let bytes = rdr.read_bytes_tolerant(16)?;
+            let bytes: [u8; 16] = bytes.try_into().map_err(|b: Vec<u8>| {
+                ManifestError::Invalid(format!("Expected 16 bytes for header GUID but got {} bytes", b.len()))
+            })?;
+            uuid::Uuid::from_bytes(bytes).to_string()
+        } else {
+            String::new()
+        };
+
+        let (rolling_hash, hash_type) = if header_size >= MANIFEST_HEADER_SIZE_HASH_TYPE as i32 {
+            (rdr.i64()?, rdr.u8()? as u32)
+        } else {
+            (0, 0)
+        };
+
         // Skip to the end of the header
         let current_pos = rdr.stream_position()?;
         if current_pos < header_size as u64 {
@@ -77,12 +97,24 @@ impl ManifestHeader {
             sha1_hash: hex::encode(hash),
             stored_as,
             version,
-            guid: String::new(), // Not used in newer versions
-            rolling_hash: 0,     // Not used in newer versions
-            hash_type: 0,        // Not used in newer versions
+            guid,
+            rolling_hash,
+            hash_type,
+            synthetic: Vec::new(),
         })
     }
 
+    /// Header GUID, for headers that carry one.
+    pub fn guid(&self) -> Option<&str> {
+        (!self.guid.is_empty()).then_some(self.guid.as_str())
+    }
+
+    /// Whether the payload hash is SHA-1, i.e. whether `sha1_hash` can be
+    /// checked by this parser. A hash type of 0 is the legacy SHA-1.
+    pub fn payload_hash_is_sha1(&self) -> bool {
+        self.hash_type == 0 || self.hash_type & HASH_SHA1 != 0
+    }
+
     /// helpers
     pub fn is_compressed(&self) -> bool {
         self.stored_as & STORED_COMPRESSED != 0
@@ -90,4 +122,15 @@ impl ManifestHeader {
     pub fn is_encrypted(&self) -> bool {
         self.stored_as & STORED_ENCRYPTED != 0
     }
+
+    /// Bytes the payload occupies after the header. Encrypted payloads are
+    /// padded to whole AES blocks, so their stored size is
+    /// `data_size_compressed` even when not compressed.
+    pub fn stored_size(&self) -> i32 {
+        if self.is_compressed() || self.is_encrypted() {
+            self.data_size_compressed
+        } else {
+            self.data_size_uncompressed
+        }
+    }
 }
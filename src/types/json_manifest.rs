@@ -1,23 +1,27 @@
 use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
 use crate::error::ManifestError;
+use crate::format::CHUNK_WINDOW_SIZE;
 use crate::types::manifest::Manifest;
 use crate::types::header::ManifestHeader;
 use crate::types::meta::ManifestMeta;
-use crate::types::chunk::{ChunkDataList, Chunk};
+use crate::types::chunk::{epic_guid, normalize_guid, ChunkDataList, Chunk};
 use crate::types::file::{FileManifestList, FileManifest};
 use crate::types::chunk::ChunkPart;
-use uuid::Uuid;
-use std::str::FromStr;
+use crate::types::custom_fields::CustomFields;
 use hex;
 
 /// JSON representation of a manifest file
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct JsonManifest {
-    #[serde(rename = "ManifestFileVersion")]
+    /// Also read from a number, then written back as a string.
+    #[serde(rename = "ManifestFileVersion", deserialize_with = "flexible::string")]
     pub manifest_file_version: String,
-    #[serde(rename = "bIsFileData")]
+    /// Also read from `0` or `1`.
+    #[serde(rename = "bIsFileData", deserialize_with = "flexible::bool")]
     pub is_file_data: bool,
-    #[serde(rename = "AppID")]
+    /// Also read from a number, then written back as a string.
+    #[serde(rename = "AppID", deserialize_with = "flexible::string")]
     pub app_id: String,
     #[serde(rename = "AppNameString")]
     pub app_name_string: String,
@@ -37,21 +41,221 @@ pub struct JsonManifest {
     pub prereq_args: String,
     #[serde(rename = "FileManifestList")]
     pub file_manifest_list: Vec<JsonFileManifest>,
+    #[serde(rename = "CustomFields", default)]
+    pub custom_fields: CustomFields,
+    /// Rolling hash of each chunk, keyed by Epic-format GUID and stored as
+    /// a UE blob string.
+    #[serde(rename = "ChunkHashList", default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub chunk_hash_list: BTreeMap<String, String>,
+    /// SHA-1 of each chunk's data in hex, keyed by Epic-format GUID.
+    #[serde(rename = "ChunkShaList", default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub chunk_sha_list: BTreeMap<String, String>,
+    /// Compressed size of each chunk file, keyed by Epic-format GUID and
+    /// stored as a UE blob string.
+    #[serde(rename = "ChunkFilesizeList", default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub chunk_filesize_list: BTreeMap<String, String>,
+    /// Fields this struct doesn't model, such as `DataGroupList`, kept as is
+    /// so they are written back on serialization.
+    #[serde(flatten)]
+    pub extra: serde_json::Map<String, serde_json::Value>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// How [`JsonManifest::to_manifest_with`] fills in what JSON manifests
+/// don't store.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct JsonConversion {
+    /// Window size given to every chunk, and the file size of chunks missing
+    /// from `ChunkFilesizeList`. Defaults to [`CHUNK_WINDOW_SIZE`].
+    pub window_size: u32,
+    /// What to do with entries whose chunk GUIDs are malformed.
+    pub invalid_guids: InvalidGuids,
+}
+
+impl Default for JsonConversion {
+    fn default() -> Self {
+        Self {
+            window_size: CHUNK_WINDOW_SIZE,
+            invalid_guids: InvalidGuids::Fail,
+        }
+    }
+}
+
+/// How [`JsonManifest::to_manifest_with`] treats malformed chunk GUIDs.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum InvalidGuids {
+    /// Fail the conversion.
+    #[default]
+    Fail,
+    /// Drop files with such a chunk part, and ignore such keys of the
+    /// per-chunk lists. Each is reported as a [`GuidIssue`].
+    Skip,
+    /// [`InvalidGuids::Skip`], keeping each dropped file entry in its
+    /// [`GuidIssue::quarantined`] for callers to repair.
+    Quarantine,
+}
+
+/// An entry dropped for a malformed GUID.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GuidIssue {
+    /// Where the GUID is, e.g. `FileManifestList[3].FileChunkParts[0].Guid`,
+    /// or the list name (`ChunkShaList`) for keys of the per-chunk lists.
+    pub path: String,
+    pub guid: String,
+    /// The dropped file entry, with [`InvalidGuids::Quarantine`].
+    pub quarantined: Option<JsonFileManifest>,
+}
+
+/// Deserializers for fields that some writers store with another JSON type.
+mod flexible {
+    use serde::de::{self, Deserializer, Visitor};
+    use std::fmt;
+
+    struct StringOrNumber;
+
+    impl Visitor<'_> for StringOrNumber {
+        type Value = String;
+
+        fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            f.write_str("a string or a number")
+        }
+
+        fn visit_str<E: de::Error>(self, v: &str) -> Result<String, E> {
+            Ok(v.to_string())
+        }
+
+        fn visit_string<E: de::Error>(self, v: String) -> Result<String, E> {
+            Ok(v)
+        }
+
+        fn visit_u64<E: de::Error>(self, v: u64) -> Result<String, E> {
+            Ok(v.to_string())
+        }
+
+        fn visit_i64<E: de::Error>(self, v: i64) -> Result<String, E> {
+            Ok(v.to_string())
+        }
+    }
+
+    struct BoolOrInt;
+
+    impl Visitor<'_> for BoolOrInt {
+        type Value = bool;
+
+        fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            f.write_str("a boolean, 0 or 1")
+        }
+
+        fn visit_bool<E: de::Error>(self, v: bool) -> Result<bool, E> {
+            Ok(v)
+        }
+
+        fn visit_u64<E: de::Error>(self, v: u64) -> Result<bool, E> {
+            match v {
+                0 => Ok(false),
+                1 => Ok(true),
+                _ => Err(E::invalid_value(de::Unexpected::Unsigned(v), &self)),
+            }
+        }
+
+        fn visit_i64<E: de::Error>(self, v: i64) -> Result<bool, E> {
+            match u64::try_from(v) {
+                Ok(v) => self.visit_u64(v),
+                Err(_) => Err(E::invalid_value(de::Unexpected::Signed(v), &self)),
+            }
+        }
+    }
+
+    pub fn string<'de, D: Deserializer<'de>>(deserializer: D) -> Result<String, D::Error> {
+        deserializer.deserialize_any(StringOrNumber)
+    }
+
+    pub fn bool<'de, D: Deserializer<'de>>(deserializer: D) -> Result<bool, D::Error> {
+        deserializer.deserialize_any(BoolOrInt)
+    }
+
+    pub fn option_bool<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<bool>, D::Error> {
+        struct OptionBoolOrInt;
+
+        impl<'de> Visitor<'de> for OptionBoolOrInt {
+            type Value = Option<bool>;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("null, a boolean, 0 or 1")
+            }
+
+            fn visit_none<E: de::Error>(self) -> Result<Option<bool>, E> {
+                Ok(None)
+            }
+
+            fn visit_unit<E: de::Error>(self) -> Result<Option<bool>, E> {
+                Ok(None)
+            }
+
+            fn visit_some<D: Deserializer<'de>>(self, deserializer: D) -> Result<Option<bool>, D::Error> {
+                bool(deserializer).map(Some)
+            }
+        }
+
+        deserializer.deserialize_option(OptionBoolOrInt)
+    }
+}
+
+/// Most problems [`JsonManifest::from_str`] collects before giving up.
+pub const MAX_JSON_ERRORS: usize = 64;
+
+/// A value of a JSON manifest that failed to deserialize.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JsonFieldError {
+    /// Where the value is, e.g. `FileManifestList[3].FileChunkParts[0].Size`.
+    pub path: String,
+    /// Index of the `FileManifestList` entry the value belongs to.
+    pub file_index: Option<usize>,
+    pub message: String,
+}
+
+impl JsonFieldError {
+    fn new(err: serde_path_to_error::Error<serde_json::Error>, file_index: Option<usize>) -> Self {
+        let path = err.path().to_string();
+        let path = match file_index {
+            Some(index) if path == "." => format!("FileManifestList[{}]", index),
+            Some(index) => format!("FileManifestList[{}].{}", index, path),
+            None => path,
+        };
+        Self {
+            path,
+            file_index,
+            message: err.into_inner().to_string(),
+        }
+    }
+}
+
+impl std::fmt::Display for JsonFieldError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {}", self.path, self.message)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct JsonFileManifest {
     #[serde(rename = "Filename")]
     pub filename: String,
     #[serde(rename = "FileHash")]
     pub file_hash: String,
-    #[serde(rename = "bIsUnixExecutable", default)]
+    #[serde(
+        rename = "bIsUnixExecutable",
+        default,
+        deserialize_with = "flexible::option_bool",
+        skip_serializing_if = "Option::is_none"
+    )]
     pub is_unix_executable: Option<bool>,
     #[serde(rename = "FileChunkParts")]
     pub file_chunk_parts: Vec<JsonFileChunkPart>,
+    /// Fields this struct doesn't model, written back on serialization.
+    #[serde(flatten)]
+    pub extra: serde_json::Map<String, serde_json::Value>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct JsonFileChunkPart {
     #[serde(rename = "Guid")]
     pub guid: String,
@@ -59,17 +263,102 @@ pub struct JsonFileChunkPart {
     pub offset: String,
     #[serde(rename = "Size")]
     pub size: String,
+    /// Fields this struct doesn't model, written back on serialization.
+    #[serde(flatten)]
+    pub extra: serde_json::Map<String, serde_json::Value>,
 }
 
 impl JsonManifest {
-    /// Parse JSON manifest from string
+    /// [`JsonManifest::from_str`] of UTF-8 bytes.
+    pub fn from_slice(data: &[u8]) -> Result<Self, ManifestError> {
+        let json_str = std::str::from_utf8(data)
+            .map_err(|e| ManifestError::Invalid(format!("Invalid UTF-8 in JSON manifest: {}", e)))?;
+        Self::from_str(json_str)
+    }
+
+    /// Parse JSON manifest from string. Values of the wrong type or missing
+    /// fields are reported as [`ManifestError::JsonFields`], with every broken
+    /// file entry (up to [`MAX_JSON_ERRORS`]) rather than just the first.
+    #[allow(clippy::should_implement_trait)]
     pub fn from_str(json_str: &str) -> Result<Self, ManifestError> {
-        serde_json::from_str(json_str)
-            .map_err(|e| ManifestError::Invalid(format!("JSON parsing error: {}", e)))
+        match serde_json::from_str(json_str) {
+            Ok(manifest) => Ok(manifest),
+            Err(e) if e.is_data() => Self::from_value(serde_json::from_str(json_str)?),
+            Err(e) => Err(ManifestError::Invalid(format!("JSON parsing error: {}", e))),
+        }
+    }
+
+    /// Deserialize the file entries one by one, so one broken entry doesn't
+    /// hide the next.
+    fn from_value(mut value: serde_json::Value) -> Result<Self, ManifestError> {
+        let files = match value.get_mut("FileManifestList") {
+            Some(files @ serde_json::Value::Array(_)) => std::mem::replace(files, serde_json::Value::Array(Vec::new())),
+            _ => serde_json::Value::Array(Vec::new()),
+        };
+        let mut errors = Vec::new();
+        let manifest = serde_path_to_error::deserialize::<_, Self>(value)
+            .map_err(|e| errors.push(JsonFieldError::new(e, None)))
+            .ok();
+        let mut file_manifest_list = Vec::new();
+        for (index, file) in files.as_array().into_iter().flatten().enumerate() {
+            if errors.len() >= MAX_JSON_ERRORS {
+                break;
+            }
+            match serde_path_to_error::deserialize(file) {
+                Ok(file) => file_manifest_list.push(file),
+                Err(e) => errors.push(JsonFieldError::new(e, Some(index))),
+            }
+        }
+        match manifest {
+            Some(manifest) if errors.is_empty() => Ok(Self {
+                file_manifest_list,
+                ..manifest
+            }),
+            _ => Err(ManifestError::JsonFields(errors)),
+        }
     }
 
     /// Convert JSON manifest to standard Manifest structure
     pub fn to_manifest(self) -> Result<Manifest, ManifestError> {
+        self.to_manifest_with(&JsonConversion::default())
+    }
+
+    /// [`JsonManifest::to_manifest`], filling in missing values per
+    /// `conversion`. Every structure lists its placeholder values in its
+    /// `synthetic` (see [`Manifest::synthetic_fields`]).
+    pub fn to_manifest_with(self, conversion: &JsonConversion) -> Result<Manifest, ManifestError> {
+        self.to_manifest_with_issues(conversion, &mut Vec::new())
+    }
+
+    /// [`JsonManifest::to_manifest_with`], adding the entries dropped under
+    /// [`InvalidGuids::Skip`] or [`InvalidGuids::Quarantine`] to `issues`.
+    pub fn to_manifest_with_issues(
+        self,
+        conversion: &JsonConversion,
+        issues: &mut Vec<GuidIssue>,
+    ) -> Result<Manifest, ManifestError> {
+        let policy = conversion.invalid_guids;
+        let mut json_files = Vec::with_capacity(self.file_manifest_list.len());
+        for (index, file) in self.file_manifest_list.iter().enumerate() {
+            let Some((part, invalid)) = file
+                .file_chunk_parts
+                .iter()
+                .enumerate()
+                .find(|(_, part)| normalize_guid(&part.guid).is_none())
+            else {
+                json_files.push(file);
+                continue;
+            };
+            if policy == InvalidGuids::Fail {
+                return Err(invalid_guid(&invalid.guid));
+            }
+            issues.push(GuidIssue {
+                path: format!("FileManifestList[{}].FileChunkParts[{}].Guid", index, part),
+                guid: invalid.guid.clone(),
+                quarantined: (policy == InvalidGuids::Quarantine).then(|| file.clone()),
+            });
+        }
+
         // Create a basic header (not used for JSON manifests)
         let header = ManifestHeader {
             header_size: 0,
@@ -77,10 +366,13 @@ impl JsonManifest {
             data_size_compressed: 0,
             sha1_hash: self.generate_manifest_sha1_hash()?,
             stored_as: 0,
-            version: self.parse_version()? as i32,
+            version: parse_number("ManifestFileVersion", &self.manifest_file_version)? as i32,
             guid: String::new(),
             rolling_hash: 0,
             hash_type: 0,
+            synthetic: ["header_size", "data_size_uncompressed", "data_size_compressed", "sha1_hash"]
+                .map(str::to_string)
+                .to_vec(),
         };
 
         // Create metadata
@@ -88,75 +380,103 @@ impl JsonManifest {
             data_size: 0, // Not applicable for JSON
             data_version: 0,
             feature_level: 0,
-            is_file_data: true,
-            app_id: self.parse_app_id()? as i32,
+            is_file_data: self.is_file_data,
+            app_id: parse_number("AppID", &self.app_id)? as i32,
             app_name: self.app_name_string.clone(),
             build_version: self.build_version_string.clone(),
             launch_exe: self.launch_exe_string.clone(),
-            launch_command: String::new(),
-            prereq_ids: Vec::new(),
-            prereq_name: String::new(),
-            prereq_path: String::new(),
-            prereq_args: String::new(),
+            launch_command: self.launch_command.clone(),
+            prereq_ids: self.prereq_ids.clone(),
+            prereq_name: self.prereq_name.clone(),
+            prereq_path: self.prereq_path.clone(),
+            prereq_args: self.prereq_args.clone(),
             build_id: None,
+            extra_data: None,
+            synthetic: ["data_size", "feature_level"].map(str::to_string).to_vec(),
         };
 
+        let mut lists = ChunkLists { policy, issues };
+        let hashes = lists.by_guid("ChunkHashList", &self.chunk_hash_list, |hash| {
+            Ok(format!("{:016x}", parse_blob_u64(hash)?))
+        })?;
+        let sha_hashes = lists.by_guid("ChunkShaList", &self.chunk_sha_list, |sha| {
+            let bytes = hex::decode(sha)?;
+            if bytes.len() != 20 {
+                return Err(ManifestError::Invalid(format!("Invalid chunk SHA-1 '{}'", sha)));
+            }
+            Ok(hex::encode(bytes))
+        })?;
+        let file_sizes = lists.by_guid("ChunkFilesizeList", &self.chunk_filesize_list, parse_blob_u64)?;
+        // JSON manifests store no window sizes.
+        let mut synthetic = vec!["window_size"];
+
         // Extract unique chunks from file chunk parts
-        // For JSON manifests, use a standard chunk size approach since the size values
-        // in the manifest represent file offsets/ranges, not actual chunk sizes
-        const STANDARD_CHUNK_SIZE: u64 = 1024 * 1024; // 1MB standard chunk size
-        let mut unique_chunks = std::collections::HashSet::<String>::new();
+        // Chunks are listed in order of first use so the output is stable.
+        let mut chunks = Vec::new();
+        let mut chunk_lookup = HashMap::new();
         
-        for file in &self.file_manifest_list {
+        for file in &json_files {
             for chunk_part in &file.file_chunk_parts {
-                let guid = Uuid::from_str(&chunk_part.guid)
-                    .map_err(|e| ManifestError::Invalid(format!("Invalid GUID: {}", e)))?;
-                unique_chunks.insert(guid.to_string());
+                let guid = parse_json_guid(&chunk_part.guid)?;
+                if chunk_lookup.contains_key(&guid) {
+                    continue;
+                }
+                // Made up from the GUID when the lists lack the chunk.
+                let mut mark = |field| {
+                    if !synthetic.contains(&field) {
+                        synthetic.push(field);
+                    }
+                };
+                let hash = hashes.get(&guid).cloned().unwrap_or_else(|| {
+                    mark("hash");
+                    Self::generate_hash_from_guid(&guid)
+                });
+                let sha_hash = sha_hashes.get(&guid).cloned().unwrap_or_else(|| {
+                    mark("sha_hash");
+                    Self::generate_sha_hash_from_guid(&guid)
+                });
+                let file_size = file_sizes.get(&guid).copied().unwrap_or_else(|| {
+                    mark("file_size");
+                    conversion.window_size as u64
+                });
+                // Derived from the GUID like Epic does, so CDN paths resolve.
+                let group = Chunk::compute_group(&guid).unwrap_or_default();
+
+                chunk_lookup.insert(guid.clone(), chunks.len() as u32);
+                chunks.push(Chunk {
+                    guid,
+                    hash,
+                    sha_hash,
+                    group,
+                    window_size: conversion.window_size,
+                    file_size: file_size.to_string(),
+                });
             }
         }
-        
-        // Create chunks with standard size
-        let mut chunks = std::collections::HashMap::new();
-        for guid in unique_chunks {
-            // Generate hash from GUID for JSON manifests since hash data is not available
-            let hash = Self::generate_hash_from_guid(&guid);
-            let sha_hash = Self::generate_sha_hash_from_guid(&guid);
-            
-            chunks.insert(guid.clone(), Chunk {
-                guid: guid.clone(),
-                hash,
-                sha_hash,
-                group: 0,
-                window_size: STANDARD_CHUNK_SIZE as u32, // Standard uncompressed size
-                file_size: STANDARD_CHUNK_SIZE.to_string(), // Standard compressed size
-            });
-        }
-
-        let chunk_lookup = chunks.iter().enumerate()
-            .map(|(i, (guid, _))| (guid.clone(), i as u32))
-            .collect();
 
         let chunk_list = ChunkDataList {
             data_size: 0, // Not applicable for JSON
             data_version: 0,
             count: chunks.len() as u32,
-            elements: chunks.into_values().collect(),
+            elements: chunks,
             chunk_lookup,
+            extra_data: None,
+            synthetic: synthetic.into_iter().map(str::to_string).collect(),
         };
 
         // Convert file manifest list
         let mut files = Vec::new();
-        for json_file in &self.file_manifest_list {
+        for json_file in &json_files {
             let mut chunk_parts = Vec::new();
             for json_chunk_part in &json_file.file_chunk_parts {
-                let guid = Uuid::from_str(&json_chunk_part.guid)
-                    .map_err(|e| ManifestError::Invalid(format!("Invalid GUID: {}", e)))?;
+                let guid = parse_json_guid(&json_chunk_part.guid)?;
                 
                 chunk_parts.push(ChunkPart {
                     data_size: 0, // Not applicable for JSON
-                    parent_guid: guid.to_string(),
-                    offset: self.parse_hex_string(&json_chunk_part.offset)? as u32,
-                    size: self.parse_hex_string(&json_chunk_part.size)? as u32,
+                    parent_guid: guid,
+                    offset: parse_number("Offset", &json_chunk_part.offset)?,
+                    size: parse_number("Size", &json_chunk_part.size)?,
+                    extra_data: None,
                     chunk: None, // Will be populated later if needed
                 });
             }
@@ -171,7 +491,9 @@ impl JsonManifest {
                 install_tags: Vec::new(),
                 chunk_parts,
                 file_size,
+                size_unknown: false,
                 mime_type: String::new(),
+                flags: None,
             });
         }
 
@@ -180,6 +502,9 @@ impl JsonManifest {
             data_version: 0,
             count: files.len() as u32,
             file_manifest_list: files,
+            path_order: Default::default(),
+            extra_data: None,
+            synthetic: vec!["data_size".to_string(), "chunk_parts.data_size".to_string()],
         };
 
         Ok(Manifest {
@@ -187,36 +512,10 @@ impl JsonManifest {
             meta: Some(meta),
             chunk_list: Some(chunk_list),
             file_list: Some(file_list),
+            custom_fields: self.custom_fields.clone(),
         })
     }
 
-    fn parse_version(&self) -> Result<u32, ManifestError> {
-        // Handle large version numbers by taking only the last 8 digits or converting to a reasonable value
-        if self.manifest_file_version.len() > 8 {
-            // Take the last 8 digits to fit in u32
-            let trimmed = &self.manifest_file_version[self.manifest_file_version.len() - 8..];
-            trimmed.parse::<u32>()
-                .map_err(|e| ManifestError::Invalid(format!("Invalid version format: {}", e)))
-        } else {
-            self.manifest_file_version.parse::<u32>()
-                .map_err(|e| ManifestError::Invalid(format!("Invalid version format: {}", e)))
-        }
-    }
-
-    fn parse_app_id(&self) -> Result<u32, ManifestError> {
-        // Parse app ID string like "000000000000" to u32
-        self.app_id.parse::<u32>()
-            .map_err(|e| ManifestError::Invalid(format!("Invalid app ID format: {}", e)))
-    }
-
-    fn parse_hex_string(&self, hex_str: &str) -> Result<i64, ManifestError> {
-        // Parse as decimal (despite the method name, these are actually decimal values in JSON manifests)
-        let value = hex_str.parse::<u64>()
-            .map_err(|e| ManifestError::Invalid(format!("Invalid number string '{}': {}", hex_str, e)))?;
-        
-        Ok(value as i64)
-    }
-
     fn parse_file_hash(&self, hash_str: &str) -> Result<[u8; 20], ManifestError> {
         // Parse file hash string to 20-byte array
         if hash_str.len() != 60 { // 20 bytes * 3 digits each
@@ -224,11 +523,11 @@ impl JsonManifest {
         }
 
         let mut hash = [0u8; 20];
-        for i in 0..20 {
+        for (i, byte) in hash.iter_mut().enumerate() {
             let start = i * 3;
             let end = start + 3;
             let byte_str = &hash_str[start..end];
-            hash[i] = byte_str.parse::<u8>()
+            *byte = byte_str.parse::<u8>()
                 .map_err(|e| ManifestError::Invalid(format!("Invalid hash byte '{}': {}", byte_str, e)))?;
         }
         Ok(hash)
@@ -276,7 +575,196 @@ impl JsonManifest {
     }
 }
 
-/// Detect if the input data is a JSON manifest
+impl JsonManifest {
+    /// Epic's JSON layout of `manifest`, the reverse of
+    /// [`JsonManifest::to_manifest`]. Numbers are written as UE blob strings
+    /// and GUIDs in Epic's 32-hex form, like Epic's own JSON manifests. The
+    /// per-chunk lists `manifest` made up values for (see
+    /// [`Manifest::synthetic_fields`]) are left out rather than exported.
+    pub fn from_manifest(manifest: &Manifest) -> Result<Self, ManifestError> {
+        let meta = manifest
+            .meta
+            .as_ref()
+            .ok_or_else(|| ManifestError::Invalid("cannot export a manifest without meta".to_string()))?;
+        let trim = |s: &str| s.trim_end_matches('\0').to_string();
+        // JSON manifests carry their feature level in the header only.
+        let feature_level = if meta.feature_level > 0 {
+            meta.feature_level
+        } else {
+            manifest.header.version
+        };
+
+        let mut chunk_hash_list = BTreeMap::new();
+        let mut chunk_sha_list = BTreeMap::new();
+        let mut chunk_filesize_list = BTreeMap::new();
+        let mut data_groups = serde_json::Map::new();
+        if let Some(chunk_list) = &manifest.chunk_list {
+            let made_up = |field: &str| chunk_list.synthetic.iter().any(|f| f == field);
+            for chunk in &chunk_list.elements {
+                let guid = epic_guid(&chunk.guid);
+                if !made_up("hash") {
+                    let hash = u64::from_str_radix(&chunk.hash, 16)
+                        .map_err(|e| ManifestError::Invalid(format!("invalid chunk hash {}: {}", chunk.hash, e)))?;
+                    chunk_hash_list.insert(guid.clone(), blob_string(hash, 8));
+                }
+                if !made_up("sha_hash") {
+                    chunk_sha_list.insert(guid.clone(), chunk.sha_hash.to_uppercase());
+                }
+                if !made_up("file_size") {
+                    let size = chunk.file_size.parse::<u64>().map_err(|e| {
+                        ManifestError::Invalid(format!("invalid chunk file size {}: {}", chunk.file_size, e))
+                    })?;
+                    chunk_filesize_list.insert(guid.clone(), blob_string(size, 8));
+                }
+                data_groups.insert(guid, blob_string(chunk.group as u64, 1).into());
+            }
+        }
+
+        let mut file_manifest_list = Vec::new();
+        for file in manifest.file_list.iter().flat_map(|f| &f.file_manifest_list) {
+            let mut sha = [0u8; 20];
+            hex::decode_to_slice(&file.sha_hash, &mut sha)
+                .map_err(|e| ManifestError::Invalid(format!("invalid SHA-1 {}: {}", file.sha_hash, e)))?;
+            let mut extra = serde_json::Map::new();
+            if file.is_readonly() {
+                extra.insert("bIsReadOnly".to_string(), true.into());
+            }
+            if file.is_compressed() {
+                extra.insert("bIsCompressed".to_string(), true.into());
+            }
+            let symlink_target = trim(&file.symlink_target);
+            if !symlink_target.is_empty() {
+                extra.insert("SymlinkTarget".to_string(), symlink_target.into());
+            }
+            let file_chunk_parts = file
+                .chunk_parts
+                .iter()
+                .map(|part| JsonFileChunkPart {
+                    guid: epic_guid(&part.parent_guid),
+                    offset: blob_string(part.offset as u64, 4),
+                    size: blob_string(part.size as u64, 4),
+                    extra: serde_json::Map::new(),
+                })
+                .collect();
+            if !file.install_tags.is_empty() {
+                let tags = file.install_tags.iter().map(|t| trim(t).into()).collect();
+                extra.insert("InstallTags".to_string(), serde_json::Value::Array(tags));
+            }
+            file_manifest_list.push(JsonFileManifest {
+                filename: trim(&file.filename),
+                file_hash: sha.iter().map(|b| format!("{:03}", b)).collect(),
+                is_unix_executable: file.is_unix_executable().then_some(true),
+                file_chunk_parts,
+                extra,
+            });
+        }
+
+        let mut extra = serde_json::Map::new();
+        if !data_groups.is_empty() {
+            extra.insert("DataGroupList".to_string(), data_groups.into());
+        }
+        Ok(Self {
+            manifest_file_version: blob_string(feature_level as u32 as u64, 4),
+            is_file_data: meta.is_file_data,
+            app_id: blob_string(meta.app_id as u32 as u64, 4),
+            app_name_string: trim(&meta.app_name),
+            build_version_string: trim(&meta.build_version),
+            launch_exe_string: trim(&meta.launch_exe),
+            launch_command: trim(&meta.launch_command),
+            prereq_ids: meta.prereq_ids.iter().map(|id| trim(id)).collect(),
+            prereq_name: trim(&meta.prereq_name),
+            prereq_path: trim(&meta.prereq_path),
+            prereq_args: trim(&meta.prereq_args),
+            file_manifest_list,
+            custom_fields: manifest.custom_fields.clone(),
+            chunk_hash_list,
+            chunk_sha_list,
+            chunk_filesize_list,
+            extra,
+        })
+    }
+}
+
+/// Reads the per-chunk JSON lists under an [`InvalidGuids`] policy.
+struct ChunkLists<'a> {
+    policy: InvalidGuids,
+    issues: &'a mut Vec<GuidIssue>,
+}
+
+impl ChunkLists<'_> {
+    /// The values of the list called `name`, keyed by parsed GUID.
+    fn by_guid<T>(
+        &mut self,
+        name: &str,
+        list: &BTreeMap<String, String>,
+        parse: impl Fn(&str) -> Result<T, ManifestError>,
+    ) -> Result<HashMap<String, T>, ManifestError> {
+        let mut values = HashMap::with_capacity(list.len());
+        for (guid, value) in list {
+            match normalize_guid(guid) {
+                Some(parsed) => {
+                    values.insert(parsed, parse(value)?);
+                }
+                None if self.policy == InvalidGuids::Fail => return Err(invalid_guid(guid)),
+                None => self.issues.push(GuidIssue {
+                    path: name.to_string(),
+                    guid: guid.clone(),
+                    quarantined: None,
+                }),
+            }
+        }
+        Ok(values)
+    }
+}
+
+/// Decode a UE blob string (three decimal digits per byte, little-endian)
+/// of up to eight bytes.
+fn parse_blob_u64(blob: &str) -> Result<u64, ManifestError> {
+    if !blob.len().is_multiple_of(3) || blob.len() > 24 || !blob.is_ascii() {
+        return Err(ManifestError::Invalid(format!("Invalid blob string '{}'", blob)));
+    }
+    let mut value = 0u64;
+    for (i, digits) in blob.as_bytes().chunks(3).enumerate() {
+        let byte = std::str::from_utf8(digits)
+            .ok()
+            .and_then(|d| d.parse::<u8>().ok())
+            .ok_or_else(|| ManifestError::Invalid(format!("Invalid blob string '{}'", blob)))?;
+        value |= (byte as u64) << (8 * i);
+    }
+    Ok(value)
+}
+
+/// Read a 32-bit field written as a UE blob string (`"000000016000"` is
+/// 1 MiB), or as a plain decimal number by writers that don't use blobs.
+fn parse_number(field: &str, value: &str) -> Result<u32, ManifestError> {
+    let is_blob = value.len() == 12
+        && value
+            .as_bytes()
+            .chunks(3)
+            .all(|d| d.iter().all(u8::is_ascii_digit) && d <= b"255".as_slice());
+    let parsed = if is_blob {
+        parse_blob_u64(value).ok().and_then(|v| u32::try_from(v).ok())
+    } else {
+        value.parse::<u32>().ok()
+    };
+    parsed.ok_or_else(|| ManifestError::Invalid(format!("Invalid {} '{}'", field, value)))
+}
+
+/// Encode `value` as a UE blob string of its `bytes` low bytes.
+pub(crate) fn blob_string(value: u64, bytes: usize) -> String {
+    value.to_le_bytes()[..bytes].iter().map(|b| format!("{:03}", b)).collect()
+}
+
+/// JSON manifests normally carry Epic's 32-hex GUIDs, but hyphenated ones
+/// are accepted too; both map to the same value as the binary path.
+fn parse_json_guid(guid: &str) -> Result<String, ManifestError> {
+    normalize_guid(guid).ok_or_else(|| invalid_guid(guid))
+}
+
+fn invalid_guid(guid: &str) -> ManifestError {
+    ManifestError::Invalid(format!("Invalid GUID: {}", guid))
+}
+
 pub fn is_json_manifest(data: &[u8]) -> bool {
     // Check if the data starts with '{' and contains expected JSON manifest fields
     if data.is_empty() || data[0] != b'{' {
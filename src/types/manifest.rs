@@ -1,12 +1,19 @@
+use crate::error::ManifestError;
+use crate::export::json::Int64Format;
 use crate::types::{
-    chunk::ChunkDataList, file::FileManifestList, header::ManifestHeader, meta::ManifestMeta,
+    chunk::{format_guid, Chunk, ChunkDataList, GuidFormat}, custom_fields::CustomFields, file::{FileManifest, FileManifestList}, header::ManifestHeader,
+    json_manifest::JsonManifest, meta::ManifestMeta,
 };
+use crate::types::meta::trim_nul;
 use serde::{Deserialize, Serialize};
-use napi_derive::napi;
+use std::collections::HashSet;
+
+/// Allocation unit assumed by [`Manifest::size_on_disk`] when given 0; the
+/// NTFS, ext4 and APFS default.
+pub const DEFAULT_CLUSTER_SIZE: u64 = 4096;
 
 /// Whole manifest, JSON-serialisable.
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
-#[napi(object)]
 pub struct Manifest {
     pub header: ManifestHeader,
     pub meta: Option<ManifestMeta>,
@@ -15,4 +22,221 @@ pub struct Manifest {
     pub chunk_list: Option<ChunkDataList>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub file_list: Option<FileManifestList>,
+    #[serde(default, skip_serializing_if = "CustomFields::is_empty")]
+    pub custom_fields: CustomFields,
+}
+
+impl Manifest {
+    /// Strip trailing NUL terminators from all parsed strings.
+    pub fn trim_nul_padding(&mut self) {
+        if let Some(meta) = &mut self.meta {
+            meta.trim_nul_padding();
+        }
+        if let Some(file_list) = &mut self.file_list {
+            file_list
+                .file_manifest_list
+                .iter_mut()
+                .for_each(|f| f.trim_nul_padding());
+            file_list.path_order.invalidate();
+        }
+        for entry in &mut self.custom_fields.entries {
+            trim_nul(&mut entry.key);
+            trim_nul(&mut entry.value);
+        }
+    }
+
+    /// Rewrite every chunk GUID (chunk list, lookup table and chunk parts)
+    /// in `format`.
+    pub fn set_guid_format(&mut self, format: GuidFormat) {
+        if let Some(chunk_list) = &mut self.chunk_list {
+            for chunk in &mut chunk_list.elements {
+                chunk.guid = format_guid(&chunk.guid, format);
+            }
+            chunk_list.chunk_lookup = std::mem::take(&mut chunk_list.chunk_lookup)
+                .into_iter()
+                .map(|(guid, i)| (format_guid(&guid, format), i))
+                .collect();
+        }
+        if let Some(file_list) = &mut self.file_list {
+            for part in file_list
+                .file_manifest_list
+                .iter_mut()
+                .flat_map(|f| f.chunk_parts.iter_mut())
+            {
+                part.parent_guid = format_guid(&part.parent_guid, format);
+                if let Some(chunk) = &mut part.chunk {
+                    chunk.guid = format_guid(&chunk.guid, format);
+                }
+            }
+        }
+    }
+
+    /// Set every file's `flags` from its `file_meta_flags`, so serialized
+    /// output carries the decoded booleans next to the raw byte.
+    pub fn fill_file_flags(&mut self) {
+        for file in self.file_list.iter_mut().flat_map(|f| &mut f.file_manifest_list) {
+            file.flags = Some(file.meta_flags());
+        }
+    }
+
+    /// Every chunk the file list references, once, in order of first
+    /// reference: files in file list order, each file's parts in order.
+    /// Chunks no file uses are left out.
+    pub fn unique_chunks(&self) -> impl Iterator<Item = &Chunk> {
+        let mut seen = HashSet::new();
+        let chunk_list = self.chunk_list.as_ref();
+        self.file_list
+            .iter()
+            .flat_map(|f| &f.file_manifest_list)
+            .flat_map(|f| &f.chunk_parts)
+            .filter_map(move |p| {
+                let list = chunk_list?;
+                let &index = list.chunk_lookup.get(&p.parent_guid)?;
+                seen.insert(index).then(|| list.elements.get(index as usize)).flatten()
+            })
+    }
+
+    /// Every field holding a placeholder rather than stored data, as dotted
+    /// paths such as `header.sha1_hash` or `chunk_list.window_size`. Empty for
+    /// binary manifests; converted JSON manifests list what they made up.
+    pub fn synthetic_fields(&self) -> Vec<String> {
+        let sections = [
+            ("header", Some(&self.header.synthetic)),
+            ("meta", self.meta.as_ref().map(|m| &m.synthetic)),
+            ("chunk_list", self.chunk_list.as_ref().map(|c| &c.synthetic)),
+            ("file_list", self.file_list.as_ref().map(|f| &f.synthetic)),
+        ];
+        sections
+            .into_iter()
+            .flat_map(|(section, fields)| fields.into_iter().flatten().map(move |field| format!("{}.{}", section, field)))
+            .collect()
+    }
+
+    /// Space the installed files occupy on a filesystem with `cluster_size`
+    /// byte allocation units (0 means [`DEFAULT_CLUSTER_SIZE`]).
+    ///
+    /// Every file is rounded up to whole clusters, and even empty files and
+    /// symlinks are charged one cluster for their metadata, so builds with
+    /// many tiny files report what launchers actually need free rather than
+    /// the raw byte sum.
+    pub fn size_on_disk(&self, cluster_size: u64) -> u64 {
+        let cluster_size = if cluster_size == 0 { DEFAULT_CLUSTER_SIZE } else { cluster_size };
+        self.file_list
+            .as_ref()
+            .map(|f| f.file_manifest_list.as_slice())
+            .unwrap_or_default()
+            .iter()
+            .map(|file| (file.file_size.max(0) as u64).div_ceil(cluster_size).max(1) * cluster_size)
+            .sum()
+    }
+
+    /// Sum of the installed files' sizes, in bytes. A lower bound when
+    /// [`Manifest::files_with_unknown_size`] finds any.
+    pub fn install_size(&self) -> u64 {
+        self.file_list
+            .iter()
+            .flat_map(|f| &f.file_manifest_list)
+            .map(|file| file.file_size.max(0) as u64)
+            .sum()
+    }
+
+    /// Files whose chunk parts were damaged, so that their `file_size` is
+    /// only a lower bound (see [`FileManifest::size_unknown`]).
+    pub fn files_with_unknown_size(&self) -> impl Iterator<Item = &FileManifest> {
+        self.file_list
+            .iter()
+            .flat_map(|f| &f.file_manifest_list)
+            .filter(|f| f.size_unknown)
+    }
+
+    /// Bytes to fetch for a full install: the compressed size of every chunk
+    /// in the chunk list.
+    pub fn download_size(&self) -> u64 {
+        self.chunk_list
+            .iter()
+            .flat_map(|c| &c.elements)
+            .map(|chunk| chunk.file_size.parse::<u64>().unwrap_or_default())
+            .sum()
+    }
+
+    /// Serialize as a compressed binary manifest; see
+    /// [`crate::writer::ManifestWriter`] for other settings.
+    pub fn to_bytes(&self) -> Result<Vec<u8>, ManifestError> {
+        crate::writer::ManifestWriter::default().to_bytes(self)
+    }
+
+    /// Convert to Epic's JSON manifest layout; see
+    /// [`JsonManifest::from_manifest`].
+    pub fn to_json_manifest(&self) -> Result<JsonManifest, ManifestError> {
+        JsonManifest::from_manifest(self)
+    }
+
+    /// JSON with 64-bit values written as `format` says; see
+    /// [`crate::export::json::to_json`].
+    pub fn to_json_with(&self, format: Int64Format) -> Result<String, ManifestError> {
+        crate::export::json::to_json(self, format)
+    }
+
+    /// [`Manifest::to_bytes`] into `out`.
+    pub fn write_binary(&self, out: impl std::io::Write) -> Result<(), ManifestError> {
+        crate::writer::ManifestWriter::default().write(self, out)
+    }
+
+    /// JSON with object keys sorted by code point, no insignificant whitespace
+    /// and integers printed without exponent or fraction, so equal manifests
+    /// always serialize to identical bytes (suitable for content hashing).
+    pub fn canonical_json(&self) -> Result<String, ManifestError> {
+        let value = serde_json::to_value(self)?;
+        let mut out = String::new();
+        write_canonical(&value, &mut out)?;
+        Ok(out)
+    }
+}
+
+fn write_canonical(value: &serde_json::Value, out: &mut String) -> Result<(), ManifestError> {
+    use serde_json::Value;
+
+    match value {
+        Value::Null | Value::Bool(_) => out.push_str(&value.to_string()),
+        Value::Number(n) => {
+            if n.is_i64() || n.is_u64() {
+                out.push_str(&n.to_string());
+            } else {
+                // Integral floats print as integers; others use the shortest
+                // round-tripping representation.
+                let f = n.as_f64().unwrap_or_default();
+                if f.fract() == 0.0 && f.abs() < 1e15 {
+                    out.push_str(&format!("{}", f as i64));
+                } else {
+                    out.push_str(&n.to_string());
+                }
+            }
+        }
+        Value::String(s) => out.push_str(&serde_json::to_string(s)?),
+        Value::Array(items) => {
+            out.push('[');
+            for (i, item) in items.iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                write_canonical(item, out)?;
+            }
+            out.push(']');
+        }
+        Value::Object(map) => {
+            let mut keys: Vec<&String> = map.keys().collect();
+            keys.sort();
+            out.push('{');
+            for (i, key) in keys.into_iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                out.push_str(&serde_json::to_string(key)?);
+                out.push(':');
+                write_canonical(&map[key], out)?;
+            }
+            out.push('}');
+        }
+    }
+    Ok(())
 }
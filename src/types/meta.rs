@@ -1,75 +1,14 @@
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
 use log::debug;
+use sha1::{Digest, Sha1};
 use serde::{Deserialize, Serialize};
-use std::io::{Read, Seek, SeekFrom};
-use napi_derive::napi;
+use std::io::{Read, Seek};
 
 use crate::error::ManifestError;
-use crate::parser::reader::ReadExt;
-
-/// A wrapper that limits reading to a specific range of data
-struct LimitedReader<'a> {
-    data: &'a [u8],
-    position: usize,
-    limit: usize,
-}
-
-impl<'a> LimitedReader<'a> {
-    fn new(data: &'a [u8], limit: usize) -> Self {
-        Self {
-            data,
-            position: 0,
-            limit: std::cmp::min(limit, data.len()),
-        }
-    }
-}
-
-impl<'a> Read for LimitedReader<'a> {
-    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
-        let available = self.limit.saturating_sub(self.position);
-        if available == 0 {
-            return Ok(0);
-        }
-        
-        let to_read = std::cmp::min(buf.len(), available);
-        let end_pos = self.position + to_read;
-        
-        if end_pos <= self.data.len() {
-            buf[..to_read].copy_from_slice(&self.data[self.position..end_pos]);
-            self.position = end_pos;
-            Ok(to_read)
-        } else {
-            Ok(0)
-        }
-    }
-}
-
-impl<'a> Seek for LimitedReader<'a> {
-    fn seek(&mut self, pos: SeekFrom) -> std::io::Result<u64> {
-        let new_pos = match pos {
-            SeekFrom::Start(offset) => offset as usize,
-            SeekFrom::End(offset) => {
-                if offset >= 0 {
-                    self.limit + offset as usize
-                } else {
-                    self.limit.saturating_sub((-offset) as usize)
-                }
-            }
-            SeekFrom::Current(offset) => {
-                if offset >= 0 {
-                    self.position + offset as usize
-                } else {
-                    self.position.saturating_sub((-offset) as usize)
-                }
-            }
-        };
-        
-        self.position = std::cmp::min(new_pos, self.limit);
-        Ok(self.position as u64)
-    }
-}
+use crate::options::{ParseOptions, StringPolicy};
+use crate::schema::{self, Value};
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
-#[napi(object)]
 pub struct ManifestMeta {
     pub data_size: u32,
     pub data_version: u8,
@@ -92,9 +31,17 @@ pub struct ManifestMeta {
     #[serde(serialize_with = "trim_null_chars")]
     pub prereq_args: String,
     pub build_id: Option<String>,
+    /// Hex of any bytes past the fields known to this parser, present when a
+    /// newer manifest version grew the meta section (per `data_size`).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub extra_data: Option<String>,
+    /// Fields holding placeholders rather than stored data, such as the
+    /// section size of JSON manifests.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub synthetic: Vec<String>,
 }
 
-fn trim_null_chars<S>(value: &String, serializer: S) -> Result<S::Ok, S::Error>
+fn trim_null_chars<S>(value: &str, serializer: S) -> Result<S::Ok, S::Error>
 where
     S: serde::Serializer,
 {
@@ -102,109 +49,122 @@ where
     serializer.serialize_str(trimmed)
 }
 
-impl ManifestMeta {
-    pub fn read_meta<R: Read + Seek>(rdr: &mut R) -> Result<(Self, u64), ManifestError> {
-        let start_pos = rdr.stream_position()?;
-
-        debug!("Reading metadata:");
-        let data_size = rdr.u32()?;
-        debug!("  Data size: {} (0x{:x})", data_size, data_size);
+/// Drop trailing NUL terminators in place.
+pub(crate) fn trim_nul(value: &mut String) {
+    let len = value.trim_end_matches('\0').len();
+    value.truncate(len);
+}
 
-        // Validate data size
-        if data_size == 0 || data_size > 1024 * 1024 * 1024 {
-            // 1GB max
-            return Err(ManifestError::Invalid(format!(
-                "Invalid data size: {} (0x{:x}). Must be between 1 and 1GB",
-                data_size, data_size
-            )));
+impl ManifestMeta {
+    /// The build id Epic derives for manifests that predate the stored
+    /// `BuildId` field: URL-safe unpadded base64 of the SHA-1 over the
+    /// little-endian app id followed by the app name, build version, launch
+    /// exe and launch command as UCS-2 (no terminators).
+    pub fn computed_build_id(&self) -> String {
+        let mut sha = Sha1::new();
+        sha.update((self.app_id as u32).to_le_bytes());
+        for value in [&self.app_name, &self.build_version, &self.launch_exe, &self.launch_command] {
+            for unit in value.trim_end_matches('\0').encode_utf16() {
+                sha.update(unit.to_le_bytes());
+            }
         }
+        URL_SAFE_NO_PAD.encode(sha.finalize())
+    }
 
-        // Read remaining data into buffer and use LimitedReader
-        let adjusted_data_size = data_size.saturating_sub(4); // Subtract the 4 bytes we already read for data_size
-        // Use tolerant reading to handle cases where less data is available than expected
-        let remaining_data = rdr.read_bytes_tolerant(adjusted_data_size as usize)?;
-        let actual_size = remaining_data.len();
-        
-        if actual_size < adjusted_data_size as usize {
-            debug!(
-                "Warning: Expected {} bytes but only {} bytes available for metadata. Using available data.",
-                adjusted_data_size, actual_size
-            );
+    /// Stored build id, or the computed one when the manifest has none.
+    pub fn build_id(&self) -> String {
+        match self.build_id.as_deref().map(|id| id.trim_end_matches('\0')) {
+            Some(id) if !id.is_empty() => id.to_string(),
+            _ => self.computed_build_id(),
         }
-        
-        let mut limited_reader = LimitedReader::new(&remaining_data, actual_size);
-        let rdr = &mut limited_reader;
-        
-        debug!(
-            "ManifestMeta: using limited reader with {} bytes",
-            adjusted_data_size
-        );
-
-        let data_version = rdr.u8()?;
-        debug!("  Data version: {} (0x{:x})", data_version, data_version);
-
-        let feature_level = rdr.i32()?;
-        debug!("  Feature level: {} (0x{:x})", feature_level, feature_level);
-
-        let is_file_data = rdr.u8()? != 0;
-        debug!("  Is file data: {}", is_file_data);
-
-        let app_id = rdr.i32()?;
-        debug!("  App ID: {} (0x{:x})", app_id, app_id);
-
-        let app_name = rdr.fstring()?;
-        debug!("  App name: {}", app_name);
-
-        let build_version = rdr.fstring()?;
-        debug!("  Build version: {}", build_version);
-
-        let launch_exe = rdr.fstring()?;
-        debug!("  Launch exe: {}", launch_exe);
-
-        let launch_command = rdr.fstring()?;
-        debug!("  Launch command: {}", launch_command);
+    }
 
-        let prereq_ids = rdr.fstring_array()?;
-        debug!("  Prerequisite IDs: {:?}", prereq_ids);
+    /// Platform the build runs on, as far as the launch executable tells:
+    /// `"Windows"` for an `.exe`, `"Mac"` for a path into an `.app` bundle.
+    /// Manifests don't store a platform.
+    pub fn platform(&self) -> Option<&'static str> {
+        let exe = self.launch_exe.trim_end_matches('\0').to_ascii_lowercase();
+        if exe.ends_with(".exe") {
+            Some("Windows")
+        } else if exe.ends_with(".app") || exe.contains(".app/") {
+            Some("Mac")
+        } else {
+            None
+        }
+    }
 
-        let prereq_name = rdr.fstring()?;
-        debug!("  Prerequisite name: {}", prereq_name);
+    /// Fill in `build_id` from [`ManifestMeta::computed_build_id`] if missing.
+    pub fn ensure_build_id(&mut self) {
+        if self.build_id.as_deref().is_none_or(|id| id.trim_end_matches('\0').is_empty()) {
+            self.build_id = Some(self.computed_build_id());
+        }
+    }
 
-        let prereq_path = rdr.fstring()?;
-        debug!("  Prerequisite path: {}", prereq_path);
+    /// Strip trailing NULs from every string field.
+    pub fn trim_nul_padding(&mut self) {
+        for value in [
+            &mut self.app_name,
+            &mut self.build_version,
+            &mut self.launch_exe,
+            &mut self.launch_command,
+            &mut self.prereq_name,
+            &mut self.prereq_path,
+            &mut self.prereq_args,
+        ] {
+            trim_nul(value);
+        }
+        self.prereq_ids.iter_mut().for_each(trim_nul);
+        if let Some(build_id) = &mut self.build_id {
+            trim_nul(build_id);
+        }
+    }
 
-        let prereq_args = rdr.fstring()?;
-        debug!("  Prerequisite args: {}", prereq_args);
+    pub fn read_meta<R: Read + Seek>(rdr: &mut R) -> Result<(Self, u64), ManifestError> {
+        Self::read_meta_with(rdr, &StringPolicy::RAW)
+    }
 
-        let build_id = if data_version >= 1 {
-            let build_id = rdr.fstring()?;
-            debug!("  Build ID: {}", build_id);
-            Some(build_id)
-        } else {
-            None
+    /// [`ManifestMeta::read_meta`] with strings decoded according to `policy`.
+    pub fn read_meta_with<R: Read + Seek>(
+        rdr: &mut R,
+        policy: &StringPolicy,
+    ) -> Result<(Self, u64), ManifestError> {
+        let options = ParseOptions {
+            strings: *policy,
+            ..ParseOptions::default()
         };
+        Self::read_meta_with_options(rdr, &options)
+    }
 
-        let end_pos = rdr.stream_position()?;
-        let bytes_read = end_pos - start_pos;
-
-        Ok((
-            Self {
-                data_size,
-                data_version,
-                feature_level,
-                is_file_data,
-                app_id,
-                app_name,
-                build_version,
-                launch_exe,
-                launch_command,
-                prereq_ids,
-                prereq_name,
-                prereq_path,
-                prereq_args,
-                build_id,
-            },
-            bytes_read,
-        ))
+    /// [`ManifestMeta::read_meta`] under the string policy, deadline and
+    /// strictness of `options`.
+    pub fn read_meta_with_options<R: Read + Seek>(
+        rdr: &mut R,
+        options: &ParseOptions,
+    ) -> Result<(Self, u64), ManifestError> {
+        let mut section = schema::SectionReader::open_with(rdr, &schema::META, options)?;
+        let mut values = section.read_all(&options.strings)?;
+        let mut take = |name| values.take_one(name);
+        let meta = Self {
+            data_size: section.data_size,
+            data_version: section.data_version,
+            feature_level: take("feature_level").map_or(0, |v| v.as_i64() as i32),
+            is_file_data: take("is_file_data").is_some_and(|v| v.as_bool()),
+            app_id: take("app_id").map_or(0, |v| v.as_i64() as i32),
+            app_name: take("app_name").map(Value::into_string).unwrap_or_default(),
+            build_version: take("build_version").map(Value::into_string).unwrap_or_default(),
+            launch_exe: take("launch_exe").map(Value::into_string).unwrap_or_default(),
+            launch_command: take("launch_command").map(Value::into_string).unwrap_or_default(),
+            prereq_ids: take("prereq_ids").map(Value::into_strings).unwrap_or_default(),
+            prereq_name: take("prereq_name").map(Value::into_string).unwrap_or_default(),
+            prereq_path: take("prereq_path").map(Value::into_string).unwrap_or_default(),
+            prereq_args: take("prereq_args").map(Value::into_string).unwrap_or_default(),
+            build_id: take("build_id").map(Value::into_string),
+            extra_data: section.extra_data(),
+            synthetic: Vec::new(),
+        };
+        debug!("  App: {} {} (feature level {})", meta.app_name, meta.build_version, meta.feature_level);
+        // The data size field plus the body read so far.
+        let bytes_read = 4 + section.rdr.position();
+        Ok((meta, bytes_read))
     }
 }
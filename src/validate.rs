@@ -0,0 +1,142 @@
+//! Consistency checks on parsed manifests.
+//!
+//! These look for values that parse fine on their own but rarely occur in
+//! real manifests, which usually means the reader lost its place in the
+//! payload and went on decoding the wrong bytes.
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+
+use crate::format::CHUNK_WINDOW_SIZE;
+use crate::types::chunk::Chunk;
+use crate::types::manifest::Manifest;
+
+/// Bytes each chunk takes in the chunk list columns before the window
+/// sizes: GUID, rolling hash, SHA-1 and group.
+const CHUNK_COLUMNS_BEFORE_WINDOW: u64 = 16 + 8 + 20 + 1;
+
+/// Chunk list data size, version and count.
+const CHUNK_LIST_HEADER_SIZE: u64 = 4 + 1 + 4;
+
+/// A chunk or chunk part that does not fit the standard window.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", tag = "kind")]
+pub enum WindowAnomaly {
+    /// A chunk whose window is not [`CHUNK_WINDOW_SIZE`].
+    NonStandardWindow { chunk_index: u32, guid: String, window_size: u32 },
+    /// A chunk part reaching past the end of its chunk's window.
+    PartExceedsWindow {
+        file_index: u32,
+        part_index: u32,
+        guid: String,
+        offset: u32,
+        size: u32,
+        window_size: u32,
+    },
+}
+
+/// Result of [`Manifest::check_chunk_windows`].
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WindowReport {
+    pub anomalies: Vec<WindowAnomaly>,
+    /// Offset into the decompressed payload of the window size field of the
+    /// first chunk involved in an anomaly. `None` when there are no
+    /// anomalies, or for manifests without binary meta to locate the chunk
+    /// list by.
+    pub first_offset: Option<u64>,
+}
+
+/// A chunk whose stored group differs from [`Chunk::compute_group`]. Its CDN
+/// URL is then wrong.
+///
+/// [`Chunk::compute_group`]: crate::types::chunk::Chunk::compute_group
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GroupMismatch {
+    pub chunk_index: u32,
+    pub guid: String,
+    pub stored: u8,
+    pub expected: u8,
+}
+
+impl WindowReport {
+    pub fn is_clean(&self) -> bool {
+        self.anomalies.is_empty()
+    }
+}
+
+impl Manifest {
+    /// Flag chunks with a window other than 1 MiB and chunk parts that
+    /// reach past their chunk's window.
+    pub fn check_chunk_windows(&self) -> WindowReport {
+        let Some(chunk_list) = &self.chunk_list else {
+            return WindowReport::default();
+        };
+        let mut anomalies = Vec::new();
+        let mut involved = BTreeSet::new();
+        for (index, chunk) in chunk_list.elements.iter().enumerate() {
+            if chunk.window_size != CHUNK_WINDOW_SIZE {
+                involved.insert(index as u32);
+                anomalies.push(WindowAnomaly::NonStandardWindow {
+                    chunk_index: index as u32,
+                    guid: chunk.guid.clone(),
+                    window_size: chunk.window_size,
+                });
+            }
+        }
+
+        let files = self.file_list.iter().flat_map(|f| &f.file_manifest_list);
+        for (file_index, file) in files.enumerate() {
+            for (part_index, part) in file.chunk_parts.iter().enumerate() {
+                let Some(&chunk_index) = chunk_list.chunk_lookup.get(&part.parent_guid) else {
+                    continue;
+                };
+                let window_size = chunk_list.elements[chunk_index as usize].window_size;
+                if part.offset as u64 + part.size as u64 > window_size as u64 {
+                    involved.insert(chunk_index);
+                    anomalies.push(WindowAnomaly::PartExceedsWindow {
+                        file_index: file_index as u32,
+                        part_index: part_index as u32,
+                        guid: part.parent_guid.clone(),
+                        offset: part.offset,
+                        size: part.size,
+                        window_size,
+                    });
+                }
+            }
+        }
+
+        // The meta section starts the payload and the chunk list follows it.
+        let chunk_list_start = self.meta.as_ref().map(|m| m.data_size as u64).filter(|&size| size > 0);
+        let first_offset = chunk_list_start.zip(involved.first()).map(|(start, &index)| {
+            start
+                + CHUNK_LIST_HEADER_SIZE
+                + chunk_list.elements.len() as u64 * CHUNK_COLUMNS_BEFORE_WINDOW
+                + index as u64 * 4
+        });
+        WindowReport {
+            anomalies,
+            first_offset,
+        }
+    }
+
+    /// Chunks whose stored group is not the one Epic derives from the GUID.
+    /// Hand-built manifests sometimes get this wrong, and their chunk URLs
+    /// then 404.
+    pub fn check_chunk_groups(&self) -> Vec<GroupMismatch> {
+        let chunks = self.chunk_list.iter().flat_map(|c| &c.elements);
+        chunks
+            .enumerate()
+            .filter_map(|(index, chunk)| {
+                let expected = Chunk::compute_group(&chunk.guid)?;
+                (chunk.group != expected).then(|| GroupMismatch {
+                    chunk_index: index as u32,
+                    guid: chunk.guid.clone(),
+                    stored: chunk.group,
+                    expected,
+                })
+            })
+            .collect()
+    }
+}
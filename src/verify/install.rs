@@ -0,0 +1,207 @@
+use log::debug;
+#[cfg(feature = "napi")]
+use napi_derive::napi;
+use serde::{Deserialize, Serialize};
+use std::fs::File;
+use std::io;
+use std::ops::ControlFlow;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use crate::error::ManifestError;
+use crate::io_backend::{backend, BatchedReader};
+use crate::parallelism::Parallelism;
+use crate::types::manifest::Manifest;
+use crate::verify::reader::{VerifyFailure, VerifyingReader};
+
+/// Why an installed file failed verification.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CorruptReason {
+    Missing,
+    SizeMismatch { expected: u64, actual: u64 },
+    HashMismatch { expected: String, actual: String },
+    Io(String),
+}
+
+impl std::fmt::Display for CorruptReason {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CorruptReason::Missing => write!(f, "missing"),
+            CorruptReason::SizeMismatch { expected, actual } => {
+                write!(f, "size mismatch: expected {} bytes, found {}", expected, actual)
+            }
+            CorruptReason::HashMismatch { expected, actual } => {
+                write!(f, "SHA-1 mismatch: expected {}, got {}", expected, actual)
+            }
+            CorruptReason::Io(e) => write!(f, "I/O: {}", e),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CorruptFile {
+    pub filename: String,
+    pub reason: CorruptReason,
+}
+
+/// Running totals, emitted after every file.
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+#[cfg_attr(feature = "napi", napi(object))]
+pub struct VerifyProgress {
+    pub files_done: u32,
+    pub files_total: u32,
+    pub bytes_done: i64,
+    pub bytes_total: i64,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct VerifyReport {
+    pub files_total: u32,
+    pub files_ok: u32,
+    pub bytes_verified: u64,
+    pub corrupt: Vec<CorruptFile>,
+    /// Set when verification stopped early because it was cancelled.
+    pub cancelled: bool,
+}
+
+impl VerifyReport {
+    pub fn is_ok(&self) -> bool {
+        self.corrupt.is_empty() && !self.cancelled
+    }
+}
+
+pub enum VerifyEvent<'a> {
+    Progress(&'a VerifyProgress),
+    FileOk { filename: &'a str },
+    FileCorrupt(&'a CorruptFile),
+    Done(&'a VerifyReport),
+}
+
+fn verify_file(path: &Path, entry: &crate::types::file::FileManifest) -> Result<u64, CorruptReason> {
+    let file = match File::open(path) {
+        Ok(file) => file,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Err(CorruptReason::Missing),
+        Err(e) => return Err(CorruptReason::Io(e.to_string())),
+    };
+    let mut reader = VerifyingReader::new(BatchedReader::new(file, backend(), 1024 * 1024, 4), entry);
+    match io::copy(&mut reader, &mut io::sink()) {
+        Ok(_) => Ok(reader.bytes_read()),
+        Err(e) if e.kind() == io::ErrorKind::InvalidData => match reader.check() {
+            Err(VerifyFailure::Size { expected, actual }) => {
+                Err(CorruptReason::SizeMismatch { expected, actual })
+            }
+            Err(VerifyFailure::Sha1 { expected, actual }) => {
+                Err(CorruptReason::HashMismatch { expected, actual })
+            }
+            Ok(()) => Err(CorruptReason::Io(e.to_string())),
+        },
+        Err(e) => Err(CorruptReason::Io(e.to_string())),
+    }
+}
+
+/// Which files [`verify_install_with`] checks and when it gives up.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct VerifyOptions {
+    /// Only verify these manifest filenames; empty means every file.
+    pub files: Vec<String>,
+    /// Stop after the first corrupt file instead of checking the rest.
+    pub stop_on_corrupt: bool,
+    /// Files hashed at once; `None` uses [`Parallelism::global`].
+    pub parallelism: Option<Parallelism>,
+}
+
+/// Hash every file of `manifest` under `install_dir`, reporting each result
+/// through `on_event`. Setting `cancel` stops after the current file.
+pub fn verify_install(
+    manifest: &Manifest,
+    install_dir: impl AsRef<Path>,
+    cancel: Option<&AtomicBool>,
+    on_event: impl FnMut(VerifyEvent),
+) -> Result<VerifyReport, ManifestError> {
+    verify_install_with(manifest, install_dir, &VerifyOptions::default(), cancel, on_event)
+}
+
+/// [`verify_install`] with explicit options
+pub fn verify_install_with(
+    manifest: &Manifest,
+    install_dir: impl AsRef<Path>,
+    options: &VerifyOptions,
+    cancel: Option<&AtomicBool>,
+    mut on_event: impl FnMut(VerifyEvent),
+) -> Result<VerifyReport, ManifestError> {
+    let install_dir = install_dir.as_ref();
+    let files: Vec<_> = manifest
+        .file_list
+        .as_ref()
+        .map(|f| f.file_manifest_list.as_slice())
+        .unwrap_or_default()
+        .iter()
+        .filter(|f| {
+            options.files.is_empty() || options.files.iter().any(|name| name == f.filename.trim_end_matches('\0'))
+        })
+        .collect();
+
+    let mut progress = VerifyProgress {
+        files_done: 0,
+        files_total: files.len() as u32,
+        bytes_done: 0,
+        bytes_total: files.iter().map(|f| f.file_size).sum(),
+    };
+    let mut report = VerifyReport {
+        files_total: progress.files_total,
+        ..Default::default()
+    };
+
+    // Files finish in any order when hashed in parallel; corrupt ones are
+    // reported in manifest order.
+    let mut corrupt: Vec<(usize, CorruptFile)> = Vec::new();
+    let parallelism = options.parallelism.clone().unwrap_or_else(Parallelism::global);
+    parallelism.run_indexed(
+        files.len(),
+        |index| {
+            if cancel.is_some_and(|c| c.load(Ordering::Relaxed)) {
+                return None;
+            }
+            let entry = files[index];
+            Some(verify_file(&install_dir.join(entry.filename.trim_end_matches('\0')), entry))
+        },
+        |index, result| {
+            let Some(result) = result else {
+                report.cancelled = true;
+                return ControlFlow::Break(());
+            };
+            let entry = files[index];
+            let filename = entry.filename.trim_end_matches('\0');
+            match result {
+                Ok(bytes) => {
+                    report.files_ok += 1;
+                    report.bytes_verified += bytes;
+                    on_event(VerifyEvent::FileOk { filename });
+                }
+                Err(reason) => {
+                    debug!("Verification failed for {}: {}", filename, reason);
+                    let file = CorruptFile {
+                        filename: filename.to_string(),
+                        reason,
+                    };
+                    on_event(VerifyEvent::FileCorrupt(&file));
+                    corrupt.push((index, file));
+                }
+            }
+
+            progress.files_done += 1;
+            progress.bytes_done += entry.file_size;
+            on_event(VerifyEvent::Progress(&progress));
+            if options.stop_on_corrupt && !corrupt.is_empty() {
+                return ControlFlow::Break(());
+            }
+            ControlFlow::Continue(())
+        },
+    );
+    corrupt.sort_by_key(|&(index, _)| index);
+    report.corrupt = corrupt.into_iter().map(|(_, file)| file).collect();
+
+    on_event(VerifyEvent::Done(&report));
+    Ok(report)
+}
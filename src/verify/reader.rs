@@ -0,0 +1,137 @@
+use sha1::{Digest, Sha1};
+use std::io::{self, Read};
+
+use crate::error::ManifestError;
+use crate::types::file::FileManifest;
+
+/// Why a stream failed verification.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum VerifyFailure {
+    Size { expected: u64, actual: u64 },
+    Sha1 { expected: String, actual: String },
+}
+
+impl std::fmt::Display for VerifyFailure {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            VerifyFailure::Size { expected, actual } => {
+                write!(f, "size mismatch: expected {} bytes, read {}", expected, actual)
+            }
+            VerifyFailure::Sha1 { expected, actual } => {
+                write!(f, "SHA-1 mismatch: expected {}, got {}", expected, actual)
+            }
+        }
+    }
+}
+
+/// Wraps the reader of a file's contents and checks them against the
+/// manifest entry as they stream past, so installers can verify without a
+/// second pass over the data.
+///
+/// Once the inner reader reports EOF, a size or SHA-1 mismatch is returned as
+/// an `InvalidData` error from `read`; [`VerifyingReader::finish`] gives the
+/// typed result.
+pub struct VerifyingReader<R> {
+    inner: R,
+    hasher: Sha1,
+    bytes_read: u64,
+    expected_sha1: String,
+    expected_size: Option<u64>,
+    result: Option<Result<(), VerifyFailure>>,
+}
+
+impl<R: Read> VerifyingReader<R> {
+    pub fn new(inner: R, entry: &FileManifest) -> Self {
+        // file_size is reconstructed from chunk parts and is 0 when they
+        // failed to parse, so only enforce it when parts are present.
+        let expected_size = (!entry.chunk_parts.is_empty()).then_some(entry.file_size as u64);
+        Self::with_expected(inner, &entry.sha_hash, expected_size)
+    }
+
+    /// Verify against an explicit hex SHA-1 and optional size.
+    pub fn with_expected(inner: R, sha1_hex: &str, expected_size: Option<u64>) -> Self {
+        Self {
+            inner,
+            hasher: Sha1::new(),
+            bytes_read: 0,
+            expected_sha1: sha1_hex.to_ascii_lowercase(),
+            expected_size,
+            result: None,
+        }
+    }
+
+    pub fn bytes_read(&self) -> u64 {
+        self.bytes_read
+    }
+
+    /// Outcome once EOF has been reached; computed on first call.
+    pub fn check(&mut self) -> Result<(), VerifyFailure> {
+        if let Some(result) = &self.result {
+            return result.clone();
+        }
+        let result = match self.expected_size {
+            Some(expected) if expected != self.bytes_read => Err(VerifyFailure::Size {
+                expected,
+                actual: self.bytes_read,
+            }),
+            _ => {
+                let actual = hex::encode(std::mem::take(&mut self.hasher).finalize());
+                if actual == self.expected_sha1 {
+                    Ok(())
+                } else {
+                    Err(VerifyFailure::Sha1 {
+                        expected: self.expected_sha1.clone(),
+                        actual,
+                    })
+                }
+            }
+        };
+        self.result = Some(result.clone());
+        result
+    }
+
+    /// Drain whatever is left and return the verification outcome. Errors
+    /// from the inner reader are returned as they are.
+    pub fn finish(mut self) -> Result<R, ManifestError> {
+        let mut buf = [0u8; 64 * 1024];
+        while self.result.is_none() {
+            match self.read(&mut buf) {
+                Ok(_) => {}
+                // Our own check failing sets `result`, which `check` reports.
+                Err(_) if self.result.is_some() => {}
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+                Err(e) => return Err(e.into()),
+            }
+        }
+        match self.check() {
+            Ok(()) => Ok(self.inner),
+            Err(VerifyFailure::Sha1 { expected, actual }) => Err(ManifestError::Sha1Mismatch { expected, actual }),
+            Err(failure) => Err(ManifestError::Invalid(failure.to_string())),
+        }
+    }
+
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+}
+
+impl<R: Read> Read for VerifyingReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.result.is_some() {
+            return self
+                .check()
+                .map(|_| 0)
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e.to_string()));
+        }
+        let n = self.inner.read(buf)?;
+        if n == 0 && !buf.is_empty() {
+            return self
+                .check()
+                .map(|_| 0)
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e.to_string()));
+        }
+        self.hasher.update(&buf[..n]);
+        self.bytes_read += n as u64;
+        Ok(n)
+    }
+}
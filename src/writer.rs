@@ -0,0 +1,316 @@
+//! Binary manifest serialization.
+//!
+//! Emits the layout [`crate::parse_with_report`] reads: a 41-byte header
+//! followed by a zlib-compressed payload of meta, chunk list and file list.
+//! Strings are written without trailing NUL padding, GUIDs in their raw byte
+//! form whatever [`crate::GuidFormat`] the manifest was parsed with, and the
+//! header SHA-1 is computed over the uncompressed payload.
+
+use miniz_oxide::deflate::compress_to_vec_zlib;
+use sha1::{Digest, Sha1};
+use std::io::Write;
+
+use crate::error::ManifestError;
+use crate::format::{CHUNK_PART_BASE_SIZE, MANIFEST_HEADER_SIZE_V2, MANIFEST_MAGIC};
+use crate::options::ParseOptions;
+use crate::types::chunk::{normalize_guid, ChunkDataList};
+use crate::types::custom_fields::CustomFields;
+use crate::types::file::FileManifestList;
+use crate::types::flags::STORED_COMPRESSED;
+use crate::types::manifest::Manifest;
+use crate::types::meta::ManifestMeta;
+
+/// zlib level used for the payload by default; fixed so output is
+/// reproducible.
+pub const DEFAULT_COMPRESSION_LEVEL: u8 = 6;
+
+/// Meta data version written: adds the stored `BuildId`.
+const META_DATA_VERSION: u8 = 1;
+
+/// Re-emit any manifest this crate accepts (binary v1/v2+, JSON) as a
+/// canonical binary manifest.
+///
+/// The output has a v2 header, a compressed payload and a meta section that
+/// always stores the build id, so equivalent uploads normalize to the same
+/// bytes. Normalizing the output again returns it unchanged.
+pub fn normalize(buf: &[u8]) -> Result<Vec<u8>, ManifestError> {
+    let (manifest, _) = crate::parse_with_report(buf, &ParseOptions::default())?;
+    write_manifest(&manifest)
+}
+
+/// Serializes manifests in Epic's binary format, e.g. to repackage a
+/// manifest after editing it. Section sizes, the payload sizes and the
+/// header SHA-1 are computed from what is written, so they need not be kept
+/// up to date on the [`Manifest`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ManifestWriter {
+    compression_level: Option<u8>,
+}
+
+impl Default for ManifestWriter {
+    fn default() -> Self {
+        Self {
+            compression_level: Some(DEFAULT_COMPRESSION_LEVEL),
+        }
+    }
+}
+
+impl ManifestWriter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// zlib level (0-10) for the payload.
+    pub fn with_compression_level(mut self, level: u8) -> Self {
+        self.compression_level = Some(level.min(10));
+        self
+    }
+
+    /// Store the payload uncompressed, as some older manifests do.
+    pub fn uncompressed(mut self) -> Self {
+        self.compression_level = None;
+        self
+    }
+
+    /// Serialize `manifest`. It needs meta and a chunk list; a missing file
+    /// list is written empty.
+    pub fn to_bytes(&self, manifest: &Manifest) -> Result<Vec<u8>, ManifestError> {
+        write_manifest_with(manifest, self.compression_level)
+    }
+
+    /// [`ManifestWriter::to_bytes`] into `out`.
+    pub fn write(&self, manifest: &Manifest, mut out: impl Write) -> Result<(), ManifestError> {
+        out.write_all(&self.to_bytes(manifest)?)?;
+        Ok(())
+    }
+}
+
+/// Serialize `manifest` as a compressed binary manifest.
+pub(crate) fn write_manifest(manifest: &Manifest) -> Result<Vec<u8>, ManifestError> {
+    ManifestWriter::default().to_bytes(manifest)
+}
+
+fn write_manifest_with(manifest: &Manifest, compression_level: Option<u8>) -> Result<Vec<u8>, ManifestError> {
+    let meta = manifest
+        .meta
+        .as_ref()
+        .ok_or_else(|| ManifestError::Invalid("cannot write a manifest without meta".to_string()))?;
+    let chunk_list = manifest
+        .chunk_list
+        .as_ref()
+        .ok_or_else(|| ManifestError::Invalid("cannot write a manifest without a chunk list".to_string()))?;
+
+    // JSON manifests carry their feature level in the header only.
+    let feature_level = if meta.feature_level > 0 {
+        meta.feature_level
+    } else {
+        manifest.header.version
+    };
+
+    let mut payload = Vec::new();
+    write_meta(&mut payload, meta, feature_level)?;
+    write_chunk_list(&mut payload, chunk_list)?;
+    match &manifest.file_list {
+        Some(file_list) => write_file_list(&mut payload, file_list)?,
+        None => write_file_list(&mut payload, &FileManifestList::default())?,
+    }
+    if !manifest.custom_fields.is_empty() {
+        write_custom_fields(&mut payload, &manifest.custom_fields)?;
+    }
+
+    let (stored, stored_as) = match compression_level {
+        Some(level) => (compress_to_vec_zlib(&payload, level), STORED_COMPRESSED),
+        None => (payload.clone(), 0),
+    };
+
+    let mut out = Vec::with_capacity(MANIFEST_HEADER_SIZE_V2 as usize + stored.len());
+    out.extend_from_slice(&MANIFEST_MAGIC.to_le_bytes());
+    out.extend_from_slice(&MANIFEST_HEADER_SIZE_V2.to_le_bytes());
+    out.extend_from_slice(&section_len(payload.len())?.to_le_bytes());
+    out.extend_from_slice(&section_len(stored.len())?.to_le_bytes());
+    out.extend_from_slice(&Sha1::digest(&payload));
+    out.push(stored_as);
+    out.extend_from_slice(&feature_level.to_le_bytes());
+    out.extend_from_slice(&stored);
+    Ok(out)
+}
+
+fn section_len(len: usize) -> Result<u32, ManifestError> {
+    u32::try_from(len).map_err(|_| ManifestError::Invalid(format!("section of {} bytes is too large", len)))
+}
+
+/// Write `value` as an FString: ANSI when every character fits in a byte,
+/// UTF-16 (negative length) otherwise, NUL-terminated either way.
+fn put_fstring(out: &mut Vec<u8>, value: &str) {
+    let value = value.trim_end_matches('\0');
+    if value.is_empty() {
+        out.extend_from_slice(&0i32.to_le_bytes());
+    } else if value.is_ascii() {
+        out.extend_from_slice(&(value.len() as i32 + 1).to_le_bytes());
+        out.extend_from_slice(value.as_bytes());
+        out.push(0);
+    } else {
+        let units: Vec<u16> = value.encode_utf16().chain(std::iter::once(0)).collect();
+        out.extend_from_slice(&(-(units.len() as i32)).to_le_bytes());
+        for unit in units {
+            out.extend_from_slice(&unit.to_le_bytes());
+        }
+    }
+}
+
+fn put_fstring_array(out: &mut Vec<u8>, values: &[String]) {
+    out.extend_from_slice(&(values.len() as u32).to_le_bytes());
+    for value in values {
+        put_fstring(out, value);
+    }
+}
+
+fn put_guid(out: &mut Vec<u8>, guid: &str) -> Result<(), ManifestError> {
+    let uuid = normalize_guid(guid)
+        .and_then(|guid| uuid::Uuid::parse_str(&guid).ok())
+        .ok_or_else(|| ManifestError::Invalid(format!("invalid GUID {}", guid)))?;
+    out.extend_from_slice(uuid.as_bytes());
+    Ok(())
+}
+
+fn put_sha1(out: &mut Vec<u8>, hex_digest: &str) -> Result<(), ManifestError> {
+    let mut digest = [0u8; 20];
+    hex::decode_to_slice(hex_digest, &mut digest)
+        .map_err(|e| ManifestError::Invalid(format!("invalid SHA-1 {}: {}", hex_digest, e)))?;
+    out.extend_from_slice(&digest);
+    Ok(())
+}
+
+/// Reserve a section's `u32` size field at the current end of `out`.
+fn begin_section(out: &mut Vec<u8>) -> usize {
+    let start = out.len();
+    out.extend_from_slice(&0u32.to_le_bytes());
+    start
+}
+
+/// Fill in the size reserved by [`begin_section`]; sizes include the field.
+fn end_section(out: &mut [u8], start: usize) -> Result<(), ManifestError> {
+    let size = section_len(out.len() - start)?;
+    out[start..start + 4].copy_from_slice(&size.to_le_bytes());
+    Ok(())
+}
+
+fn write_meta(out: &mut Vec<u8>, meta: &ManifestMeta, feature_level: i32) -> Result<(), ManifestError> {
+    let start = begin_section(out);
+    out.push(META_DATA_VERSION);
+    out.extend_from_slice(&feature_level.to_le_bytes());
+    out.push(meta.is_file_data as u8);
+    out.extend_from_slice(&meta.app_id.to_le_bytes());
+    put_fstring(out, &meta.app_name);
+    put_fstring(out, &meta.build_version);
+    put_fstring(out, &meta.launch_exe);
+    put_fstring(out, &meta.launch_command);
+    put_fstring_array(out, &meta.prereq_ids);
+    put_fstring(out, &meta.prereq_name);
+    put_fstring(out, &meta.prereq_path);
+    put_fstring(out, &meta.prereq_args);
+    put_fstring(out, &meta.build_id());
+    end_section(out, start)
+}
+
+fn write_chunk_list(out: &mut Vec<u8>, chunk_list: &ChunkDataList) -> Result<(), ManifestError> {
+    let chunks = &chunk_list.elements;
+    let start = begin_section(out);
+    out.push(0);
+    out.extend_from_slice(&(chunks.len() as u32).to_le_bytes());
+    for chunk in chunks {
+        put_guid(out, &chunk.guid)?;
+    }
+    for chunk in chunks {
+        let hash = u64::from_str_radix(&chunk.hash, 16)
+            .map_err(|e| ManifestError::Invalid(format!("invalid chunk hash {}: {}", chunk.hash, e)))?;
+        out.extend_from_slice(&hash.to_le_bytes());
+    }
+    for chunk in chunks {
+        put_sha1(out, &chunk.sha_hash)?;
+    }
+    for chunk in chunks {
+        out.push(chunk.group);
+    }
+    for chunk in chunks {
+        out.extend_from_slice(&chunk.window_size.to_le_bytes());
+    }
+    for chunk in chunks {
+        let file_size: u64 = chunk
+            .file_size
+            .parse()
+            .map_err(|e| ManifestError::Invalid(format!("invalid chunk file size {}: {}", chunk.file_size, e)))?;
+        out.extend_from_slice(&file_size.to_le_bytes());
+    }
+    end_section(out, start)
+}
+
+fn write_file_list(out: &mut Vec<u8>, file_list: &FileManifestList) -> Result<(), ManifestError> {
+    let files = &file_list.file_manifest_list;
+    // Version 2 is only needed to carry MIME types.
+    let data_version = if files.iter().any(|f| !f.mime_type.trim_end_matches('\0').is_empty()) {
+        2
+    } else {
+        0
+    };
+
+    let start = begin_section(out);
+    out.push(data_version);
+    out.extend_from_slice(&(files.len() as u32).to_le_bytes());
+    for file in files {
+        put_fstring(out, &file.filename);
+    }
+    for file in files {
+        put_fstring(out, &file.symlink_target);
+    }
+    for file in files {
+        put_sha1(out, &file.sha_hash)?;
+    }
+    for file in files {
+        out.push(file.file_meta_flags);
+    }
+    for file in files {
+        put_fstring_array(out, &file.install_tags);
+    }
+    for file in files {
+        out.extend_from_slice(&(file.chunk_parts.len() as u32).to_le_bytes());
+        for part in &file.chunk_parts {
+            let extra = match &part.extra_data {
+                Some(extra) => hex::decode(extra)
+                    .map_err(|e| ManifestError::Invalid(format!("invalid chunk part extra data: {}", e)))?,
+                None => Vec::new(),
+            };
+            out.extend_from_slice(&(CHUNK_PART_BASE_SIZE + extra.len() as u32).to_le_bytes());
+            put_guid(out, &part.parent_guid)?;
+            out.extend_from_slice(&part.offset.to_le_bytes());
+            out.extend_from_slice(&part.size.to_le_bytes());
+            out.extend_from_slice(&extra);
+        }
+    }
+    if data_version >= 2 {
+        // No per-file MD5 or SHA-256 is known; write the empty forms.
+        for _ in files {
+            out.extend_from_slice(&0u32.to_le_bytes());
+        }
+        for file in files {
+            put_fstring(out, &file.mime_type);
+        }
+        for _ in files {
+            out.extend_from_slice(&[0u8; 32]);
+        }
+    }
+    end_section(out, start)
+}
+
+fn write_custom_fields(out: &mut Vec<u8>, fields: &CustomFields) -> Result<(), ManifestError> {
+    let start = begin_section(out);
+    out.push(0);
+    out.extend_from_slice(&(fields.len() as u32).to_le_bytes());
+    for (key, _) in fields.iter() {
+        put_fstring(out, key);
+    }
+    for (_, value) in fields.iter() {
+        put_fstring(out, value);
+    }
+    end_section(out, start)
+}
@@ -0,0 +1,148 @@
+#!/usr/bin/env python3
+"""Regenerate the synthetic snapshot fixtures in this directory.
+
+Every value is made up; the files only exercise the parser's branches. Run
+from anywhere: `python3 test-manifests/fixtures/generate.py`.
+"""
+
+import hashlib
+import json
+import os
+import struct
+import zlib
+
+HERE = os.path.dirname(os.path.abspath(__file__))
+MAGIC = 0x44BEC00C
+
+
+def fstring(s, utf16=False):
+    if not s:
+        return struct.pack("<i", 0)
+    if utf16:
+        data = (s + "\0").encode("utf-16-le")
+        return struct.pack("<i", -(len(data) // 2)) + data
+    data = (s + "\0").encode("utf-8")
+    return struct.pack("<i", len(data)) + data
+
+
+def farray(items):
+    return struct.pack("<I", len(items)) + b"".join(fstring(i) for i in items)
+
+
+def meta(data_version=0, app_name="FixtureApp", utf16_name=False, build_id=None):
+    body = struct.pack("<BiBi", data_version, 18, 1, 4242)
+    body += fstring(app_name, utf16_name)
+    body += fstring("1.0.0-fixture")
+    body += fstring("Game/Binaries/Fixture.exe")
+    body += fstring("-fixture")
+    body += farray(["prereq-a", "prereq-b"])
+    body += fstring("Fixture Prerequisites")
+    body += fstring("Prereqs/setup.exe")
+    body += fstring("/quiet")
+    if data_version >= 1:
+        body += fstring(build_id)
+    return struct.pack("<I", len(body) + 4) + body
+
+
+GUIDS = [bytes(range(i * 16, i * 16 + 16)) for i in range(3)]
+
+
+def chunk_list():
+    n = len(GUIDS)
+    body = struct.pack("<BI", 0, n)
+    body += b"".join(GUIDS)
+    body += b"".join(struct.pack("<Q", 0x1111_0000_0000_0000 + i) for i in range(n))
+    body += b"".join(hashlib.sha1(g).digest() for g in GUIDS)
+    body += bytes(i * 7 % 100 for i in range(n))
+    body += b"".join(struct.pack("<I", 1024 * 1024) for _ in range(n))
+    body += b"".join(struct.pack("<Q", 4096 + i) for i in range(n))
+    return struct.pack("<I", len(body) + 4) + body
+
+
+FILES = [
+    # filename, symlink, flags, tags, parts [(chunk index, offset, size)]
+    ("Game/Binaries/Fixture.exe", "", 4, ["core"], [(0, 0, 700), (1, 0, 300)]),
+    ("Game/Content/data.pak", "", 0, ["core", "content"], [(1, 300, 5000)]),
+    ("Game/readme.txt", "", 0, [], [(2, 0, 12)]),
+    ("Game/latest.txt", "readme.txt", 0, [], []),
+]
+
+
+def file_list(version=0, corrupt_count_of=None):
+    n = len(FILES)
+    body = b"".join(fstring(f[0]) for f in FILES)
+    body += b"".join(fstring(f[1]) for f in FILES)
+    body += b"".join(hashlib.sha1(f[0].encode()).digest() for f in FILES)
+    body += bytes(f[2] for f in FILES)
+    body += b"".join(farray(f[3]) for f in FILES)
+    for i, f in enumerate(FILES):
+        count = 0x7FFF_0001 if i == corrupt_count_of else len(f[4])
+        body += struct.pack("<I", count)
+        for chunk, offset, size in f[4]:
+            body += struct.pack("<I", 28) + GUIDS[chunk] + struct.pack("<II", offset, size)
+    if version >= 2:
+        body += b"".join(struct.pack("<I", 0) for _ in FILES)
+        body += b"".join(fstring("application/octet-stream") for _ in FILES)
+        body += b"\0" * (32 * n)
+    return struct.pack("<IBI", len(body) + 9, version, n) + body
+
+
+def binary(payload, compressed=True):
+    data = zlib.compress(payload) if compressed else payload
+    header = struct.pack("<Iiii", MAGIC, 41, len(payload), len(data))
+    header += hashlib.sha1(payload).digest()
+    header += struct.pack("<Bi", 1 if compressed else 0, 18)
+    return header + data
+
+
+def json_manifest():
+    def blob(n):
+        return "".join("%03d" % b for b in struct.pack("<I", n))
+
+    def epic(guid):
+        return "".join("%08X" % v for v in struct.unpack("<4I", guid))
+
+    return {
+        "ManifestFileVersion": "013000000000",
+        "bIsFileData": False,
+        "AppID": "000000000000",
+        "AppNameString": "FixtureApp",
+        "BuildVersionString": "1.0.0-fixture",
+        "LaunchExeString": "Game/Binaries/Fixture.exe",
+        "LaunchCommand": "",
+        "PrereqIds": [],
+        "PrereqName": "",
+        "PrereqPath": "",
+        "PrereqArgs": "",
+        "FileManifestList": [
+            {
+                "Filename": name,
+                "FileHash": "".join("%03d" % b for b in hashlib.sha1(name.encode()).digest()),
+                "FileChunkParts": [
+                    {"Guid": epic(GUIDS[c]), "Offset": blob(o), "Size": blob(s)}
+                    for c, o, s in parts
+                ],
+            }
+            for name, _, _, _, parts in FILES
+            if parts
+        ],
+    }
+
+
+def main():
+    fixtures = {
+        "minimal.manifest": binary(meta() + chunk_list() + file_list()),
+        "uncompressed-v2.manifest": binary(
+            meta(1, "FixtureÄpp", True, "fixture-build-id") + chunk_list() + file_list(2),
+            compressed=False,
+        ),
+        "corrupt-chunk-count.manifest": binary(meta() + chunk_list() + file_list(0, 1)),
+        "small.json.manifest": json.dumps(json_manifest(), separators=(",", ":")).encode(),
+    }
+    for name, data in fixtures.items():
+        with open(os.path.join(HERE, name), "wb") as f:
+            f.write(data)
+
+
+if __name__ == "__main__":
+    main()
@@ -3,7 +3,7 @@ import { readFileSync, existsSync } from 'fs';
 import { join } from 'path';
 
 // Import the NAPI functions
-import { parseManifestSync, parseManifestAsync, parseManifestBuffer } from '../index.js';
+import { parseManifestSync, parseManifestAsync, parseManifestBuffer, ManifestHandle } from '../index.js';
 
 describe('NAPI Manifest Parser', () => {
     const manifestPath = join(__dirname, '..', 'test-manifests', 'valid-small.manifest');
@@ -242,6 +242,50 @@ describe('NAPI Manifest Parser', () => {
         });
     });
 
+    describe('NUL padding', () => {
+        // Binary manifests store strings with a trailing NUL; none of it
+        // may reach JS, whichever entry point parsed the manifest.
+        const expectNoNul = (result: ReturnType<typeof parseManifestSync>) => {
+            const { meta, fileList } = result;
+            const metaStrings = [
+                meta?.appName,
+                meta?.buildVersion,
+                meta?.launchExe,
+                meta?.launchCommand,
+                meta?.prereqName,
+                meta?.prereqPath,
+                meta?.prereqArgs,
+                ...(meta?.prereqIds ?? []),
+            ];
+            expect(meta?.appName.length).toBeGreaterThan(0);
+            for (const value of metaStrings) {
+                expect(value).not.toContain('\0');
+            }
+
+            expect(fileList?.fileManifestList.length).toBeGreaterThan(0);
+            for (const file of fileList?.fileManifestList ?? []) {
+                expect(file.filename.length).toBeGreaterThan(0);
+                expect(file.filename).not.toContain('\0');
+                for (const tag of file.installTags) {
+                    expect(tag).not.toContain('\0');
+                }
+            }
+        };
+
+        it('should strip NUL padding when parsing a file', () => {
+            // valid-small.manifest stores its strings NUL-terminated.
+            expectNoNul(parseManifestSync(manifestPath));
+        });
+
+        it('should strip NUL padding when parsing a buffer', () => {
+            expectNoNul(parseManifestBuffer(manifestBuffer));
+        });
+
+        it('should strip NUL padding when parsing asynchronously', async () => {
+            expectNoNul(await parseManifestAsync(manifestPath));
+        });
+    });
+
     describe('JSON Manifest Parsing', () => {
         it('should parse JSON manifest file synchronously', () => {
             const result = parseManifestSync(jsonManifestPath);
@@ -337,6 +381,43 @@ describe('NAPI Manifest Parser', () => {
         });
     });
 
+    describe('ManifestHandle accessors', () => {
+        it('should expose computed properties without copying the manifest', () => {
+            const handle = ManifestHandle.fromBuffer(manifestBuffer);
+            const manifest = parseManifestBuffer(manifestBuffer);
+
+            expect(handle.fileCount).toBe(manifest.fileList!.fileManifestList.length);
+            expect(handle.chunkCount).toBe(manifest.chunkList!.elements.length);
+            expect(handle.totalDownloadSize).toBeGreaterThan(0);
+            expect(handle.totalInstallSize).toBe(
+                manifest.fileList!.fileManifestList.reduce((sum, f) => sum + f.fileSize, 0),
+            );
+
+            const first = manifest.fileList!.fileManifestList[0];
+            const file = handle.file(first.filename.replace(/\0+$/, ''))!;
+            expect(file.fileSize).toBe(first.fileSize);
+            expect(file.isUnixExecutable).toBe((first.fileMetaFlags & 4) !== 0);
+            expect(handle.fileAt(handle.fileCount)).toBeFalsy();
+
+            const chunk = handle.chunk(file.chunkGuids()[0])!;
+            expect(chunk.cdnPath(15)).toMatch(/^ChunksV4\/\d{2}\/[0-9A-F]{16}_[0-9A-F]{32}\.chunk$/);
+        });
+
+        it('should iterate files in batches', async () => {
+            const handle = ManifestHandle.fromBuffer(manifestBuffer);
+            const manifest = parseManifestBuffer(manifestBuffer);
+
+            const sizes: number[] = [];
+            const names: string[] = [];
+            for await (const batch of handle.files(50)) {
+                sizes.push(batch.length);
+                names.push(...batch.map((f) => f.filename));
+            }
+            expect(sizes.every((size) => size <= 50)).toBe(true);
+            expect(names).toEqual(manifest.fileList!.fileManifestList.map((f) => f.filename));
+        });
+    });
+
     describe('Performance tests', () => {
         it('sync parsing should complete within reasonable time', () => {
             const start = Date.now();
@@ -0,0 +1,126 @@
+// Soak test for the NAPI layer: parses the test manifests thousands of times
+// through every entry point and fails if RSS keeps growing or native objects
+// outlive their JS owners. Run with `npm run test:soak` after a build.
+//
+// Environment:
+//   SOAK_ITERATIONS       rounds over the manifests (default 2000)
+//   SOAK_MAX_GROWTH_MB    allowed RSS growth after warm-up (default 32)
+//   SOAK_MANIFEST_DIR     extra directory of manifests to include
+
+import { createRequire } from 'module';
+import { readFileSync, readdirSync } from 'fs';
+import { dirname, join } from 'path';
+import { fileURLToPath } from 'url';
+
+const require = createRequire(import.meta.url);
+const {
+    parseManifestSync,
+    parseManifestBuffer,
+    detectFormat,
+    assessManifest,
+    ManifestHandle,
+    nativeStats,
+} = require('../index.js');
+
+if (typeof globalThis.gc !== 'function') {
+    console.error('soak: run node with --expose-gc');
+    process.exit(2);
+}
+
+const root = join(dirname(fileURLToPath(import.meta.url)), '..');
+const iterations = Number(process.env.SOAK_ITERATIONS ?? 2000);
+const maxGrowthMb = Number(process.env.SOAK_MAX_GROWTH_MB ?? 32);
+const warmup = Math.max(1, Math.floor(iterations / 10));
+
+const manifestPaths = [
+    join(root, 'test-manifests', 'valid-small.manifest'),
+    join(root, 'test-manifests', 'valid-json-format.manifest'),
+];
+if (process.env.SOAK_MANIFEST_DIR) {
+    const dir = process.env.SOAK_MANIFEST_DIR;
+    for (const name of readdirSync(dir)) {
+        if (name.endsWith('.manifest')) manifestPaths.push(join(dir, name));
+    }
+}
+const manifests = manifestPaths.map((path) => ({ path, buffer: readFileSync(path) }));
+
+// Exercises the buffer, object conversion and handle paths once per manifest.
+function round(i) {
+    for (const { path, buffer } of manifests) {
+        // A fresh copy each time, so the JS side allocates Buffers too.
+        const copy = Buffer.from(buffer);
+        detectFormat(copy);
+        assessManifest(copy);
+
+        const manifest = parseManifestBuffer(copy, { fileFlags: true });
+        if (!manifest.fileList || manifest.fileList.fileManifestList.length === 0) {
+            throw new Error(`${path}: no files`);
+        }
+
+        const handle = ManifestHandle.fromBuffer(copy);
+        handle.fileCount;
+        handle.totalDownloadSize;
+        handle.buildId;
+        const file = handle.fileAt(i % handle.fileCount);
+        file.filename;
+        file.installTags;
+        for (const guid of file.chunkGuids()) {
+            handle.chunk(guid)?.cdnPath();
+        }
+        handle.filesUnder('');
+        for (const batch of handle.files(64)) batch.length;
+
+        if (i % 10 === 0) parseManifestSync(path);
+    }
+}
+
+async function collect() {
+    // NAPI finalizers may run after the GC pass that queued them.
+    for (let i = 0; i < 4; i++) {
+        globalThis.gc();
+        await new Promise((resolve) => setImmediate(resolve));
+    }
+}
+
+const mb = (bytes) => (bytes / 1024 / 1024).toFixed(1);
+
+for (let i = 0; i < warmup; i++) round(i);
+await collect();
+const baseline = process.memoryUsage().rss;
+const copiedBefore = nativeStats().buffersCopied;
+console.log(`soak: ${manifests.length} manifests, ${iterations} rounds, baseline RSS ${mb(baseline)} MB`);
+
+const step = Math.max(1, Math.floor(iterations / 10));
+for (let i = 0; i < iterations; i++) {
+    round(i);
+    if ((i + 1) % step === 0) {
+        await collect();
+        const stats = nativeStats();
+        console.log(
+            `soak: round ${i + 1}: RSS ${mb(process.memoryUsage().rss)} MB, ` +
+                `live handles ${stats.liveManifestHandles}/${stats.liveFileHandles}/${stats.liveChunkHandles}`,
+        );
+    }
+}
+await collect();
+
+const failures = [];
+const growth = process.memoryUsage().rss - baseline;
+if (growth > maxGrowthMb * 1024 * 1024) {
+    failures.push(`RSS grew ${mb(growth)} MB (limit ${maxGrowthMb} MB)`);
+}
+const stats = nativeStats();
+for (const key of ['liveManifestHandles', 'liveFileHandles', 'liveChunkHandles', 'liveFileBatches', 'liveInstallVerifiers']) {
+    if (stats[key] !== 0) failures.push(`${key} is ${stats[key]} after GC`);
+}
+// parseManifestBuffer and ManifestHandle.fromBuffer each copy once.
+const expectedCopies = iterations * manifests.length * 2;
+if (stats.buffersCopied - copiedBefore !== expectedCopies) {
+    failures.push(`copied ${stats.buffersCopied - copiedBefore} buffers, expected ${expectedCopies}`);
+}
+
+if (failures.length > 0) {
+    for (const failure of failures) console.error(`soak: FAIL: ${failure}`);
+    process.exit(1);
+}
+console.log(`soak: ok, RSS growth ${mb(growth)} MB`);